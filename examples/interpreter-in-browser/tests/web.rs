@@ -0,0 +1,39 @@
+//! Test suite for the Web and headless browsers.
+#![cfg(target_arch = "wasm32")]
+
+extern crate wasm_bindgen_test;
+
+use wasm_bindgen_test::*;
+
+use liblumen_alloc::erts::term::prelude::Atom;
+
+use liblumen_eir_interpreter::VM;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+// There are no static constructors on wasm32, so this checks the BIFs from `lumen_runtime` are
+// registered without them
+#[wasm_bindgen_test]
+fn runtime_bifs_are_registered() {
+    let modules = VM.modules.read().unwrap();
+
+    for (module, function, arity) in &[
+        ("erlang", "abs", 1),
+        ("lists", "reverse", 1),
+        ("maps", "get", 2),
+    ] {
+        assert!(
+            modules
+                .lookup_function(
+                    Atom::try_from_str(module).unwrap(),
+                    Atom::try_from_str(function).unwrap(),
+                    *arity
+                )
+                .is_some(),
+            "{}:{}/{} is not registered",
+            module,
+            function,
+            arity
+        );
+    }
+}
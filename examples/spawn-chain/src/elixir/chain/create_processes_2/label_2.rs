@@ -3,9 +3,8 @@ use std::sync::Arc;
 
 use liblumen_alloc::borrow::clone_to_process::CloneToProcess;
 use liblumen_alloc::erts::exception::Alloc;
-use liblumen_alloc::erts::message::{self, Message};
 use liblumen_alloc::erts::process::code::stack::frame::{Frame, Placement};
-use liblumen_alloc::erts::process::{code, Process};
+use liblumen_alloc::erts::process::{code, Process, Receive};
 use liblumen_alloc::erts::term::prelude::*;
 
 use crate::elixir::chain::create_processes_2::label_3;
@@ -16,6 +15,7 @@ pub fn place_frame_with_arguments(
     output: Term,
 ) -> Result<(), Alloc> {
     process.stack_push(output)?;
+    process.receive_start(None);
     process.place_frame(frame(process), placement);
 
     Ok(())
@@ -34,94 +34,53 @@ pub fn place_frame_with_arguments(
 /// end
 /// ```
 fn code(arc_process: &Arc<Process>) -> code::Result {
-    // locked mailbox scope
-    let received = {
-        let mailbox_guard = arc_process.mailbox.lock();
-        let mut mailbox = mailbox_guard.borrow_mut();
-        let seen = mailbox.seen();
-        let mut found_position = None;
+    arc_process.reduce();
 
-        for (position, message) in mailbox.iter().enumerate() {
-            if seen < (position as isize) {
-                let message_data = match message {
-                    Message::Process(message::Process { data }) => data,
-                    Message::HeapFragment(message::HeapFragment { data, .. }) => data,
+    // Only the messages that arrived since the last time this label was run are looked at
+    match arc_process.receive_next() {
+        Receive::Message(message) => {
+            if message.is_integer() {
+                let final_answer = if arc_process.receive_last_off_heap() {
+                    match message.clone_to_heap(&mut arc_process.acquire_heap()) {
+                        Ok(heap_data) => heap_data,
+                        Err(alloc) => return Err(alloc.into()),
+                    }
+                } else {
+                    message
                 };
 
-                if message_data.is_integer() {
-                    let final_answer = match message {
-                        Message::Process(message::Process { data }) => *data,
-                        Message::HeapFragment(message::HeapFragment { data, .. }) => {
-                            match data.clone_to_heap(&mut arc_process.acquire_heap()) {
-                                Ok(heap_data) => heap_data,
-                                Err(alloc) => {
-                                    arc_process.reduce();
-
-                                    return Err(alloc.into());
-                                }
-                            }
-                        }
-                    };
+                arc_process.receive_done();
 
-                    let sent = arc_process.stack_pop().unwrap();
-                    assert!(sent.is_integer());
-                    let output = arc_process.stack_pop().unwrap();
-                    assert!(output.is_boxed_function());
+                let sent = arc_process.stack_pop().unwrap();
+                assert!(sent.is_integer());
+                let output = arc_process.stack_pop().unwrap();
+                assert!(output.is_boxed_function());
 
-                    label_3::place_frame_with_arguments(
-                        arc_process,
-                        Placement::Replace,
-                        final_answer,
-                    )
+                label_3::place_frame_with_arguments(arc_process, Placement::Replace, final_answer)
                     .unwrap();
 
-                    let output_closure: Boxed<Closure> = output.try_into().unwrap();
-                    // TODO use `<>` and `to_string` to more closely emulate interpolation
-                    let binary = arc_process
-                        .binary_from_str(&format!("Result is {}", final_answer))
-                        .unwrap();
-                    output_closure
-                        .place_frame_with_arguments(arc_process, Placement::Push, vec![binary])
-                        .unwrap();
-
-                    found_position = Some(position);
-
-                    break;
-                } else {
-                    // NOT in original Elixir source and would not be in compiled code, but helps
-                    // debug runtime bugs leading to deadlocks.
-                    panic!(
-                        "Non-integer message ({:?}) received in {:?}",
-                        message_data, arc_process
-                    );
-                }
-            }
-        }
-
-        // separate because can't remove during iteration
-        match found_position {
-            Some(position) => {
-                mailbox.remove(position, arc_process);
-                mailbox.unmark_seen();
-
-                true
-            }
-            None => {
-                mailbox.mark_seen();
+                let output_closure: Boxed<Closure> = output.try_into().unwrap();
+                // TODO use `<>` and `to_string` to more closely emulate interpolation
+                let binary = arc_process
+                    .binary_from_str(&format!("Result is {}", final_answer))
+                    .unwrap();
+                output_closure
+                    .place_frame_with_arguments(arc_process, Placement::Push, vec![binary])
+                    .unwrap();
 
-                false
+                Process::call_code(arc_process)
+            } else {
+                // NOT in original Elixir source and would not be in compiled code, but helps
+                // debug runtime bugs leading to deadlocks.
+                panic!(
+                    "Non-integer message ({:?}) received in {:?}",
+                    message, arc_process
+                );
             }
         }
-    };
-
-    arc_process.reduce();
-
-    if received {
-        Process::call_code(arc_process)
-    } else {
-        arc_process.wait();
-
-        Ok(())
+        // `receive_next` already put the process in the waiting status
+        Receive::Wait => Ok(()),
+        Receive::Timeout => unreachable!("receive has no after clause"),
     }
 }
 
@@ -1,6 +1,7 @@
 use core::cell::Cell;
 use core::cmp::{self, Ord, PartialEq, PartialOrd};
 use core::hash::{Hash, Hasher};
+use core::sync::atomic::{AtomicU32, Ordering};
 
 use liblumen_core::locks::Mutex;
 
@@ -10,7 +11,7 @@ use crate::erts::term::prelude::Atom;
 pub struct Node {
     id: usize,
     name: Mutex<Cell<Atom>>,
-    creation: u32,
+    creation: AtomicU32,
 }
 
 impl Node {
@@ -18,12 +19,17 @@ impl Node {
         Self {
             id,
             name: Mutex::new(Cell::new(name)),
-            creation,
+            creation: AtomicU32::new(creation),
         }
     }
 
     pub fn creation(&self) -> u32 {
-        self.creation
+        self.creation.load(Ordering::SeqCst)
+    }
+
+    /// Only the running node's creation changes, when it becomes alive.
+    pub fn set_creation(&self, creation: u32) {
+        self.creation.store(creation, Ordering::SeqCst)
     }
 
     pub fn id(&self) -> usize {
@@ -33,6 +39,11 @@ impl Node {
     pub fn name(&self) -> Atom {
         self.name.lock().get()
     }
+
+    /// Only the running node's name changes, when it goes from dead to alive.
+    pub fn set_name(&self, name: Atom) {
+        self.name.lock().set(name)
+    }
 }
 
 impl Eq for Node {}
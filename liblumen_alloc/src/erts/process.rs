@@ -18,6 +18,7 @@ use core::ptr::{self, NonNull};
 use core::str::Chars;
 use core::sync::atomic::{AtomicU16, AtomicU64, AtomicUsize, Ordering};
 
+use ::alloc::collections::VecDeque;
 use ::alloc::sync::Arc;
 
 use anyhow::*;
@@ -58,6 +59,15 @@ cfg_if::cfg_if! {
   }
 }
 
+/// The reductions of all processes, including those that exited
+static TOTAL_REDUCTIONS: AtomicU64 = AtomicU64::new(0);
+
+/// The reductions of all processes, including those that exited, as used by
+/// `erlang:statistics(reductions)`
+pub fn total_reductions() -> u64 {
+    TOTAL_REDUCTIONS.load(Ordering::SeqCst)
+}
+
 /// Represents the primary control structure for processes
 ///
 /// NOTE FOR LUKE: Like we discussed, when performing GC we will
@@ -71,19 +81,25 @@ pub struct Process {
     /// ID of the scheduler that is running the process
     scheduler_id: Mutex<Option<scheduler::ID>>,
     /// The priority of the process in `scheduler`.
-    pub priority: Priority,
+    priority: Mutex<Priority>,
     /// Process flags, e.g. `Process.flag/1`
     flags: AtomicProcessFlags,
     /// Minimum size of the heap that this process will start with
-    min_heap_size: usize,
-    /// The maximum size of the heap allowed for this process
-    max_heap_size: usize,
+    min_heap_size: AtomicUsize,
+    /// The maximum size of the heap allowed for this process, or `0` for no maximum
+    max_heap_size: AtomicUsize,
     /// Minimum virtual heap size for this process
-    min_vheap_size: usize,
+    min_vheap_size: AtomicUsize,
+    /// The number of calls to save for `process_info(pid, last_calls)`, or `0` to save none
+    save_calls: AtomicUsize,
+    /// The last `save_calls` calls, oldest first
+    last_calls: Mutex<VecDeque<ModuleFunctionArity>>,
+    /// The module that handles calls to undefined functions
+    error_handler: Mutex<Atom>,
     /// The percentage of used to unused space at which a collection is triggered
     gc_threshold: f64,
     /// The maximum number of minor collections before a full sweep occurs
-    max_gen_gcs: usize,
+    max_gen_gcs: AtomicUsize,
     /// off-heap allocations
     off_heap: SpinLock<LinkedList<HeapFragmentAdapter>>,
     off_heap_size: AtomicUsize,
@@ -105,6 +121,9 @@ pub struct Process {
     /// Pids of processes that are linked to this process and need to be exited when this process
     /// exits
     pub linked_pid_set: Mutex<HashSet<Pid>>,
+    /// Ports that are linked to this process and need to be closed or exited when this process
+    /// exits
+    pub linked_port_set: Mutex<HashSet<Port>>,
     /// Maps monitor references to the PID of the process that is monitoring through that
     /// reference.
     pub monitor_by_reference: Mutex<HashMap<Reference, Monitor>>,
@@ -138,12 +157,20 @@ impl Process {
         };
 
         Self {
-            flags: AtomicProcessFlags::new(ProcessFlags::Default),
-            min_heap_size: heap_size,
-            max_heap_size: 0,
-            min_vheap_size: 0,
+            // Like the BEAM, exceeding a `max_heap_size` kills and logs unless told otherwise
+            flags: AtomicProcessFlags::new(
+                ProcessFlags::Default
+                    | ProcessFlags::MaxHeapSizeKill
+                    | ProcessFlags::MaxHeapSizeErrorLogger,
+            ),
+            min_heap_size: AtomicUsize::new(heap_size),
+            max_heap_size: AtomicUsize::new(0),
+            min_vheap_size: AtomicUsize::new(0),
+            save_calls: AtomicUsize::new(0),
+            last_calls: Default::default(),
+            error_handler: Mutex::new(Atom::try_from_str("error_handler").unwrap()),
             gc_threshold: 0.75,
-            max_gen_gcs: 65535,
+            max_gen_gcs: AtomicUsize::new(65535),
             off_heap,
             off_heap_size: AtomicUsize::new(0),
             dictionary: Default::default(),
@@ -153,7 +180,7 @@ impl Process {
             heap: Mutex::new(heap),
             code_stack: Default::default(),
             scheduler_id: Mutex::new(None),
-            priority,
+            priority: Mutex::new(priority),
             parent_pid,
             group_leader_pid: Mutex::new(group_leader_pid),
             initial_module_function_arity,
@@ -161,6 +188,7 @@ impl Process {
             total_reductions: Default::default(),
             registered_name: Default::default(),
             linked_pid_set: Default::default(),
+            linked_port_set: Default::default(),
             monitor_by_reference: Default::default(),
             monitored_pid_by_reference: Default::default(),
         }
@@ -176,6 +204,19 @@ impl Process {
         *self.scheduler_id.lock() = Some(scheduler_id);
     }
 
+    pub fn priority(&self) -> Priority {
+        *self.priority.lock()
+    }
+
+    /// Sets the priority, returning the old priority.
+    ///
+    /// Only the run queue a process is enqueued in depends on its priority, so the process moves
+    /// to the queue for `priority` the next time it is enqueued.  A running process is enqueued
+    /// when it stops running, so a process changing its own priority moves immediately.
+    pub fn set_priority(&self, priority: Priority) -> Priority {
+        mem::replace(&mut *self.priority.lock(), priority)
+    }
+
     // Flags
 
     pub fn are_flags_set(&self, flags: ProcessFlags) -> bool {
@@ -195,8 +236,15 @@ impl Process {
     }
 
     pub fn trap_exit(&self, value: bool) -> bool {
-        let flag = ProcessFlags::TrapExit;
+        self.put_flag(ProcessFlags::TrapExit, value)
+    }
+
+    pub fn traps_exit(&self) -> bool {
+        self.are_flags_set(ProcessFlags::TrapExit)
+    }
 
+    /// Sets or clears `flag`, returning whether it was set before
+    pub fn put_flag(&self, flag: ProcessFlags, value: bool) -> bool {
         let old_flags = if value {
             self.set_flags(flag)
         } else {
@@ -206,8 +254,59 @@ impl Process {
         old_flags.are_set(flag)
     }
 
-    pub fn traps_exit(&self) -> bool {
-        self.are_flags_set(ProcessFlags::TrapExit)
+    /// Whether the data of the process, such as its messages and dictionary, is hidden from
+    /// `process_info` and tracing
+    pub fn is_sensitive(&self) -> bool {
+        self.are_flags_set(ProcessFlags::Sensitive)
+    }
+
+    pub fn error_handler(&self) -> Atom {
+        *self.error_handler.lock()
+    }
+
+    /// Sets the module that handles calls to undefined functions, returning the old module
+    pub fn set_error_handler(&self, module: Atom) -> Atom {
+        mem::replace(&mut *self.error_handler.lock(), module)
+    }
+
+    pub fn save_calls(&self) -> usize {
+        self.save_calls.load(Ordering::SeqCst)
+    }
+
+    /// Sets the number of calls to save, returning the old number.
+    ///
+    /// Like the BEAM, the calls saved so far are discarded.
+    pub fn set_save_calls(&self, save_calls: usize) -> usize {
+        let mut last_calls = self.last_calls.lock();
+        last_calls.clear();
+
+        self.save_calls.swap(save_calls, Ordering::SeqCst)
+    }
+
+    /// Saves a call for `process_info(pid, last_calls)` if the process is saving calls
+    pub fn save_call(&self, module_function_arity: ModuleFunctionArity) {
+        let save_calls = self.save_calls();
+
+        if 0 < save_calls {
+            let mut last_calls = self.last_calls.lock();
+
+            while save_calls <= last_calls.len() {
+                last_calls.pop_front();
+            }
+
+            last_calls.push_back(module_function_arity);
+        }
+    }
+
+    /// The saved calls, oldest first, or `None` if the process is not saving calls
+    pub fn last_calls(&self) -> Option<Vec<ModuleFunctionArity>> {
+        let last_calls = self.last_calls.lock();
+
+        if self.save_calls() == 0 {
+            None
+        } else {
+            Some(last_calls.iter().copied().collect())
+        }
     }
 
     // Alloc
@@ -453,7 +552,15 @@ impl Process {
 
     /// Returns `true` if the process should stop waiting and be rescheduled as runnable.
     pub fn send_from_other(&self, data: Term) -> AllocResult<bool> {
-        match self.heap.try_lock() {
+        // With `message_queue_data` `off_heap`, messages are always kept in heap fragments, so
+        // that sending doesn't contend for or grow the heap
+        let option_destination_heap = if self.are_flags_set(ProcessFlags::OffHeapMessageQueue) {
+            None
+        } else {
+            self.heap.try_lock()
+        };
+
+        match option_destination_heap {
             Some(ref mut destination_heap) => match data.clone_to_heap(destination_heap) {
                 Ok(destination_data) => {
                     self.send_message(Message::Process(message::Process {
@@ -491,6 +598,110 @@ impl Process {
         self.mailbox.lock().borrow_mut().push(message)
     }
 
+    // Receive
+
+    /// Marks the current end of the mailbox right after making `reference`, such as the reply
+    /// reference of a `gen_server:call`.  No other process has the reference yet, so messages
+    /// before the mark can't contain it and `receive_start(Some(&reference))` skips them.
+    pub fn receive_mark(&self, reference: Reference) {
+        self.mailbox.lock().borrow_mut().recv_mark(reference)
+    }
+
+    /// Starts a receive, which looks at the messages in order with `receive_next`.  Messages that
+    /// fail to match are not looked at again when the receive continues after waiting.
+    ///
+    /// With `marked`, the messages before the last `receive_mark` are skipped if it was for that
+    /// reference, which is only correct if every clause matches the reference.
+    pub fn receive_start(&self, marked: Option<&Reference>) {
+        self.mailbox.lock().borrow_mut().recv_start(marked)
+    }
+
+    /// Looks at the next message of the current receive.
+    ///
+    /// On `Receive::Wait`, the process has already been put in the waiting status while the
+    /// mailbox was still locked, so that a message sent after looking wakes the process.
+    pub fn receive_next(&self) -> Receive {
+        let mailbox_guard = self.mailbox.lock();
+        let next = mailbox_guard.borrow_mut().recv_next();
+
+        if next == Receive::Wait {
+            self.wait();
+        }
+
+        next
+    }
+
+    /// The message matched by the current receive, which is the last one from `receive_next`
+    pub fn receive_last(&self) -> Term {
+        self.mailbox.lock().borrow().recv_last()
+    }
+
+    /// Whether the message matched by the current receive is in a heap fragment, which is freed
+    /// by `receive_done`, so terms matched out of it need to be copied to the heap first
+    pub fn receive_last_off_heap(&self) -> bool {
+        self.mailbox.lock().borrow().recv_last_off_heap()
+    }
+
+    /// Ends the current receive by removing the message it matched.
+    ///
+    /// Returns the timer of the receive's `after` timeout, which should be cancelled.
+    pub fn receive_done(&self) -> Option<Reference> {
+        self.mailbox.lock().borrow_mut().recv_finish(self)
+    }
+
+    /// Sets the timer whose expiry times out the current receive
+    pub fn receive_set_timer(&self, timer_reference: Reference) {
+        self.mailbox
+            .lock()
+            .borrow_mut()
+            .recv_set_timer(timer_reference)
+    }
+
+    /// Times out the current receive as soon as every message has been looked at, as for
+    /// `after 0`
+    pub fn receive_expire(&self) {
+        self.mailbox.lock().borrow_mut().recv_expire()
+    }
+
+    /// Times out the current receive if `timer_reference` is its timer.
+    ///
+    /// Returns `true` if the process should stop waiting and be rescheduled as runnable.
+    pub fn receive_expire_timer(&self, timer_reference: &Reference) -> bool {
+        if self
+            .mailbox
+            .lock()
+            .borrow_mut()
+            .recv_expire_timer(timer_reference)
+        {
+            let mut writable_status = self.status.write();
+
+            if *writable_status == Status::Waiting {
+                *writable_status = Status::Runnable;
+
+                true
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    /// Puts the process in the waiting status if it has no messages, as for `hibernate`, checking
+    /// while the mailbox is locked so that a message sent after checking wakes the process.
+    ///
+    /// Returns `true` if the process is waiting.
+    pub fn wait_for_message(&self) -> bool {
+        let mailbox_guard = self.mailbox.lock();
+        let empty = mailbox_guard.borrow().len() == 0;
+
+        if empty {
+            self.wait();
+        }
+
+        empty
+    }
+
     // Terms
 
     pub fn binary_from_bytes(&self, bytes: &[u8]) -> AllocResult<Term> {
@@ -567,6 +778,24 @@ impl Process {
             .map(|pid| pid.into())
     }
 
+    pub fn external_port(&self, node: Arc<Node>, port: Port) -> AllocResult<Term> {
+        self.acquire_heap()
+            .external_port(node, port)
+            .map(|port| port.into())
+    }
+
+    pub fn external_reference(
+        &self,
+        node: Arc<Node>,
+        scheduler_id: scheduler::ID,
+        number: ReferenceNumber,
+        extra_ids: &[u32],
+    ) -> AllocResult<Term> {
+        self.acquire_heap()
+            .external_reference(node, scheduler_id, number, extra_ids)
+            .map(|reference| reference.into())
+    }
+
     pub fn float(&self, f: f64) -> AllocResult<Term> {
         self.acquire_heap().float(f).map(|f| f.into())
     }
@@ -848,9 +1077,11 @@ impl Process {
         if self.is_gc_delayed() || self.is_gc_disabled() {
             return false;
         }
-        // Check if young generation requires collection
+        // Check if young generation requires collection.  Like the BEAM, binaries only trigger a
+        // collection once they exceed `min_bin_vheap_size`, even when the young generation is
+        // smaller.
         let heap = self.heap.lock();
-        heap.should_collect(self.gc_threshold)
+        heap.should_collect(self.gc_threshold, self.min_bin_vheap_size())
     }
 
     #[inline(always)]
@@ -878,6 +1109,23 @@ impl Process {
         self.flags.are_set(ProcessFlags::NeedFullSweep)
     }
 
+    /// Reports the heap growing to `heap_size` words, past `max_heap_size`, with an error if
+    /// `ProcessFlags::MaxHeapSizeErrorLogger` is set.
+    ///
+    /// Returns whether the process should be killed instead of letting the heap grow.
+    pub(super) fn max_heap_size_exceeded(&self, heap_size: usize) -> bool {
+        if self.are_flags_set(ProcessFlags::MaxHeapSizeErrorLogger) {
+            log::error!(
+                "Process {} exceeded its max_heap_size of {} words with a heap of {} words",
+                self.pid(),
+                self.max_heap_size(),
+                heap_size
+            );
+        }
+
+        self.are_flags_set(ProcessFlags::MaxHeapSizeKill)
+    }
+
     /// Inserts roots from the process into the given root set.
     /// This includes all process dictionary entries.
     #[inline]
@@ -901,12 +1149,20 @@ impl Process {
     #[inline]
     pub fn garbage_collect(&self, need: usize, roots: &mut [Term]) -> Result<usize, GcError> {
         let mut heap = self.heap.lock();
+        let used_before = heap.generations_used() + self.off_heap_size();
         // The roots passed in here are pointers to the native stack/registers, all other roots
         // we are able to pick up from the current process context
         let mut rootset = RootSet::new(roots);
         self.base_root_set(&mut rootset);
         // Initialize the collector with the given root set
-        heap.garbage_collect(self, need, rootset)
+        let result = heap.garbage_collect(self, need, rootset);
+
+        if result.is_ok() {
+            let used_after = heap.generations_used() + self.off_heap_size();
+            gc::record_collection(used_before.saturating_sub(used_after));
+        }
+
+        result
     }
 
     /// Cleans up any linked HeapFragments which should have had any live
@@ -965,6 +1221,14 @@ impl Process {
         MAX_REDUCTIONS_PER_RUN <= self.run_reductions.load(Ordering::SeqCst)
     }
 
+    /// Uses up the rest of the reductions of the current `run`, so that `call_code` stops and the
+    /// process goes to the back of its run queue, as for `erlang:yield/0`.
+    pub fn yield_run(&self) {
+        if !self.is_reduced() {
+            self.run_reductions.store(MAX_REDUCTIONS_PER_RUN, Ordering::SeqCst);
+        }
+    }
+
     /// Run process until `reductions` exceeds `MAX_REDUCTIONS` or process exits
     pub fn run(arc_process: &Arc<Process>) -> code::Result {
         arc_process.start_running();
@@ -991,10 +1255,10 @@ impl Process {
     }
 
     fn stop_running(&self) {
-        self.total_reductions.fetch_add(
-            self.run_reductions.load(Ordering::SeqCst) as u64,
-            Ordering::SeqCst,
-        );
+        let run_reductions = self.run_reductions.load(Ordering::SeqCst) as u64;
+        self.total_reductions
+            .fetch_add(run_reductions, Ordering::SeqCst);
+        TOTAL_REDUCTIONS.fetch_add(run_reductions, Ordering::SeqCst);
         self.run_reductions.store(0, Ordering::SeqCst);
 
         let mut writable_status = self.status.write();
@@ -1139,6 +1403,72 @@ impl Process {
     pub fn stacktrace(&self) -> stack::Trace {
         self.code_stack.lock().trace()
     }
+
+    // Info
+
+    /// The size of the heap in words, excluding heap fragments
+    pub fn heap_size(&self) -> usize {
+        self.heap.lock().heap_size()
+    }
+
+    /// The size of the heap and all heap fragments in words
+    pub fn total_heap_size(&self) -> usize {
+        self.heap_size() + self.off_heap_size()
+    }
+
+    pub fn min_heap_size(&self) -> usize {
+        self.min_heap_size.load(Ordering::SeqCst)
+    }
+
+    /// Sets the size the heap won't shrink below in words, returning the old size
+    pub fn set_min_heap_size(&self, min_heap_size: usize) -> usize {
+        self.min_heap_size.swap(min_heap_size, Ordering::SeqCst)
+    }
+
+    pub fn min_bin_vheap_size(&self) -> usize {
+        self.min_vheap_size.load(Ordering::SeqCst)
+    }
+
+    /// Sets the minimum binary virtual heap size in words, returning the old size
+    pub fn set_min_bin_vheap_size(&self, min_bin_vheap_size: usize) -> usize {
+        self.min_vheap_size
+            .swap(min_bin_vheap_size, Ordering::SeqCst)
+    }
+
+    /// The maximum size of the heap in words, or `0` if there is no maximum
+    pub fn max_heap_size(&self) -> usize {
+        self.max_heap_size.load(Ordering::SeqCst)
+    }
+
+    /// Sets the maximum size of the heap in words, or `0` for no maximum, returning the old size.
+    ///
+    /// What happens when the heap would exceed the size is controlled by the
+    /// `ProcessFlags::MaxHeapSizeKill` and `ProcessFlags::MaxHeapSizeErrorLogger` flags.
+    pub fn set_max_heap_size(&self, max_heap_size: usize) -> usize {
+        self.max_heap_size.swap(max_heap_size, Ordering::SeqCst)
+    }
+
+    /// The number of minor collections before a full sweep
+    pub fn fullsweep_after(&self) -> usize {
+        self.max_gen_gcs.load(Ordering::SeqCst)
+    }
+
+    /// Sets the number of minor collections before a full sweep, returning the old number
+    pub fn set_fullsweep_after(&self, fullsweep_after: usize) -> usize {
+        self.max_gen_gcs.swap(fullsweep_after, Ordering::SeqCst)
+    }
+
+    /// Copies the key/value pairs of the process dictionary.
+    ///
+    /// The terms are only valid for as long as the heap is not garbage collected, so another
+    /// process reading them must hold `acquire_heap` while it uses them.
+    pub fn dictionary_entries(&self) -> Vec<(Term, Term)> {
+        self.dictionary
+            .lock()
+            .iter()
+            .map(|(key, value)| (*key, *value))
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -1166,6 +1496,12 @@ impl Process {
         let heap = self.heap.lock();
         heap.heap().old_generation().active()
     }
+
+    /// The number of minor collections since the last full sweep
+    #[inline]
+    fn minor_collections(&self) -> usize {
+        self.heap.lock().gen_gc_count
+    }
 }
 
 impl fmt::Debug for Process {
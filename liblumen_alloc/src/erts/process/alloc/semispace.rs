@@ -1,4 +1,5 @@
 use core::alloc::Layout;
+use core::cmp;
 use core::mem;
 use core::ptr::NonNull;
 
@@ -84,8 +85,10 @@ where
 
     // Check if either the young generation, or the virtual heap, require
     // collection by comparing usage against a percentage threshold
+    //
+    // The virtual heap is treated as being at least `min_virtual_size` bytes
     #[inline]
-    pub fn should_collect(&self, gc_threshold: f64) -> bool {
+    pub fn should_collect(&self, gc_threshold: f64, min_virtual_size: usize) -> bool {
         // First, check young generation
         let used = self.young.heap_used();
         let unused = self.young.heap_available();
@@ -93,16 +96,11 @@ where
         if used >= threshold {
             return true;
         }
-        // Next, check virtual heap
+        // Next, check virtual heap, which is always collected once it is exceeded
         let used = self.young.virtual_heap_used();
-        let unused = self.young.virtual_heap_unused();
-        if unused > 0 {
-            let threshold = ((used + unused) as f64 * gc_threshold).ceil() as usize;
-            used >= threshold
-        } else {
-            // We've exceeded the virtual heap size
-            true
-        }
+        let size = cmp::max(self.young.virtual_size(), min_virtual_size);
+        let threshold = (size as f64 * gc_threshold).ceil() as usize;
+        used >= threshold
     }
 }
 impl<A, B> GenerationalHeap for SemispaceHeap<A, B>
@@ -306,6 +306,34 @@ pub trait TermAlloc: Heap {
         Ok(unsafe { Boxed::new_unchecked(boxed) })
     }
 
+    fn external_port(&mut self, arc_node: Arc<Node>, port: Port) -> AllocResult<Boxed<ExternalPort>>
+    where
+        Self: Sized,
+    {
+        let port = ExternalPort::new(arc_node, port).clone_to_heap(self)?;
+        let boxed: *mut ExternalPort = port.dyn_cast();
+
+        Ok(unsafe { Boxed::new_unchecked(boxed) })
+    }
+
+    fn external_reference(
+        &mut self,
+        arc_node: Arc<Node>,
+        scheduler_id: scheduler::ID,
+        number: ReferenceNumber,
+        extra_ids: &[u32],
+    ) -> AllocResult<Boxed<ExternalReference>>
+    where
+        Self: Sized,
+    {
+        let reference =
+            ExternalReference::with_extra_ids(arc_node, scheduler_id, number, extra_ids)
+                .clone_to_heap(self)?;
+        let boxed: *mut ExternalReference = reference.dyn_cast();
+
+        Ok(unsafe { Boxed::new_unchecked(boxed) })
+    }
+
     /// Constructs a heap-allocated binary from the given byte slice, and associated with the given
     /// process
     #[inline]
@@ -62,6 +62,13 @@ impl Debug for Stack {
 
 pub struct Trace(Vec<Arc<ModuleFunctionArity>>);
 
+impl Trace {
+    /// Iterates from the current function to the outermost caller
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<ModuleFunctionArity>> {
+        self.0.iter()
+    }
+}
+
 impl Debug for Trace {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for module_function_arity in self.0.iter() {
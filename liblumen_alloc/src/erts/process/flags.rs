@@ -26,6 +26,18 @@ impl ProcessFlags {
     /// This flag indicates the processes linked to this process should send exit messages instead
     /// of causing this process to exit when they exit
     pub const TrapExit: Self = Self(1 << 6);
+    /// This flag indicates the process should be killed when its heap would exceed its
+    /// `max_heap_size`
+    pub const MaxHeapSizeKill: Self = Self(1 << 7);
+    /// This flag indicates an error should be logged when the heap would exceed its
+    /// `max_heap_size`
+    pub const MaxHeapSizeErrorLogger: Self = Self(1 << 8);
+    /// This flag indicates messages should be kept in heap fragments instead of being copied to
+    /// the heap
+    pub const OffHeapMessageQueue: Self = Self(1 << 9);
+    /// This flag indicates the messages, dictionary and stack of the process should be hidden
+    /// from `process_info` and tracing
+    pub const Sensitive: Self = Self(1 << 10);
 
     pub fn are_set(&self, flags: ProcessFlags) -> bool {
         (*self & flags) == flags
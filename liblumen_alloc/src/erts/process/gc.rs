@@ -17,10 +17,17 @@ pub use self::rootset::RootSet;
 pub use self::sweep::{Sweep, Sweepable, Sweeper};
 pub use self::young_heap::YoungHeap;
 
+use core::sync::atomic::{AtomicU64, Ordering};
+
 use super::alloc::SemispaceHeap;
 use crate::erts::exception;
 use thiserror::Error;
 
+/// The number of garbage collections of all processes
+static COLLECTIONS: AtomicU64 = AtomicU64::new(0);
+/// The number of words reclaimed by the garbage collections of all processes
+static WORDS_RECLAIMED: AtomicU64 = AtomicU64::new(0);
+
 /// Represents the types of errors that can occur during garbage collection.
 ///
 /// See the documentation for each variant to get general advice for how to
@@ -77,3 +84,18 @@ pub fn estimate_cost(moved_live_words: usize, resize_moved_words: usize) -> usiz
         reds
     }
 }
+
+/// Counts a garbage collection that reclaimed `words_reclaimed`
+pub fn record_collection(words_reclaimed: usize) {
+    COLLECTIONS.fetch_add(1, Ordering::Relaxed);
+    WORDS_RECLAIMED.fetch_add(words_reclaimed as u64, Ordering::Relaxed);
+}
+
+/// The number of garbage collections of all processes and the words they reclaimed, as used by
+/// `erlang:statistics(garbage_collection)`
+pub fn statistics() -> (u64, u64) {
+    (
+        COLLECTIONS.load(Ordering::Relaxed),
+        WORDS_RECLAIMED.load(Ordering::Relaxed),
+    )
+}
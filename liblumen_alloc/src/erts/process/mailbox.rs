@@ -7,45 +7,127 @@ use crate::borrow::CloneToProcess;
 use crate::erts::exception::AllocResult;
 use crate::erts::message::{self, Message};
 use crate::erts::process::Process;
-use crate::erts::term::prelude::Term;
+use crate::erts::term::prelude::{Reference, Term};
+
+/// What the current receive should do after looking for the next message
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Receive {
+    /// The next message the receive has not looked at yet.  It may be in a heap fragment, so
+    /// anything matched out of it needs to be copied to the process heap before the receive is
+    /// done.
+    Message(Term),
+    /// Every message has been looked at, so the process should wait for more
+    Wait,
+    /// Every message has been looked at and the receive's `after` timeout expired, so the receive
+    /// has ended without removing a message
+    Timeout,
+}
 
 #[derive(Debug)]
 pub struct Mailbox {
     messages: VecDeque<Message>,
-    seen: isize,
 
-    cursor: usize,
+    /// The index of the next message the current receive looks at.  Messages before it have
+    /// already failed to match, so the receive doesn't look at them again after waiting.
+    save: usize,
+    /// The reference made at the last `recv_mark` and the length of the mailbox then.  Messages
+    /// before the mark can't contain the reference, so a receive for it starts after them.
+    mark: Option<(Reference, usize)>,
+    /// The timer of the current receive's `after` timeout
+    timer: Option<Reference>,
+    timed_out: bool,
 }
 
 impl Mailbox {
-    // Start receive implementation for the eir interpreter
-    pub fn recv_start(&self) {
-        debug_assert!(self.cursor == 0);
-    }
-    /// Important to remember that this might return a term in a heap
-    /// fragment, and that it needs to be copied over to the process
-    /// heap before the message is removed from the mailbox.
-    pub fn recv_peek(&self) -> Option<Term> {
-        match self.messages.get(self.cursor) {
-            None => None,
-            Some(Message::Process(message::Process { data })) => Some(*data),
-            Some(Message::HeapFragment(message::HeapFragment { data, .. })) => Some(*data),
+    /// Marks the current end of the mailbox as where messages containing `reference`, which
+    /// was just made, can start.
+    pub fn recv_mark(&mut self, reference: Reference) {
+        self.mark = Some((reference, self.len()));
+    }
+
+    /// Starts a receive.  With `marked`, the messages before the mark are skipped if the last
+    /// `recv_mark` was for that reference, which is only correct if every clause matches it.
+    pub fn recv_start(&mut self, marked: Option<&Reference>) {
+        self.save = match (marked, &self.mark) {
+            (Some(reference), Some((mark_reference, mark))) if reference == mark_reference => {
+                (*mark).min(self.len())
+            }
+            _ => 0,
+        };
+        self.timer = None;
+        self.timed_out = false;
+    }
+
+    /// Looks at the message at the save pointer and moves the save pointer past it.
+    pub fn recv_next(&mut self) -> Receive {
+        match self.messages.get(self.save) {
+            Some(message) => {
+                self.save += 1;
+
+                Receive::Message(*message.data())
+            }
+            None if self.timed_out => {
+                self.recv_timeout();
+
+                Receive::Timeout
+            }
+            None => Receive::Wait,
         }
     }
+
+    /// The message matched by the current receive, which is the last one returned by
+    /// `recv_next`
+    pub fn recv_last(&self) -> Term {
+        *self.messages[self.save - 1].data()
+    }
+
     pub fn recv_last_off_heap(&self) -> bool {
-        match &self.messages[self.cursor - 1] {
+        match &self.messages[self.save - 1] {
             Message::Process(_) => false,
             Message::HeapFragment(_) => true,
         }
     }
-    pub fn recv_increment(&mut self) {
-        self.cursor += 1;
+
+    /// Ends the current receive by removing the message it matched.
+    ///
+    /// Returns the timer of the receive's `after` timeout, which should be cancelled.
+    pub fn recv_finish(&mut self, proc: &Process) -> Option<Reference> {
+        self.remove(self.save - 1, proc);
+
+        self.recv_timeout()
     }
-    pub fn recv_finish(&mut self, proc: &Process) {
-        self.remove(self.cursor - 1, proc);
-        self.cursor = 0;
+
+    /// Ends a receive that timed out without removing a message
+    pub fn recv_timeout(&mut self) -> Option<Reference> {
+        self.save = 0;
+        self.mark = None;
+        self.timed_out = false;
+
+        self.timer.take()
+    }
+
+    /// Sets the timer of the current receive's `after` timeout
+    pub fn recv_set_timer(&mut self, timer_reference: Reference) {
+        self.timer = Some(timer_reference);
+    }
+
+    /// Times out the current receive once every message has been looked at, as for `after 0`
+    pub fn recv_expire(&mut self) {
+        self.timed_out = true;
+    }
+
+    /// Times out the current receive if `timer_reference` is its timer.
+    ///
+    /// Returns `false` for the timer of a receive that already ended.
+    pub fn recv_expire_timer(&mut self, timer_reference: &Reference) -> bool {
+        if self.timer.as_ref() == Some(timer_reference) {
+            self.recv_expire();
+
+            true
+        } else {
+            false
+        }
     }
-    // End receive implementation for the eir interpreter
 
     pub fn flush<F>(&mut self, predicate: F, process: &Process) -> bool
     where
@@ -69,15 +151,11 @@ impl Mailbox {
         self.messages.len()
     }
 
-    pub fn mark_seen(&mut self) {
-        self.seen = (self.len() as isize) - 1;
-    }
-
     /// Pops the `message` out of the mailbox from the front of the queue.
     pub fn pop(&mut self) -> Option<Message> {
         match self.messages.pop_front() {
             option_message @ Some(_) => {
-                self.decrement_seen();
+                self.removed(0);
 
                 option_message
             }
@@ -95,7 +173,7 @@ impl Mailbox {
     pub fn receive(&mut self, process: &Process) -> Option<AllocResult<Term>> {
         self.messages.pop_front().map(|message| match message {
             Message::Process(message::Process { data }) => {
-                self.decrement_seen();
+                self.removed(0);
 
                 Ok(data)
             }
@@ -114,7 +192,7 @@ impl Mailbox {
                             .expect("HeapFragment was not in process's off_heap");
                     }
 
-                    self.decrement_seen();
+                    self.removed(0);
 
                     Ok(heap_data)
                 }
@@ -145,24 +223,22 @@ impl Mailbox {
             }
         }
 
-        if (index as isize) <= self.seen {
-            self.seen -= 1;
-        }
-    }
-
-    pub fn seen(&self) -> isize {
-        self.seen
-    }
-
-    pub fn unmark_seen(&mut self) {
-        self.seen = -1;
+        self.removed(index);
     }
 
     // Private
 
-    fn decrement_seen(&mut self) {
-        if 0 <= self.seen {
-            self.seen -= 1;
+    /// Keeps the save pointer and mark on the same messages after the message at `index` is
+    /// removed
+    fn removed(&mut self, index: usize) {
+        if index < self.save {
+            self.save -= 1;
+        }
+
+        if let Some((_, mark)) = self.mark.as_mut() {
+            if index < *mark {
+                *mark -= 1;
+            }
         }
     }
 }
@@ -171,8 +247,10 @@ impl Default for Mailbox {
     fn default() -> Mailbox {
         Mailbox {
             messages: Default::default(),
-            seen: -1,
-            cursor: 0,
+            save: 0,
+            mark: None,
+            timer: None,
+            timed_out: false,
         }
     }
 }
@@ -34,3 +34,14 @@ impl TryFrom<Term> for Priority {
         }
     }
 }
+
+impl From<Priority> for Term {
+    fn from(priority: Priority) -> Self {
+        match priority {
+            Priority::Low => atom!("low"),
+            Priority::Normal => atom!("normal"),
+            Priority::High => atom!("high"),
+            Priority::Max => atom!("max"),
+        }
+    }
+}
@@ -51,6 +51,127 @@ mod traps_exit {
     }
 }
 
+mod last_calls {
+    use super::*;
+
+    #[test]
+    fn process_returns_none_by_default() {
+        let process = process();
+
+        process.save_call(module_function_arity("called", 0));
+
+        assert_eq!(process.last_calls(), None);
+    }
+
+    #[test]
+    fn returns_last_save_calls_calls_oldest_first() {
+        let process = process();
+
+        assert_eq!(process.set_save_calls(2), 0);
+
+        process.save_call(module_function_arity("first", 0));
+        process.save_call(module_function_arity("second", 1));
+        process.save_call(module_function_arity("third", 2));
+
+        assert_eq!(
+            process.last_calls(),
+            Some(vec![
+                module_function_arity("second", 1),
+                module_function_arity("third", 2)
+            ])
+        );
+    }
+
+    #[test]
+    fn set_save_calls_discards_saved_calls() {
+        let process = process();
+
+        process.set_save_calls(2);
+        process.save_call(module_function_arity("first", 0));
+        process.set_save_calls(3);
+
+        assert_eq!(process.last_calls(), Some(vec![]));
+    }
+
+    fn module_function_arity(function: &str, arity: u8) -> ModuleFunctionArity {
+        ModuleFunctionArity {
+            module: atom_from_str!("module"),
+            function: atom_from_str!(function),
+            arity,
+        }
+    }
+}
+
+mod garbage_collect {
+    use super::*;
+
+    use core::mem;
+
+    use crate::erts::process::gc::RootSet;
+    use crate::erts::term::prelude::*;
+
+    #[test]
+    fn with_default_fullsweep_after_does_minor_collections() {
+        let process = process();
+
+        garbage_collect(&process);
+        garbage_collect(&process);
+
+        assert_eq!(process.minor_collections(), 2);
+    }
+
+    #[test]
+    fn with_fullsweep_after_does_full_sweep_after_that_many_minor_collections() {
+        let process = process();
+        process.set_fullsweep_after(1);
+
+        garbage_collect(&process);
+
+        assert_eq!(process.minor_collections(), 1);
+
+        garbage_collect(&process);
+
+        assert_eq!(process.minor_collections(), 0);
+    }
+
+    #[test]
+    fn with_default_min_bin_vheap_size_binaries_past_heap_size_trigger_collection() {
+        let process = process();
+
+        process.binary_from_bytes(&vec![0; heap_bytes()]).unwrap();
+
+        assert!(process.should_collect());
+    }
+
+    #[test]
+    fn with_min_bin_vheap_size_binaries_trigger_collection_past_min_bin_vheap_size() {
+        let process = process();
+        process.set_min_bin_vheap_size(4 * alloc::default_heap_size());
+
+        process.binary_from_bytes(&vec![0; heap_bytes()]).unwrap();
+
+        assert!(!process.should_collect());
+
+        process
+            .binary_from_bytes(&vec![0; 2 * heap_bytes()])
+            .unwrap();
+
+        assert!(process.should_collect());
+    }
+
+    fn garbage_collect(process: &Process) {
+        let mut heap = process.acquire_heap();
+
+        heap.garbage_collect(process, 0, RootSet::new(&mut []))
+            .unwrap();
+    }
+
+    /// The size of the default heap in bytes, which is also the size of its binary virtual heap
+    fn heap_bytes() -> usize {
+        alloc::default_heap_size() * mem::size_of::<Term>()
+    }
+}
+
 mod integer {
     use super::*;
 
@@ -68,6 +189,146 @@ mod integer {
     }
 }
 
+mod receive {
+    use super::*;
+
+    use crate::erts::term::prelude::*;
+
+    #[test]
+    fn continues_from_save_pointer_after_waiting() {
+        let process = process();
+        let first = process.integer(1).unwrap();
+        let second = process.integer(2).unwrap();
+        let third = process.integer(3).unwrap();
+        process.send_from_self(first);
+        process.send_from_self(second);
+
+        process.receive_start(None);
+
+        assert_eq!(process.receive_next(), Receive::Message(first));
+        assert_eq!(process.receive_next(), Receive::Message(second));
+        assert_eq!(process.receive_next(), Receive::Wait);
+
+        process.send_from_self(third);
+
+        assert_eq!(process.receive_next(), Receive::Message(third));
+        assert_eq!(process.receive_last(), third);
+        assert_eq!(process.receive_done(), None);
+        assert_eq!(process.mailbox.lock().borrow().len(), 2);
+
+        process.receive_start(None);
+
+        assert_eq!(process.receive_next(), Receive::Message(first));
+    }
+
+    #[test]
+    fn with_marked_skips_messages_before_mark() {
+        let process = process();
+        let before = process.integer(1).unwrap();
+        let after = process.integer(2).unwrap();
+        let reference = Reference::new(process.scheduler_id().unwrap(), 1);
+        process.send_from_self(before);
+        process.receive_mark(reference);
+        process.send_from_self(after);
+
+        process.receive_start(Some(&reference));
+
+        assert_eq!(process.receive_next(), Receive::Message(after));
+        assert_eq!(process.receive_done(), None);
+
+        process.receive_start(None);
+
+        assert_eq!(process.receive_next(), Receive::Message(before));
+    }
+
+    #[test]
+    fn mark_stays_on_message_when_earlier_message_is_removed() {
+        let process = process();
+        let first = process.integer(1).unwrap();
+        let second = process.integer(2).unwrap();
+        let third = process.integer(3).unwrap();
+        let reference = Reference::new(process.scheduler_id().unwrap(), 1);
+        process.send_from_self(first);
+        process.send_from_self(second);
+        process.receive_mark(reference);
+        process.send_from_self(third);
+
+        process.mailbox.lock().borrow_mut().remove(0, &process);
+        process.receive_start(Some(&reference));
+
+        assert_eq!(process.receive_next(), Receive::Message(third));
+    }
+
+    #[test]
+    fn with_marked_for_other_reference_looks_at_every_message() {
+        let process = process();
+        let before = process.integer(1).unwrap();
+        let after = process.integer(2).unwrap();
+        let scheduler_id = process.scheduler_id().unwrap();
+        let reference = Reference::new(scheduler_id, 1);
+        let other_reference = Reference::new(scheduler_id, 2);
+        process.send_from_self(before);
+        process.receive_mark(reference);
+        process.send_from_self(after);
+        process.receive_mark(other_reference);
+
+        process.receive_start(Some(&reference));
+
+        assert_eq!(process.receive_next(), Receive::Message(before));
+    }
+
+    #[test]
+    fn with_expire_times_out_after_looking_at_every_message() {
+        let process = process();
+        let message = process.integer(1).unwrap();
+        process.send_from_self(message);
+
+        process.receive_start(None);
+        process.receive_expire();
+
+        assert_eq!(process.receive_next(), Receive::Message(message));
+        assert_eq!(process.receive_next(), Receive::Timeout);
+        assert_eq!(process.mailbox.lock().borrow().len(), 1);
+
+        process.receive_start(None);
+
+        assert_eq!(process.receive_next(), Receive::Message(message));
+        assert_eq!(process.receive_next(), Receive::Wait);
+    }
+
+    #[test]
+    fn expire_timer_only_times_out_with_timer_of_current_receive() {
+        let process = process();
+        let scheduler_id = process.scheduler_id().unwrap();
+        let timer_reference = Reference::new(scheduler_id, 1);
+        let other_timer_reference = Reference::new(scheduler_id, 2);
+
+        process.receive_start(None);
+        process.receive_set_timer(timer_reference);
+
+        assert!(!process.receive_expire_timer(&other_timer_reference));
+        assert_eq!(process.receive_next(), Receive::Wait);
+
+        process.receive_expire_timer(&timer_reference);
+
+        assert_eq!(process.receive_next(), Receive::Timeout);
+    }
+
+    #[test]
+    fn done_returns_timer_to_cancel() {
+        let process = process();
+        let message = process.integer(1).unwrap();
+        let timer_reference = Reference::new(process.scheduler_id().unwrap(), 1);
+        process.send_from_self(message);
+
+        process.receive_start(None);
+        process.receive_set_timer(timer_reference);
+
+        assert_eq!(process.receive_next(), Receive::Message(message));
+        assert_eq!(process.receive_done(), Some(timer_reference));
+    }
+}
+
 pub(super) fn process() -> Process {
     let init = atom_from_str!("init");
     let initial_module_function_arity = Arc::new(ModuleFunctionArity {
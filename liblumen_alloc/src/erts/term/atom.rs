@@ -51,6 +51,11 @@ impl Atom {
         ATOMS.read().get_name(self.0).unwrap()
     }
 
+    /// The number of atoms in the atom table
+    pub fn count() -> usize {
+        ATOMS.read().names.len()
+    }
+
     /// Returns true if this atom is a boolean value
     #[inline]
     pub fn is_boolean(&self) -> bool {
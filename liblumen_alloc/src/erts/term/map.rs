@@ -105,6 +105,15 @@ impl Map {
         self.value.keys().into_iter().copied().collect()
     }
 
+    /// The keys in map key order, which unlike `keys` does not depend on the hashing of the keys
+    pub fn sorted_keys(&self) -> Vec<Term> {
+        let mut key_vec: Vec<Term> = Vec::new();
+        key_vec.extend(self.value.keys());
+        key_vec.sort_unstable_by(|key1, key2| key_cmp(*key1, *key2));
+
+        key_vec
+    }
+
     pub fn values(&self) -> Vec<Term> {
         self.value.values().into_iter().copied().collect()
     }
@@ -150,15 +159,55 @@ impl Map {
     pub fn iter_mut(&mut self) -> hashbrown::hash_map::IterMut<Term, Term> {
         self.value.iter_mut()
     }
+}
 
-    // Private
-
-    fn sorted_keys(&self) -> Vec<Term> {
-        let mut key_vec: Vec<Term> = Vec::new();
-        key_vec.extend(self.value.keys());
-        key_vec.sort_unstable_by(|key1, key2| key1.cmp(&key2));
+/// Map key order, which is term order except that integers are less than the floats that
+/// compare equal to them, so that distinct keys never compare equal
+fn key_cmp(key1: Term, key2: Term) -> cmp::Ordering {
+    key1.cmp(&key2).then_with(|| exact_cmp(key1, key2))
+}
 
-        key_vec
+/// Orders terms that are equal in term order, which can only differ in the types of the
+/// numbers they contain
+fn exact_cmp(term1: Term, term2: Term) -> cmp::Ordering {
+    use cmp::Ordering::*;
+
+    match (term1.decode().unwrap(), term2.decode().unwrap()) {
+        (TypedTerm::Float(_), TypedTerm::Float(_)) => Equal,
+        (TypedTerm::Float(_), _) => Greater,
+        (_, TypedTerm::Float(_)) => Less,
+        (TypedTerm::Tuple(tuple1), TypedTerm::Tuple(tuple2)) => tuple1
+            .iter()
+            .zip(tuple2.iter())
+            .map(|(element1, element2)| exact_cmp(*element1, *element2))
+            .find(|ordering| *ordering != Equal)
+            .unwrap_or(Equal),
+        (TypedTerm::List(cons1), TypedTerm::List(cons2)) => cons1
+            .into_iter()
+            .zip(cons2.into_iter())
+            .map(|(result1, result2)| {
+                let element1 = result1.unwrap_or_else(|improper_list| improper_list.tail);
+                let element2 = result2.unwrap_or_else(|improper_list| improper_list.tail);
+
+                exact_cmp(element1, element2)
+            })
+            .find(|ordering| *ordering != Equal)
+            .unwrap_or(Equal),
+        (TypedTerm::Map(map1), TypedTerm::Map(map2)) => {
+            let key_vec1 = map1.sorted_keys();
+            let key_vec2 = map2.sorted_keys();
+
+            key_vec1
+                .iter()
+                .zip(key_vec2.iter())
+                .map(|(key1, key2)| exact_cmp(*key1, *key2))
+                .chain(key_vec1.iter().zip(key_vec2.iter()).map(|(key1, key2)| {
+                    exact_cmp(map1.get(*key1).unwrap(), map2.get(*key2).unwrap())
+                }))
+                .find(|ordering| *ordering != Equal)
+                .unwrap_or(Equal)
+        }
+        _ => Equal,
     }
 }
 
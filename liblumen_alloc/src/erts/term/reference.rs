@@ -153,15 +153,67 @@ pub struct ExternalReference {
     header: Header<ExternalReference>,
     arc_node: Arc<Node>,
     reference: Reference,
+    /// The ID words after the first 3, of which only `extra_ids_len` are used
+    extra_ids: [u32; ExternalReference::EXTRA_IDS_MAX],
+    extra_ids_len: usize,
 }
 impl_static_header!(ExternalReference, Term::HEADER_EXTERN_REF);
+impl ExternalReference {
+    /// Other nodes make references of up to 5 ID words, such as their pid-refs, while the first 3
+    /// are the scheduler ID and number
+    pub const EXTRA_IDS_MAX: usize = 2;
+
+    pub fn new(arc_node: Arc<Node>, scheduler_id: scheduler::ID, number: ReferenceNumber) -> Self {
+        Self::with_extra_ids(arc_node, scheduler_id, number, &[])
+    }
+
+    /// Panics if there are more than `EXTRA_IDS_MAX` `extra_ids`
+    pub fn with_extra_ids(
+        arc_node: Arc<Node>,
+        scheduler_id: scheduler::ID,
+        number: ReferenceNumber,
+        extra_ids: &[u32],
+    ) -> Self {
+        let mut extra_ids_array = [0; Self::EXTRA_IDS_MAX];
+        extra_ids_array[..extra_ids.len()].copy_from_slice(extra_ids);
+
+        Self {
+            header: Default::default(),
+            arc_node,
+            reference: Reference::new(scheduler_id, number),
+            extra_ids: extra_ids_array,
+            extra_ids_len: extra_ids.len(),
+        }
+    }
+
+    pub fn arc_node(&self) -> Arc<Node> {
+        self.arc_node.clone()
+    }
+
+    pub fn scheduler_id(&self) -> scheduler::ID {
+        self.reference.scheduler_id()
+    }
+
+    pub fn number(&self) -> ReferenceNumber {
+        self.reference.number()
+    }
+
+    pub fn extra_ids(&self) -> &[u32] {
+        &self.extra_ids[..self.extra_ids_len]
+    }
+}
 impl CloneToProcess for ExternalReference {
-    #[inline]
-    fn clone_to_heap<A>(&self, _heap: &mut A) -> AllocResult<Term>
+    fn clone_to_heap<A>(&self, heap: &mut A) -> AllocResult<Term>
     where
         A: ?Sized + TermAlloc,
     {
-        unimplemented!()
+        unsafe {
+            let layout = Layout::new::<Self>();
+            let ptr = heap.alloc_layout(layout)?.as_ptr() as *mut Self;
+            ptr.write(self.clone());
+
+            Ok(ptr.into())
+        }
     }
 
     fn size_in_words(&self) -> usize {
@@ -170,8 +222,20 @@ impl CloneToProcess for ExternalReference {
 }
 
 impl Display for ExternalReference {
-    fn fmt(&self, _f: &mut fmt::Formatter) -> fmt::Result {
-        unimplemented!()
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "#Reference<{}.{}.{}",
+            self.arc_node.id(),
+            self.reference.scheduler_id,
+            self.reference.number
+        )?;
+
+        for extra_id in self.extra_ids() {
+            write!(f, ".{}", extra_id)?;
+        }
+
+        write!(f, ">")
     }
 }
 
@@ -179,12 +243,15 @@ impl Hash for ExternalReference {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.arc_node.hash(state);
         self.reference.hash(state);
+        self.extra_ids().hash(state);
     }
 }
 
 impl PartialEq for ExternalReference {
     fn eq(&self, other: &ExternalReference) -> bool {
-        self.arc_node == other.arc_node && self.reference == other.reference
+        self.arc_node == other.arc_node
+            && self.reference == other.reference
+            && self.extra_ids() == other.extra_ids()
     }
 }
 impl PartialEq<Reference> for ExternalReference {
@@ -211,7 +278,10 @@ impl PartialEq<Boxed<Reference>> for ExternalReference {
 impl PartialOrd for ExternalReference {
     fn partial_cmp(&self, other: &ExternalReference) -> Option<Ordering> {
         match self.arc_node.partial_cmp(&other.arc_node) {
-            Some(Ordering::Equal) => self.reference.partial_cmp(&other.reference),
+            Some(Ordering::Equal) => match self.reference.partial_cmp(&other.reference) {
+                Some(Ordering::Equal) => self.extra_ids().partial_cmp(other.extra_ids()),
+                result => result,
+            },
             result => result,
         }
     }
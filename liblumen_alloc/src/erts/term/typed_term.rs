@@ -565,7 +565,16 @@ impl Ord for TypedTerm {
                 TypedTerm::Atom(rhs) => lhs.cmp(rhs),
                 _ => Less,
             },
-            TypedTerm::Port(lhs) => unimplemented!("Port {:?} cmp {:?}", lhs, other),
+            TypedTerm::Port(lhs) => match other {
+                TypedTerm::SmallInteger(_) => Greater,
+                TypedTerm::Float(_)
+                | TypedTerm::BigInteger(_)
+                | TypedTerm::Reference(_)
+                | TypedTerm::Closure(_) => Greater,
+                TypedTerm::Atom(_) => Greater,
+                TypedTerm::Port(rhs) => lhs.cmp(rhs),
+                _ => Less,
+            },
             TypedTerm::ExternalPort(lhs) => {
                 unimplemented!("ExternalPort {:?} cmp {:?}", lhs, other)
             }
@@ -16,6 +16,7 @@
 pub mod ast;
 pub mod error;
 pub mod format;
+pub mod printer;
 
 #[cfg(test)]
 mod test;
@@ -41,4 +42,9 @@ impl AST {
             module: ast::ModuleDecl { forms },
         })
     }
+
+    /// Prints this module as Erlang source code
+    pub fn to_erlang_source(&self) -> String {
+        printer::print(&self.module.forms)
+    }
 }
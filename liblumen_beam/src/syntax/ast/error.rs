@@ -41,6 +41,12 @@ pub enum FromBeamError {
     #[fail(display = "debug info is required but not present")]
     NoDebugInfo,
 
+    #[fail(display = "unsupported debug info backend: {}", _0)]
+    UnsupportedDebugInfo(String),
+
+    #[fail(display = "unable to translate elixir debug info: {}", _0)]
+    UnsupportedElixir(String),
+
     #[fail(display = "missing module attribute")]
     NoModuleAttribute,
 
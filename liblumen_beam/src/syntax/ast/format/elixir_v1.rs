@@ -0,0 +1,1522 @@
+//! Translation of the `elixir_v1` debug info format to Erlang's abstract format.
+//!
+//! Since Elixir 1.5, BEAM files produced by Elixir no longer contain an `"Abst"` chunk, instead
+//! the `"Dbgi"` chunk holds `{debug_info_v1, elixir_erl, {elixir_v1, Map, Specs}}`, where `Map`
+//! contains the expanded Elixir AST of each definition. On the BEAM, `elixir_erl:debug_info/4`
+//! produces the abstract format on demand; this module does the same for the subset of the
+//! expanded AST needed to compile ordinary modules. Macros are not translated, as they are never
+//! called at runtime.
+//!
+//! The result is a list of forms in the `raw_abstract_v1` format, so that it can be loaded by
+//! `AbstractCode` just like code compiled by `erlc`.
+//!
+//! # References
+//!
+//! * [`elixir_erl` and `elixir_erl_pass`](https://github.com/elixir-lang/elixir/tree/master/lib/elixir/src)
+use std::collections::HashMap;
+
+use num::bigint::BigInt;
+use num::traits::{Signed, Zero};
+
+use crate::serialization::etf::{self, Term};
+use crate::syntax::ast::error::FromBeamError;
+use crate::syntax::ast::FromBeamResult;
+
+/// Translates the `Map` of an `{elixir_v1, Map, Specs}` debug info term to abstract forms
+pub fn to_abstract_forms(map: &Term) -> FromBeamResult<Vec<Term>> {
+    let map = match *map {
+        Term::Map(ref map) => map,
+        _ => return Err(unsupported("debug info is not a map", map)),
+    };
+    let get = |key: &str| {
+        map.entries
+            .iter()
+            .find(|(k, _)| is_atom(k, key))
+            .map(|(_, v)| v)
+    };
+    let module = get("module")
+        .and_then(atom_name)
+        .ok_or_else(|| FromBeamError::UnsupportedElixir("missing module".to_string()))?
+        .to_string();
+    let line = get("line").and_then(to_i32).unwrap_or(0);
+    let definitions = match get("definitions") {
+        Some(Term::List(ref list)) => &list.elements[..],
+        _ => &[],
+    };
+
+    let mut exports = Vec::new();
+    let mut functions = Vec::new();
+    for definition in definitions.iter() {
+        let (name, arity, kind, clauses) = match_definition(definition)?;
+        match kind {
+            "def" => exports.push((name.to_string(), arity)),
+            "defp" => (),
+            // Macros are expanded at compile time, so they are never needed at runtime
+            "defmacro" | "defmacrop" => continue,
+            _ => return Err(unsupported("definition kind", definition)),
+        }
+        let mut translator = Translator::new();
+        functions.push(translator.function(name, arity, clauses)?);
+    }
+    exports.push(("__info__".to_string(), 1));
+    exports.sort();
+
+    let mut forms = Vec::with_capacity(functions.len() + 4);
+    forms.push(tuple(vec![
+        atom("attribute"),
+        int(line),
+        atom("module"),
+        atom(&module),
+    ]));
+    forms.push(tuple(vec![
+        atom("attribute"),
+        int(line),
+        atom("export"),
+        list(
+            exports
+                .iter()
+                .map(|(name, arity)| tuple(vec![atom(name), int(*arity as i32)]))
+                .collect(),
+        ),
+    ]));
+    forms.push(info_function(&module, &exports, line));
+    forms.extend(functions);
+    forms.push(tuple(vec![atom("eof"), int(line)]));
+    Ok(forms)
+}
+
+/// Generates `__info__/1`, which the Elixir runtime expects every module to define
+///
+/// As in `elixir_erl`, the keys which depend on the loaded module are answered by
+/// `erlang:get_module_info/2`
+fn info_function(module: &str, exports: &[(String, u32)], line: i32) -> Term {
+    let functions = exports
+        .iter()
+        .filter(|(name, _)| name != "__info__")
+        .rev()
+        .fold(nil(line), |tail, (name, arity)| {
+            cons(
+                line,
+                tuple(vec![
+                    atom("tuple"),
+                    int(line),
+                    list(vec![lit_atom(line, name), lit_int(line, *arity as i64)]),
+                ]),
+                tail,
+            )
+        });
+    let clause = |key: &str, body: Term| {
+        tuple(vec![
+            atom("clause"),
+            int(line),
+            list(vec![lit_atom(line, key)]),
+            list(vec![]),
+            list(vec![body]),
+        ])
+    };
+    let module_info = |key: &str| {
+        let remote = tuple(vec![
+            atom("remote"),
+            int(line),
+            lit_atom(line, "erlang"),
+            lit_atom(line, "get_module_info"),
+        ]);
+        let args = list(vec![lit_atom(line, module), lit_atom(line, key)]);
+        clause(key, tuple(vec![atom("call"), int(line), remote, args]))
+    };
+    tuple(vec![
+        atom("function"),
+        int(line),
+        atom("__info__"),
+        int(1),
+        list(vec![
+            clause("module", lit_atom(line, module)),
+            clause("functions", functions),
+            clause("macros", nil(line)),
+            module_info("attributes"),
+            module_info("compile"),
+            module_info("md5"),
+            clause("deprecated", nil(line)),
+        ]),
+    ])
+}
+
+/// Matches `{{Name, Arity}, Kind, Meta, Clauses}`
+fn match_definition(term: &Term) -> FromBeamResult<(&str, u32, &str, &[Term])> {
+    if let Some([name_arity, kind, _meta, Term::List(ref clauses)]) = tuple_elements(term) {
+        if let Some([name, arity]) = tuple_elements(name_arity) {
+            if let (Some(name), Some(arity), Some(kind)) =
+                (atom_name(name), to_i32(arity), atom_name(kind))
+            {
+                return Ok((name, arity as u32, kind, &clauses.elements[..]));
+            }
+        }
+    }
+    Err(unsupported("definition", term))
+}
+
+fn unsupported(what: &str, term: &Term) -> FromBeamError {
+    FromBeamError::UnsupportedElixir(format!("{}: {}", what, term))
+}
+
+/// A lexical scope, mapping Elixir variables to the current Erlang variable bound for them
+#[derive(Debug, Clone, Default)]
+struct Scope {
+    vars: HashMap<String, String>,
+    /// The variable holding the stacktrace in the innermost `catch`/`rescue`, if any
+    stacktrace: Option<String>,
+}
+
+/// Translates the clauses of a single function
+struct Translator {
+    line: i32,
+    /// The number of times each Elixir variable has been bound, used to version variables,
+    /// as Elixir allows rebinding while Erlang does not
+    versions: HashMap<String, u32>,
+    /// Counter for compiler-generated variables
+    temporaries: u32,
+}
+impl Translator {
+    fn new() -> Self {
+        Self {
+            line: 0,
+            versions: HashMap::new(),
+            temporaries: 0,
+        }
+    }
+
+    fn function(&mut self, name: &str, arity: u32, clauses: &[Term]) -> FromBeamResult<Term> {
+        let mut translated = Vec::with_capacity(clauses.len());
+        for clause in clauses.iter() {
+            // {Meta, Args, Guards, Body}
+            match tuple_elements(clause) {
+                Some([meta, Term::List(ref args), Term::List(ref guards), body]) => {
+                    self.set_line(meta);
+                    let guards = guards.elements.iter().collect::<Vec<_>>();
+                    translated.push(self.clause(
+                        &args.elements,
+                        &guards,
+                        body,
+                        &Scope::default(),
+                    )?);
+                }
+                _ => return Err(unsupported("function clause", clause)),
+            }
+        }
+        Ok(tuple(vec![
+            atom("function"),
+            int(self.line),
+            atom(name),
+            int(arity as i32),
+            list(translated),
+        ]))
+    }
+
+    fn set_line(&mut self, meta: &Term) {
+        if let Some(line) = keyword(meta, "line").and_then(to_i32) {
+            self.line = line;
+        }
+    }
+
+    fn temporary(&mut self) -> String {
+        self.temporaries += 1;
+        format!("_@{}", self.temporaries)
+    }
+
+    /// Translates a clause with the given argument patterns and alternative guards
+    fn clause(
+        &mut self,
+        args: &[Term],
+        guards: &[&Term],
+        body: &Term,
+        scope: &Scope,
+    ) -> FromBeamResult<Term> {
+        let line = self.line;
+        let mut bindings = HashMap::new();
+        let patterns = args
+            .iter()
+            .map(|arg| self.pattern(arg, scope, &mut bindings))
+            .collect::<FromBeamResult<Vec<_>>>()?;
+        let mut inner = scope.clone();
+        inner.vars.extend(bindings);
+        let guards = guards
+            .iter()
+            .map(|guard| Ok(list(vec![self.expr(guard, &mut inner)?])))
+            .collect::<FromBeamResult<Vec<_>>>()?;
+        let body = self.body(body, &mut inner)?;
+        Ok(tuple(vec![
+            atom("clause"),
+            int(line),
+            list(patterns),
+            list(guards),
+            list(body),
+        ]))
+    }
+
+    /// Translates a `Pattern -> Body` clause of `case`, `receive`, `fn` and friends,
+    /// where the head may contain a `when`
+    fn arrow_clause(&mut self, clause: &Term, scope: &Scope) -> FromBeamResult<Term> {
+        match node(clause) {
+            Some(("->", meta, [Term::List(ref head), body])) => {
+                self.set_line(meta);
+                let (args, guards) = split_when(&head.elements);
+                self.clause(&args, &guards, body, scope)
+            }
+            _ => Err(unsupported("clause", clause)),
+        }
+    }
+
+    fn arrow_clauses(&mut self, clauses: &Term, scope: &Scope) -> FromBeamResult<Vec<Term>> {
+        match *clauses {
+            Term::List(ref clauses) => clauses
+                .elements
+                .iter()
+                .map(|clause| self.arrow_clause(clause, scope))
+                .collect(),
+            // An empty `do` block, e.g. `receive do after 0 -> :ok end`
+            _ if is_empty_block(clauses) => Ok(Vec::new()),
+            _ => Err(unsupported("clauses", clauses)),
+        }
+    }
+
+    /// Translates an expression which may be a `__block__` into a sequence of expressions
+    fn body(&mut self, body: &Term, scope: &mut Scope) -> FromBeamResult<Vec<Term>> {
+        match node(body) {
+            Some(("__block__", _, exprs)) if !exprs.is_empty() => {
+                exprs.iter().map(|e| self.expr(e, scope)).collect()
+            }
+            Some(("__block__", _, _)) => Ok(vec![lit_atom(self.line, "nil")]),
+            _ => Ok(vec![self.expr(body, scope)?]),
+        }
+    }
+
+    fn block(&self, mut exprs: Vec<Term>) -> Term {
+        if exprs.len() == 1 {
+            exprs.pop().unwrap()
+        } else {
+            tuple(vec![atom("block"), int(self.line), list(exprs)])
+        }
+    }
+
+    fn expr(&mut self, term: &Term, scope: &mut Scope) -> FromBeamResult<Term> {
+        match *term {
+            Term::List(ref l) => self.list(&l.elements, |t, e| t.expr(e, scope)),
+            Term::Tuple(ref t) if t.elements.len() == 2 => {
+                let elements = t
+                    .elements
+                    .iter()
+                    .map(|e| self.expr(e, scope))
+                    .collect::<FromBeamResult<Vec<_>>>()?;
+                Ok(tuple(vec![atom("tuple"), int(self.line), list(elements)]))
+            }
+            Term::Tuple(ref t) if t.elements.len() == 3 => {
+                let meta = &t.elements[1];
+                self.set_line(meta);
+                self.expr_node(term, scope)
+            }
+            _ => self.literal(term),
+        }
+    }
+
+    fn expr_node(&mut self, term: &Term, scope: &mut Scope) -> FromBeamResult<Term> {
+        let line = self.line;
+        let elements = tuple_elements(term).unwrap();
+        let (head, meta, args) = (&elements[0], &elements[1], &elements[2]);
+
+        // Calls, e.g. `Mod.fun(args)`, `var.field` or `fun.(args)`
+        if let Some((".", _, dot_args)) = node(head) {
+            let args = match *args {
+                Term::List(ref args) => &args.elements[..],
+                _ => return Err(unsupported("call", term)),
+            };
+            return match dot_args {
+                [fun] => {
+                    let fun = self.expr(fun, scope)?;
+                    let args = self.exprs(args, scope)?;
+                    Ok(tuple(vec![atom("call"), int(line), fun, list(args)]))
+                }
+                [Term::Atom(ref module), Term::Atom(ref function)] => {
+                    let args = self.exprs(args, scope)?;
+                    Ok(self.remote_call(&module.name, &function.name, args))
+                }
+                [receiver, Term::Atom(ref field)]
+                    if args.is_empty() && keyword(meta, "no_parens").is_some() =>
+                {
+                    let receiver = self.expr(receiver, scope)?;
+                    Ok(self.field_access(receiver, &field.name))
+                }
+                [module, Term::Atom(ref function)] => {
+                    let module = self.expr(module, scope)?;
+                    let args = self.exprs(args, scope)?;
+                    let remote = tuple(vec![
+                        atom("remote"),
+                        int(line),
+                        module,
+                        lit_atom(line, &function.name),
+                    ]);
+                    Ok(tuple(vec![atom("call"), int(line), remote, list(args)]))
+                }
+                _ => Err(unsupported("call", term)),
+            };
+        }
+
+        let name = match atom_name(head) {
+            Some(name) => name,
+            None => return Err(unsupported("expression", term)),
+        };
+        // Variables are `{Name, Meta, Context}` where the context is an atom
+        if let Term::Atom(_) = *args {
+            if name == "__STACKTRACE__" {
+                return match scope.stacktrace {
+                    Some(ref var) => Ok(var_(line, var)),
+                    None => Ok(self.remote_call("erlang", "get_stacktrace", vec![])),
+                };
+            }
+            let key = var_key(name, meta, args);
+            return match scope.vars.get(&key) {
+                Some(var) => Ok(var_(line, var)),
+                None => Err(unsupported("unbound variable", term)),
+            };
+        }
+        let args = match *args {
+            Term::List(ref args) => &args.elements[..],
+            _ => return Err(unsupported("expression", term)),
+        };
+        match (name, args) {
+            ("__block__", _) => {
+                let exprs = self.body(term, scope)?;
+                Ok(self.block(exprs))
+            }
+            ("=", [left, right]) => {
+                let right = self.expr(right, scope)?;
+                let mut bindings = HashMap::new();
+                let left = self.pattern(left, scope, &mut bindings)?;
+                scope.vars.extend(bindings);
+                Ok(tuple(vec![atom("match"), int(line), left, right]))
+            }
+            ("{}", elements) => {
+                let elements = self.exprs(elements, scope)?;
+                Ok(tuple(vec![atom("tuple"), int(line), list(elements)]))
+            }
+            ("%{}", pairs) => self.map_expr(None, pairs, scope),
+            ("%", [module, map]) => match node(map) {
+                Some(("%{}", _, pairs)) => self.map_expr(Some(module), pairs, scope),
+                _ => Err(unsupported("struct", term)),
+            },
+            ("<<>>", segments) => self.binary(segments, &mut |t, e| t.expr(e, scope)),
+            ("fn", clauses) => {
+                let clauses = clauses
+                    .iter()
+                    .map(|clause| self.arrow_clause(clause, scope))
+                    .collect::<FromBeamResult<Vec<_>>>()?;
+                Ok(tuple(vec![
+                    atom("fun"),
+                    int(line),
+                    tuple(vec![atom("clauses"), list(clauses)]),
+                ]))
+            }
+            ("case", [expr, opts]) => {
+                let expr = self.expr(expr, scope)?;
+                let clauses = match keyword(opts, "do") {
+                    Some(clauses) => self.arrow_clauses(clauses, scope)?,
+                    None => return Err(unsupported("case", term)),
+                };
+                Ok(tuple(vec![atom("case"), int(line), expr, list(clauses)]))
+            }
+            ("cond", [opts]) => match keyword(opts, "do") {
+                Some(Term::List(ref clauses)) => self.cond(&clauses.elements, scope),
+                _ => Err(unsupported("cond", term)),
+            },
+            ("receive", [opts]) => self.receive(opts, scope),
+            ("try", [opts]) => self.try_(opts, scope),
+            ("for", args) if !args.is_empty() => self.comprehension(args, scope),
+            ("with", args) if !args.is_empty() => {
+                let (opts, clauses) = args.split_last().unwrap();
+                let body = match keyword(opts, "do") {
+                    Some(body) => body,
+                    None => return Err(unsupported("with", term)),
+                };
+                let else_clauses = keyword(opts, "else");
+                self.with(clauses, body, else_clauses, scope)
+            }
+            ("&", [capture]) => self.capture(capture),
+            ("super", _) => Err(unsupported("super", term)),
+            (name, args) => {
+                let args = self.exprs(args, scope)?;
+                Ok(tuple(vec![
+                    atom("call"),
+                    int(line),
+                    lit_atom(line, name),
+                    list(args),
+                ]))
+            }
+        }
+    }
+
+    fn exprs(&mut self, terms: &[Term], scope: &mut Scope) -> FromBeamResult<Vec<Term>> {
+        terms.iter().map(|e| self.expr(e, scope)).collect()
+    }
+
+    /// Translates a call to `Module.function(args)`, using operators for calls to `:erlang`
+    /// operators so that they remain valid in guards
+    fn remote_call(&self, module: &str, function: &str, mut args: Vec<Term>) -> Term {
+        let line = self.line;
+        if module == "erlang" {
+            match args.len() {
+                1 if UNARY_OPERATORS.contains(&function) => {
+                    let operand = args.pop().unwrap();
+                    return tuple(vec![atom("op"), int(line), atom(function), operand]);
+                }
+                2 if BINARY_OPERATORS.contains(&function) => {
+                    let right = args.pop().unwrap();
+                    let left = args.pop().unwrap();
+                    return tuple(vec![atom("op"), int(line), atom(function), left, right]);
+                }
+                _ => (),
+            }
+        }
+        let remote = tuple(vec![
+            atom("remote"),
+            int(line),
+            lit_atom(line, module),
+            lit_atom(line, function),
+        ]);
+        tuple(vec![atom("call"), int(line), remote, list(args)])
+    }
+
+    /// Translates `receiver.field`, which is a map lookup if `receiver` is a map,
+    /// or a zero-arity call if it is a module
+    fn field_access(&mut self, receiver: Term, field: &str) -> Term {
+        let line = self.line;
+        let value = self.temporary();
+        let other = self.temporary();
+        let map_clause = tuple(vec![
+            atom("clause"),
+            int(line),
+            list(vec![tuple(vec![
+                atom("map"),
+                int(line),
+                list(vec![tuple(vec![
+                    atom("map_field_exact"),
+                    int(line),
+                    lit_atom(line, field),
+                    var_(line, &value),
+                ])]),
+            ])]),
+            list(vec![]),
+            list(vec![var_(line, &value)]),
+        ]);
+        let remote = tuple(vec![
+            atom("remote"),
+            int(line),
+            var_(line, &other),
+            lit_atom(line, field),
+        ]);
+        let other_clause = tuple(vec![
+            atom("clause"),
+            int(line),
+            list(vec![var_(line, &other)]),
+            list(vec![]),
+            list(vec![tuple(vec![
+                atom("call"),
+                int(line),
+                remote,
+                list(vec![]),
+            ])]),
+        ]);
+        tuple(vec![
+            atom("case"),
+            int(line),
+            receiver,
+            list(vec![map_clause, other_clause]),
+        ])
+    }
+
+    fn map_expr(
+        &mut self,
+        module: Option<&Term>,
+        pairs: &[Term],
+        scope: &mut Scope,
+    ) -> FromBeamResult<Term> {
+        let line = self.line;
+        // `%{map | key: value}` updates existing keys
+        if let [update] = pairs {
+            if let Some(("|", _, [base, Term::List(ref pairs)])) = node(update) {
+                let base = self.expr(base, scope)?;
+                let fields = self.map_fields(&pairs.elements, "map_field_exact", scope)?;
+                return Ok(tuple(vec![atom("map"), int(line), base, list(fields)]));
+            }
+        }
+        let mut fields = Vec::with_capacity(pairs.len() + 1);
+        if let Some(module) = module {
+            let module = self.expr(module, scope)?;
+            fields.push(tuple(vec![
+                atom("map_field_assoc"),
+                int(line),
+                lit_atom(line, "__struct__"),
+                module,
+            ]));
+        }
+        fields.extend(self.map_fields(pairs, "map_field_assoc", scope)?);
+        Ok(tuple(vec![atom("map"), int(line), list(fields)]))
+    }
+
+    fn map_fields(
+        &mut self,
+        pairs: &[Term],
+        kind: &str,
+        scope: &mut Scope,
+    ) -> FromBeamResult<Vec<Term>> {
+        pairs
+            .iter()
+            .map(|pair| match tuple_elements(pair) {
+                Some([key, value]) => {
+                    let key = self.expr(key, scope)?;
+                    let value = self.expr(value, scope)?;
+                    Ok(tuple(vec![atom(kind), int(self.line), key, value]))
+                }
+                _ => Err(unsupported("map field", pair)),
+            })
+            .collect()
+    }
+
+    /// Translates `cond` into nested `case` expressions on the truthiness of each condition
+    fn cond(&mut self, clauses: &[Term], scope: &mut Scope) -> FromBeamResult<Term> {
+        let line = self.line;
+        let (clause, rest) = match clauses.split_first() {
+            Some(split) => split,
+            None => {
+                let reason = lit_atom(line, "cond_clause");
+                return Ok(self.remote_call("erlang", "error", vec![reason]));
+            }
+        };
+        let (condition, body) = match node(clause) {
+            Some(("->", _, [Term::List(ref head), body])) if head.elements.len() == 1 => {
+                (&head.elements[0], body)
+            }
+            _ => return Err(unsupported("cond clause", clause)),
+        };
+        let mut inner = scope.clone();
+        let body = self.body(body, &mut inner)?;
+        let body = self.block(body);
+        if is_truthy_literal(condition) {
+            return Ok(body);
+        }
+        let condition = self.expr(condition, scope)?;
+        let rest = self.cond(rest, scope)?;
+        Ok(self.truthy_case(condition, body, rest))
+    }
+
+    /// Builds `case Condition of X when X =:= false orelse X =:= nil -> Falsy; _ -> Truthy end`
+    fn truthy_case(&mut self, condition: Term, truthy: Term, falsy: Term) -> Term {
+        let line = self.line;
+        let var = self.temporary();
+        let is = |value: &str| {
+            tuple(vec![
+                atom("op"),
+                int(line),
+                atom("=:="),
+                var_(line, &var),
+                lit_atom(line, value),
+            ])
+        };
+        let guard = tuple(vec![
+            atom("op"),
+            int(line),
+            atom("orelse"),
+            is("false"),
+            is("nil"),
+        ]);
+        let falsy_clause = tuple(vec![
+            atom("clause"),
+            int(line),
+            list(vec![var_(line, &var)]),
+            list(vec![list(vec![guard])]),
+            list(vec![falsy]),
+        ]);
+        let truthy_clause = tuple(vec![
+            atom("clause"),
+            int(line),
+            list(vec![var_(line, "_")]),
+            list(vec![]),
+            list(vec![truthy]),
+        ]);
+        tuple(vec![
+            atom("case"),
+            int(line),
+            condition,
+            list(vec![falsy_clause, truthy_clause]),
+        ])
+    }
+
+    fn receive(&mut self, opts: &Term, scope: &mut Scope) -> FromBeamResult<Term> {
+        let line = self.line;
+        let clauses = match keyword(opts, "do") {
+            Some(clauses) => self.arrow_clauses(clauses, scope)?,
+            None => Vec::new(),
+        };
+        match keyword(opts, "after") {
+            None => Ok(tuple(vec![atom("receive"), int(line), list(clauses)])),
+            Some(Term::List(ref after)) if after.elements.len() == 1 => {
+                let after = &after.elements[0];
+                match node(after) {
+                    Some(("->", _, [Term::List(ref head), body])) if head.elements.len() == 1 => {
+                        let timeout = self.expr(&head.elements[0], scope)?;
+                        let mut inner = scope.clone();
+                        let body = self.body(body, &mut inner)?;
+                        Ok(tuple(vec![
+                            atom("receive"),
+                            int(line),
+                            list(clauses),
+                            timeout,
+                            list(body),
+                        ]))
+                    }
+                    _ => Err(unsupported("receive after", after)),
+                }
+            }
+            Some(after) => Err(unsupported("receive after", after)),
+        }
+    }
+
+    fn try_(&mut self, opts: &Term, scope: &mut Scope) -> FromBeamResult<Term> {
+        let line = self.line;
+        let body = match keyword(opts, "do") {
+            Some(body) => {
+                let mut inner = scope.clone();
+                self.body(body, &mut inner)?
+            }
+            None => return Err(unsupported("try", opts)),
+        };
+        let else_clauses = match keyword(opts, "else") {
+            Some(clauses) => self.arrow_clauses(clauses, scope)?,
+            None => Vec::new(),
+        };
+        let mut catch_clauses = Vec::new();
+        if let Some(Term::List(ref clauses)) = keyword(opts, "catch") {
+            for clause in clauses.elements.iter() {
+                catch_clauses.push(self.catch_clause(clause, scope)?);
+            }
+        }
+        if let Some(Term::List(ref clauses)) = keyword(opts, "rescue") {
+            catch_clauses.push(self.rescue(&clauses.elements, scope)?);
+        }
+        let after = match keyword(opts, "after") {
+            Some(after) => {
+                let mut inner = scope.clone();
+                self.body(after, &mut inner)?
+            }
+            None => Vec::new(),
+        };
+        Ok(tuple(vec![
+            atom("try"),
+            int(line),
+            list(body),
+            list(else_clauses),
+            list(catch_clauses),
+            list(after),
+        ]))
+    }
+
+    /// Translates `kind, reason -> body` or `reason -> body` (which catches throws)
+    fn catch_clause(&mut self, clause: &Term, scope: &Scope) -> FromBeamResult<Term> {
+        let line = self.line;
+        let (head, body) = match node(clause) {
+            Some(("->", _, [Term::List(ref head), body])) => (&head.elements, body),
+            _ => return Err(unsupported("catch clause", clause)),
+        };
+        let (args, guards) = split_when(head);
+        let mut bindings = HashMap::new();
+        let (kind, reason) = match args.as_slice() {
+            [reason] => (
+                lit_atom(line, "throw"),
+                self.pattern(reason, scope, &mut bindings)?,
+            ),
+            [kind, reason] => (
+                self.pattern(kind, scope, &mut bindings)?,
+                self.pattern(reason, scope, &mut bindings)?,
+            ),
+            _ => return Err(unsupported("catch clause", clause)),
+        };
+        let stacktrace = self.temporary();
+        let mut inner = scope.clone();
+        inner.vars.extend(bindings);
+        inner.stacktrace = Some(stacktrace.clone());
+        let guards = guards
+            .iter()
+            .map(|guard| Ok(list(vec![self.expr(guard, &mut inner)?])))
+            .collect::<FromBeamResult<Vec<_>>>()?;
+        let body = self.body(body, &mut inner)?;
+        let pattern = tuple(vec![
+            atom("tuple"),
+            int(line),
+            list(vec![kind, reason, var_(line, &stacktrace)]),
+        ]);
+        Ok(tuple(vec![
+            atom("clause"),
+            int(line),
+            list(vec![pattern]),
+            list(guards),
+            list(body),
+        ]))
+    }
+
+    /// Translates all `rescue` clauses into a single `error:Reason:Stacktrace` catch clause,
+    /// which normalizes the error to an exception and re-raises it if no clause matches
+    fn rescue(&mut self, clauses: &[Term], scope: &Scope) -> FromBeamResult<Term> {
+        let line = self.line;
+        let reason = self.temporary();
+        let stacktrace = self.temporary();
+
+        let mut inner = scope.clone();
+        inner.stacktrace = Some(stacktrace.clone());
+        let mut case_clauses = Vec::with_capacity(clauses.len() + 1);
+        for clause in clauses.iter() {
+            let (head, body) = match node(clause) {
+                Some(("->", _, [Term::List(ref head), body])) if head.elements.len() == 1 => {
+                    (&head.elements[0], body)
+                }
+                _ => return Err(unsupported("rescue clause", clause)),
+            };
+            let (var, modules) = match node(head) {
+                Some(("in", _, [var, Term::List(ref modules)])) => (var, &modules.elements[..]),
+                _ => (head, &[][..]),
+            };
+            let mut bindings = HashMap::new();
+            let var = self.pattern(var, scope, &mut bindings)?;
+            let mut clause_scope = inner.clone();
+            clause_scope.vars.extend(bindings);
+            let (pattern, guards) = if modules.is_empty() {
+                (var, Vec::new())
+            } else {
+                // #{'__struct__' := Module} = Var when Module =:= A orelse Module =:= B
+                let module = self.temporary();
+                let struct_pattern = tuple(vec![
+                    atom("map"),
+                    int(line),
+                    list(vec![tuple(vec![
+                        atom("map_field_exact"),
+                        int(line),
+                        lit_atom(line, "__struct__"),
+                        var_(line, &module),
+                    ])]),
+                ]);
+                let mut guard = None;
+                for m in modules.iter().rev() {
+                    let m = self.literal(m)?;
+                    let test = tuple(vec![
+                        atom("op"),
+                        int(line),
+                        atom("=:="),
+                        var_(line, &module),
+                        m,
+                    ]);
+                    guard = Some(match guard {
+                        None => test,
+                        Some(rest) => {
+                            tuple(vec![atom("op"), int(line), atom("orelse"), test, rest])
+                        }
+                    });
+                }
+                (
+                    tuple(vec![atom("match"), int(line), struct_pattern, var]),
+                    vec![list(vec![guard.unwrap()])],
+                )
+            };
+            let body = self.body(body, &mut clause_scope)?;
+            case_clauses.push(tuple(vec![
+                atom("clause"),
+                int(line),
+                list(vec![pattern]),
+                list(guards),
+                list(body),
+            ]));
+        }
+        let reraise = self.remote_call(
+            "erlang",
+            "raise",
+            vec![
+                lit_atom(line, "error"),
+                var_(line, &reason),
+                var_(line, &stacktrace),
+            ],
+        );
+        case_clauses.push(tuple(vec![
+            atom("clause"),
+            int(line),
+            list(vec![var_(line, "_")]),
+            list(vec![]),
+            list(vec![reraise]),
+        ]));
+
+        let normalize = self.remote_call(
+            "Elixir.Exception",
+            "normalize",
+            vec![
+                lit_atom(line, "error"),
+                var_(line, &reason),
+                var_(line, &stacktrace),
+            ],
+        );
+        let body = vec![tuple(vec![
+            atom("case"),
+            int(line),
+            normalize,
+            list(case_clauses),
+        ])];
+        let pattern = tuple(vec![
+            atom("tuple"),
+            int(line),
+            list(vec![
+                lit_atom(line, "error"),
+                var_(line, &reason),
+                var_(line, &stacktrace),
+            ]),
+        ]);
+        Ok(tuple(vec![
+            atom("clause"),
+            int(line),
+            list(vec![pattern]),
+            list(vec![]),
+            list(body),
+        ]))
+    }
+
+    /// Translates `for` into a list comprehension, converting each enumerable to a list
+    fn comprehension(&mut self, args: &[Term], scope: &mut Scope) -> FromBeamResult<Term> {
+        let line = self.line;
+        let (opts, qualifiers) = args.split_last().unwrap();
+        if keyword(opts, "reduce").is_some() {
+            return Err(unsupported("for with :reduce", opts));
+        }
+        let body = match keyword(opts, "do") {
+            Some(body) => body,
+            None => return Err(unsupported("for", opts)),
+        };
+
+        let mut inner = scope.clone();
+        let mut translated = Vec::with_capacity(qualifiers.len());
+        for qualifier in qualifiers.iter() {
+            match node(qualifier) {
+                Some(("<-", _, [pattern, enumerable])) => {
+                    let enumerable = self.expr(enumerable, &mut inner)?;
+                    let enumerable = self.remote_call("Elixir.Enum", "to_list", vec![enumerable]);
+                    let (patterns, guards) = split_when(std::slice::from_ref(pattern));
+                    let mut bindings = HashMap::new();
+                    let pattern = self.pattern(&patterns[0], &inner, &mut bindings)?;
+                    inner.vars.extend(bindings);
+                    translated.push(tuple(vec![
+                        atom("generate"),
+                        int(line),
+                        pattern,
+                        enumerable,
+                    ]));
+                    for guard in guards.iter() {
+                        translated.push(self.expr(guard, &mut inner)?);
+                    }
+                }
+                Some(("<<>>", _, [generator])) => match node(generator) {
+                    Some(("<-", _, [segment, bitstring])) => {
+                        let bitstring = self.expr(bitstring, &mut inner)?;
+                        let mut bindings = HashMap::new();
+                        let pattern = {
+                            let outer = inner.clone();
+                            self.binary(std::slice::from_ref(segment), &mut |t, e| {
+                                t.pattern(e, &outer, &mut bindings)
+                            })?
+                        };
+                        inner.vars.extend(bindings);
+                        translated.push(tuple(vec![
+                            atom("b_generate"),
+                            int(line),
+                            pattern,
+                            bitstring,
+                        ]));
+                    }
+                    _ => return Err(unsupported("for generator", qualifier)),
+                },
+                _ => {
+                    let filter = self.expr(qualifier, &mut inner)?;
+                    let truthy = lit_atom(line, "true");
+                    let falsy = lit_atom(line, "false");
+                    translated.push(self.truthy_case(filter, truthy, falsy));
+                }
+            }
+        }
+        let body = self.body(body, &mut inner)?;
+        let body = self.block(body);
+        let mut result = tuple(vec![atom("lc"), int(line), body, list(translated)]);
+        if let Some(Term::Atom(ref uniq)) = keyword(opts, "uniq") {
+            if uniq.name == "true" {
+                result = self.remote_call("Elixir.Enum", "uniq", vec![result]);
+            }
+        }
+        match keyword(opts, "into") {
+            None => Ok(result),
+            Some(Term::List(ref l)) if l.is_nil() => Ok(result),
+            Some(into) => {
+                let into = self.expr(into, scope)?;
+                Ok(self.remote_call("Elixir.Enum", "into", vec![result, into]))
+            }
+        }
+    }
+
+    /// Translates `with` into nested `case` expressions
+    fn with(
+        &mut self,
+        clauses: &[Term],
+        body: &Term,
+        else_clauses: Option<&Term>,
+        scope: &mut Scope,
+    ) -> FromBeamResult<Term> {
+        let line = self.line;
+        let (clause, rest) = match clauses.split_first() {
+            Some(split) => split,
+            None => {
+                let mut inner = scope.clone();
+                let body = self.body(body, &mut inner)?;
+                return Ok(self.block(body));
+            }
+        };
+        match node(clause) {
+            Some(("<-", _, [pattern, expr])) => {
+                let expr = self.expr(expr, scope)?;
+                let (patterns, guards) = split_when(std::slice::from_ref(pattern));
+                let mut bindings = HashMap::new();
+                let pattern = self.pattern(&patterns[0], scope, &mut bindings)?;
+                let mut inner = scope.clone();
+                inner.vars.extend(bindings);
+                let guards = guards
+                    .iter()
+                    .map(|guard| Ok(list(vec![self.expr(guard, &mut inner)?])))
+                    .collect::<FromBeamResult<Vec<_>>>()?;
+                let matched = self.with(rest, body, else_clauses, &mut inner)?;
+                let other = self.temporary();
+                let unmatched = match else_clauses {
+                    None => var_(line, &other),
+                    Some(else_clauses) => {
+                        let mut clauses = self.arrow_clauses(else_clauses, scope)?;
+                        let reason = tuple(vec![
+                            atom("tuple"),
+                            int(line),
+                            list(vec![lit_atom(line, "with_clause"), var_(line, &other)]),
+                        ]);
+                        let error = self.remote_call("erlang", "error", vec![reason]);
+                        clauses.push(tuple(vec![
+                            atom("clause"),
+                            int(line),
+                            list(vec![var_(line, "_")]),
+                            list(vec![]),
+                            list(vec![error]),
+                        ]));
+                        tuple(vec![
+                            atom("case"),
+                            int(line),
+                            var_(line, &other),
+                            list(clauses),
+                        ])
+                    }
+                };
+                let clauses = vec![
+                    tuple(vec![
+                        atom("clause"),
+                        int(line),
+                        list(vec![pattern]),
+                        list(guards),
+                        list(vec![matched]),
+                    ]),
+                    tuple(vec![
+                        atom("clause"),
+                        int(line),
+                        list(vec![var_(line, &other)]),
+                        list(vec![]),
+                        list(vec![unmatched]),
+                    ]),
+                ];
+                Ok(tuple(vec![atom("case"), int(line), expr, list(clauses)]))
+            }
+            _ => {
+                let mut inner = scope.clone();
+                let expr = self.expr(clause, &mut inner)?;
+                let rest = self.with(rest, body, else_clauses, &mut inner)?;
+                Ok(self.block(vec![expr, rest]))
+            }
+        }
+    }
+
+    /// Translates `&Mod.fun/arity` and `&fun/arity`
+    fn capture(&mut self, capture: &Term) -> FromBeamResult<Term> {
+        let line = self.line;
+        if let Some(("/", _, [fun, arity])) = node(capture) {
+            if let Some(arity) = to_i32(arity) {
+                match node(fun) {
+                    Some((".", _, _)) => (),
+                    Some((name, _, _)) => {
+                        let function = tuple(vec![atom("function"), atom(name), int(arity)]);
+                        return Ok(tuple(vec![atom("fun"), int(line), function]));
+                    }
+                    None => (),
+                }
+                if let Some(elements) = tuple_elements(fun) {
+                    if let Some((".", _, [Term::Atom(ref m), Term::Atom(ref f)])) =
+                        node(&elements[0])
+                    {
+                        let function = tuple(vec![
+                            atom("function"),
+                            lit_atom(line, &m.name),
+                            lit_atom(line, &f.name),
+                            lit_int(line, arity as i64),
+                        ]);
+                        return Ok(tuple(vec![atom("fun"), int(line), function]));
+                    }
+                }
+            }
+        }
+        Err(unsupported("capture", capture))
+    }
+
+    fn pattern(
+        &mut self,
+        term: &Term,
+        outer: &Scope,
+        bindings: &mut HashMap<String, String>,
+    ) -> FromBeamResult<Term> {
+        let line = self.line;
+        let elements = match *term {
+            Term::List(ref l) => {
+                return self.list(&l.elements, |t, e| t.pattern(e, outer, bindings));
+            }
+            Term::Tuple(ref t) if t.elements.len() == 2 => {
+                let elements = t
+                    .elements
+                    .iter()
+                    .map(|e| self.pattern(e, outer, bindings))
+                    .collect::<FromBeamResult<Vec<_>>>()?;
+                return Ok(tuple(vec![atom("tuple"), int(line), list(elements)]));
+            }
+            Term::Tuple(ref t) if t.elements.len() == 3 => &t.elements,
+            _ => return self.literal(term),
+        };
+        let (head, meta, args) = (&elements[0], &elements[1], &elements[2]);
+        let name = match atom_name(head) {
+            Some(name) => name,
+            None => return Err(unsupported("pattern", term)),
+        };
+        if let Term::Atom(_) = *args {
+            if name == "_" {
+                return Ok(var_(line, "_"));
+            }
+            let key = var_key(name, meta, args);
+            if let Some(var) = bindings.get(&key) {
+                return Ok(var_(line, var));
+            }
+            let var = self.bind(name, &key);
+            bindings.insert(key, var.clone());
+            return Ok(var_(line, &var));
+        }
+        let args = match *args {
+            Term::List(ref args) => &args.elements[..],
+            _ => return Err(unsupported("pattern", term)),
+        };
+        match (name, args) {
+            ("^", [var]) => match tuple_elements(var) {
+                Some([name, meta, ctx]) => {
+                    let key = var_key(atom_name(name).unwrap_or("_"), meta, ctx);
+                    match outer.vars.get(&key) {
+                        Some(var) => Ok(var_(line, var)),
+                        None => Err(unsupported("unbound pinned variable", term)),
+                    }
+                }
+                _ => Err(unsupported("pin", term)),
+            },
+            ("=", [left, right]) => {
+                let left = self.pattern(left, outer, bindings)?;
+                let right = self.pattern(right, outer, bindings)?;
+                Ok(tuple(vec![atom("match"), int(line), left, right]))
+            }
+            ("{}", elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|e| self.pattern(e, outer, bindings))
+                    .collect::<FromBeamResult<Vec<_>>>()?;
+                Ok(tuple(vec![atom("tuple"), int(line), list(elements)]))
+            }
+            ("%{}", pairs) => self.map_pattern(None, pairs, outer, bindings),
+            ("%", [module, map]) => match node(map) {
+                Some(("%{}", _, pairs)) => self.map_pattern(Some(module), pairs, outer, bindings),
+                _ => Err(unsupported("struct pattern", term)),
+            },
+            ("<<>>", segments) => self.binary(segments, &mut |t, e| t.pattern(e, outer, bindings)),
+            _ => Err(unsupported("pattern", term)),
+        }
+    }
+
+    fn map_pattern(
+        &mut self,
+        module: Option<&Term>,
+        pairs: &[Term],
+        outer: &Scope,
+        bindings: &mut HashMap<String, String>,
+    ) -> FromBeamResult<Term> {
+        let line = self.line;
+        let mut fields = Vec::with_capacity(pairs.len() + 1);
+        if let Some(module) = module {
+            let module = self.pattern(module, outer, bindings)?;
+            fields.push(tuple(vec![
+                atom("map_field_exact"),
+                int(line),
+                lit_atom(line, "__struct__"),
+                module,
+            ]));
+        }
+        for pair in pairs.iter() {
+            match tuple_elements(pair) {
+                Some([key, value]) => {
+                    let key = self.pattern(key, outer, bindings)?;
+                    let value = self.pattern(value, outer, bindings)?;
+                    fields.push(tuple(vec![atom("map_field_exact"), int(line), key, value]));
+                }
+                _ => return Err(unsupported("map pattern field", pair)),
+            }
+        }
+        Ok(tuple(vec![atom("map"), int(line), list(fields)]))
+    }
+
+    /// Allocates a new Erlang variable for a binding of the given Elixir variable
+    fn bind(&mut self, name: &str, key: &str) -> String {
+        let version = self.versions.entry(key.to_string()).or_insert(0);
+        *version += 1;
+        format!("_{}@{}", name, version)
+    }
+
+    /// Translates a list, where the last element may be `head | tail`
+    fn list<F>(&mut self, elements: &[Term], mut f: F) -> FromBeamResult<Term>
+    where
+        F: FnMut(&mut Self, &Term) -> FromBeamResult<Term>,
+    {
+        let line = self.line;
+        let mut result = nil(line);
+        for (i, element) in elements.iter().enumerate().rev() {
+            if i == elements.len() - 1 {
+                if let Some(("|", _, [head, tail])) = node(element) {
+                    let head = f(self, head)?;
+                    let tail = f(self, tail)?;
+                    result = cons(line, head, tail);
+                    continue;
+                }
+            }
+            let element = f(self, element)?;
+            result = cons(line, element, result);
+        }
+        Ok(result)
+    }
+
+    /// Translates the segments of `<<>>`, each of the form `value :: spec`
+    fn binary(
+        &mut self,
+        segments: &[Term],
+        f: &mut dyn FnMut(&mut Self, &Term) -> FromBeamResult<Term>,
+    ) -> FromBeamResult<Term> {
+        let line = self.line;
+        let mut elements = Vec::with_capacity(segments.len());
+        for segment in segments.iter() {
+            let (value, spec) = match node(segment) {
+                Some(("::", _, [value, spec])) => (value, Some(spec)),
+                _ => (segment, None),
+            };
+            let mut size = None;
+            let mut specs = Vec::new();
+            if let Some(spec) = spec {
+                self.binary_spec(spec, &mut size, &mut specs)?;
+            }
+            // Erlang does not allow type specifiers on string literals
+            let value = match *value {
+                Term::Binary(ref bin) if size.is_none() => {
+                    specs.retain(|s| !is_atom(s, "binary") && !is_atom(s, "bitstring"));
+                    string(line, &bin.bytes)
+                }
+                _ => f(self, value)?,
+            };
+            let size = match size {
+                Some(size) => f(self, &size)?,
+                None => atom("default"),
+            };
+            let specs = if specs.is_empty() {
+                atom("default")
+            } else {
+                list(specs)
+            };
+            elements.push(tuple(vec![
+                atom("bin_element"),
+                int(line),
+                value,
+                size,
+                specs,
+            ]));
+        }
+        Ok(tuple(vec![atom("bin"), int(line), list(elements)]))
+    }
+
+    fn binary_spec(
+        &mut self,
+        spec: &Term,
+        size: &mut Option<Term>,
+        specs: &mut Vec<Term>,
+    ) -> FromBeamResult<()> {
+        match node(spec) {
+            Some(("-", _, [left, right])) => {
+                self.binary_spec(left, size, specs)?;
+                self.binary_spec(right, size, specs)
+            }
+            Some(("size", _, [n])) => {
+                *size = Some(n.clone());
+                Ok(())
+            }
+            Some(("unit", _, [n])) => match to_i32(n) {
+                Some(unit) => {
+                    specs.push(tuple(vec![atom("unit"), int(unit)]));
+                    Ok(())
+                }
+                None => Err(unsupported("binary unit", spec)),
+            },
+            Some(("*", _, [n, unit])) => match to_i32(unit) {
+                Some(unit) => {
+                    *size = Some(n.clone());
+                    specs.push(tuple(vec![atom("unit"), int(unit)]));
+                    Ok(())
+                }
+                None => Err(unsupported("binary unit", spec)),
+            },
+            Some((name, _, [])) | Some((name, _, [Term::Atom(_)])) => {
+                specs.push(atom(name));
+                Ok(())
+            }
+            _ if to_i32(spec).is_some() => {
+                *size = Some(spec.clone());
+                Ok(())
+            }
+            _ => Err(unsupported("binary type", spec)),
+        }
+    }
+
+    fn literal(&mut self, term: &Term) -> FromBeamResult<Term> {
+        let line = self.line;
+        match *term {
+            Term::Atom(ref a) => Ok(lit_atom(line, &a.name)),
+            Term::FixInteger(ref i) => Ok(lit_int(line, i.value as i64)),
+            Term::BigInteger(ref i) => {
+                let abs = tuple(vec![
+                    atom("integer"),
+                    int(line),
+                    Term::from(etf::BigInteger {
+                        value: i.value.abs(),
+                    }),
+                ]);
+                Ok(negate_if(line, i.value < BigInt::zero(), abs))
+            }
+            Term::Float(ref f) => {
+                let abs = tuple(vec![
+                    atom("float"),
+                    int(line),
+                    Term::from(etf::Float::from(f.value.abs())),
+                ]);
+                Ok(negate_if(line, f.value < 0.0, abs))
+            }
+            Term::Binary(ref bin) => {
+                let elements = if bin.bytes.is_empty() {
+                    vec![]
+                } else {
+                    vec![tuple(vec![
+                        atom("bin_element"),
+                        int(line),
+                        string(line, &bin.bytes),
+                        atom("default"),
+                        atom("default"),
+                    ])]
+                };
+                Ok(tuple(vec![atom("bin"), int(line), list(elements)]))
+            }
+            Term::BitBinary(ref bin) => {
+                let last = bin.bytes.len().saturating_sub(1);
+                let elements = bin
+                    .bytes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, byte)| {
+                        let (value, size) = if i == last && bin.tail_bits_size < 8 {
+                            let bits = bin.tail_bits_size as u32;
+                            ((*byte as i64) >> (8 - bits), lit_int(line, bits as i64))
+                        } else {
+                            (*byte as i64, atom("default"))
+                        };
+                        tuple(vec![
+                            atom("bin_element"),
+                            int(line),
+                            lit_int(line, value),
+                            size,
+                            atom("default"),
+                        ])
+                    })
+                    .collect();
+                Ok(tuple(vec![atom("bin"), int(line), list(elements)]))
+            }
+            _ => Err(unsupported("literal", term)),
+        }
+    }
+}
+
+const UNARY_OPERATORS: &[&str] = &["-", "+", "not", "bnot"];
+const BINARY_OPERATORS: &[&str] = &[
+    "+", "-", "*", "/", "div", "rem", "band", "bor", "bxor", "bsl", "bsr", "and", "or", "xor",
+    "andalso", "orelse", "==", "/=", "=<", "<", ">=", ">", "=:=", "=/=", "++", "--", "!",
+];
+
+/// Splits clause heads of the form `[args..., guard]` wrapped in `when`, where
+/// `a when b when c` means either guard `b` or `c` may succeed
+fn split_when(head: &[Term]) -> (Vec<Term>, Vec<&Term>) {
+    if let [single] = head {
+        if let Some(("when", _, args)) = node(single) {
+            if let Some((guard, args)) = args.split_last() {
+                let mut guards = Vec::new();
+                let mut guard = guard;
+                while let Some(("when", _, [left, right])) = node(guard) {
+                    guards.push(left);
+                    guard = right;
+                }
+                guards.push(guard);
+                return (args.to_vec(), guards);
+            }
+        }
+    }
+    (head.to_vec(), Vec::new())
+}
+
+/// Identifies an Elixir variable by name and hygiene context, so that variables introduced by
+/// macros do not clash with variables of the same name in user code
+fn var_key(name: &str, meta: &Term, context: &Term) -> String {
+    let context = atom_name(context).unwrap_or("nil");
+    match keyword(meta, "counter").and_then(to_i32) {
+        Some(counter) => format!("{}/{}/{}", name, context, counter),
+        None => format!("{}/{}", name, context),
+    }
+}
+
+/// Matches an Elixir AST node, `{Name, Meta, Args}`
+fn node(term: &Term) -> Option<(&str, &Term, &[Term])> {
+    match tuple_elements(term) {
+        Some([head, meta, Term::List(ref args)]) => {
+            atom_name(head).map(|name| (name, meta, &args.elements[..]))
+        }
+        Some([head, meta, args @ Term::Atom(_)]) => {
+            atom_name(head).map(|name| (name, meta, std::slice::from_ref(args)))
+        }
+        _ => None,
+    }
+}
+
+fn is_empty_block(term: &Term) -> bool {
+    match node(term) {
+        Some(("__block__", _, args)) => args.is_empty(),
+        _ => false,
+    }
+}
+
+fn is_truthy_literal(term: &Term) -> bool {
+    match *term {
+        Term::Atom(ref a) => a.name != "false" && a.name != "nil",
+        Term::FixInteger(_) | Term::BigInteger(_) | Term::Float(_) | Term::Binary(_) => true,
+        _ => false,
+    }
+}
+
+/// Looks up `key` in a keyword list
+fn keyword<'a>(term: &'a Term, key: &str) -> Option<&'a Term> {
+    match *term {
+        Term::List(ref l) => l.elements.iter().find_map(|e| match tuple_elements(e) {
+            Some([k, v]) if is_atom(k, key) => Some(v),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+fn tuple_elements(term: &Term) -> Option<&[Term]> {
+    match *term {
+        Term::Tuple(ref t) => Some(&t.elements[..]),
+        _ => None,
+    }
+}
+
+fn atom_name(term: &Term) -> Option<&str> {
+    match *term {
+        Term::Atom(ref a) => Some(a.name.as_str()),
+        _ => None,
+    }
+}
+
+fn is_atom(term: &Term, name: &str) -> bool {
+    atom_name(term) == Some(name)
+}
+
+fn to_i32(term: &Term) -> Option<i32> {
+    match *term {
+        Term::FixInteger(ref i) => Some(i.value),
+        _ => None,
+    }
+}
+
+fn atom(name: &str) -> Term {
+    Term::from(etf::Atom::from(name))
+}
+
+fn int(value: i32) -> Term {
+    Term::from(etf::FixInteger::from(value))
+}
+
+fn tuple(elements: Vec<Term>) -> Term {
+    Term::from(etf::Tuple::from(elements))
+}
+
+fn list(elements: Vec<Term>) -> Term {
+    Term::from(etf::List::from(elements))
+}
+
+fn var_(line: i32, name: &str) -> Term {
+    tuple(vec![atom("var"), int(line), atom(name)])
+}
+
+fn nil(line: i32) -> Term {
+    tuple(vec![atom("nil"), int(line)])
+}
+
+fn cons(line: i32, head: Term, tail: Term) -> Term {
+    tuple(vec![atom("cons"), int(line), head, tail])
+}
+
+fn lit_atom(line: i32, name: &str) -> Term {
+    tuple(vec![atom("atom"), int(line), atom(name)])
+}
+
+fn lit_int(line: i32, value: i64) -> Term {
+    let abs = value.abs();
+    let abs = if abs <= std::i32::MAX as i64 {
+        int(abs as i32)
+    } else {
+        Term::from(etf::BigInteger::from(abs))
+    };
+    let abs = tuple(vec![atom("integer"), int(line), abs]);
+    negate_if(line, value < 0, abs)
+}
+
+fn negate_if(line: i32, negative: bool, abs: Term) -> Term {
+    if negative {
+        tuple(vec![atom("op"), int(line), atom("-"), abs])
+    } else {
+        abs
+    }
+}
+
+fn string(line: i32, bytes: &[u8]) -> Term {
+    let chars = bytes.iter().map(|b| int(*b as i32)).collect();
+    tuple(vec![atom("string"), int(line), list(chars)])
+}
@@ -40,14 +40,48 @@ pub struct AbstractCode {
     pub code: etf::Term,
 }
 impl AbstractCode {
+    /// Loads the abstract code from the `"Abst"` chunk, or if that is missing or empty,
+    /// from the `"Dbgi"` chunk used by OTP 20+ and Elixir
     pub fn from_beam_file<P: AsRef<Path>>(path: P) -> FromBeamResult<Self> {
         let beam = crate::beam::reader::RawBeamFile::from_file(path)?;
-        let chunk = beam
-            .chunks()
-            .into_iter()
-            .find(|c| c.id() == b"Abst")
+        let chunks = beam.chunks();
+        if let Some(chunk) = chunks
+            .iter()
+            .find(|c| c.id() == b"Abst" && !c.data.is_empty())
+        {
+            let code = etf::Term::decode(std::io::Cursor::new(&chunk.data))?;
+            return Ok(AbstractCode { code });
+        }
+        let chunk = chunks
+            .iter()
+            .find(|c| c.id() == b"Dbgi")
             .ok_or(FromBeamError::NoDebugInfo)?;
-        let code = etf::Term::decode(std::io::Cursor::new(&chunk.data))?;
+        let debug_info = etf::Term::decode(std::io::Cursor::new(&chunk.data))?;
+        Self::from_debug_info(&debug_info)
+    }
+
+    /// Converts a `{debug_info_v1, Backend, Data}` term to abstract code
+    fn from_debug_info(debug_info: &etf::Term) -> FromBeamResult<Self> {
+        let (_, backend, data) = debug_info.as_match(("debug_info_v1", atom(), any()))?;
+        let forms = match backend.as_str() {
+            "erl_abstract_code" => {
+                if data.as_match("none").is_ok() {
+                    return Err(FromBeamError::NoDebugInfo);
+                }
+                let (forms, _options) = data.as_match((any(), any()))?;
+                forms.clone()
+            }
+            "elixir_erl" => {
+                let (_, map, _specs) = data.as_match(("elixir_v1", any(), any()))?;
+                let forms = super::elixir_v1::to_abstract_forms(map)?;
+                etf::Term::from(etf::List::from(forms))
+            }
+            _ => return Err(FromBeamError::UnsupportedDebugInfo(backend)),
+        };
+        let code = etf::Term::from(etf::Tuple::from(vec![
+            etf::Term::from(etf::Atom::from("raw_abstract_v1")),
+            forms,
+        ]));
         Ok(AbstractCode { code })
     }
     pub fn to_forms(&self) -> FromBeamResult<Vec<form::Form>> {
@@ -0,0 +1,816 @@
+//! Prints an `AST` back to Erlang source code.
+//!
+//! This is used to feed modules loaded from BEAM files through a frontend which only accepts
+//! Erlang source. Type specs, callbacks and `-file` attributes are not needed to compile a module
+//! and are omitted, everything else is printed in a form which preserves its meaning, with
+//! operator expressions fully parenthesized.
+use std::fmt::Write;
+
+use crate::serialization::etf;
+
+use super::ast::clause::Clause;
+use super::ast::common;
+use super::ast::expr::{self, Expression, Qualifier};
+use super::ast::form::{self, Form};
+use super::ast::guard::{Guard, OrGuard};
+use super::ast::literal;
+use super::ast::pat::Pattern;
+
+/// Prints the given forms as Erlang source
+pub fn print(forms: &[Form]) -> String {
+    let mut printer = Printer::default();
+    for form in forms.iter() {
+        printer.form(form);
+    }
+    printer.buf
+}
+
+/// Implemented by the syntax node types which can be printed as Erlang source
+trait Print {
+    fn print(&self, p: &mut Printer);
+
+    /// Whether this node can be printed without surrounding parentheses in any position
+    fn is_primary(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Default)]
+struct Printer {
+    buf: String,
+}
+impl Printer {
+    fn push(&mut self, s: &str) {
+        self.buf.push_str(s);
+    }
+
+    fn form(&mut self, form: &Form) {
+        match *form {
+            Form::Module(ref x) => {
+                self.push("-module(");
+                self.atom(&x.name);
+                self.push(").\n");
+            }
+            Form::Behaviour(ref x) => {
+                self.push("-behaviour(");
+                self.atom(&x.name);
+                self.push(").\n");
+            }
+            Form::Export(ref x) => {
+                self.push("-export([");
+                self.sep(&x.funs, ", ", |p, e| p.name_arity(&e.fun, e.arity));
+                self.push("]).\n");
+            }
+            Form::Import(ref x) => {
+                self.push("-import(");
+                self.atom(&x.module);
+                self.push(", [");
+                self.sep(&x.funs, ", ", |p, i| p.name_arity(&i.fun, i.arity));
+                self.push("]).\n");
+            }
+            Form::Compile(ref x) => {
+                if is_literal(&x.options) {
+                    self.push("-compile(");
+                    self.term(&x.options);
+                    self.push(").\n");
+                }
+            }
+            Form::Attr(ref x) => {
+                // Typed record declarations are encoded as a `type` attribute, and like other
+                // type information are not needed for compilation
+                if !TYPE_ATTRIBUTES.contains(&x.name.as_str()) && is_literal(&x.value) {
+                    self.push("-");
+                    self.atom(&x.name);
+                    self.push("(");
+                    self.term(&x.value);
+                    self.push(").\n");
+                }
+            }
+            Form::Record(ref x) => {
+                self.push("-record(");
+                self.atom(&x.name);
+                self.push(", {");
+                self.sep(&x.fields, ", ", |p, f| p.record_field_decl(f));
+                self.push("}).\n");
+            }
+            Form::Fun(ref x) => {
+                let name = x.name.clone();
+                self.sep(&x.clauses, ";\n", |p, c| {
+                    p.atom(&name);
+                    p.clause(c);
+                });
+                self.push(".\n");
+            }
+            Form::ExportType(_) | Form::Type(_) | Form::Spec(_) | Form::File(_) | Form::Eof(_) => {}
+        }
+    }
+
+    fn record_field_decl(&mut self, field: &form::RecordFieldDecl) {
+        self.atom(&field.name);
+        self.push(" = ");
+        field.default_value.print(self);
+    }
+
+    fn name_arity(&mut self, name: &str, arity: u32) {
+        self.atom(name);
+        write!(self.buf, "/{}", arity).unwrap();
+    }
+
+    fn sep<T, F>(&mut self, items: &[T], sep: &str, mut f: F)
+    where
+        F: FnMut(&mut Self, &T),
+    {
+        for (i, item) in items.iter().enumerate() {
+            if i != 0 {
+                self.push(sep);
+            }
+            f(self, item);
+        }
+    }
+
+    fn list<T: Print>(&mut self, items: &[T]) {
+        self.sep(items, ", ", |p, x| x.print(p));
+    }
+
+    fn body(&mut self, body: &[Expression]) {
+        self.sep(body, ",\n    ", |p, x| x.print(p));
+    }
+
+    /// Prints the head and body of a clause, i.e. `(Patterns) when Guards -> Body`
+    fn clause(&mut self, clause: &Clause) {
+        self.push("(");
+        self.list(&clause.patterns);
+        self.push(")");
+        self.guards_and_body(clause);
+    }
+
+    fn case_clauses(&mut self, clauses: &[Clause]) {
+        self.sep(clauses, ";\n    ", |p, c| {
+            p.list(&c.patterns);
+            p.guards_and_body(c);
+        });
+    }
+
+    fn guards_and_body(&mut self, clause: &Clause) {
+        if !clause.guards.is_empty() {
+            self.push(" when ");
+            self.guards(&clause.guards);
+        }
+        self.push(" ->\n    ");
+        self.body(&clause.body);
+    }
+
+    fn guards(&mut self, guards: &[OrGuard]) {
+        self.sep(guards, "; ", |p, g| p.list(&g.and_guards));
+    }
+
+    fn catch_clauses(&mut self, clauses: &[Clause]) {
+        self.sep(clauses, ";\n    ", |p, c| {
+            // Catch clause patterns are represented as a `{Class, Reason, Stacktrace}` tuple
+            match c.patterns.first() {
+                Some(Pattern::Tuple(ref t)) if t.elements.len() == 3 => {
+                    t.elements[0].print(p);
+                    p.push(":");
+                    t.elements[1].print(p);
+                    match t.elements[2] {
+                        Pattern::Var(ref v) if v.is_anonymous() => (),
+                        ref stacktrace => {
+                            p.push(":");
+                            stacktrace.print(p);
+                        }
+                    }
+                }
+                _ => p.list(&c.patterns),
+            }
+            p.guards_and_body(c);
+        });
+    }
+
+    fn atom(&mut self, name: &str) {
+        if is_bare_atom(name) {
+            self.push(name);
+        } else {
+            self.push("'");
+            self.escaped(name, '\'');
+            self.push("'");
+        }
+    }
+
+    fn string(&mut self, s: &str) {
+        self.push("\"");
+        self.escaped(s, '"');
+        self.push("\"");
+    }
+
+    fn escaped(&mut self, s: &str, quote: char) {
+        for c in s.chars() {
+            match c {
+                '\\' => self.push("\\\\"),
+                '\n' => self.push("\\n"),
+                '\t' => self.push("\\t"),
+                c if c == quote => {
+                    self.buf.push('\\');
+                    self.buf.push(c);
+                }
+                c if c < ' ' || c > '~' => write!(self.buf, "\\x{{{:X}}}", c as u32).unwrap(),
+                c => self.buf.push(c),
+            }
+        }
+    }
+
+    fn float(&mut self, value: f64) {
+        // Erlang requires digits on both sides of the decimal point, even with an exponent
+        let s = format!("{:?}", value);
+        match s.find('e') {
+            Some(i) if !s[..i].contains('.') => write!(self.buf, "{}.0{}", &s[..i], &s[i..]),
+            _ => write!(self.buf, "{}", s),
+        }
+        .unwrap();
+    }
+
+    /// Prints a term as found in attribute values, see `is_literal`
+    fn term(&mut self, term: &etf::Term) {
+        use crate::serialization::etf::Term;
+        match *term {
+            Term::Atom(ref x) => self.atom(&x.name),
+            Term::FixInteger(ref x) => write!(self.buf, "{}", x.value).unwrap(),
+            Term::BigInteger(ref x) => write!(self.buf, "{}", x.value).unwrap(),
+            Term::Float(ref x) => self.float(x.value),
+            Term::Binary(ref x) => write!(self.buf, "{}", x).unwrap(),
+            Term::BitBinary(ref x) => write!(self.buf, "{}", x).unwrap(),
+            Term::List(ref x) => {
+                self.push("[");
+                self.sep(&x.elements, ", ", |p, e| p.term(e));
+                self.push("]");
+            }
+            Term::ImproperList(ref x) => {
+                self.push("[");
+                self.sep(&x.elements, ", ", |p, e| p.term(e));
+                self.push(" | ");
+                self.term(&x.last);
+                self.push("]");
+            }
+            Term::Tuple(ref x) => {
+                self.push("{");
+                self.sep(&x.elements, ", ", |p, e| p.term(e));
+                self.push("}");
+            }
+            Term::Map(ref x) => {
+                self.push("#{");
+                self.sep(&x.entries, ", ", |p, (k, v)| {
+                    p.term(k);
+                    p.push(" => ");
+                    p.term(v);
+                });
+                self.push("}");
+            }
+            Term::Pid(_)
+            | Term::Port(_)
+            | Term::Reference(_)
+            | Term::ExternalFun(_)
+            | Term::InternalFun(_) => unreachable!("not a literal term: {}", term),
+        }
+    }
+
+    /// Prints `node`, wrapping it in parentheses unless it is a primary expression
+    fn primary<T: Print>(&mut self, node: &T) {
+        if node.is_primary() {
+            node.print(self);
+        } else {
+            self.push("(");
+            node.print(self);
+            self.push(")");
+        }
+    }
+}
+
+const TYPE_ATTRIBUTES: &[&str] = &["type", "opaque", "spec", "callback", "export_type"];
+
+/// Returns true if `term` can be written as a literal in Erlang source
+fn is_literal(term: &etf::Term) -> bool {
+    use crate::serialization::etf::Term;
+    match *term {
+        Term::Pid(_)
+        | Term::Port(_)
+        | Term::Reference(_)
+        | Term::ExternalFun(_)
+        | Term::InternalFun(_) => false,
+        Term::List(ref x) => x.elements.iter().all(is_literal),
+        Term::ImproperList(ref x) => x.elements.iter().all(is_literal) && is_literal(&x.last),
+        Term::Tuple(ref x) => x.elements.iter().all(is_literal),
+        Term::Map(ref x) => x
+            .entries
+            .iter()
+            .all(|(k, v)| is_literal(k) && is_literal(v)),
+        _ => true,
+    }
+}
+
+const RESERVED_WORDS: &[&str] = &[
+    "after", "and", "andalso", "band", "begin", "bnot", "bor", "bsl", "bsr", "bxor", "case",
+    "catch", "cond", "div", "end", "fun", "if", "let", "not", "of", "or", "orelse", "receive",
+    "rem", "try", "when", "xor",
+];
+
+fn is_bare_atom(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_lowercase() => (),
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+        && !RESERVED_WORDS.contains(&name)
+}
+
+impl Print for literal::Integer {
+    fn print(&self, p: &mut Printer) {
+        write!(p.buf, "{}", self.value).unwrap();
+    }
+    fn is_primary(&self) -> bool {
+        true
+    }
+}
+impl Print for literal::Float {
+    fn print(&self, p: &mut Printer) {
+        p.float(self.value);
+    }
+    fn is_primary(&self) -> bool {
+        true
+    }
+}
+impl Print for literal::Str {
+    fn print(&self, p: &mut Printer) {
+        p.string(&self.value);
+    }
+    fn is_primary(&self) -> bool {
+        true
+    }
+}
+impl Print for literal::Char {
+    fn print(&self, p: &mut Printer) {
+        write!(p.buf, "{}", self.value as u32).unwrap();
+    }
+    fn is_primary(&self) -> bool {
+        true
+    }
+}
+impl Print for literal::Atom {
+    fn print(&self, p: &mut Printer) {
+        p.atom(&self.value);
+    }
+    fn is_primary(&self) -> bool {
+        true
+    }
+}
+impl Print for common::Var {
+    fn print(&self, p: &mut Printer) {
+        p.push(&self.name);
+    }
+    fn is_primary(&self) -> bool {
+        true
+    }
+}
+impl Print for common::Nil {
+    fn print(&self, p: &mut Printer) {
+        p.push("[]");
+    }
+    fn is_primary(&self) -> bool {
+        true
+    }
+}
+impl<L: Print, R: Print> Print for common::Match<L, R> {
+    fn print(&self, p: &mut Printer) {
+        p.primary(&self.left);
+        p.push(" = ");
+        self.right.print(p);
+    }
+}
+impl<T: Print> Print for common::Tuple<T> {
+    fn print(&self, p: &mut Printer) {
+        p.push("{");
+        p.list(&self.elements);
+        p.push("}");
+    }
+    fn is_primary(&self) -> bool {
+        true
+    }
+}
+impl<T: Print + AsCons<T>> Print for common::Cons<T> {
+    fn print(&self, p: &mut Printer) {
+        p.push("[");
+        self.head.print(p);
+        let mut tail = &self.tail;
+        loop {
+            if tail.is_nil() {
+                break;
+            }
+            match tail.as_cons() {
+                Some(cons) => {
+                    p.push(", ");
+                    cons.head.print(p);
+                    tail = &cons.tail;
+                }
+                None => {
+                    p.push(" | ");
+                    tail.print(p);
+                    break;
+                }
+            }
+        }
+        p.push("]");
+    }
+    fn is_primary(&self) -> bool {
+        true
+    }
+}
+impl<T: Print> Print for common::Binary<T> {
+    fn print(&self, p: &mut Printer) {
+        p.push("<<");
+        p.sep(&self.elements, ", ", |p, e| e.print(p));
+        p.push(">>");
+    }
+    fn is_primary(&self) -> bool {
+        true
+    }
+}
+impl<T: Print> Print for common::BinElement<T> {
+    fn print(&self, p: &mut Printer) {
+        p.primary(&self.element);
+        if let Some(ref size) = self.size {
+            p.push(":");
+            p.primary(size);
+        }
+        if let Some(ref tsl) = self.tsl {
+            p.push("/");
+            p.sep(tsl, "-", |p, spec| {
+                p.push(&spec.name);
+                if let Some(value) = spec.value {
+                    write!(p.buf, ":{}", value).unwrap();
+                }
+            });
+        }
+    }
+}
+impl<T: Print> Print for common::UnaryOp<T> {
+    fn print(&self, p: &mut Printer) {
+        p.push(&self.operator);
+        p.push(" ");
+        p.primary(&self.operand);
+    }
+}
+impl<T: Print> Print for common::BinaryOp<T> {
+    fn print(&self, p: &mut Printer) {
+        p.primary(&self.left_operand);
+        p.push(" ");
+        p.push(&self.operator);
+        p.push(" ");
+        p.primary(&self.right_operand);
+    }
+}
+impl<T: Print> Print for common::Record<T> {
+    fn print(&self, p: &mut Printer) {
+        if let Some(ref base) = self.base {
+            p.primary(base);
+        }
+        p.push("#");
+        p.atom(&self.name);
+        p.push("{");
+        p.sep(&self.fields, ", ", |p, f| {
+            match f.name {
+                Some(ref name) => p.atom(name),
+                None => p.push("_"),
+            }
+            p.push(" = ");
+            f.value.print(p);
+        });
+        p.push("}");
+    }
+}
+impl<T: Print> Print for common::RecordIndex<T> {
+    fn print(&self, p: &mut Printer) {
+        if let Some(ref base) = self.base {
+            p.primary(base);
+        }
+        p.push("#");
+        p.atom(&self.record);
+        p.push(".");
+        p.atom(&self.field);
+    }
+}
+impl<T: Print> Print for common::Map<T> {
+    fn print(&self, p: &mut Printer) {
+        if let Some(ref base) = self.base {
+            p.primary(base);
+        }
+        p.push("#{");
+        p.sep(&self.pairs, ", ", |p, pair| {
+            pair.key.print(p);
+            p.push(if pair.is_assoc { " => " } else { " := " });
+            pair.value.print(p);
+        });
+        p.push("}");
+    }
+    fn is_primary(&self) -> bool {
+        self.base.is_none()
+    }
+}
+impl<T: Print> Print for common::LocalCall<T> {
+    fn print(&self, p: &mut Printer) {
+        p.primary(&self.function);
+        p.push("(");
+        p.list(&self.args);
+        p.push(")");
+    }
+    fn is_primary(&self) -> bool {
+        true
+    }
+}
+impl<T: Print> Print for common::RemoteCall<T> {
+    fn print(&self, p: &mut Printer) {
+        p.primary(&self.module);
+        p.push(":");
+        p.primary(&self.function);
+        p.push("(");
+        p.list(&self.args);
+        p.push(")");
+    }
+    fn is_primary(&self) -> bool {
+        true
+    }
+}
+impl Print for common::InternalFun {
+    fn print(&self, p: &mut Printer) {
+        p.push("fun ");
+        p.name_arity(&self.function, self.arity);
+    }
+    fn is_primary(&self) -> bool {
+        true
+    }
+}
+impl Print for common::ExternalFun {
+    fn print(&self, p: &mut Printer) {
+        p.push("fun ");
+        p.primary(&self.module);
+        p.push(":");
+        p.primary(&self.function);
+        p.push("/");
+        p.primary(&self.arity);
+    }
+    fn is_primary(&self) -> bool {
+        true
+    }
+}
+
+/// Allows printing cons cells as flat lists regardless of the node type
+trait AsCons<T> {
+    fn as_cons(&self) -> Option<&common::Cons<T>>;
+    fn is_nil(&self) -> bool;
+}
+impl AsCons<Expression> for Expression {
+    fn as_cons(&self) -> Option<&common::Cons<Expression>> {
+        match *self {
+            Expression::Cons(ref x) => Some(x),
+            _ => None,
+        }
+    }
+    fn is_nil(&self) -> bool {
+        match *self {
+            Expression::Nil(_) => true,
+            _ => false,
+        }
+    }
+}
+impl AsCons<Pattern> for Pattern {
+    fn as_cons(&self) -> Option<&common::Cons<Pattern>> {
+        match *self {
+            Pattern::Cons(ref x) => Some(x),
+            _ => None,
+        }
+    }
+    fn is_nil(&self) -> bool {
+        match *self {
+            Pattern::Nil(_) => true,
+            _ => false,
+        }
+    }
+}
+impl AsCons<Guard> for Guard {
+    fn as_cons(&self) -> Option<&common::Cons<Guard>> {
+        match *self {
+            Guard::Cons(ref x) => Some(x),
+            _ => None,
+        }
+    }
+    fn is_nil(&self) -> bool {
+        match *self {
+            Guard::Nil(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Print for Expression {
+    fn print(&self, p: &mut Printer) {
+        match *self {
+            Expression::Integer(ref x) => x.print(p),
+            Expression::Float(ref x) => x.print(p),
+            Expression::String(ref x) => x.print(p),
+            Expression::Char(ref x) => x.print(p),
+            Expression::Atom(ref x) => x.print(p),
+            Expression::Match(ref x) => x.print(p),
+            Expression::Var(ref x) => x.print(p),
+            Expression::Tuple(ref x) => x.print(p),
+            Expression::Nil(ref x) => x.print(p),
+            Expression::Cons(ref x) => x.print(p),
+            Expression::Binary(ref x) => x.print(p),
+            Expression::UnaryOp(ref x) => x.print(p),
+            Expression::BinaryOp(ref x) => x.print(p),
+            Expression::Record(ref x) => x.print(p),
+            Expression::RecordIndex(ref x) => x.print(p),
+            Expression::Map(ref x) => x.print(p),
+            Expression::Catch(ref x) => {
+                p.push("catch ");
+                x.expr.print(p);
+            }
+            Expression::LocalCall(ref x) => x.print(p),
+            Expression::RemoteCall(ref x) => x.print(p),
+            Expression::Comprehension(ref x) => x.print(p),
+            Expression::Block(ref x) => {
+                p.push("begin\n    ");
+                p.body(&x.body);
+                p.push("\nend");
+            }
+            Expression::If(ref x) => {
+                p.push("if\n    ");
+                p.sep(&x.clauses, ";\n    ", |p, c| {
+                    p.guards(&c.guards);
+                    p.push(" ->\n    ");
+                    p.body(&c.body);
+                });
+                p.push("\nend");
+            }
+            Expression::Case(ref x) => {
+                p.push("case ");
+                x.expr.print(p);
+                p.push(" of\n    ");
+                p.case_clauses(&x.clauses);
+                p.push("\nend");
+            }
+            Expression::Try(ref x) => x.print(p),
+            Expression::Receive(ref x) => x.print(p),
+            Expression::InternalFun(ref x) => x.print(p),
+            Expression::ExternalFun(ref x) => x.print(p),
+            Expression::AnonymousFun(ref x) => {
+                p.push("fun");
+                let name = x.name.clone();
+                p.sep(&x.clauses, ";\n    ", |p, c| {
+                    p.push(" ");
+                    if let Some(ref name) = name {
+                        p.push(name);
+                    }
+                    p.clause(c);
+                });
+                p.push("\nend");
+            }
+        }
+    }
+    fn is_primary(&self) -> bool {
+        match *self {
+            Expression::Integer(ref x) => x.is_primary(),
+            Expression::Float(ref x) => x.is_primary(),
+            Expression::String(ref x) => x.is_primary(),
+            Expression::Char(ref x) => x.is_primary(),
+            Expression::Atom(ref x) => x.is_primary(),
+            Expression::Var(ref x) => x.is_primary(),
+            Expression::Tuple(ref x) => x.is_primary(),
+            Expression::Nil(ref x) => x.is_primary(),
+            Expression::Cons(ref x) => x.is_primary(),
+            Expression::Binary(ref x) => x.is_primary(),
+            Expression::Map(ref x) => x.is_primary(),
+            Expression::LocalCall(ref x) => x.is_primary(),
+            Expression::RemoteCall(ref x) => x.is_primary(),
+            Expression::InternalFun(ref x) => x.is_primary(),
+            Expression::ExternalFun(ref x) => x.is_primary(),
+            Expression::Comprehension(_)
+            | Expression::Block(_)
+            | Expression::If(_)
+            | Expression::Case(_)
+            | Expression::Try(_)
+            | Expression::Receive(_) => true,
+            _ => false,
+        }
+    }
+}
+impl Print for expr::Comprehension {
+    fn print(&self, p: &mut Printer) {
+        p.push(if self.is_list { "[" } else { "<< " });
+        self.expr.print(p);
+        p.push(" || ");
+        p.sep(&self.qualifiers, ", ", |p, q| match *q {
+            Qualifier::Generator(ref g) => {
+                g.pattern.print(p);
+                p.push(" <- ");
+                g.expr.print(p);
+            }
+            Qualifier::BitStringGenerator(ref g) => {
+                g.pattern.print(p);
+                p.push(" <= ");
+                g.expr.print(p);
+            }
+            Qualifier::Filter(ref e) => e.print(p),
+        });
+        p.push(if self.is_list { "]" } else { " >>" });
+    }
+}
+impl Print for expr::Try {
+    fn print(&self, p: &mut Printer) {
+        p.push("try\n    ");
+        p.body(&self.body);
+        if !self.case_clauses.is_empty() {
+            p.push("\nof\n    ");
+            p.case_clauses(&self.case_clauses);
+        }
+        if !self.catch_clauses.is_empty() {
+            p.push("\ncatch\n    ");
+            p.catch_clauses(&self.catch_clauses);
+        }
+        if !self.after.is_empty() {
+            p.push("\nafter\n    ");
+            p.body(&self.after);
+        }
+        p.push("\nend");
+    }
+}
+impl Print for expr::Receive {
+    fn print(&self, p: &mut Printer) {
+        p.push("receive\n    ");
+        p.case_clauses(&self.clauses);
+        if let Some(ref timeout) = self.timeout {
+            p.push("\nafter\n    ");
+            timeout.print(p);
+            p.push(" ->\n    ");
+            p.body(&self.after);
+        }
+        p.push("\nend");
+    }
+}
+
+impl Print for Pattern {
+    fn print(&self, p: &mut Printer) {
+        match *self {
+            Pattern::Integer(ref x) => x.print(p),
+            Pattern::Float(ref x) => x.print(p),
+            Pattern::String(ref x) => x.print(p),
+            Pattern::Char(ref x) => x.print(p),
+            Pattern::Atom(ref x) => x.print(p),
+            Pattern::Var(ref x) => x.print(p),
+            Pattern::Match(ref x) => x.print(p),
+            Pattern::Tuple(ref x) => x.print(p),
+            Pattern::Nil(ref x) => x.print(p),
+            Pattern::Cons(ref x) => x.print(p),
+            Pattern::Binary(ref x) => x.print(p),
+            Pattern::UnaryOp(ref x) => x.print(p),
+            Pattern::BinaryOp(ref x) => x.print(p),
+            Pattern::Record(ref x) => x.print(p),
+            Pattern::RecordIndex(ref x) => x.print(p),
+            Pattern::Map(ref x) => x.print(p),
+        }
+    }
+    fn is_primary(&self) -> bool {
+        match *self {
+            Pattern::Match(_) | Pattern::UnaryOp(_) | Pattern::BinaryOp(_) => false,
+            Pattern::Record(_) | Pattern::RecordIndex(_) => false,
+            _ => true,
+        }
+    }
+}
+
+impl Print for Guard {
+    fn print(&self, p: &mut Printer) {
+        match *self {
+            Guard::Integer(ref x) => x.print(p),
+            Guard::Float(ref x) => x.print(p),
+            Guard::String(ref x) => x.print(p),
+            Guard::Char(ref x) => x.print(p),
+            Guard::Atom(ref x) => x.print(p),
+            Guard::Var(ref x) => x.print(p),
+            Guard::Tuple(ref x) => x.print(p),
+            Guard::Nil(ref x) => x.print(p),
+            Guard::Cons(ref x) => x.print(p),
+            Guard::Binary(ref x) => x.print(p),
+            Guard::UnaryOp(ref x) => x.print(p),
+            Guard::BinaryOp(ref x) => x.print(p),
+            Guard::Record(ref x) => x.print(p),
+            Guard::RecordIndex(ref x) => x.print(p),
+            Guard::LocalCall(ref x) => x.print(p),
+            Guard::RemoteCall(ref x) => x.print(p),
+        }
+    }
+    fn is_primary(&self) -> bool {
+        match *self {
+            Guard::UnaryOp(_) | Guard::BinaryOp(_) => false,
+            Guard::Record(_) | Guard::RecordIndex(_) => false,
+            _ => true,
+        }
+    }
+}
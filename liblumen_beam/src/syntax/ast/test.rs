@@ -9,3 +9,508 @@ fn it_works() {
         })
         .unwrap();
 }
+
+#[test]
+fn prints_erlang_source() {
+    let ast = AST::from_beam_file("tests/testdata/ast/test.beam").unwrap();
+    let source = ast.to_erlang_source();
+    assert!(source.starts_with("-module(test)."));
+}
+
+#[test]
+fn loads_elixir_debug_info() {
+    let ast = AST::from_beam_file("tests/testdata/reader/Elixir.Unicode.beam").unwrap();
+    let source = ast.to_erlang_source();
+    assert!(source.starts_with("-module('Elixir.Unicode')."));
+    assert!(source.contains("'__info__'(module) ->"));
+}
+
+#[test]
+fn prints_records() {
+    let source = test_beam_source();
+    // Field types are dropped along with the other type information
+    assert!(
+        source.contains("-record(my_record, {a = undefined, b = 10, c = undefined, d = foo}).\n")
+    );
+    assert!(source.contains("    #my_record{c = self(), _ = '_'}.\n"));
+    assert!(source.contains("guard(#my_record{_ = 10}) ->\n"));
+}
+
+#[test]
+fn prints_maps() {
+    let source = test_beam_source();
+    assert!(source.contains("#{123 => abc}"));
+    assert!(source.contains("guard(#{hello := X}) when "));
+    assert!(source.contains("guard({_, #{}, "));
+}
+
+#[test]
+fn prints_binaries() {
+    let source = test_beam_source();
+    assert!(source.contains("<<\"123\">>, <<\"123\", 2:2>>"));
+    assert!(source.contains("hello(<<Name/binary>>) ->\n"));
+    assert!(source.contains("<<10, Bin/binary>>"));
+}
+
+#[test]
+fn prints_funs() {
+    let source = test_beam_source();
+    assert!(source.contains("    [Fun(X) || X <- List].\n"));
+    assert!(source.contains(
+        "    (fun Rec([]) ->\n    0;\n     Rec([X | Xs]) ->\n    X + Rec(Xs)\nend)(List).\n"
+    ));
+}
+
+#[test]
+fn omits_types() {
+    let source = test_beam_source();
+    assert!(!source.contains("-type"));
+    assert!(!source.contains("-opaque"));
+    assert!(!source.contains("-spec"));
+    assert!(!source.contains("-callback"));
+    assert!(!source.contains("-export_type"));
+    // Operators used in specs are still printed in function bodies
+    assert!(source.contains("guard(X) when is_integer(X); is_atom(X) ->\n"));
+    assert!(source.contains("    (Num + 1) band 4294967295.\n"));
+}
+
+#[test]
+fn prints_try() {
+    use ast::clause::Clause;
+    use ast::common::{LocalCall, Tuple, Var};
+    use ast::expr::{Expression, Try};
+    use ast::form::FunDecl;
+    use ast::literal::Atom;
+    use ast::pat::Pattern;
+
+    let call = |name: &str| -> Expression {
+        LocalCall::new(1, Atom::new(1, name.to_string()).into(), vec![]).into()
+    };
+    let var = |name: &str| Var::new(1, name.to_string());
+
+    let try_ = Try::new(
+        1,
+        vec![call("f")],
+        vec![Clause::new(
+            1,
+            vec![Pattern::from(var("X"))],
+            vec![],
+            vec![var("X").into()],
+        )],
+        vec![
+            Clause::new(
+                1,
+                vec![Tuple::new(
+                    1,
+                    vec![
+                        Atom::new(1, "error".to_string()).into(),
+                        var("Reason").into(),
+                        var("_").into(),
+                    ],
+                )
+                .into()],
+                vec![],
+                vec![var("Reason").into()],
+            ),
+            Clause::new(
+                1,
+                vec![Tuple::new(
+                    1,
+                    vec![
+                        Atom::new(1, "throw".to_string()).into(),
+                        var("Value").into(),
+                        var("Stacktrace").into(),
+                    ],
+                )
+                .into()],
+                vec![],
+                vec![var("Stacktrace").into()],
+            ),
+        ],
+        vec![call("g")],
+    );
+    let fun = FunDecl::new(
+        1,
+        "t".to_string(),
+        vec![Clause::new(1, vec![], vec![], vec![try_.into()])],
+    );
+
+    assert_eq!(
+        printer::print(&[fun.into()]),
+        "t() ->\n    try\n    f()\nof\n    X ->\n    X\ncatch\n    error:Reason ->\n    Reason;\n    \
+         throw:Value:Stacktrace ->\n    Stacktrace\nafter\n    g()\nend.\n"
+    );
+}
+
+#[test]
+fn prints_receive() {
+    use ast::clause::Clause;
+    use ast::common::{Tuple, Var};
+    use ast::expr::Receive;
+    use ast::form::FunDecl;
+    use ast::literal::{Atom, Integer};
+
+    let var = |name: &str| Var::new(1, name.to_string());
+
+    let receive = Receive::new(
+        1,
+        vec![Clause::new(
+            1,
+            vec![Tuple::new(
+                1,
+                vec![Atom::new(1, "msg".to_string()).into(), var("X").into()],
+            )
+            .into()],
+            vec![],
+            vec![var("X").into()],
+        )],
+    )
+    .timeout(Integer::new(1, 100u32.into()).into())
+    .after(vec![Atom::new(1, "timeout".to_string()).into()]);
+    let fun = FunDecl::new(
+        1,
+        "r".to_string(),
+        vec![Clause::new(1, vec![], vec![], vec![receive.into()])],
+    );
+
+    assert_eq!(
+        printer::print(&[fun.into()]),
+        "r() ->\n    receive\n    {msg, X} ->\n    X\nafter\n    100 ->\n    timeout\nend.\n"
+    );
+}
+
+fn test_beam_source() -> String {
+    AST::from_beam_file("tests/testdata/ast/test.beam")
+        .unwrap()
+        .to_erlang_source()
+}
+
+/// Translation of the expanded Elixir AST found in the `elixir_v1` debug info
+mod elixir_v1 {
+    use crate::serialization::etf::{self, Term};
+    use crate::syntax::ast::format::elixir_v1::to_abstract_forms;
+    use crate::syntax::ast::format::raw_abstract_v1::AbstractCode;
+    use crate::syntax::ast::printer;
+
+    #[test]
+    fn translates_clauses_with_guards() {
+        // def sign(x) when is_integer(x) and x > 0, do: :positive
+        // def sign(_), do: :other
+        let source = source(vec![def(
+            "sign",
+            "def",
+            vec![
+                clause(
+                    vec![var("x")],
+                    vec![erlang(
+                        "andalso",
+                        vec![
+                            erlang("is_integer", vec![var("x")]),
+                            erlang(">", vec![var("x"), int(0)]),
+                        ],
+                    )],
+                    atom("positive"),
+                ),
+                clause(vec![var("_")], vec![], atom("other")),
+            ],
+        )]);
+        assert!(source.contains(
+            "sign(_x@1) when erlang:is_integer(_x@1) andalso (_x@1 > 0) ->\n    positive;\n\
+             sign(_) ->\n    other.\n"
+        ));
+    }
+
+    #[test]
+    fn translates_rebinding_to_new_variables() {
+        // defp inc(x) do
+        //   x = x + 1
+        //   x
+        // end
+        let source = source(vec![def(
+            "inc",
+            "defp",
+            vec![clause(
+                vec![var("x")],
+                vec![],
+                node(
+                    "__block__",
+                    vec![
+                        node("=", vec![var("x"), erlang("+", vec![var("x"), int(1)])]),
+                        var("x"),
+                    ],
+                ),
+            )],
+        )]);
+        assert!(source.contains("inc(_x@1) ->\n    _x@2 = _x@1 + 1,\n    _x@2.\n"));
+        // Private functions are not exported
+        assert!(source.contains("-export(['__info__'/1]).\n"));
+    }
+
+    #[test]
+    fn translates_maps() {
+        // def update(%{a: a} = m), do: %{m | a: a + 1, b: %{"c" => 2}}
+        let source = source(vec![def(
+            "update",
+            "def",
+            vec![clause(
+                vec![node(
+                    "=",
+                    vec![
+                        node("%{}", vec![tuple(vec![atom("a"), var("a")])]),
+                        var("m"),
+                    ],
+                )],
+                vec![],
+                node(
+                    "%{}",
+                    vec![node(
+                        "|",
+                        vec![
+                            var("m"),
+                            list(vec![
+                                tuple(vec![atom("a"), erlang("+", vec![var("a"), int(1)])]),
+                                tuple(vec![
+                                    atom("b"),
+                                    node("%{}", vec![tuple(vec![binary("c"), int(2)])]),
+                                ]),
+                            ]),
+                        ],
+                    )],
+                ),
+            )],
+        )]);
+        assert!(source.contains(
+            "update(#{a := _a@1} = _m@1) ->\n    _m@1#{a := _a@1 + 1, b := #{<<\"c\">> => 2}}.\n"
+        ));
+    }
+
+    #[test]
+    fn translates_binaries() {
+        // def tag(<<size::8, rest::binary>>), do: <<"tag", size::16, rest::binary>>
+        let segment = |value: Term, spec: Term| node("::", vec![value, spec]);
+        let source = source(vec![def(
+            "tag",
+            "def",
+            vec![clause(
+                vec![node(
+                    "<<>>",
+                    vec![
+                        segment(var("size"), int(8)),
+                        segment(var("rest"), node("binary", vec![])),
+                    ],
+                )],
+                vec![],
+                node(
+                    "<<>>",
+                    vec![
+                        segment(binary("tag"), node("binary", vec![])),
+                        segment(var("size"), int(16)),
+                        segment(var("rest"), node("binary", vec![])),
+                    ],
+                ),
+            )],
+        )]);
+        assert!(source.contains(
+            "tag(<<_size@1:8, _rest@1/binary>>) ->\n    \
+             <<\"tag\", _size@1:16, _rest@1/binary>>.\n"
+        ));
+    }
+
+    #[test]
+    fn translates_try() {
+        // def safe(f) do
+        //   try do
+        //     f.()
+        //   rescue
+        //     e in [ArgumentError] -> e
+        //   catch
+        //     value -> value
+        //   after
+        //     :ok
+        //   end
+        // end
+        let rescue = arrow(
+            vec![node(
+                "in",
+                vec![var("e"), list(vec![atom("Elixir.ArgumentError")])],
+            )],
+            var("e"),
+        );
+        let catch = arrow(vec![var("value")], var("value"));
+        let try_ = node(
+            "try",
+            vec![keywords(vec![
+                ("do", node_with(node(".", vec![var("f")]), vec![])),
+                ("rescue", list(vec![rescue])),
+                ("catch", list(vec![catch])),
+                ("after", atom("ok")),
+            ])],
+        );
+        let source = source(vec![def(
+            "safe",
+            "def",
+            vec![clause(vec![var("f")], vec![], try_)],
+        )]);
+        assert!(source.contains("    try\n    _f@1()\ncatch\n"));
+        assert!(source.contains("    throw:_value@1:_@1 ->\n    _value@1;\n"));
+        assert!(source.contains(
+            "    error:_@2:_@3 ->\n    \
+             case 'Elixir.Exception':normalize(error, _@2, _@3) of\n"
+        ));
+        assert!(source.contains(
+            "    #{'__struct__' := _@4} = _e@1 when _@4 =:= 'Elixir.ArgumentError' ->\n    _e@1;\n"
+        ));
+        assert!(source
+            .contains("    _ ->\n    erlang:raise(error, _@2, _@3)\nend\nafter\n    ok\nend.\n"));
+    }
+
+    #[test]
+    fn translates_receive() {
+        // def wait do
+        //   receive do
+        //     {:msg, x} when is_atom(x) -> x
+        //   after
+        //     100 -> :timeout
+        //   end
+        // end
+        let source = source(vec![def(
+            "wait",
+            "def",
+            vec![clause(
+                vec![],
+                vec![],
+                node(
+                    "receive",
+                    vec![keywords(vec![
+                        (
+                            "do",
+                            list(vec![arrow(
+                                vec![node(
+                                    "when",
+                                    vec![
+                                        tuple(vec![atom("msg"), var("x")]),
+                                        erlang("is_atom", vec![var("x")]),
+                                    ],
+                                )],
+                                var("x"),
+                            )]),
+                        ),
+                        ("after", list(vec![arrow(vec![int(100)], atom("timeout"))])),
+                    ])],
+                ),
+            )],
+        )]);
+        assert!(source.contains(
+            "wait() ->\n    receive\n    {msg, _x@1} when erlang:is_atom(_x@1) ->\n    _x@1\n\
+             after\n    100 ->\n    timeout\nend.\n"
+        ));
+    }
+
+    #[test]
+    fn generates_info_function() {
+        let source = source(vec![
+            def("public", "def", vec![clause(vec![], vec![], atom("ok"))]),
+            def("private", "defp", vec![clause(vec![], vec![], atom("ok"))]),
+        ]);
+        assert!(source.contains("-export(['__info__'/1, public/0]).\n"));
+        assert!(source.contains("'__info__'(module) ->\n    'Elixir.Test';\n"));
+        assert!(source.contains("'__info__'(functions) ->\n    [{public, 0}];\n"));
+        for key in &["attributes", "compile", "md5"] {
+            assert!(source.contains(&format!(
+                "'__info__'({}) ->\n    erlang:get_module_info('Elixir.Test', {});\n",
+                key, key
+            )));
+        }
+    }
+
+    /// Translates the given definitions of `Elixir.Test` and prints them as Erlang source
+    fn source(definitions: Vec<Term>) -> String {
+        let map = Term::from(etf::Map::from(vec![
+            (atom("module"), atom("Elixir.Test")),
+            (atom("line"), int(1)),
+            (atom("definitions"), list(definitions)),
+        ]));
+        let forms = to_abstract_forms(&map).unwrap();
+        let code = AbstractCode {
+            code: tuple(vec![atom("raw_abstract_v1"), list(forms)]),
+        };
+        printer::print(&code.to_forms().unwrap())
+    }
+
+    /// `{{Name, Arity}, Kind, Meta, Clauses}`
+    fn def(name: &str, kind: &str, clauses: Vec<Term>) -> Term {
+        let arity = match clauses[0] {
+            Term::Tuple(ref clause) => match clause.elements[1] {
+                Term::List(ref args) => args.elements.len() as i32,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        tuple(vec![
+            tuple(vec![atom(name), int(arity)]),
+            atom(kind),
+            meta(),
+            list(clauses),
+        ])
+    }
+
+    /// `{Meta, Args, Guards, Body}`
+    fn clause(args: Vec<Term>, guards: Vec<Term>, body: Term) -> Term {
+        tuple(vec![meta(), list(args), list(guards), body])
+    }
+
+    /// `Head -> Body`
+    fn arrow(head: Vec<Term>, body: Term) -> Term {
+        node("->", vec![list(head), body])
+    }
+
+    /// A call to `:erlang.function(args)`, which is what operators and guards expand to
+    fn erlang(function: &str, args: Vec<Term>) -> Term {
+        node_with(node(".", vec![atom("erlang"), atom(function)]), args)
+    }
+
+    fn var(name: &str) -> Term {
+        tuple(vec![atom(name), meta(), atom("nil")])
+    }
+
+    fn node(name: &str, args: Vec<Term>) -> Term {
+        node_with(atom(name), args)
+    }
+
+    fn node_with(head: Term, args: Vec<Term>) -> Term {
+        tuple(vec![head, meta(), list(args)])
+    }
+
+    fn meta() -> Term {
+        keywords(vec![("line", int(1))])
+    }
+
+    fn keywords(pairs: Vec<(&str, Term)>) -> Term {
+        list(
+            pairs
+                .into_iter()
+                .map(|(key, value)| tuple(vec![atom(key), value]))
+                .collect(),
+        )
+    }
+
+    fn atom(name: &str) -> Term {
+        Term::from(etf::Atom::from(name))
+    }
+
+    fn int(value: i32) -> Term {
+        Term::from(etf::FixInteger::from(value))
+    }
+
+    fn binary(value: &str) -> Term {
+        Term::from(etf::Binary::from(value.as_bytes()))
+    }
+
+    fn tuple(elements: Vec<Term>) -> Term {
+        Term::from(etf::Tuple::from(elements))
+    }
+
+    fn list(elements: Vec<Term>) -> Term {
+        Term::from(etf::List::from(elements))
+    }
+}
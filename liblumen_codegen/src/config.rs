@@ -29,7 +29,7 @@ impl ConfigBuilder {
     pub fn new<T: AsRef<str>>(triple: T) -> Result<Self> {
         let s = triple.as_ref();
         match llvm::target::from_triple(s) {
-            Err(err) => Err(CodeGenError::InvalidTarget(err.to_string())),
+            Err(err) => Err(CodeGenError::InvalidTarget(err.to_string()).into()),
             Ok(target) => {
                 let build_dir = Self::default_build_dir(s);
                 Ok(Self {
@@ -57,7 +57,7 @@ impl ConfigBuilder {
 
     #[inline]
     pub fn set_output_dir(mut self, output_dir: PathBuf) -> Self {
-        self.output_dir = output_dir;
+        self.output_dir = Some(output_dir);
         self
     }
 
@@ -102,7 +102,7 @@ impl ConfigBuilder {
         if self.target.has_target_machine() {
             Config::new(self)
         } else {
-            Err(CodeGenError::no_target_machine(self.target_triple, self.target_cpu, self.target_features))
+            Err(CodeGenError::no_target_machine(self.target_triple, self.target_cpu, self.target_features).into())
         }
     }
 }
@@ -123,9 +123,10 @@ impl fmt::Debug for ConfigBuilder {
 }
 impl Default for ConfigBuilder {
     fn default() -> Self {
-        let build_dir = Self::default_build_dir();
+        let target_triple = llvm::target::default_triple();
+        let build_dir = Self::default_build_dir(target_triple.as_str());
         Self {
-            target_triple: llvm::target::default_triple(),
+            target_triple,
             target_features: llvm::target::host_features(),
             target_cpu: llvm::target::host_cpu(),
             target: llvm::target::current(),
@@ -171,10 +172,14 @@ impl Config {
             code_model
         );
         match machine {
-            None => Err(CodeGenError::no_target_machine(triple, cpu, features)),
+            None => Err(CodeGenError::no_target_machine(triple, cpu, features).into()),
             Some(target_machine) => {
                 let target_data = target_machine.get_target_data();
-                Self {
+                let build_dir = target_config.build_dir;
+                let output_dir = target_config
+                    .output_dir
+                    .unwrap_or_else(|| build_dir.clone());
+                Ok(Self {
                     target,
                     target_machine,
                     target_data,
@@ -183,9 +188,9 @@ impl Config {
                     relocation_mode,
                     code_model,
                     output_type,
-                    build_dir: target_config.build_dir,
-                    output_dir: target_config.output_dir,
-                }
+                    build_dir,
+                    output_dir,
+                })
             }
         }
     }
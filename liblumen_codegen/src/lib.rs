@@ -49,16 +49,35 @@ impl CodeGenError {
     }
 }
 
+/// Initializes the LLVM targets, must be called before building a `Config`
+pub fn init() {
+    llvm::target::initialize();
+}
+
 /// Runs the code generator against the given set of modules with the selected output type
-pub fn run(modules: Vec<Module>, config: &Config) -> Result<()> {
-    // Lower EIR modules to LLVM modules compiled to bitcode or assembly
-    let lls = modules.iter()
-        .map(|m| lower::module(m, config))
-        .collect::<Vec<PathBuf>>();
+///
+/// Returns the paths of the files written to the configured output directory
+pub fn run(modules: Vec<Module>, config: &Config) -> Result<Vec<PathBuf>> {
+    let output_dir = config.output_dir();
+    std::fs::create_dir_all(output_dir)?;
+
+    let mut outputs = Vec::with_capacity(modules.len());
+    for module in modules.iter() {
+        // Lower EIR modules to LLVM modules compiled to bitcode or assembly
+        let llvm_module = lower::module(module, config)?;
+        let path = output_dir.join(format!(
+            "{}.{}",
+            module.name.as_str().get(),
+            config.output_type()
+        ));
+        llvm::emit(&llvm_module, config.target_machine(), config.output_type(), &path)
+            .map_err(|err| CodeGenError::llvm(&err.to_string()))?;
+        outputs.push(path);
+    }
     // Link together LLVM assembly/bitcode files into an object file
-    //let obj = linker::link(lls, config)?;
+    //let obj = linker::link(outputs, config)?;
     // Perform native object file linking and generation
     //let _bin = linker::link_native(obj, config)?;
 
-    Ok(())
+    Ok(outputs)
 }
@@ -6,8 +6,9 @@ use std::path::Path;
 use inkwell::module::Module;
 use inkwell::passes::{PassManagerBuilder, PassManager};
 use inkwell::support::LLVMString;
+use inkwell::targets::TargetMachine;
 
-use self::enums::OptimizationLevel;
+use self::enums::{OptimizationLevel, OutputType};
 
 /// Parses an LLVM bitcode file into a `Module`
 #[allow(unused)]
@@ -16,6 +17,20 @@ pub fn parse_bitcode_from_path<P: AsRef<Path>>(path: P) -> Result<Module, LLVMSt
     Module::parse_bitcode_from_path(path)
 }
 
+/// Writes an LLVM module to `path` in the given output format
+pub fn emit<P: AsRef<Path>>(
+    module: &Module,
+    target_machine: &TargetMachine,
+    output_type: OutputType,
+    path: P,
+) -> Result<(), LLVMString> {
+    let path = path.as_ref();
+    match output_type {
+        OutputType::IR => module.print_to_file(path),
+        ty => target_machine.write_to_file(module, ty.into(), path),
+    }
+}
+
 /// Runs optimizations against an LLVM module
 #[allow(unused)]
 pub fn optimize(module: &Module, level: OptimizationLevel) {
@@ -20,7 +20,7 @@ use libeir_ir::{Const, ConstKind};
 use libeir_ir::AtomicTerm;
 
 use liblumen_alloc::erts::term::Term;
-use liblumen_core::sys::sysconfg::MIN_ALIGN;
+use liblumen_core::sys::sysconf::MIN_ALIGN;
 
 use super::{Result, CodeGenError, Config};
 
@@ -0,0 +1,347 @@
+//! A content-hashed build cache, used to skip recompiling modules whose
+//! sources, included headers and compiler settings are unchanged.
+//!
+//! The cache is a manifest stored in the cache directory (by default `.cache` in the output
+//! directory),
+//! with one line per source file of the form:
+//!
+//! ```text
+//! <fingerprint>\t<module>\t<source path>\t<output path>
+//! ```
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use super::config::{CompilerSettings, FileType};
+
+const MANIFEST: &str = "manifest";
+
+/// The result of a previous compilation of a single source file
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub fingerprint: String,
+    pub module: String,
+    pub output: PathBuf,
+}
+
+#[derive(Debug)]
+pub struct BuildCache {
+    dir: PathBuf,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+impl BuildCache {
+    /// Loads the cache from the given directory
+    ///
+    /// A missing or unreadable manifest results in an empty cache, so that
+    /// everything is rebuilt rather than failing the build.
+    pub fn load(dir: &Path) -> Self {
+        let entries = fs::read_to_string(dir.join(MANIFEST))
+            .map(|manifest| manifest.lines().filter_map(parse_entry).collect())
+            .unwrap_or_default();
+        Self {
+            dir: dir.to_path_buf(),
+            entries,
+        }
+    }
+
+    /// Returns the entry for `source` if it was compiled with the given fingerprint
+    /// and its output still exists
+    pub fn get(&self, source: &Path, fingerprint: &str) -> Option<&CacheEntry> {
+        self.entries
+            .get(source)
+            .filter(|entry| entry.fingerprint == fingerprint && entry.output.is_file())
+    }
+
+    pub fn insert(&mut self, source: PathBuf, entry: CacheEntry) {
+        self.entries.insert(source, entry);
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let mut manifest = String::new();
+        // Sorted, so that the manifest is stable across builds
+        let entries = self.entries.iter().collect::<BTreeMap<_, _>>();
+        for (source, entry) in entries {
+            manifest.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                entry.fingerprint,
+                entry.module,
+                source.display(),
+                entry.output.display()
+            ));
+        }
+        fs::write(self.dir.join(MANIFEST), manifest)
+    }
+}
+
+fn parse_entry(line: &str) -> Option<(PathBuf, CacheEntry)> {
+    let mut fields = line.split('\t');
+    let fingerprint = fields.next()?.to_string();
+    let module = fields.next()?.to_string();
+    let source = PathBuf::from(fields.next()?);
+    let output = PathBuf::from(fields.next()?);
+    Some((
+        source,
+        CacheEntry {
+            fingerprint,
+            module,
+            output,
+        },
+    ))
+}
+
+/// Computes the fingerprint of a source file, which covers its contents, the contents of
+/// any headers it includes (transitively), and the settings which affect compilation
+pub fn fingerprint(config: &CompilerSettings, file: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.input(env!("CARGO_PKG_VERSION"));
+    hasher.input(format!("{:?}", config.file_type));
+    hasher.input(config.output_dir.to_string_lossy().as_bytes());
+    // Macro definitions are unordered, so sort them to get a stable hash
+    let mut defines = config
+        .defines
+        .iter()
+        .map(|(name, def)| format!("{:?}={:?}", name, def))
+        .collect::<Vec<_>>();
+    defines.sort();
+    for define in defines.iter() {
+        hasher.input(define);
+    }
+    for path in config.include_path.iter().chain(config.code_path.iter()) {
+        hasher.input(path.to_string_lossy().as_bytes());
+    }
+
+    let source = fs::read(file)?;
+    hasher.input(&source);
+    if FileType::from_path(file) == Some(FileType::Erlang) {
+        let mut visited = HashSet::new();
+        hash_includes(config, file, &source, &mut hasher, &mut visited)?;
+    }
+
+    Ok(format!("{:x}", hasher.result()))
+}
+
+fn hash_includes(
+    config: &CompilerSettings,
+    file: &Path,
+    source: &[u8],
+    hasher: &mut Sha256,
+    visited: &mut HashSet<PathBuf>,
+) -> io::Result<()> {
+    let source = String::from_utf8_lossy(source);
+    for (include, is_lib) in includes(&source) {
+        hasher.input(include);
+        let header = match resolve_include(config, file, include, is_lib) {
+            // An unresolvable include is an error reported by the parser, the
+            // name alone is enough to keep the fingerprint deterministic
+            None => continue,
+            Some(header) => header,
+        };
+        if !visited.insert(header.clone()) {
+            continue;
+        }
+        let contents = fs::read(&header)?;
+        hasher.input(&contents);
+        hash_includes(config, &header, &contents, hasher, visited)?;
+    }
+    Ok(())
+}
+
+/// Scans Erlang source for `-include("..").` and `-include_lib("..").` attributes,
+/// returning each path and whether it is an `include_lib`
+fn includes(source: &str) -> Vec<(&str, bool)> {
+    source
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let (rest, is_lib) = if let Some(rest) = line.strip_prefix("-include_lib") {
+                (rest, true)
+            } else if let Some(rest) = line.strip_prefix("-include") {
+                (rest, false)
+            } else {
+                return None;
+            };
+            let start = rest.find('"')? + 1;
+            let end = start + rest[start..].find('"')?;
+            Some((&rest[start..end], is_lib))
+        })
+        .collect()
+}
+
+/// Resolves an include the same way as the preprocessor: relative to the including file,
+/// then each include path; `include_lib` paths are also resolved against the application
+/// directories containing each code path
+fn resolve_include(
+    config: &CompilerSettings,
+    file: &Path,
+    include: &str,
+    is_lib: bool,
+) -> Option<PathBuf> {
+    let include = Path::new(include);
+    let relative = file.parent().map(|dir| dir.join(include));
+    let candidates = relative
+        .into_iter()
+        .chain(config.include_path.iter().map(|dir| dir.join(include)));
+    if let Some(header) = candidates.into_iter().find(|path| path.is_file()) {
+        return Some(header);
+    }
+    if !is_lib {
+        return None;
+    }
+    // `-include_lib("app/include/header.hrl")` where `app` is found via `app/ebin` in the code path
+    let mut components = include.components();
+    let app = components.next()?.as_os_str().to_str()?;
+    let rest = components.as_path();
+    config
+        .code_path
+        .iter()
+        .filter_map(|ebin| ebin.parent())
+        .filter(|dir| {
+            dir.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name == app || name.starts_with(&format!("{}-", app)))
+                .unwrap_or(false)
+        })
+        .map(|dir| dir.join(rest))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::{Arc, Mutex};
+
+    use libeir_diagnostics::{CodeMap, ColorChoice};
+    use tempfile::TempDir;
+
+    use crate::config::{parse_define, Verbosity};
+
+    #[test]
+    fn fingerprint_is_stable() {
+        let dir = TempDir::new().unwrap();
+        let config = settings(&dir);
+        let file = write(&dir, "a.erl", "-module(a).\n");
+
+        assert_eq!(
+            fingerprint(&config, &file).unwrap(),
+            fingerprint(&config, &file).unwrap()
+        );
+    }
+
+    #[test]
+    fn changing_source_changes_fingerprint() {
+        let dir = TempDir::new().unwrap();
+        let config = settings(&dir);
+        let file = write(&dir, "a.erl", "-module(a).\n");
+        let before = fingerprint(&config, &file).unwrap();
+
+        write(&dir, "a.erl", "-module(a).\n-export([]).\n");
+
+        assert_ne!(before, fingerprint(&config, &file).unwrap());
+    }
+
+    #[test]
+    fn changing_transitive_include_changes_fingerprint() {
+        let dir = TempDir::new().unwrap();
+        let config = settings(&dir);
+        let file = write(&dir, "a.erl", "-module(a).\n-include(\"a.hrl\").\n");
+        write(&dir, "a.hrl", "-include(\"b.hrl\").\n");
+        write(&dir, "b.hrl", "-define(B, 1).\n");
+        let before = fingerprint(&config, &file).unwrap();
+
+        write(&dir, "b.hrl", "-define(B, 2).\n");
+
+        assert_ne!(before, fingerprint(&config, &file).unwrap());
+    }
+
+    #[test]
+    fn changing_define_changes_fingerprint() {
+        let dir = TempDir::new().unwrap();
+        let mut config = settings(&dir);
+        let file = write(&dir, "a.erl", "-module(a).\n");
+        let before = fingerprint(&config, &file).unwrap();
+
+        let (name, def) = parse_define(&config.codemap, "VSN=1").unwrap();
+        config.defines.insert(name, def);
+        let with_define = fingerprint(&config, &file).unwrap();
+        assert_ne!(before, with_define);
+
+        let (name, def) = parse_define(&config.codemap, "VSN=2").unwrap();
+        config.defines.insert(name, def);
+        assert_ne!(with_define, fingerprint(&config, &file).unwrap());
+    }
+
+    #[test]
+    fn changing_paths_changes_fingerprint() {
+        let dir = TempDir::new().unwrap();
+        let mut config = settings(&dir);
+        let file = write(&dir, "a.erl", "-module(a).\n");
+        let before = fingerprint(&config, &file).unwrap();
+
+        config.include_path.push_back(dir.path().join("include"));
+        let with_include = fingerprint(&config, &file).unwrap();
+        assert_ne!(before, with_include);
+
+        config.code_path.push(dir.path().join("ebin"));
+        let with_code_path = fingerprint(&config, &file).unwrap();
+        assert_ne!(with_include, with_code_path);
+
+        config.output_dir = dir.path().join("other");
+        assert_ne!(with_code_path, fingerprint(&config, &file).unwrap());
+    }
+
+    #[test]
+    fn saved_entries_are_loaded_while_unchanged() {
+        let dir = TempDir::new().unwrap();
+        let cache_dir = dir.path().join("cache");
+        let source = write(&dir, "a.erl", "-module(a).\n");
+        let output = write(&dir, "a.o", "");
+
+        let mut cache = BuildCache::load(&cache_dir);
+        cache.insert(
+            source.clone(),
+            CacheEntry {
+                fingerprint: "abc".to_string(),
+                module: "a".to_string(),
+                output: output.clone(),
+            },
+        );
+        cache.save().unwrap();
+
+        let cache = BuildCache::load(&cache_dir);
+        assert_eq!(cache.get(&source, "abc").unwrap().module, "a");
+        assert!(cache.get(&source, "def").is_none());
+
+        fs::remove_file(&output).unwrap();
+        assert!(cache.get(&source, "abc").is_none());
+    }
+
+    fn settings(dir: &TempDir) -> CompilerSettings {
+        CompilerSettings {
+            file_type: FileType::Erlang,
+            color: ColorChoice::Never,
+            source_dir: dir.path().to_path_buf(),
+            output_dir: dir.path().join("target"),
+            defines: HashMap::new(),
+            warnings_as_errors: false,
+            no_warn: false,
+            verbosity: Verbosity::Silent,
+            code_path: Vec::new(),
+            include_path: VecDeque::new(),
+            jobs: 1,
+            cache_dir: Some(dir.path().join("cache")),
+            codemap: Arc::new(Mutex::new(CodeMap::new())),
+        }
+    }
+
+    fn write(dir: &TempDir, name: &str, contents: &str) -> PathBuf {
+        let path = dir.path().join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+}
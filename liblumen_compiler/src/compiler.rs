@@ -1,8 +1,9 @@
-#![allow(unused)]
-use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt::Display;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use anyhow::Result;
 
@@ -10,30 +11,53 @@ use libeir_diagnostics::emitter::{cyan, white, yellow, yellow_bold};
 use libeir_diagnostics::{ColorSpec, Emitter, NullEmitter, StandardStreamEmitter};
 use libeir_diagnostics::{Diagnostic, Severity};
 
-use libeir_intern::Ident;
 use libeir_ir::Module;
 
-use libeir_passes::PassManager;
-use libeir_syntax_erl::{ParseConfig, Parser};
+#[cfg(feature = "codegen")]
+use liblumen_codegen::{ConfigBuilder, OutputType};
 
+use super::cache::{self, BuildCache, CacheEntry};
 use super::config::{CompilerSettings, FileType, Verbosity};
 use super::errors::CompilerError;
+use super::lower::Lowerer;
 
 /// The result produced by compiler functions
-
+#[derive(Debug, Clone)]
 pub struct CompilationInfo {
     num_modules: usize,
+    /// The number of modules which were up to date in the build cache
+    num_cached: usize,
+    /// Wall time spent compiling, in milliseconds
     compilation_time: usize,
 }
 impl CompilationInfo {
     pub fn new() -> Self {
         CompilationInfo {
             num_modules: 0,
+            num_cached: 0,
             compilation_time: 0,
         }
     }
+
+    #[inline]
+    pub fn num_modules(&self) -> usize {
+        self.num_modules
+    }
+
+    #[inline]
+    pub fn num_cached(&self) -> usize {
+        self.num_cached
+    }
+
+    #[inline]
+    pub fn compilation_time(&self) -> usize {
+        self.compilation_time
+    }
 }
 
+/// The outcome of compiling a single file: the module name and the files written
+type CompileResult = Result<(String, Vec<PathBuf>)>;
+
 pub struct Compiler {
     config: CompilerSettings,
     info: CompilationInfo,
@@ -59,19 +83,111 @@ impl Compiler {
     }
 
     pub fn compile(&mut self) -> Result<()> {
-        unimplemented!()
+        // Without code generation there is nothing to write for any module
+        if !cfg!(feature = "codegen") {
+            return Err(CompilerError::NoCodegen.into());
+        }
+
+        let start = Instant::now();
+
+        // Skip any modules which are unchanged since they were last compiled
+        let mut cache = self
+            .config
+            .cache_dir
+            .as_ref()
+            .map(|dir| BuildCache::load(dir));
+        let mut jobs = Vec::new();
+        for file in self.source_files() {
+            let fingerprint = cache::fingerprint(&self.config, &file)?;
+            if let Some(ref cache) = cache {
+                if let Some(entry) = cache.get(&file, &fingerprint) {
+                    self.verbose(format!(
+                        "Cache hit: {} ({})\n",
+                        entry.module,
+                        file.display()
+                    ));
+                    self.info.num_cached += 1;
+                    continue;
+                }
+                self.verbose(format!("Cache miss: {}\n", file.display()));
+            }
+            jobs.push((file, fingerprint));
+        }
+
+        // Parse, lower and generate code for the remaining modules in parallel
+        #[cfg(feature = "codegen")]
+        liblumen_codegen::init();
+        let results = self.compile_parallel(&jobs);
+
+        let mut num_compiled = 0;
+        let mut error = None;
+        for ((file, fingerprint), (result, diagnostics)) in jobs.into_iter().zip(results) {
+            for diagnostic in diagnostics.iter() {
+                self.diagnostic(diagnostic);
+            }
+            match result {
+                Ok((module, outputs)) => {
+                    for output in outputs.iter() {
+                        self.debug(format!("Wrote {}\n", output.display()));
+                    }
+                    if let (Some(cache), Some(output)) = (cache.as_mut(), outputs.first()) {
+                        let output = output.clone();
+                        let entry = CacheEntry {
+                            fingerprint,
+                            module,
+                            output,
+                        };
+                        cache.insert(file, entry);
+                    }
+                    num_compiled += 1;
+                }
+                Err(err) if error.is_none() => error = Some(err),
+                Err(_) => (),
+            }
+        }
+        // Modules which compiled successfully are cached even if others failed
+        if let Some(cache) = cache {
+            cache.save()?;
+        }
+        if let Some(err) = error {
+            return Err(err);
+        }
+
+        self.info.num_modules = num_compiled + self.info.num_cached;
+        self.info.compilation_time = start.elapsed().as_millis() as usize;
+        self.info(format!(
+            "Compiled {} modules ({} up to date) in {}ms\n",
+            self.info.num_modules, self.info.num_cached, self.info.compilation_time
+        ));
+
+        Ok(())
     }
 
-    // Parses all modules into a map. The map uses the module name symbol
-    // as the key, and the AST for the module as the value.
-    fn parse_modules(&mut self) -> Result<HashMap<Ident, Module>> {
-        use walkdir::{DirEntry, WalkDir};
+    /// Parses and lowers all modules to EIR without generating code or using the build cache,
+    /// e.g. to load them into the interpreter
+    pub fn lower_modules(&mut self) -> Result<Vec<Module>> {
+        let mut lowerer = Lowerer::new(&self.config);
+        let mut modules = Vec::new();
+        for file in self.source_files() {
+            let result = lowerer.lower_file(&file);
+            for diagnostic in lowerer.take_diagnostics() {
+                self.diagnostic(&diagnostic);
+            }
+            modules.push(result?);
+        }
+        self.info.num_modules = modules.len();
+        Ok(modules)
+    }
 
-        let mut parser = Parser::new(self.config.clone().into());
+    pub fn compilation_info(&self) -> &CompilationInfo {
+        &self.info
+    }
 
-        let extension = match self.config.file_type {
-            FileType::Erlang => "erl",
-        };
+    // Finds all source files of the configured type in the source directory
+    fn source_files(&self) -> Vec<PathBuf> {
+        use walkdir::{DirEntry, WalkDir};
+
+        let file_type = self.config.file_type;
 
         fn is_hidden(entry: &DirEntry) -> bool {
             entry
@@ -81,13 +197,13 @@ impl Compiler {
                 .unwrap_or(false)
         }
 
-        fn is_source_file(entry: &DirEntry, extension: &str) -> bool {
+        fn is_source_file(entry: &DirEntry, file_type: FileType) -> bool {
             if !entry.file_type().is_file() {
                 return false;
             }
-            match Path::new(entry.file_name()).extension() {
+            match FileType::from_path(entry.path()) {
                 None => false,
-                Some(ext) => ext == extension,
+                Some(ty) => file_type.includes(ty),
             }
         }
 
@@ -95,52 +211,75 @@ impl Compiler {
             .follow_links(true)
             .into_iter();
 
-        let mut modules = HashMap::new();
-        let config = ParseConfig::default();
-        let mut parser = Parser::new(config);
-
-        for entry in walker.filter_entry(|e| !is_hidden(e) && is_source_file(e, extension)) {
-            let entry = entry.unwrap();
-            let file = entry.path();
-
-            let mut module = match self.config.file_type {
-                FileType::Erlang => self.parse_erl(&mut parser, file)?,
-            };
-
-            modules.insert(module.name().clone(), module);
-        }
-
-        Ok(modules)
+        // Only prune hidden entries while walking, directories still need to be descended into
+        let files: Vec<PathBuf> = walker
+            .filter_entry(|e| e.depth() == 0 || !is_hidden(e))
+            .filter_map(|e| e.ok())
+            .filter(|e| is_source_file(e, file_type))
+            .map(|e| e.into_path())
+            .collect();
+
+        // A BEAM file for a module which is also present as source was compiled from that
+        // source, so only the source is compiled
+        let erlang_modules: HashSet<_> = files
+            .iter()
+            .filter(|f| FileType::from_path(f) == Some(FileType::Erlang))
+            .filter_map(|f| f.file_stem().map(|stem| stem.to_owned()))
+            .collect();
+        files
+            .into_iter()
+            .filter(|f| {
+                FileType::from_path(f) != Some(FileType::Beam)
+                    || !f
+                        .file_stem()
+                        .map(|stem| erlang_modules.contains(stem))
+                        .unwrap_or(false)
+            })
+            .collect()
     }
 
-    // Compiles a .erl file to Erlang AST
-    fn parse_erl(&self, parser: &mut Parser, file: &Path) -> Result<Module> {
-        use libeir_syntax_erl::ast;
-        let codemap = parser.config.codemap.clone();
-        match parser.parse_file::<&Path, ast::Module>(file) {
-            Ok(ast) => {
-                let (res, messages) = {
-                    // TODO: Revisit this if the same codemap is used across threads
-                    let codemap_guard = codemap.lock().unwrap();
-                    libeir_syntax_erl::lower_module(&*codemap_guard, &ast)
-                };
-                for msg in messages.iter() {
-                    self.diagnostic(&msg.to_diagnostic());
-                }
-                match res.ok() {
-                    Some(mut ir) => {
-                        let mut pass_manager = PassManager::default();
-                        pass_manager.run(&mut ir);
-                        Ok(ir)
+    // Compiles the given files using up to `jobs` worker threads, returning
+    // the result and diagnostics for each file in the same order as `jobs`
+    fn compile_parallel(
+        &self,
+        jobs: &[(PathBuf, String)],
+    ) -> Vec<(CompileResult, Vec<Diagnostic>)> {
+        let config = &self.config;
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::with_capacity(jobs.len()));
+        let num_workers = config.jobs.max(1).min(jobs.len());
+
+        crossbeam_utils::thread::scope(|scope| {
+            for _ in 0..num_workers {
+                scope.spawn(|_| {
+                    let mut worker = Worker::new(config);
+                    loop {
+                        let index = next.fetch_add(1, Ordering::SeqCst);
+                        let file = match jobs.get(index) {
+                            Some((file, _)) => file,
+                            None => break,
+                        };
+                        let result = worker.compile(file);
+                        let diagnostics = worker.lowerer.take_diagnostics();
+                        results.lock().unwrap().push((index, result, diagnostics));
                     }
-                    None => Err(CompilerError::Failed.into()),
-                }
+                });
             }
-            Err(errs) => Err(CompilerError::Parser {
-                codemap: self.config.codemap.clone(),
-                errs: errs.iter().map(|e| e.to_diagnostic()).collect(),
-            }
-            .into()),
+        })
+        .expect("compiler worker panicked");
+
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(index, _, _)| *index);
+        results
+            .into_iter()
+            .map(|(_, result, diagnostics)| (result, diagnostics))
+            .collect()
+    }
+
+    // Only reported when running with `-v`
+    fn verbose<M: Display>(&self, message: M) {
+        if self.config.verbosity <= Verbosity::Info {
+            self.info(message);
         }
     }
 
@@ -205,3 +344,45 @@ fn verbosity_to_severity(v: Verbosity) -> Severity {
         Verbosity::Debug => Severity::Note,
     }
 }
+
+// Generates an object file for `module` in `output_dir`
+#[cfg(feature = "codegen")]
+fn codegen(module: Module, output_dir: &Path) -> Result<Vec<PathBuf>> {
+    let codegen_config = ConfigBuilder::default()
+        .set_output_dir(output_dir.to_path_buf())
+        .set_output_type(OutputType::Object)
+        .finalize()?;
+
+    liblumen_codegen::run(vec![module], &codegen_config)
+}
+
+#[cfg(not(feature = "codegen"))]
+fn codegen(_module: Module, _output_dir: &Path) -> Result<Vec<PathBuf>> {
+    Err(CompilerError::NoCodegen.into())
+}
+
+/// Compiles modules on a single thread
+///
+/// Each worker has its own lowerer; the codemap is shared, so diagnostics from every worker can
+/// be rendered by the compiler's emitter once the worker is done with them.
+struct Worker<'a> {
+    config: &'a CompilerSettings,
+    lowerer: Lowerer<'a>,
+}
+impl<'a> Worker<'a> {
+    fn new(config: &'a CompilerSettings) -> Self {
+        Self {
+            config,
+            lowerer: Lowerer::new(config),
+        }
+    }
+
+    // Compiles a single file to the output directory
+    fn compile(&mut self, file: &Path) -> CompileResult {
+        let module = self.lowerer.lower_file(file)?;
+        let name = module.name().as_str().get().to_string();
+        let outputs = codegen(module, &self.config.output_dir)?;
+
+        Ok((name, outputs))
+    }
+}
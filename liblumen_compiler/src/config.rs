@@ -1,20 +1,46 @@
-use std::collections::VecDeque;
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
 use std::convert::Into;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
 use super::errors::{self, CompilerError};
 
-use libeir_diagnostics::{CodeMap, ColorChoice};
+use libeir_diagnostics::{CodeMap, ColorChoice, FileName};
+use libeir_intern::Symbol;
+use libeir_syntax_erl::lexer::{FileMapSource, Lexer, LexicalToken, Scanner};
+use libeir_syntax_erl::preprocessor::{MacroDef, MacroIdent};
 use libeir_syntax_erl::ParseConfig;
 
 /// Determines which type of compilation to perform,
 /// either parsing modules from BEAM files, or by
 /// parsing modules from Erlang source code.
+///
+/// When compiling Erlang source, BEAM files found alongside
+/// the sources are compiled too.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd)]
 pub enum FileType {
     Erlang,
+    Beam,
+}
+impl FileType {
+    /// Returns the type of the given file based on its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("erl") => Some(FileType::Erlang),
+            Some("beam") => Some(FileType::Beam),
+            _ => None,
+        }
+    }
+
+    /// Returns true if files of type `other` are inputs when compiling this type
+    pub fn includes(self, other: FileType) -> bool {
+        match self {
+            FileType::Erlang => true,
+            FileType::Beam => other == FileType::Beam,
+        }
+    }
 }
 impl FromStr for FileType {
     type Err = errors::CompilerError;
@@ -22,6 +48,7 @@ impl FromStr for FileType {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "erl" => Ok(FileType::Erlang),
+            "beam" => Ok(FileType::Beam),
             _ => Err(CompilerError::FileType(s.to_owned())),
         }
     }
@@ -58,12 +85,16 @@ pub struct CompilerSettings {
     pub color: ColorChoice,
     pub source_dir: PathBuf,
     pub output_dir: PathBuf,
-    //pub defines: HashMap<Symbol, MacroDef>,
+    pub defines: HashMap<MacroIdent, MacroDef>,
     pub warnings_as_errors: bool,
     pub no_warn: bool,
     pub verbosity: Verbosity,
     pub code_path: Vec<PathBuf>,
     pub include_path: VecDeque<PathBuf>,
+    /// The number of modules to compile in parallel
+    pub jobs: usize,
+    /// Where to keep the build cache, or `None` to always rebuild every module
+    pub cache_dir: Option<PathBuf>,
     pub codemap: Arc<Mutex<CodeMap>>,
 }
 impl Into<ParseConfig> for CompilerSettings {
@@ -74,7 +105,115 @@ impl Into<ParseConfig> for CompilerSettings {
             no_warn: self.no_warn,
             code_paths: self.code_path.clone().into(),
             include_paths: self.include_path.clone(),
-            macros: None,
+            macros: if self.defines.is_empty() {
+                None
+            } else {
+                Some(self.defines.clone())
+            },
+        }
+    }
+}
+
+/// Parses a macro definition given on the command line, e.g. `-DTEST` or `-DVSN=1`
+///
+/// Like `erlc`, a bare `NAME` is defined as `true`, while `VALUE` in `NAME=VALUE` is scanned as
+/// Erlang, so `-DVSN=1` defines `?VSN` as the integer `1` and `-DVSN='"1"'` as a string.
+pub fn parse_define(
+    codemap: &Arc<Mutex<CodeMap>>,
+    define: &str,
+) -> Result<(MacroIdent, MacroDef), CompilerError> {
+    let mut parts = define.splitn(2, '=');
+    let name = Symbol::intern(parts.next().unwrap());
+    let def = match parts.next() {
+        None => MacroDef::Boolean(true),
+        Some(value) => {
+            let filemap = codemap.lock().unwrap().add_filemap(
+                FileName::Virtual(Cow::Owned(format!("-D{}", define))),
+                value.to_owned(),
+            );
+            let lexer = Lexer::new(Scanner::new(FileMapSource::new(filemap)));
+            let tokens = lexer
+                .collect::<Result<Vec<LexicalToken>, _>>()
+                .map_err(|_| CompilerError::Define(define.to_owned()))?;
+
+            MacroDef::Dynamic(tokens)
         }
+    };
+    Ok((MacroIdent::Const(name), def))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use libeir_syntax_erl::ast::Module;
+    use libeir_syntax_erl::Parser;
+
+    #[test]
+    fn erlang_file_type_includes_beam_files() {
+        let erl = FileType::from_path(Path::new("src/foo.erl")).unwrap();
+        let beam = FileType::from_path(Path::new("ebin/bar.beam")).unwrap();
+
+        assert_eq!(erl, FileType::Erlang);
+        assert_eq!(beam, FileType::Beam);
+        assert_eq!(FileType::from_path(Path::new("include/foo.hrl")), None);
+
+        assert!(FileType::Erlang.includes(erl));
+        assert!(FileType::Erlang.includes(beam));
+        assert!(!FileType::Beam.includes(erl));
+        assert!(FileType::Beam.includes(beam));
+    }
+
+    #[test]
+    fn parse_define_with_integer_value_defines_integer() {
+        assert_eq!(
+            parse_with_define("VSN=3", "vsn() -> ?VSN."),
+            parse_with_define("VSN=3", "vsn() -> 3.")
+        );
+    }
+
+    #[test]
+    fn parse_define_with_string_value_defines_string() {
+        assert_eq!(
+            parse_with_define("VSN=\"3\"", "vsn() -> ?VSN."),
+            parse_with_define("VSN=\"3\"", "vsn() -> \"3\".")
+        );
+        assert_ne!(
+            parse_with_define("VSN=\"3\"", "vsn() -> ?VSN."),
+            parse_with_define("VSN=\"3\"", "vsn() -> 3.")
+        );
+    }
+
+    #[test]
+    fn parse_define_without_value_defines_true() {
+        assert_eq!(
+            parse_with_define("TEST", "test() -> ?TEST."),
+            parse_with_define("TEST", "test() -> true.")
+        );
+    }
+
+    fn parse_with_define(define: &str, function: &str) -> Module {
+        let codemap = Arc::new(Mutex::new(CodeMap::new()));
+        let mut defines = HashMap::new();
+        let (ident, def) = parse_define(&codemap, define).unwrap();
+        defines.insert(ident, def);
+
+        let config = ParseConfig {
+            codemap,
+            warnings_as_errors: false,
+            no_warn: true,
+            code_paths: VecDeque::new(),
+            include_paths: VecDeque::new(),
+            macros: Some(defines),
+        };
+        let source = format!(
+            "-module(test).\n-export([{}/0]).\n{}\n",
+            &function[..function.find('(').unwrap()],
+            function
+        );
+
+        Parser::new(config)
+            .parse_string::<&str, Module>(&source)
+            .unwrap()
     }
 }
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 use thiserror::Error;
@@ -16,11 +17,20 @@ pub enum CompilerError {
         errs: Vec<Diagnostic>,
     },
 
+    #[error("unable to load {path:?}: {reason}")]
+    Beam { path: PathBuf, reason: String },
+
     #[error("compilation failed")]
     Failed,
 
     #[error("invalid file type: '{0}'")]
     FileType(String),
+
+    #[error("invalid macro definition: '{0}'")]
+    Define(String),
+
+    #[error("code generation is not supported by this build, rebuild with the `codegen` feature")]
+    NoCodegen,
 }
 
 unsafe impl Send for CompilerError {}
@@ -1,7 +1,9 @@
+mod cache;
 mod compiler;
 mod config;
 mod errors;
+mod lower;
 
 pub use self::compiler::{CompilationInfo, Compiler};
-pub use self::config::{CompilerSettings, FileType, Verbosity};
+pub use self::config::{parse_define, CompilerSettings, FileType, Verbosity};
 pub use self::errors::CompilerError;
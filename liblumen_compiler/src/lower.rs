@@ -0,0 +1,100 @@
+//! Parsing and lowering of modules to EIR.
+//!
+//! This is everything up to code generation, so it is available without the `codegen` feature,
+//! e.g. to load modules into the interpreter.
+use std::path::Path;
+
+use anyhow::Result;
+
+use libeir_diagnostics::Diagnostic;
+use libeir_ir::Module;
+use libeir_passes::PassManager;
+use libeir_syntax_erl::Parser;
+
+use super::config::{CompilerSettings, FileType};
+use super::errors::CompilerError;
+
+/// Parses files and lowers them to EIR on a single thread
+///
+/// Each lowerer has its own parser and pass manager; the codemap is shared, so the diagnostics
+/// collected here can be rendered by the compiler's emitter.
+pub(crate) struct Lowerer<'a> {
+    config: &'a CompilerSettings,
+    parser: Parser,
+    pass_manager: PassManager,
+    diagnostics: Vec<Diagnostic>,
+}
+impl<'a> Lowerer<'a> {
+    pub fn new(config: &'a CompilerSettings) -> Self {
+        Self {
+            config,
+            parser: Parser::new(config.clone().into()),
+            pass_manager: PassManager::default(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Returns the diagnostics reported since the last call
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.diagnostics.drain(..).collect()
+    }
+
+    /// Parses a single file and lowers it to EIR, running the EIR passes over the result
+    pub fn lower_file(&mut self, file: &Path) -> Result<Module> {
+        let mut module = match FileType::from_path(file) {
+            Some(FileType::Beam) => self.parse_beam(file)?,
+            _ => self.parse_erl(file)?,
+        };
+        self.pass_manager.run(&mut module);
+        Ok(module)
+    }
+
+    // Compiles a .erl file to EIR
+    fn parse_erl(&mut self, file: &Path) -> Result<Module> {
+        use libeir_syntax_erl::ast;
+        match self.parser.parse_file::<&Path, ast::Module>(file) {
+            Ok(ast) => self.lower(&ast),
+            Err(errs) => Err(CompilerError::Parser {
+                codemap: self.config.codemap.clone(),
+                errs: errs.iter().map(|e| e.to_diagnostic()).collect(),
+            }
+            .into()),
+        }
+    }
+
+    // Compiles a .beam file to EIR, using the abstract code from its debug info
+    fn parse_beam(&mut self, file: &Path) -> Result<Module> {
+        use libeir_syntax_erl::ast;
+        use liblumen_beam::syntax::ast::AST;
+
+        let beam = AST::from_beam_file(file).map_err(|err| CompilerError::Beam {
+            path: file.to_path_buf(),
+            reason: err.to_string(),
+        })?;
+        let source = beam.to_erlang_source();
+        match self.parser.parse_string::<&str, ast::Module>(&source) {
+            Ok(ast) => self.lower(&ast),
+            Err(errs) => Err(CompilerError::Parser {
+                codemap: self.config.codemap.clone(),
+                errs: errs.iter().map(|e| e.to_diagnostic()).collect(),
+            }
+            .into()),
+        }
+    }
+
+    // Lowers a parsed module to EIR
+    fn lower(&mut self, ast: &libeir_syntax_erl::ast::Module) -> Result<Module> {
+        let codemap = self.config.codemap.clone();
+        let (res, messages) = {
+            // Lowering holds the codemap lock, so only this phase is serialized across workers
+            let codemap_guard = codemap.lock().unwrap();
+            libeir_syntax_erl::lower_module(&*codemap_guard, ast)
+        };
+        self.diagnostics
+            .extend(messages.iter().map(|msg| msg.to_diagnostic()));
+        match res.ok() {
+            Some(ir) => Ok(ir),
+            None => Err(CompilerError::Failed.into()),
+        }
+    }
+}
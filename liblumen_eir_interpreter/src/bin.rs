@@ -17,7 +17,7 @@ use liblumen_eir_interpreter::VM;
 
 use liblumen_alloc::erts::term::prelude::Atom;
 
-use lumen_runtime::scheduler::Scheduler;
+use lumen_runtime::scheduler::smp;
 
 fn parse_file<T, P>(path: P, config: ParseConfig) -> (T, Parser)
 where
@@ -71,8 +71,7 @@ fn main() {
 
     &*VM;
 
-    let arc_scheduler = Scheduler::current();
-    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+    let init_arc_process = smp::runtime().spawn_init(0).unwrap();
 
     let module = Atom::try_from_str(&ident.module.as_str()).unwrap();
     let function = Atom::try_from_str(&ident.name.as_str()).unwrap();
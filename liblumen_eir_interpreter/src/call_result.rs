@@ -1,13 +1,14 @@
 use core::ptr::NonNull;
 
 use std::convert::TryInto;
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::*;
 
 use liblumen_alloc::borrow::clone_to_process::CloneToProcess;
-use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::exception::{self, RuntimeException};
 use liblumen_alloc::erts::process::code;
 use liblumen_alloc::erts::process::{Process, Status};
 use liblumen_alloc::erts::term::prelude::*;
@@ -15,7 +16,6 @@ use liblumen_alloc::erts::HeapFragment;
 
 use lumen_runtime::process::spawn::options::Options;
 use lumen_runtime::scheduler::{Scheduler, Spawned};
-use lumen_runtime::system;
 
 /// A sort of ghetto-future used to get the result from a process
 /// spawn.
@@ -48,43 +48,91 @@ pub fn call_run_erlang(
     let recv = call_erlang(proc, module, function, args);
     let run_arc_process = recv.process.clone();
 
+    run_until_exit(recv).unwrap_or_else(|| {
+        panic!(
+            "{:?} did not run.  Deadlock likely in {:#?}",
+            run_arc_process,
+            Scheduler::current()
+        )
+    })
+}
+
+/// How often a process run by another thread is checked for exiting without a result
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Runs the scheduler until the called process exits, or returns `None` if it is waiting and
+/// no process can run to wake it.
+///
+/// A process run by the scheduler of another thread, such as the scheduler threads, is waited on
+/// instead, which doesn't return until the process exits.
+pub fn run_until_exit(recv: ProcessResultReceiver) -> Option<ProcessResult> {
+    let run_arc_process = recv.process.clone();
+
+    if run_arc_process.scheduler_id() != Some(Scheduler::current().id) {
+        return Some(wait_until_exit(recv));
+    }
+
     loop {
         let ran = Scheduler::current().run_through(&run_arc_process);
 
         match *run_arc_process.status.read() {
-            Status::Exiting(_) => {
-                return recv.try_get().unwrap();
+            Status::Exiting(ref exception) => {
+                return Some(recv.try_get().unwrap_or_else(|| exited(exception)));
             }
             Status::Waiting => {
                 if ran {
-                    system::io::puts(&format!(
+                    log::trace!(
                         "WAITING Run queues len = {:?}",
                         Scheduler::current().run_queues_len()
-                    ));
-                } else {
-                    panic!(
-                        "{:?} did not run.  Deadlock likely in {:#?}",
-                        run_arc_process,
-                        Scheduler::current()
                     );
+                } else {
+                    return None;
                 }
             }
             Status::Runnable => {
-                system::io::puts(&format!(
+                log::trace!(
                     "RUNNABLE Run queues len = {:?}",
                     Scheduler::current().run_queues_len()
-                ));
+                );
             }
             Status::Running => {
-                system::io::puts(&format!(
+                log::trace!(
                     "RUNNING Run queues len = {:?}",
                     Scheduler::current().run_queues_len()
-                ));
+                );
+            }
+        }
+    }
+}
+
+fn wait_until_exit(recv: ProcessResultReceiver) -> ProcessResult {
+    loop {
+        match recv.rx.recv_timeout(EXIT_POLL_INTERVAL) {
+            Ok(result) => break result,
+            // Exiting without a result, such as when exited by a linked process, doesn't send
+            // anything
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => {
+                if let Status::Exiting(ref exception) = *recv.process.status.read() {
+                    break recv.try_get().unwrap_or_else(|| exited(exception));
+                }
             }
         }
     }
 }
 
+/// The result of a process that exited without calling either continuation, such as when a
+/// linked process exited
+fn exited(exception: &RuntimeException) -> ProcessResult {
+    let class = Atom::str_to_term(&exception.class().unwrap().to_string());
+    let (reason, heap) = exception.reason().unwrap().clone_to_fragment().unwrap();
+    let stacktrace = Term::NIL;
+
+    ProcessResult {
+        heap,
+        result: Err((class, reason, stacktrace)),
+    }
+}
+
 pub fn call_erlang(
     proc: Arc<Process>,
     module: Atom,
@@ -178,9 +226,7 @@ fn return_ok(arc_process: &Arc<Process>) -> code::Result {
     let sender_any: Resource = sender_resource.into();
     let sender: &ProcessResultSender = sender_any.downcast_ref().unwrap();
 
-    let mut fragment = HeapFragment::new_from_word_size(100).unwrap();
-    let frag_mut = unsafe { fragment.as_mut() };
-    let ret = argument_vec[0].clone_to_heap(frag_mut).unwrap();
+    let (ret, fragment) = argument_vec[0].clone_to_fragment().unwrap();
 
     sender
         .tx
@@ -215,7 +261,12 @@ fn return_throw(arc_process: &Arc<Process>) -> code::Result {
     let sender_any: Resource = sender_resource.into();
     let sender: &ProcessResultSender = sender_any.downcast_ref().unwrap();
 
-    let mut fragment = HeapFragment::new_from_word_size(100).unwrap();
+    // The fragment outlives this process, so it must fit the whole exception
+    let size = argument_vec[..3]
+        .iter()
+        .map(|term| term.size_in_words())
+        .sum();
+    let mut fragment = HeapFragment::new_from_word_size(size).unwrap();
     let frag_mut = unsafe { fragment.as_mut() };
 
     let ret_type = argument_vec[0].clone_to_heap(frag_mut).unwrap();
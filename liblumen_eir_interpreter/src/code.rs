@@ -146,19 +146,24 @@ pub fn interpreter_closure_code(arc_process: &Arc<Process>) -> code::Result {
     let closure_term = arc_process.stack_pop().unwrap();
 
     let closure: Boxed<Closure> = closure_term.try_into().unwrap();
-    println!("{:?}", closure);
+    log::trace!("{:?}", closure);
 
     let mfa = arc_process.current_module_function_arity().unwrap();
     let definition = arc_process.current_definition().unwrap();
 
     let block_id;
     let function_index;
+    let module_unique;
     match definition {
         Definition::Anonymous {
-            index, old_unique, ..
+            index,
+            old_unique,
+            unique,
+            ..
         } => {
             block_id = index;
             function_index = old_unique;
+            module_unique = unique;
         }
         _ => unreachable!(),
     }
@@ -186,6 +191,7 @@ pub fn interpreter_closure_code(arc_process: &Arc<Process>) -> code::Result {
         &crate::VM,
         arc_process,
         mfa.module,
+        module_unique,
         FunctionIndex::new(function_index as usize),
         //mfa.function,
         //arity as usize,
@@ -202,15 +208,10 @@ pub fn apply(arc_process: &Arc<Process>) -> code::Result {
     let function_term = arc_process.stack_pop().unwrap();
     let argument_list = arc_process.stack_pop().unwrap();
 
-    let module: Atom = module_term.try_into().unwrap();
-    let function: Atom = function_term.try_into().unwrap();
-
-    let arity;
-    match argument_list.decode().unwrap() {
-        TypedTerm::Nil => panic!(),
-        TypedTerm::List(argument_cons) => arity = argument_cons.into_iter().count() - 2,
-        _ => panic!(),
-    }
+    let (module, function, arity) = match apply_mfa(module_term, function_term, argument_list) {
+        Ok(mfa) => mfa,
+        Err(err) => return code::result_from_exception(arc_process, 0, err.into()),
+    };
 
     let module_function_arity = Arc::new(ModuleFunctionArity {
         module,
@@ -225,3 +226,28 @@ pub fn apply(arc_process: &Arc<Process>) -> code::Result {
 
     Process::call_code(arc_process)
 }
+
+/// The module, function and arity of an `apply/3` whose argument list starts with the
+/// continuations
+fn apply_mfa(
+    module_term: Term,
+    function_term: Term,
+    argument_list: Term,
+) -> anyhow::Result<(Atom, Atom, usize)> {
+    let module: Atom = module_term
+        .try_into()
+        .with_context(|| format!("module ({}) is not an atom", module_term))?;
+    let function: Atom = function_term
+        .try_into()
+        .with_context(|| format!("function ({}) is not an atom", function_term))?;
+    let arguments: Boxed<Cons> = argument_list
+        .try_into()
+        .with_context(|| format!("arguments ({}) is not a list", argument_list))?;
+    let len = arguments
+        .into_iter()
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| anyhow!("arguments ({}) is not a proper list", argument_list))?
+        .len();
+
+    Ok((module, function, len - 2))
+}
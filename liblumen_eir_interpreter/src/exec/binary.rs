@@ -0,0 +1,478 @@
+//! Bit syntax construction (`BinaryPush`) and matching (`MatchKind::Binary`).
+//!
+//! Construction copies the bits into a new binary, while matching extracts values from the
+//! original binary and returns the rest as a sub binary of it, so that parsing a binary one
+//! segment at a time doesn't copy the remainder each time.
+use std::convert::TryInto;
+
+use num_bigint::{BigInt, Sign};
+use num_traits::ToPrimitive;
+
+use libeir_ir::{BinaryEntrySpecifier, Endianness};
+
+use liblumen_alloc::erts::exception::AllocResult;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+/// Bits packed most significant bit first
+#[derive(Default)]
+pub struct BitVec {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitVec {
+    /// Copies the bits of a bitstring, returning `None` if the term is not a bitstring
+    pub fn from_term(term: Term) -> Option<Self> {
+        with_bits(term, |bits| {
+            let mut vec = Self::default();
+            vec.push_slice(bits, 0, bits.len);
+            vec
+        })
+    }
+
+    pub fn push_bit(&mut self, bit: bool) {
+        if self.len % 8 == 0 {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 0x80 >> (self.len % 8);
+        }
+        self.len += 1;
+    }
+
+    pub fn push_bytes(&mut self, bytes: &[u8]) {
+        if self.len % 8 == 0 {
+            self.bytes.extend_from_slice(bytes);
+            self.len += bytes.len() * 8;
+        } else {
+            for byte in bytes {
+                self.push_uint(*byte as u64, 8);
+            }
+        }
+    }
+
+    /// Pushes the `n` least significant bits of `value`
+    fn push_uint(&mut self, value: u64, n: usize) {
+        for i in (0..n).rev() {
+            self.push_bit(value >> i & 1 == 1);
+        }
+    }
+
+    fn push_slice(&mut self, bits: Bits, start: usize, n: usize) {
+        for i in start..start + n {
+            self.push_bit(bits.bit(i));
+        }
+    }
+
+    pub fn to_term(&self, process: &Process) -> AllocResult<Term> {
+        let binary = process.binary_from_bytes(&self.bytes)?;
+        if self.len % 8 == 0 {
+            Ok(binary)
+        } else {
+            process.subbinary_from_original(binary, 0, 0, self.len / 8, (self.len % 8) as u8)
+        }
+    }
+}
+
+/// A view of the bits of a bitstring within its original binary
+#[derive(Clone, Copy)]
+struct Bits<'a> {
+    original: Term,
+    bytes: &'a [u8],
+    /// Offset in bits into `bytes`
+    offset: usize,
+    len: usize,
+}
+
+impl<'a> Bits<'a> {
+    /// Without a length, the bits run to the end of `bytes`
+    fn new(original: Term, bytes: &'a [u8], offset: usize, len: Option<usize>) -> Self {
+        Self {
+            original,
+            bytes,
+            offset,
+            len: len.unwrap_or(bytes.len() * 8 - offset),
+        }
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        let absolute = self.offset + index;
+        self.bytes[absolute / 8] & (0x80 >> (absolute % 8)) != 0
+    }
+
+    /// Reads `n` bits from `start` as an unsigned integer, most significant bit first
+    fn uint(&self, start: usize, n: usize) -> BigInt {
+        let mut bytes = Vec::with_capacity(n / 8 + 1);
+        let mut byte = 0u8;
+        // Left pad to a whole number of bytes
+        let padding = (8 - n % 8) % 8;
+        for i in 0..padding + n {
+            let bit = i >= padding && self.bit(start + i - padding);
+            byte = byte << 1 | bit as u8;
+            if i % 8 == 7 {
+                bytes.push(byte);
+                byte = 0;
+            }
+        }
+        BigInt::from_bytes_be(Sign::Plus, &bytes)
+    }
+
+    /// Reorders the `n` bits of a number from `start`, most significant first, into the order
+    /// they are stored in a segment with the given endianness
+    ///
+    /// For little endian, the least significant byte is stored first and any remaining most
+    /// significant bits are stored last, so `<<1:12/little>>` is `<<1, 0:4>>`.
+    fn to_segment(&self, start: usize, n: usize, endianness: &Endianness) -> BitVec {
+        let mut vec = BitVec::default();
+        if is_little(endianness) {
+            for byte in 0..n / 8 {
+                vec.push_slice(*self, start + n - 8 * (byte + 1), 8);
+            }
+            vec.push_slice(*self, start, n % 8);
+        } else {
+            vec.push_slice(*self, start, n);
+        }
+        vec
+    }
+
+    /// The inverse of `to_segment`
+    fn from_segment(&self, start: usize, n: usize, endianness: &Endianness) -> BitVec {
+        let mut vec = BitVec::default();
+        if is_little(endianness) {
+            let full_bytes = n / 8;
+            vec.push_slice(*self, start + full_bytes * 8, n % 8);
+            for byte in (0..full_bytes).rev() {
+                vec.push_slice(*self, start + byte * 8, 8);
+            }
+        } else {
+            vec.push_slice(*self, start, n);
+        }
+        vec
+    }
+
+    /// The bits from `start` to the end, as a sub binary of the original
+    fn rest(&self, process: &Process, start: usize) -> AllocResult<Term> {
+        self.sub(process, start, self.len - start)
+    }
+
+    fn sub(&self, process: &Process, start: usize, n: usize) -> AllocResult<Term> {
+        let absolute = self.offset + start;
+        process.subbinary_from_original(
+            self.original,
+            absolute / 8,
+            (absolute % 8) as u8,
+            n / 8,
+            (n % 8) as u8,
+        )
+    }
+}
+
+impl BitVec {
+    fn bits(&self) -> Bits {
+        Bits::new(Term::NIL, &self.bytes, 0, Some(self.len))
+    }
+}
+
+/// Calls `f` with the bits of a bitstring, returning `None` if the term is not a bitstring
+fn with_bits<R, F: FnOnce(Bits) -> R>(term: Term, f: F) -> Option<R> {
+    let (original, offset, len) = match term.decode().ok()? {
+        TypedTerm::SubBinary(sub) => (
+            sub.original(),
+            sub.byte_offset() * 8 + sub.bit_offset() as usize,
+            Some(sub.full_byte_len() * 8 + sub.partial_byte_bit_len() as usize),
+        ),
+        _ => (term, 0, None),
+    };
+    match original.decode().ok()? {
+        TypedTerm::HeapBinary(bin) => Some(f(Bits::new(original, bin.as_bytes(), offset, len))),
+        TypedTerm::ProcBin(bin) => Some(f(Bits::new(original, bin.as_bytes(), offset, len))),
+        TypedTerm::BinaryLiteral(bin) => Some(f(Bits::new(original, bin.as_bytes(), offset, len))),
+        _ => None,
+    }
+}
+
+fn is_little(endianness: &Endianness) -> bool {
+    match endianness {
+        Endianness::Big => false,
+        Endianness::Little => true,
+        Endianness::Native => cfg!(target_endian = "little"),
+    }
+}
+
+fn unit(unit: &i64) -> usize {
+    *unit as usize
+}
+
+/// The size of a segment in bits, or `None` if the size is not a non-negative integer
+fn segment_bits(size: Option<Term>, default: usize, unit: usize) -> Option<Option<usize>> {
+    match size {
+        None => Some(if default == 0 {
+            None
+        } else {
+            Some(default * unit)
+        }),
+        Some(size) => {
+            let size: usize = size.try_into().ok()?;
+            Some(Some(size * unit))
+        }
+    }
+}
+
+fn to_bigint(term: Term) -> Option<BigInt> {
+    match term.decode().ok()? {
+        TypedTerm::SmallInteger(small) => {
+            let i: isize = small.into();
+            Some(BigInt::from(i as i64))
+        }
+        TypedTerm::BigInteger(big) => Some(big.into()),
+        _ => None,
+    }
+}
+
+fn to_f64(term: Term) -> Option<f64> {
+    match term.decode().ok()? {
+        TypedTerm::Float(float) => Some(float.into()),
+        TypedTerm::SmallInteger(small) => {
+            let i: isize = small.into();
+            Some(i as f64)
+        }
+        TypedTerm::BigInteger(big) => Some(big.into()),
+        _ => None,
+    }
+}
+
+/// Appends `value` to `bits` according to the specifier,
+/// returning `None` if the value or size is invalid for the specifier
+pub fn push(
+    bits: &mut BitVec,
+    specifier: &BinaryEntrySpecifier,
+    value: Term,
+    size: Option<Term>,
+) -> Option<()> {
+    match specifier {
+        BinaryEntrySpecifier::Integer {
+            endianness,
+            unit: u,
+            ..
+        } => {
+            let n = segment_bits(size, 8, unit(u))??;
+            let value = to_bigint(value)?;
+            // Two's complement, sign extended to cover `n` bits
+            let mut bytes = value.to_signed_bytes_be();
+            let fill = if value.sign() == Sign::Minus { 0xff } else { 0 };
+            let needed = n / 8 + 1;
+            if bytes.len() < needed {
+                let mut extended = vec![fill; needed - bytes.len()];
+                extended.extend_from_slice(&bytes);
+                bytes = extended;
+            }
+            let source = Bits::new(Term::NIL, &bytes, 0, None);
+            let ordered = source.to_segment(source.len - n, n, endianness);
+            bits.push_slice(ordered.bits(), 0, n);
+        }
+        BinaryEntrySpecifier::Float {
+            endianness,
+            unit: u,
+        } => {
+            let n = segment_bits(size, 64, unit(u))??;
+            let value = to_f64(value)?;
+            let bytes = match n {
+                64 => value.to_bits().to_be_bytes().to_vec(),
+                32 => (value as f32).to_bits().to_be_bytes().to_vec(),
+                _ => return None,
+            };
+            let source = Bits::new(Term::NIL, &bytes, 0, None);
+            bits.push_slice(source.to_segment(0, n, endianness).bits(), 0, n);
+        }
+        BinaryEntrySpecifier::Bytes { unit: u } | BinaryEntrySpecifier::Bits { unit: u } => {
+            let is_binary = match specifier {
+                BinaryEntrySpecifier::Bytes { .. } => true,
+                _ => false,
+            };
+            let n = segment_bits(size, 0, unit(u))?;
+            with_bits(value, |source| {
+                let n = n.unwrap_or(source.len);
+                if n > source.len || (is_binary && size.is_none() && n % 8 != 0) {
+                    return None;
+                }
+                bits.push_slice(source, 0, n);
+                Some(())
+            })??;
+        }
+        BinaryEntrySpecifier::Utf8 => {
+            let c = to_char(value)?;
+            let mut buffer = [0; 4];
+            bits.push_bytes(c.encode_utf8(&mut buffer).as_bytes());
+        }
+        BinaryEntrySpecifier::Utf16 { endianness } => {
+            let c = to_char(value)?;
+            let mut buffer = [0; 2];
+            for unit in c.encode_utf16(&mut buffer).iter() {
+                let bytes = if is_little(endianness) {
+                    unit.to_le_bytes()
+                } else {
+                    unit.to_be_bytes()
+                };
+                bits.push_bytes(&bytes);
+            }
+        }
+        BinaryEntrySpecifier::Utf32 { endianness } => {
+            let c = to_char(value)? as u32;
+            let bytes = if is_little(endianness) {
+                c.to_le_bytes()
+            } else {
+                c.to_be_bytes()
+            };
+            bits.push_bytes(&bytes);
+        }
+    }
+    Some(())
+}
+
+fn to_char(term: Term) -> Option<char> {
+    let i: u32 = to_bigint(term)?.to_u32()?;
+    std::char::from_u32(i)
+}
+
+/// Matches the start of `subject` against the specifier, returning the matched value and the
+/// rest of the bitstring, or `None` if it doesn't match
+pub fn read(
+    process: &Process,
+    subject: Term,
+    specifier: &BinaryEntrySpecifier,
+    size: Option<Term>,
+) -> AllocResult<Option<(Term, Term)>> {
+    let matched = with_bits(subject, |bits| -> AllocResult<Option<(Term, Term)>> {
+        let (value, n) = match read_value(process, bits, specifier, size)? {
+            Some(matched) => matched,
+            None => return Ok(None),
+        };
+        let rest = bits.rest(process, n)?;
+        Ok(Some((value, rest)))
+    });
+    match matched {
+        Some(result) => result,
+        None => Ok(None),
+    }
+}
+
+/// Returns the value and the number of bits it used
+fn read_value(
+    process: &Process,
+    bits: Bits,
+    specifier: &BinaryEntrySpecifier,
+    size: Option<Term>,
+) -> AllocResult<Option<(Term, usize)>> {
+    let result = match specifier {
+        BinaryEntrySpecifier::Integer {
+            signed,
+            endianness,
+            unit: u,
+        } => {
+            let n = match segment_bits(size, 8, unit(u)) {
+                Some(Some(n)) if n <= bits.len => n,
+                _ => return Ok(None),
+            };
+            let ordered = bits.from_segment(0, n, endianness);
+            let mut value = ordered.bits().uint(0, n);
+            if *signed && n > 0 && ordered.bits().bit(0) {
+                value -= BigInt::from(1) << n;
+            }
+            (process.integer(value)?, n)
+        }
+        BinaryEntrySpecifier::Float {
+            endianness,
+            unit: u,
+        } => {
+            let n = match segment_bits(size, 64, unit(u)) {
+                Some(Some(n)) if n <= bits.len => n,
+                _ => return Ok(None),
+            };
+            let ordered = bits.from_segment(0, n, endianness);
+            let value: u64 = ordered.bits().uint(0, n).to_u64().unwrap();
+            let value = match n {
+                64 => f64::from_bits(value),
+                32 => f32::from_bits(value as u32) as f64,
+                _ => return Ok(None),
+            };
+            if !value.is_finite() {
+                return Ok(None);
+            }
+            (process.float(value)?, n)
+        }
+        BinaryEntrySpecifier::Bytes { unit: u } | BinaryEntrySpecifier::Bits { unit: u } => {
+            let is_binary = match specifier {
+                BinaryEntrySpecifier::Bytes { .. } => true,
+                _ => false,
+            };
+            let n = match segment_bits(size, 0, unit(u)) {
+                Some(Some(n)) if n <= bits.len => n,
+                // Without a size, the segment is the rest of the bitstring
+                Some(None) if !is_binary || bits.len % 8 == 0 => bits.len,
+                _ => return Ok(None),
+            };
+            (bits.sub(process, 0, n)?, n)
+        }
+        BinaryEntrySpecifier::Utf8 => {
+            if bits.len < 8 {
+                return Ok(None);
+            }
+            let first: u32 = bits.uint(0, 8).to_u32().unwrap();
+            let len = match first {
+                0x00..=0x7f => 1,
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => return Ok(None),
+            };
+            if bits.len < len * 8 {
+                return Ok(None);
+            }
+            let bytes = (0..len)
+                .map(|i| bits.uint(i * 8, 8).to_u8().unwrap())
+                .collect::<Vec<u8>>();
+            match std::str::from_utf8(&bytes)
+                .ok()
+                .and_then(|s| s.chars().next())
+            {
+                Some(c) => (process.integer(c as u32 as usize)?, len * 8),
+                None => return Ok(None),
+            }
+        }
+        BinaryEntrySpecifier::Utf16 { endianness } => {
+            let unit = |i: usize| -> u16 {
+                let ordered = bits.from_segment(i * 16, 16, endianness);
+                ordered.bits().uint(0, 16).to_u16().unwrap()
+            };
+            if bits.len < 16 {
+                return Ok(None);
+            }
+            let first = unit(0);
+            let (units, len) = if (0xd800..0xdc00).contains(&first) {
+                if bits.len < 32 {
+                    return Ok(None);
+                }
+                (vec![first, unit(1)], 32)
+            } else {
+                (vec![first], 16)
+            };
+            match std::char::decode_utf16(units).next() {
+                Some(Ok(c)) => (process.integer(c as u32 as usize)?, len),
+                _ => return Ok(None),
+            }
+        }
+        BinaryEntrySpecifier::Utf32 { endianness } => {
+            if bits.len < 32 {
+                return Ok(None);
+            }
+            let ordered = bits.from_segment(0, 32, endianness);
+            let value: u32 = ordered.bits().uint(0, 32).to_u32().unwrap();
+            match std::char::from_u32(value) {
+                Some(c) => (process.integer(c as u32 as usize)?, 32),
+                None => return Ok(None),
+            }
+        }
+    };
+    Ok(Some(result))
+}
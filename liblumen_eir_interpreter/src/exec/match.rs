@@ -1,12 +1,15 @@
 use std::sync::Arc;
 
+use anyhow::anyhow;
+
 use libeir_ir::{BasicType, Block, MatchKind};
 
-use liblumen_alloc::erts::exception::SystemException;
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::exception;
 use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::{Encoded, ExactEq, TypedTerm};
 
-use super::{CallExecutor, OpResult};
+use super::{binary, CallExecutor, OpResult};
 use crate::module::ErlangFunction;
 
 pub fn match_op(
@@ -15,16 +18,13 @@ pub fn match_op(
     fun: &ErlangFunction,
     branches: &[MatchKind],
     block: Block,
-) -> std::result::Result<OpResult, SystemException> {
+) -> exception::Result<OpResult> {
     let reads = fun.fun.block_reads(block);
 
     let branches_dests = reads[0];
 
-    let unpack_term = exec
-        .make_term(proc, fun, reads[1])
-        .unwrap()
-        .decode()
-        .unwrap();
+    let unpack_term_raw = exec.make_term(proc, fun, reads[1])?;
+    let unpack_term = unpack_term_raw.decode().unwrap();
 
     for (idx, kind) in branches.iter().enumerate() {
         let branch = fun.fun.value_list_get_n(branches_dests, idx).unwrap();
@@ -36,22 +36,22 @@ pub fn match_op(
             MatchKind::Value => {
                 assert!(branch_args_len == 1);
                 let arg = fun.fun.value_list_get_n(branch_args_val, 0).unwrap();
-                let rhs = exec.make_term(proc, fun, arg).unwrap();
+                let rhs = exec.make_term(proc, fun, arg)?;
 
                 if unpack_term.exact_eq(&rhs.decode().unwrap()) {
                     return exec.val_call(proc, fun, branch);
                 }
             }
-            MatchKind::Type(BasicType::Map) => {
+            MatchKind::Type(typ) => {
                 assert!(branch_args_len == 0);
-                if unpack_term.is_map() {
+                if is_type(&unpack_term, typ) {
                     return exec.val_call(proc, fun, branch);
                 }
             }
             MatchKind::MapItem => {
                 assert!(branch_args_len == 1);
                 let arg = fun.fun.value_list_get_n(branch_args_val, 0).unwrap();
-                let key = exec.make_term(proc, fun, arg).unwrap();
+                let key = exec.make_term(proc, fun, arg)?;
 
                 match unpack_term {
                     TypedTerm::Map(map) => {
@@ -60,7 +60,18 @@ pub fn match_op(
                             return exec.val_call(proc, fun, branch);
                         }
                     }
-                    _ => unreachable!(),
+                    // The compiler checks the type before matching items, so this is only reached
+                    // by a match without the check
+                    _ => {
+                        let reason = proc.tuple_from_slice(&[atom!("badmap"), unpack_term_raw])?;
+                        return Err(exception::error(
+                            reason,
+                            None,
+                            None,
+                            anyhow!("{} is not a map", unpack_term_raw).into(),
+                        )
+                        .into());
+                    }
                 }
             }
             MatchKind::Tuple(arity) => {
@@ -88,13 +99,54 @@ pub fn match_op(
                     _ => (),
                 }
             }
+            MatchKind::Binary(specifier) => {
+                let size = match branch_args_len {
+                    0 => None,
+                    1 => {
+                        let arg = fun.fun.value_list_get_n(branch_args_val, 0).unwrap();
+                        Some(exec.make_term(proc, fun, arg)?)
+                    }
+                    _ => unreachable!(),
+                };
+
+                if let Some((value, rest)) = binary::read(proc, unpack_term_raw, specifier, size)? {
+                    exec.next_args.push(value);
+                    exec.next_args.push(rest);
+                    return exec.val_call(proc, fun, branch);
+                }
+            }
             MatchKind::Wildcard => {
                 assert!(branch_args_len == 0);
                 return exec.val_call(proc, fun, branch);
             }
-            kind => unimplemented!("{:?}", kind),
         }
     }
 
-    panic!()
+    // The compiler ends every match with a branch that raises the Erlang error for the construct
+    // the match came from, so this is only reached by a match without one
+    let reason = proc.tuple_from_slice(&[atom!("badmatch"), unpack_term_raw])?;
+    Err(exception::error(
+        reason,
+        None,
+        None,
+        anyhow!("no match branch matched").into(),
+    )
+    .into())
+}
+
+fn is_type(term: &TypedTerm, typ: &BasicType) -> bool {
+    match (typ, term) {
+        (BasicType::List, TypedTerm::Nil) | (BasicType::List, TypedTerm::List(_)) => true,
+        (BasicType::ListCell, TypedTerm::List(_)) => true,
+        (BasicType::Nil, TypedTerm::Nil) => true,
+        (BasicType::Tuple(arity), TypedTerm::Tuple(tuple)) => tuple.len() == *arity,
+        (BasicType::Map, TypedTerm::Map(_)) => true,
+        (BasicType::Number, TypedTerm::SmallInteger(_))
+        | (BasicType::Number, TypedTerm::BigInteger(_))
+        | (BasicType::Number, TypedTerm::Float(_)) => true,
+        (BasicType::Integer, TypedTerm::SmallInteger(_))
+        | (BasicType::Integer, TypedTerm::BigInteger(_)) => true,
+        (BasicType::Float, TypedTerm::Float(_)) => true,
+        _ => false,
+    }
 }
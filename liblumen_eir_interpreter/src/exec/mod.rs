@@ -1,7 +1,9 @@
 use std::convert::{AsRef, TryInto};
+use std::fmt::Debug;
 use std::process::abort;
 use std::sync::Arc;
 
+use anyhow::anyhow;
 use hashbrown::HashMap;
 
 use cranelift_entity::EntityRef;
@@ -13,20 +15,25 @@ use libeir_ir::{
 };
 
 use liblumen_alloc::atom;
-use liblumen_alloc::erts::exception::{Exception, RuntimeException, SystemException};
+use liblumen_alloc::erts::exception::{self, Exception, RuntimeException, SystemException};
 use liblumen_alloc::erts::process::code;
 use liblumen_alloc::erts::process::gc::RootSet;
-use liblumen_alloc::erts::process::{Process, ProcessFlags};
+use liblumen_alloc::erts::process::{Process, ProcessFlags, Receive};
+use liblumen_alloc::erts::term::closure::Unique;
 use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::ModuleFunctionArity;
 
-use crate::module::{ErlangFunction, NativeFunctionKind, ResolvedFunction};
+use lumen_runtime::otp::erlang;
+use lumen_runtime::time::{monotonic, Milliseconds};
+use lumen_runtime::timer;
+
+use crate::module::{ErlangFunction, ModuleRegistry, NativeFunctionKind, ResolvedFunction};
+use crate::trace;
 use crate::vm::VMState;
 
+mod binary;
 mod r#match;
-
-macro_rules! trace {
-    ($($t:tt)*) => (lumen_runtime::system::io::puts(&format_args!($($t)*).to_string()))
-}
+mod receive;
 
 const VALUE_LIST_MARKER: &str = "eir_value_list_marker_df8gy43h";
 
@@ -105,9 +112,7 @@ where
                 // Terms are in root set
                 unsafe { terms.add(&mut rootset) };
 
-                lumen_runtime::system::io::puts(
-                    "=================================================== GC",
-                );
+                log::trace!("GC {}", proc.pid());
                 match heap.garbage_collect(proc, 0, rootset) {
                     Ok(_) => (),
                     Err(_) => {
@@ -119,9 +124,7 @@ where
                         // Terms are in root set
                         unsafe { terms.add(&mut rootset) };
 
-                        lumen_runtime::system::io::puts(
-                            "=================================================== FULL GC",
-                        );
+                        log::trace!("FULL GC {}", proc.pid());
                         match heap.garbage_collect(proc, 0, rootset) {
                             Ok(_) => (),
                             Err(_) => panic!(),
@@ -138,7 +141,7 @@ where
 }
 
 /// Sets up the current stack frame of `proc` to call `closure` with `args`.
-fn call_closure(proc: &Arc<Process>, mut closure: Term, args: &mut [Term]) {
+pub(crate) fn call_closure(proc: &Arc<Process>, mut closure: Term, args: &mut [Term]) {
     try_gc(proc, &mut (&mut closure, args), &mut |(
         closure_term,
         args,
@@ -167,7 +170,15 @@ fn call_closure_inner(
             proc.replace_frame(closure.frame());
             Ok(())
         }
-        t => panic!("CALL TO: {:?}", t),
+        _ => code::result_from_exception(
+            proc,
+            0,
+            exception::badfun(
+                proc,
+                closure_term,
+                anyhow!("called a term that is not a fun").into(),
+            ),
+        ),
     }
 }
 
@@ -189,7 +200,13 @@ impl CallExecutor {
         arity: usize,
         args: &mut [Term],
     ) {
-        trace!("======== RUN {} ========", proc.pid());
+        log::trace!("RUN {}", proc.pid());
+        trace::call(proc, module, function, &args[2..]);
+        proc.save_call(ModuleFunctionArity {
+            module,
+            function,
+            arity: arity.try_into().unwrap(),
+        });
         let modules = vm.modules.read().unwrap();
 
         // Make sure no non-heap terms make it into the process
@@ -200,8 +217,7 @@ impl CallExecutor {
                     use liblumen_alloc::erts::process::alloc::Heap;
                     let ptr: *const Term = arg.dyn_cast();
                     if !heap.is_owner(ptr) {
-                        println!("NON HEAP BOXED: {:?}", arg);
-                        panic!();
+                        panic!("non heap boxed term: {:?}", arg);
                     }
                 }
             }
@@ -209,12 +225,12 @@ impl CallExecutor {
 
         match modules.lookup_function(module, function, arity) {
             None => {
-                self.fun_not_found(proc, args[1], module, function, arity)
-                    .unwrap();
+                assert!(arity + 2 == args.len());
+                self.undefined_function(vm, proc, &modules, args, module, function);
             }
             Some(ResolvedFunction::Native(native)) => {
                 assert!(arity + 2 == args.len());
-                self.run_native(vm, proc, native, args);
+                self.run_native(vm, proc, module, function, native, args);
             }
             Some(ResolvedFunction::Erlang(fun)) => {
                 let entry = fun.fun.block_entry();
@@ -229,14 +245,15 @@ impl CallExecutor {
         vm: &VMState,
         proc: &Arc<Process>,
         module: Atom,
+        module_unique: Unique,
         fun_idx: FunctionIndex,
         args: &mut [Term],
         block: Block,
         env: &mut [Term],
     ) {
-        trace!("======== RUN {} ========", proc.pid());
+        log::trace!("RUN {}", proc.pid());
         let modules = vm.modules.read().unwrap();
-        match modules.lookup_function_idx(module, fun_idx) {
+        match modules.lookup_function_idx(module, module_unique, fun_idx) {
             None => unreachable!(),
             Some(fun) => {
                 let live = &fun.live.live_at(block);
@@ -251,49 +268,101 @@ impl CallExecutor {
         }
     }
 
+    /// Calls `undefined_function/3` of the process's `error_handler`, like the BEAM, or raises
+    /// `undef` if the error handler does not define it
+    fn undefined_function(
+        &mut self,
+        vm: &VMState,
+        proc: &Arc<Process>,
+        modules: &ModuleRegistry,
+        mut args: &mut [Term],
+        module: Atom,
+        function: Atom,
+    ) {
+        let error_handler = proc.error_handler();
+        let undefined_function = Atom::try_from_str("undefined_function").unwrap();
+
+        match modules.lookup_function(error_handler, undefined_function, 3) {
+            // Calls to the error handler's own undefined functions are not handled by it, so that
+            // they can't recurse forever
+            Some(resolved) if error_handler != module => {
+                let mut handler_args = try_gc(proc, &mut args, &mut |args| {
+                    let arguments = proc.list_from_slice(&args[2..])?;
+
+                    Ok(vec![
+                        args[0],
+                        args[1],
+                        module.encode().unwrap(),
+                        function.encode().unwrap(),
+                        arguments,
+                    ])
+                });
+
+                match resolved {
+                    ResolvedFunction::Native(native) => self.run_native(
+                        vm,
+                        proc,
+                        error_handler,
+                        undefined_function,
+                        native,
+                        &mut handler_args,
+                    ),
+                    ResolvedFunction::Erlang(fun) => {
+                        let entry = fun.fun.block_entry();
+                        self.run_erlang(vm, proc, fun, entry, &mut handler_args);
+                    }
+                }
+            }
+            _ => self.fun_not_found(proc, args, module, function),
+        }
+    }
+
+    /// Raises `undef` to the throw continuation, with the call as the only stacktrace entry
     fn fun_not_found(
         &self,
-        _proc: &Arc<Process>,
-        _throw_cont: Term,
+        proc: &Arc<Process>,
+        mut args: &mut [Term],
         module: Atom,
         function: Atom,
-        arity: usize,
-    ) -> code::Result {
-        panic!("Undef: {} {} {}", module, function, arity);
-        //let exit_atom = Atom::str_to_term("EXIT");
-        //let undef_atom = Atom::str_to_term("undef");
-        //let trace_atom = Atom::str_to_term("trace");
-        //self.call_closure(proc, throw_cont, &[exit_atom, undef_atom, trace_atom])
+    ) {
+        try_gc(proc, &mut args, &mut |args| {
+            let arguments = proc.list_from_slice(&args[2..])?;
+            let location = Term::NIL;
+            let frame = proc.tuple_from_slice(&[
+                module.encode().unwrap(),
+                function.encode().unwrap(),
+                arguments,
+                location,
+            ])?;
+            let stacktrace = proc.cons(frame, Term::NIL)?;
+            Ok(call_closure(
+                proc,
+                args[1],
+                &mut [atom!("error"), atom!("undef"), stacktrace],
+            ))
+        })
     }
 
     fn run_native(
         &mut self,
         _vm: &VMState,
         proc: &Arc<Process>,
+        module: Atom,
+        function: Atom,
         native: NativeFunctionKind,
         mut args: &mut [Term],
     ) {
         try_gc(proc, &mut args, &mut |args| match native {
             NativeFunctionKind::Simple(ptr) => match ptr(proc, &args[2..]) {
-                Ok(ret) => Ok(call_closure(proc, args[0], &mut [ret])),
-                Err(err) => match err {
-                    Exception::Runtime(RuntimeException::Throw(err)) => Ok(call_closure(
-                        proc,
-                        args[1],
-                        &mut [atom!("throw"), err.reason(), atom!("trace")],
-                    )),
-                    Exception::Runtime(RuntimeException::Exit(err)) => Ok(call_closure(
-                        proc,
-                        args[1],
-                        &mut [atom!("EXIT"), err.reason(), atom!("trace")],
-                    )),
-                    Exception::Runtime(RuntimeException::Error(err)) => Ok(call_closure(
-                        proc,
-                        args[1],
-                        &mut [atom!("error"), err.reason(), atom!("trace")],
-                    )),
-                    Exception::System(err) => return Err(err),
-                },
+                Ok(ret) => {
+                    mark_new_reference(proc, module, function, ret);
+                    trace::return_from(proc, module, function, args.len() - 2, ret);
+                    Ok(call_closure(proc, args[0], &mut [ret]))
+                }
+                Err(Exception::Runtime(err)) => {
+                    Ok(call_closure(proc, args[1], &mut throw_args(&err)))
+                }
+                Err(Exception::System(err)) => return Err(err),
             },
             NativeFunctionKind::Yielding(ptr) => ptr(proc, args),
         })
@@ -327,18 +396,51 @@ impl CallExecutor {
 
             match try_gc(proc, &mut exec, &mut |exec| {
                 exec.next_args.clear();
-                exec.run_erlang_op(vm, proc, fun, block)
+                // Only system exceptions are retried after a GC
+                match exec.run_erlang_op(vm, proc, fun, block) {
+                    Ok(op_result) => Ok(Ok(op_result)),
+                    Err(Exception::Runtime(err)) => Ok(Err(err)),
+                    Err(Exception::System(err)) => Err(err),
+                }
             }) {
-                OpResult::Block(b) => {
+                Ok(OpResult::Block(b)) => {
                     block = b;
                     continue;
                 }
-                OpResult::Term(t) => break call_closure(proc, t, &mut exec.next_args),
-                OpResult::TermYield(t) => break call_closure(proc, t, &mut exec.next_args),
+                Ok(OpResult::Term(t)) => {
+                    exec.trace_return(proc, fun, t);
+                    break call_closure(proc, t, &mut exec.next_args);
+                }
+                Ok(OpResult::TermYield(t)) => break call_closure(proc, t, &mut exec.next_args),
+                // Like an error from a native call, an error the op raises goes to the throw
+                // continuation the function was called with, so that it can be caught
+                Err(err) => {
+                    let throw = fun.fun.block_args(fun.fun.block_entry())[1];
+                    let throw_continuation = exec.binds[&throw];
+                    break call_closure(proc, throw_continuation, &mut throw_args(&err));
+                }
             }
         }
     }
 
+    /// Traces a return if `continuation` is the return continuation the function was called with
+    fn trace_return(&self, proc: &Arc<Process>, fun: &ErlangFunction, continuation: Term) {
+        if !trace::is_enabled() || self.next_args.len() != 1 {
+            return;
+        }
+        let ret = fun.fun.block_args(fun.fun.block_entry())[0];
+        if self.binds.get(&ret) == Some(&continuation) {
+            let ident = fun.fun.ident();
+            trace::return_from(
+                proc,
+                Atom::try_from_str(ident.module.as_str()).unwrap(),
+                Atom::try_from_str(ident.name.as_str()).unwrap(),
+                ident.arity,
+                self.next_args[0],
+            );
+        }
+    }
+
     fn make_const_term(
         &self,
         proc: &Arc<Process>,
@@ -348,6 +450,8 @@ impl CallExecutor {
         let res = match fun.fun.cons().const_kind(const_val) {
             ConstKind::Atomic(AtomicTerm::Atom(atom)) => Ok(Atom::str_to_term(&atom.0.as_str())),
             ConstKind::Atomic(AtomicTerm::Int(int)) => Ok(proc.integer(int.0)?),
+            ConstKind::Atomic(AtomicTerm::BigInt(int)) => Ok(proc.integer(int.0.clone())?),
+            ConstKind::Atomic(AtomicTerm::Float(flt)) => Ok(proc.float(flt.0.inner())?),
             ConstKind::Atomic(AtomicTerm::Binary(bin)) => Ok(proc.binary_from_bytes(&bin.0)?),
             ConstKind::Tuple { entries } => {
                 let vec: Result<Vec<_>, _> = entries
@@ -382,7 +486,6 @@ impl CallExecutor {
 
                 Ok(proc.map_from_hash_map(map)?)
             }
-            kind => unimplemented!("{:?}", kind),
         };
         res
     }
@@ -392,7 +495,7 @@ impl CallExecutor {
         proc: &Arc<Process>,
         fun: &ErlangFunction,
         block: Block,
-    ) -> Result<Term, SystemException> {
+    ) -> exception::Result<Term> {
         let live = &fun.live.live_at(block);
 
         // FIXME vec alloc
@@ -411,8 +514,8 @@ impl CallExecutor {
             block.as_u32(),
             // TODO calculate `old_unique` from `code`
             fun.index.index() as u32,
-            // TODO calculate `unique` from `code`
-            Default::default(),
+            // Calls the version of the module that made the closure, even after it is reloaded
+            fun.module_unique,
             arity,
             Some(crate::code::interpreter_closure_code),
             proc.pid().into(),
@@ -427,11 +530,11 @@ impl CallExecutor {
         proc: &Arc<Process>,
         fun: &ErlangFunction,
         value: Value,
-    ) -> Result<Term, SystemException> {
+    ) -> exception::Result<Term> {
         match fun.fun.value_kind(value) {
             ValueKind::Block(block) => self.make_closure(proc, fun, block),
             ValueKind::Argument(_, _) => Ok(self.binds[&value]),
-            ValueKind::Const(cons) => self.make_const_term(proc, fun, cons),
+            ValueKind::Const(cons) => Ok(self.make_const_term(proc, fun, cons)?),
             ValueKind::PrimOp(prim) => {
                 let reads = fun.fun.primop_reads(prim);
                 match fun.fun.primop_kind(prim) {
@@ -463,7 +566,7 @@ impl CallExecutor {
                     PrimOpKind::LogicOp(LogicOp::And) => {
                         let mut acc = true;
                         for read in reads.iter() {
-                            let term = self.make_term(proc, fun, *read).unwrap();
+                            let term = self.make_term(proc, fun, *read)?;
                             let res: bool = term.try_into().ok().unwrap();
                             acc = acc & res;
                         }
@@ -472,16 +575,37 @@ impl CallExecutor {
                     PrimOpKind::LogicOp(LogicOp::Or) => {
                         let mut acc = false;
                         for read in reads.iter() {
-                            let term = self.make_term(proc, fun, *read).unwrap();
+                            let term = self.make_term(proc, fun, *read)?;
                             let res: bool = term.try_into().ok().unwrap();
                             acc = acc | res;
                         }
                         Ok(acc.into())
                     }
-                    PrimOpKind::BinOp(BinOp::Equal) => {
-                        let lhs: Atom = self.make_term(proc, fun, reads[0])?.try_into().unwrap();
-                        let rhs: Atom = self.make_term(proc, fun, reads[1])?.try_into().unwrap();
-                        Ok((lhs == rhs).into())
+                    PrimOpKind::Map => {
+                        assert!(reads.len() % 2 == 0);
+                        let mut map = HashMap::with_capacity(reads.len() / 2);
+                        for entry in reads.chunks(2) {
+                            let key = self.make_term(proc, fun, entry[0])?;
+                            let value = self.make_term(proc, fun, entry[1])?;
+                            map.insert(key, value);
+                        }
+                        Ok(proc.map_from_hash_map(map)?)
+                    }
+                    PrimOpKind::BinOp(op) => {
+                        assert!(reads.len() == 2);
+                        let lhs = self.make_term(proc, fun, reads[0])?;
+                        let rhs = self.make_term(proc, fun, reads[1])?;
+                        let compare = match op {
+                            BinOp::Equal => erlang::are_equal_after_conversion_2::native,
+                            BinOp::NotEqual => erlang::are_not_equal_after_conversion_2::native,
+                            BinOp::ExactEqual => erlang::are_exactly_equal_2::native,
+                            BinOp::ExactNotEqual => erlang::are_exactly_not_equal_2::native,
+                            BinOp::Less => erlang::is_less_than_2::native,
+                            BinOp::LessEqual => erlang::is_equal_or_less_than_2::native,
+                            BinOp::Greater => erlang::is_greater_than_2::native,
+                            BinOp::GreaterEqual => erlang::is_greater_than_or_equal_2::native,
+                        };
+                        Ok(compare(lhs, rhs))
                     }
                     PrimOpKind::CaptureFunction => {
                         let module: Atom = self.make_term(proc, fun, reads[0])?.try_into().unwrap();
@@ -496,18 +620,44 @@ impl CallExecutor {
                             Some(crate::code::interpreter_mfa_code),
                         )?)
                     }
-                    kind => unimplemented!("{:?}", kind),
+                    kind => Err(unsupported(proc, kind)),
                 }
             }
         }
     }
 
+    /// Calls a failure continuation with an `error` exception, passing only the reason if the
+    /// continuation doesn't also take the class and stacktrace
+    fn val_call_error(
+        &mut self,
+        proc: &Arc<Process>,
+        fun: &ErlangFunction,
+        value: Value,
+        reason: Term,
+    ) -> exception::Result<OpResult> {
+        let arity = match fun.fun.value_kind(value) {
+            ValueKind::Block(block) => fun.fun.block_args(block).len(),
+            // Throw continuations passed in from a caller
+            _ => 3,
+        };
+        match arity {
+            0 => (),
+            1 => self.next_args.push(reason),
+            _ => {
+                self.next_args.push(atom!("error"));
+                self.next_args.push(reason);
+                self.next_args.push(Term::NIL);
+            }
+        }
+        self.val_call(proc, fun, value)
+    }
+
     fn val_call(
         &mut self,
         proc: &Arc<Process>,
         fun: &ErlangFunction,
         value: Value,
-    ) -> Result<OpResult, SystemException> {
+    ) -> exception::Result<OpResult> {
         if let ValueKind::Block(block) = fun.fun.value_kind(value) {
             Ok(OpResult::Block(block))
         } else {
@@ -522,11 +672,10 @@ impl CallExecutor {
         proc: &Arc<Process>,
         fun: &ErlangFunction,
         block: Block,
-    ) -> Result<OpResult, SystemException> {
+    ) -> exception::Result<OpResult> {
         let reads = fun.fun.block_reads(block);
         let kind = fun.fun.block_kind(block).unwrap();
-        trace!("OP: {:?} {}", kind, block);
-        println!("{:?}", reads);
+        log::trace!("OP: {:?} {} {:?}", kind, block, reads);
 
         proc.reduce();
 
@@ -585,7 +734,7 @@ impl CallExecutor {
             }
             OpKind::IfBool => {
                 let call_n = if reads.len() == 4 {
-                    let bool_term = self.make_term(proc, fun, reads[3]).unwrap();
+                    let bool_term = self.make_term(proc, fun, reads[3])?;
                     let b: std::result::Result<bool, _> = bool_term.try_into();
                     match b {
                         Ok(true) => 0,
@@ -593,7 +742,7 @@ impl CallExecutor {
                         Err(_) => 2,
                     }
                 } else if reads.len() == 3 {
-                    let bool_term = self.make_term(proc, fun, reads[2]).unwrap();
+                    let bool_term = self.make_term(proc, fun, reads[2])?;
                     let b: std::result::Result<bool, _> = bool_term.try_into();
                     match b {
                         Ok(true) => 0,
@@ -607,8 +756,26 @@ impl CallExecutor {
                 self.val_call(proc, fun, reads[call_n])
             }
             OpKind::Match { branches } => self::r#match::match_op(self, proc, fun, branches, block),
+            OpKind::TraceCaptureRaw => {
+                assert!(reads.len() == 1);
+                // Stacktraces are not tracked yet, so the raw trace is always empty
+                self.next_args.push(Term::NIL);
+                self.val_call(proc, fun, reads[0])
+            }
+            OpKind::TraceConstruct => {
+                assert!(reads.len() >= 1);
+                self.next_args.push(Term::NIL);
+                self.val_call(proc, fun, reads[0])
+            }
             OpKind::MapPut { action } => {
-                let map_term: Boxed<Map> = self.make_term(proc, fun, reads[2])?.try_into().unwrap();
+                let map = self.make_term(proc, fun, reads[2])?;
+                let map_term: Boxed<Map> = match map.try_into() {
+                    Ok(map_term) => map_term,
+                    Err(_) => {
+                        let reason = proc.tuple_from_slice(&[atom!("badmap"), map])?;
+                        return self.val_call_error(proc, fun, reads[1], reason);
+                    }
+                };
                 let hashmap_ref: &HashMap<Term, Term> = map_term.as_ref().as_ref();
                 let mut hashmap = hashmap_ref.clone();
 
@@ -623,8 +790,9 @@ impl CallExecutor {
                             hashmap.insert(key, val);
                         }
                         MapPutUpdate::Update => {
-                            if hashmap.contains_key(&key) {
-                                unimplemented!()
+                            if !hashmap.contains_key(&key) {
+                                let reason = proc.tuple_from_slice(&[atom!("badkey"), key])?;
+                                return self.val_call_error(proc, fun, reads[1], reason);
                             }
                             hashmap.insert(key, val);
                         }
@@ -638,77 +806,172 @@ impl CallExecutor {
                 assert!(reads.len() == 2);
 
                 let timeout = self.make_term(proc, fun, reads[1])?;
-                // Only infinity supported
-                assert!(timeout == Atom::str_to_term("infinity"));
+                let milliseconds: Option<Milliseconds> = if timeout == atom!("infinity") {
+                    None
+                } else {
+                    match timeout.try_into() {
+                        Ok(milliseconds) => Some(milliseconds),
+                        Err(_) => {
+                            return Err(exception::error(
+                                atom!("timeout_value"),
+                                None,
+                                None,
+                                anyhow!("invalid receive timeout: {}", timeout).into(),
+                            )
+                            .into())
+                        }
+                    }
+                };
 
-                proc.mailbox.lock().borrow_mut().recv_start();
+                // The messages from before a reference was made can't match a receive where
+                // every clause matches the reference
+                let marked = receive::matched_value(&fun.fun, reads[0])
+                    .and_then(|value| self.binds.get(&value))
+                    .and_then(|term| match term.decode() {
+                        Ok(TypedTerm::Reference(reference)) => Some(*reference),
+                        _ => None,
+                    });
+                proc.receive_start(marked.as_ref());
+
+                if let Some(milliseconds) = milliseconds {
+                    if milliseconds == 0 {
+                        // `after 0` times out as soon as the mailbox has been searched
+                        proc.receive_expire();
+                    } else {
+                        timer::start_receive_timeout(
+                            monotonic::time_in_milliseconds() + milliseconds,
+                            proc,
+                        );
+                    }
+                }
 
-                self.next_args.push(Term::NIL);
+                // The timeout is kept by the process for the receive, so the token is only
+                // passed through `receive_wait`
+                self.next_args.push(timeout);
                 self.val_call(proc, fun, reads[0])
             }
             OpKind::Intrinsic(name) if *name == Symbol::intern("receive_wait") => {
                 assert!(reads.len() == 2);
 
+                let token = self.binds[&fun.fun.block_args(block)[0]];
                 let curr_cont = self.make_closure(proc, fun, block)?;
 
-                let mailbox_lock = proc.mailbox.lock();
-                let mut mailbox = mailbox_lock.borrow_mut();
-                if let Some(msg_term) = mailbox.recv_peek() {
-                    mailbox.recv_increment();
-
-                    std::mem::drop(mailbox);
-                    std::mem::drop(mailbox_lock);
-
-                    self.next_args.push(msg_term);
-                    self.val_call(proc, fun, reads[1])
-                } else {
-                    // If there are no messages, schedule a call
-                    // to the current block for later.
-                    self.next_args.push(Term::NIL);
-                    proc.wait();
-                    Ok(OpResult::TermYield(curr_cont))
+                match proc.receive_next() {
+                    Receive::Message(msg_term) => {
+                        self.next_args.push(msg_term);
+                        self.val_call(proc, fun, reads[1])
+                    }
+                    Receive::Timeout => self.val_call(proc, fun, reads[0]),
+                    Receive::Wait => {
+                        // The process is already waiting, so schedule a call to the current
+                        // block for when a message arrives or the timeout expires.
+                        self.next_args.push(token);
+                        Ok(OpResult::TermYield(curr_cont))
+                    }
                 }
             }
             OpKind::Intrinsic(name) if *name == Symbol::intern("receive_done") => {
                 assert!(reads.len() >= 1);
 
-                let mailbox_lock = proc.mailbox.lock();
-                let mut mailbox = mailbox_lock.borrow_mut();
-
-                if mailbox.recv_last_off_heap() {
-                    // Copy to process heap
-                    unimplemented!()
-                } else {
-                    for n in 0..(reads.len() - 1) {
-                        let term = self.make_term(proc, fun, reads[n + 1]).unwrap();
+                // The message is freed along with its heap fragment when removed from the
+                // mailbox, so anything read from it is copied to the process heap first
+                let off_heap = proc.receive_last_off_heap();
+                trace::receive(proc, proc.receive_last());
+                for n in 0..(reads.len() - 1) {
+                    let term = self.make_term(proc, fun, reads[n + 1])?;
+                    if off_heap {
+                        let mut heap = proc.acquire_heap();
+                        self.next_args.push(term.clone_to_heap(&mut heap)?);
+                    } else {
                         self.next_args.push(term);
                     }
                 }
 
-                mailbox.recv_finish(proc);
+                if let Some(timer_reference) = proc.receive_done() {
+                    timer::cancel(&timer_reference);
+                }
 
                 self.val_call(proc, fun, reads[0])
             }
-            OpKind::BinaryPush {
-                specifier: BinaryEntrySpecifier::Bytes { .. },
-            } => {
-                assert!(reads.len() == 4);
-                let head = self.make_term(proc, fun, reads[2])?;
-                let tail = self.make_term(proc, fun, reads[3])?;
-
-                let mut head_bin: Vec<u8> = head.try_into().unwrap();
-                let tail_bin: Vec<u8> = tail.try_into().unwrap();
-                head_bin.extend(tail_bin.iter());
-
-                self.next_args.push(proc.binary_from_bytes(&head_bin)?);
-                self.val_call(proc, fun, reads[0])
-            }
-            OpKind::Unreachable => {
-                println!("==== Reached OpKind::Unreachable! ====");
-                println!("Fun: {} Block: {}", fun.fun.ident(), block);
-                unreachable!();
+            OpKind::BinaryPush { specifier } => {
+                assert!(reads.len() == 4 || reads.len() == 5);
+                let bin = self.make_term(proc, fun, reads[2])?;
+                let value = self.make_term(proc, fun, reads[3])?;
+                let size = match reads.get(4) {
+                    Some(size) => Some(self.make_term(proc, fun, *size)?),
+                    None => None,
+                };
+
+                let pushed = binary::BitVec::from_term(bin).and_then(|mut bits| {
+                    binary::push(&mut bits, specifier, value, size)?;
+                    Some(bits)
+                });
+                match pushed {
+                    Some(bits) => {
+                        self.next_args.push(bits.to_term(proc)?);
+                        self.val_call(proc, fun, reads[0])
+                    }
+                    None => self.val_call_error(proc, fun, reads[1], atom!("badarg")),
+                }
             }
-            kind => unimplemented!("{:?}", kind),
+            // Only reached if the compiler was wrong about a call never returning, which is raised
+            // instead of panicking so that only this process exits
+            OpKind::Unreachable => Err(exception::error(
+                atom!("unreachable"),
+                None,
+                None,
+                anyhow!("reached unreachable in {} at {}", fun.fun.ident(), block).into(),
+            )
+            .into()),
+            // Intrinsics the interpreter doesn't know and ops that should have been lowered by the
+            // compiler passes
+            kind => Err(unsupported(proc, kind)),
         }
     }
 }
+
+/// Marks the mailbox with the reference returned by `erlang:make_ref/0` or `erlang:monitor/2`,
+/// which a receive right after may match in every clause
+fn mark_new_reference(proc: &Process, module: Atom, function: Atom, ret: Term) {
+    match (module.name(), function.name()) {
+        ("erlang", "make_ref") | ("erlang", "monitor") => (),
+        _ => return,
+    }
+
+    if let Ok(TypedTerm::Reference(reference)) = ret.decode() {
+        proc.receive_mark(*reference);
+    }
+}
+
+/// `error:{unsupported, Op}`, where `Op` describes an op or primop that the interpreter can't
+/// run, such as one added to EIR after the interpreter
+fn unsupported<O: Debug>(proc: &Process, op: O) -> Exception {
+    let description = format!("{:?}", op);
+    let reason = proc
+        .binary_from_str(&description)
+        .and_then(|op| proc.tuple_from_slice(&[atom!("unsupported"), op]));
+
+    match reason {
+        Ok(reason) => exception::error(
+            reason,
+            None,
+            None,
+            anyhow!("unsupported op: {}", description).into(),
+        )
+        .into(),
+        Err(err) => err.into(),
+    }
+}
+
+/// The `[class, reason, stacktrace]` arguments of a throw continuation
+fn throw_args(err: &RuntimeException) -> [Term; 3] {
+    let class = match err {
+        RuntimeException::Throw(_) => atom!("throw"),
+        RuntimeException::Error(_) => atom!("error"),
+        RuntimeException::Exit(_) => atom!("exit"),
+    };
+    let reason = err.reason().unwrap();
+    let stacktrace = err.stacktrace().unwrap_or(Term::NIL);
+
+    [class, reason, stacktrace]
+}
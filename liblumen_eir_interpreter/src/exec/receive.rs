@@ -0,0 +1,158 @@
+//! Finds receives where every clause matches a reference made before the receive, like the reply
+//! of a `gen_server:call`. A receive like that can skip the messages that were already in the
+//! mailbox when the reference was made.
+use std::collections::HashSet;
+
+use libeir_intern::Symbol;
+use libeir_ir::{BinOp, Block, Function, MatchKind, OpKind, PrimOpKind, Value, ValueKind};
+
+/// The value that every clause of a receive compares part of the message to, if there is one.
+///
+/// `wait` is the continuation of `receive_start`, which is the `receive_wait` loop of the
+/// receive.
+pub fn matched_value(fun: &Function, wait: Value) -> Option<Value> {
+    let wait = block_of(fun, wait)?;
+    match fun.block_kind(wait) {
+        Some(OpKind::Intrinsic(name)) if *name == Symbol::intern("receive_wait") => (),
+        _ => return None,
+    }
+    let message = block_of(fun, fun.block_reads(wait)[1])?;
+
+    // The clauses are the blocks from the message to `receive_done`, which go back to the wait
+    // loop when the message doesn't match
+    let mut clauses = HashSet::new();
+    let mut compared = Vec::new();
+    collect(fun, wait, message, &mut clauses, &mut compared);
+
+    compared.into_iter().find(|value| {
+        let defined_before = match fun.value_kind(*value) {
+            ValueKind::Argument(defined_in, _) => {
+                defined_in != wait && !clauses.contains(&defined_in)
+            }
+            _ => false,
+        };
+
+        defined_before && every_path_compares(fun, wait, message, *value, &mut HashSet::new())
+    })
+}
+
+/// Collects the blocks reachable from `block` before going back to `wait`, along with the values
+/// they compare to
+fn collect(
+    fun: &Function,
+    wait: Block,
+    block: Block,
+    blocks: &mut HashSet<Block>,
+    compared: &mut Vec<Value>,
+) {
+    if block == wait || !blocks.insert(block) {
+        return;
+    }
+
+    for (next, next_compared) in successors(fun, block) {
+        for value in next_compared {
+            if !compared.contains(&value) {
+                compared.push(value);
+            }
+        }
+        collect(fun, wait, next, blocks, compared);
+    }
+}
+
+/// Whether every path from `block` to `receive_done` compares to `value` first
+fn every_path_compares(
+    fun: &Function,
+    wait: Block,
+    block: Block,
+    value: Value,
+    visited: &mut HashSet<Block>,
+) -> bool {
+    if block == wait || !visited.insert(block) {
+        return true;
+    }
+
+    let reads = fun.block_reads(block);
+    match fun.block_kind(block) {
+        Some(OpKind::Intrinsic(name)) if *name == Symbol::intern("receive_done") => return false,
+        // Returning or raising, so where the path goes is unknown
+        Some(OpKind::Call(_)) if is_argument(fun, reads[0]) => return false,
+        None => return false,
+        _ => (),
+    }
+
+    successors(fun, block).into_iter().all(|(next, compared)| {
+        compared.contains(&value) || every_path_compares(fun, wait, next, value, visited)
+    })
+}
+
+/// The blocks that `block` can continue to, each with the values it has compared equal to on the
+/// way
+fn successors(fun: &Function, block: Block) -> Vec<(Block, Vec<Value>)> {
+    let reads = fun.block_reads(block);
+
+    match fun.block_kind(block) {
+        Some(OpKind::Match { branches }) => branches
+            .iter()
+            .enumerate()
+            .filter_map(|(index, kind)| {
+                let next = block_of(fun, fun.value_list_get_n(reads[0], index)?)?;
+                let compared = match kind {
+                    MatchKind::Value => fun
+                        .value_list_get_n(reads[index + 2], 0)
+                        .into_iter()
+                        .collect(),
+                    _ => Vec::new(),
+                };
+
+                Some((next, compared))
+            })
+            .collect(),
+        Some(OpKind::IfBool) => {
+            let condition = reads[reads.len() - 1];
+            let compared = match fun.value_kind(condition) {
+                ValueKind::PrimOp(prim) => match fun.primop_kind(prim) {
+                    PrimOpKind::BinOp(BinOp::Equal) | PrimOpKind::BinOp(BinOp::ExactEqual) => {
+                        fun.primop_reads(prim).to_vec()
+                    }
+                    _ => Vec::new(),
+                },
+                _ => Vec::new(),
+            };
+
+            reads[..reads.len() - 1]
+                .iter()
+                .enumerate()
+                .filter_map(|(index, read)| {
+                    let next = block_of(fun, *read)?;
+                    // Only the `true` branch has compared equal
+                    let compared = if index == 0 {
+                        compared.clone()
+                    } else {
+                        Vec::new()
+                    };
+
+                    Some((next, compared))
+                })
+                .collect()
+        }
+        _ => reads
+            .iter()
+            .filter_map(|read| block_of(fun, *read))
+            .map(|next| (next, Vec::new()))
+            .collect(),
+    }
+}
+
+fn block_of(fun: &Function, value: Value) -> Option<Block> {
+    match fun.value_kind(value) {
+        ValueKind::Block(block) => Some(block),
+        _ => None,
+    }
+}
+
+fn is_argument(fun: &Function, value: Value) -> bool {
+    match fun.value_kind(value) {
+        ValueKind::Argument(_, _) => true,
+        _ => false,
+    }
+}
@@ -4,8 +4,13 @@ pub mod code;
 mod exec;
 mod module;
 pub use module::NativeModule;
+pub use vm::CallError;
 pub mod call_result;
 mod native;
+pub mod print;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod shell;
+pub mod trace;
 mod vm;
 
 #[cfg(test)]
@@ -1,4 +1,5 @@
 use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use libeir_ir::{Function, FunctionIndex, LiveValues, Module};
@@ -6,15 +7,9 @@ use libeir_ir::{Function, FunctionIndex, LiveValues, Module};
 use liblumen_alloc::erts::exception::Exception;
 use liblumen_alloc::erts::process::code::Result;
 use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::closure::Unique;
 use liblumen_alloc::erts::term::prelude::*;
 
-macro_rules! trace {
-    ($($t:tt)*) => (lumen_runtime::system::io::puts(&format_args!($($t)*).to_string()))
-}
-//macro_rules! trace {
-//    ($($t:tt)*) => ()
-//}
-
 pub enum ResolvedFunction<'a> {
     Native(NativeFunctionKind),
     Erlang(&'a ErlangFunction),
@@ -22,13 +17,35 @@ pub enum ResolvedFunction<'a> {
 
 pub struct ModuleRegistry {
     map: HashMap<Atom, ModuleType>,
+    /// The versions of reloaded modules that were replaced, which funs made by them still call
+    old_versions: HashMap<(Atom, Unique), ErlangModule>,
 }
 
 impl ModuleRegistry {
+    /// Creates a registry containing every BIF implemented in `lumen_runtime`
     pub fn new() -> Self {
-        ModuleRegistry {
+        let mut registry = ModuleRegistry {
             map: HashMap::new(),
+            old_versions: HashMap::new(),
+        };
+
+        let mut natives: HashMap<Atom, NativeModule> = HashMap::new();
+        for native_function in lumen_runtime::code::native::iter() {
+            let module = native_function.module();
+            natives
+                .entry(module)
+                .or_insert_with(|| NativeModule::new(module))
+                .add_simple(
+                    native_function.function(),
+                    native_function.arity as usize,
+                    native_function.apply,
+                );
+        }
+        for (_, native) in natives {
+            registry.register_native_module(native);
         }
+
+        registry
     }
 
     pub fn register_erlang_module(&mut self, module: Module) {
@@ -37,20 +54,40 @@ impl ModuleRegistry {
             None => self
                 .map
                 .insert(erl_module.name, ModuleType::Erlang(erl_module)),
+            // Reloading replaces the previous version of the module for calls by name
+            Some(ModuleType::Erlang(old_erl_module)) => {
+                self.keep_old_version(old_erl_module);
+                self.map
+                    .insert(erl_module.name, ModuleType::Erlang(erl_module))
+            }
             Some(ModuleType::Native(native)) => self
                 .map
                 .insert(erl_module.name, ModuleType::Overlayed(erl_module, native)),
-            _ => panic!(),
+            Some(ModuleType::Overlayed(old_erl_module, native)) => {
+                self.keep_old_version(old_erl_module);
+                self.map
+                    .insert(erl_module.name, ModuleType::Overlayed(erl_module, native))
+            }
         };
     }
 
+    /// Registers native functions, replacing any with the same name and arity that are already
+    /// registered for the module
     pub fn register_native_module(&mut self, native: NativeModule) {
         match self.map.remove(&native.name) {
             None => self.map.insert(native.name, ModuleType::Native(native)),
             Some(ModuleType::Erlang(erl)) => self
                 .map
                 .insert(native.name, ModuleType::Overlayed(erl, native)),
-            _ => panic!(),
+            Some(ModuleType::Native(mut existing)) => {
+                existing.functions.extend(native.functions);
+                self.map.insert(native.name, ModuleType::Native(existing))
+            }
+            Some(ModuleType::Overlayed(erl, mut existing)) => {
+                existing.functions.extend(native.functions);
+                self.map
+                    .insert(native.name, ModuleType::Overlayed(erl, existing))
+            }
         };
     }
 
@@ -60,7 +97,7 @@ impl ModuleRegistry {
         function: Atom,
         arity: usize,
     ) -> Option<ResolvedFunction> {
-        trace!("LOOKUP {}:{}/{}", module, function, arity);
+        log::trace!("LOOKUP {}:{}/{}", module, function, arity);
         match self.map.get(&module) {
             None => None,
             Some(ModuleType::Erlang(erl)) => erl
@@ -86,24 +123,35 @@ impl ModuleRegistry {
         }
     }
 
+    /// Looks up a function of the version of `module` with `unique`, which is the version that
+    /// made the fun calling it
     pub fn lookup_function_idx(
         &self,
         module: Atom,
+        unique: Unique,
         index: FunctionIndex,
     ) -> Option<&ErlangFunction> {
-        let ret = match self.map.get(&module) {
-            None => None,
-            Some(ModuleType::Erlang(erl)) => Some(&erl.funs[&index]),
-            Some(ModuleType::Overlayed(erl, _)) => Some(&erl.funs[&index]),
-            Some(ModuleType::Native(_)) => unreachable!(),
+        let erl_module = match self.map.get(&module) {
+            Some(ModuleType::Erlang(erl)) | Some(ModuleType::Overlayed(erl, _))
+                if erl.unique == unique =>
+            {
+                Some(erl)
+            }
+            _ => self.old_versions.get(&(module, unique)),
         };
+        let ret = erl_module.map(|erl| &erl.funs[&index]);
 
         if let Some(erl) = ret.as_ref() {
-            trace!("LOOKUP IDX {}", erl.fun.ident());
+            log::trace!("LOOKUP IDX {}", erl.fun.ident());
         }
 
         ret
     }
+
+    fn keep_old_version(&mut self, erl_module: ErlangModule) {
+        self.old_versions
+            .insert((erl_module.name, erl_module.unique), erl_module);
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -149,17 +197,22 @@ pub struct ErlangFunction {
     pub fun: Function,
     pub index: FunctionIndex,
     pub live: LiveValues,
+    /// The `unique` of the version of the module the function is from
+    pub module_unique: Unique,
 }
 
 pub struct ErlangModule {
     pub name: Atom,
     pub funs: BTreeMap<FunctionIndex, ErlangFunction>,
     pub name_map: BTreeMap<(Atom, usize), FunctionIndex>,
+    /// Tells this version of the module apart from the others loaded with the same name
+    pub unique: Unique,
 }
 
 impl ErlangModule {
     pub fn from_eir(module: Module) -> Self {
         let name_atom = Atom::try_from_str(module.name().as_str()).unwrap();
+        let unique = next_unique();
 
         let funs = module
             .function_iter()
@@ -169,6 +222,7 @@ impl ErlangModule {
                     live: fun.live_values(),
                     index: fun_def.index(),
                     fun: fun.clone(),
+                    module_unique: unique,
                 };
                 (fun_def.index(), nfun)
             })
@@ -188,10 +242,21 @@ impl ErlangModule {
             name: name_atom,
             funs,
             name_map,
+            unique,
         }
     }
 }
 
+/// A `unique` that no other loaded module version has
+fn next_unique() -> Unique {
+    static NEXT_UNIQUE: AtomicU64 = AtomicU64::new(0);
+
+    let mut unique = Unique::default();
+    unique[..8].copy_from_slice(&NEXT_UNIQUE.fetch_add(1, Ordering::Relaxed).to_be_bytes());
+
+    unique
+}
+
 pub enum ModuleType {
     Erlang(ErlangModule),
     Overlayed(ErlangModule, NativeModule),
@@ -1,63 +1,40 @@
+use anyhow::anyhow;
+
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::code;
+use liblumen_alloc::erts::process::code::stack::frame::Placement;
+use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::*;
 
 use lumen_runtime::otp::erlang;
 
 use crate::module::NativeModule;
+use crate::trace::{self, Tracer};
 
+/// Functions which need to behave differently in the interpreter than the BIFs registered by
+/// `lumen_runtime`, or which are only needed by the interpreter
 pub fn make_erlang() -> NativeModule {
     let mut native = NativeModule::new(Atom::try_from_str("erlang").unwrap());
 
-    native.add_simple(Atom::try_from_str("*").unwrap(), 2, |proc, args| {
-        erlang::multiply_2::native(proc, args[0], args[1])
-    });
-
-    native.add_simple(Atom::try_from_str("/").unwrap(), 2, |proc, args| {
-        erlang::div_2::native(proc, args[0], args[1])
-    });
-
-    native.add_simple(Atom::try_from_str("<").unwrap(), 2, |_proc, args| {
-        Ok(erlang::is_less_than_2::native(args[0], args[1]))
-    });
-    native.add_simple(Atom::try_from_str(">").unwrap(), 2, |_proc, args| {
-        Ok(erlang::is_greater_than_2::native(args[0], args[1]))
-    });
-    native.add_simple(Atom::try_from_str("=<").unwrap(), 2, |_proc, args| {
-        Ok(erlang::is_equal_or_less_than_2::native(args[0], args[1]))
-    });
-    native.add_simple(Atom::try_from_str(">=").unwrap(), 2, |_proc, args| {
-        Ok(erlang::is_greater_than_or_equal_2::native(args[0], args[1]))
-    });
-    native.add_simple(Atom::try_from_str("==").unwrap(), 2, |_proc, args| {
-        Ok(erlang::are_equal_after_conversion_2::native(
-            args[0], args[1],
-        ))
-    });
-    native.add_simple(Atom::try_from_str("=:=").unwrap(), 2, |_proc, args| {
-        Ok(erlang::are_exactly_equal_2::native(args[0], args[1]))
-    });
-
     native.add_simple(Atom::try_from_str("spawn_opt").unwrap(), 4, |proc, args| {
-        match args[3].decode().unwrap() {
+        let is_link = match args[3].decode()? {
             TypedTerm::List(cons) => {
-                let mut iter = cons.into_iter();
-                assert!(iter.next() == Some(Ok(Atom::str_to_term("link").into())));
-                assert!(iter.next() == None);
+                let options = cons.into_iter().collect::<Result<Vec<_>, _>>().ok();
+                options == Some(vec![atom!("link")])
             }
-            t => panic!("{:?}", t),
+            _ => false,
+        };
+        if !is_link {
+            return Err(anyhow!("options ({}) are not supported, only [link] is", args[3]).into());
         }
 
-        let ret = crate::code::return_clean_closure(proc)?;
-
-        let inner_args = proc.cons(ret, proc.cons(ret, args[2])?)?;
-
-        let res = erlang::spawn_link_3::native(proc, args[0], args[1], inner_args)?;
-        Ok(res)
+        let inner_args = with_continuations(proc, args[2])?;
+        erlang::spawn_link_3::native(proc, args[0], args[1], inner_args)
     });
 
     native.add_simple(Atom::try_from_str("spawn").unwrap(), 3, |proc, args| {
-        let ret = crate::code::return_clean_closure(proc)?;
-
-        let inner_args = proc.cons(ret, proc.cons(ret, args[2])?)?;
+        let inner_args = with_continuations(proc, args[2])?;
         erlang::spawn_3::native(proc, args[0], args[1], inner_args)
     });
 
@@ -65,114 +42,71 @@ pub fn make_erlang() -> NativeModule {
         Atom::try_from_str("spawn_link").unwrap(),
         3,
         |proc, args| {
-            let ret = crate::code::return_clean_closure(proc)?;
-
-            let inner_args = proc.cons(ret, proc.cons(ret, args[2])?)?;
+            let inner_args = with_continuations(proc, args[2])?;
             erlang::spawn_link_3::native(proc, args[0], args[1], inner_args)
         },
     );
 
-    native.add_simple(Atom::try_from_str("exit").unwrap(), 1, |_proc, args| {
-        panic!("{:?}", args[0]);
-        //Ok(erlang::exit_1::native(args[0]).unwrap())
-    });
-
-    native.add_simple(Atom::try_from_str("monitor").unwrap(), 2, |proc, args| {
-        erlang::monitor_2::native(proc, args[0], args[1])
-    });
-    native.add_simple(Atom::try_from_str("demonitor").unwrap(), 2, |proc, args| {
-        erlang::demonitor_2::native(proc, args[0], args[1])
-    });
-
-    native.add_simple(Atom::try_from_str("register").unwrap(), 2, |proc, args| {
-        erlang::register_2::native(proc.clone(), args[0], args[1])
-    });
-    native.add_simple(
-        Atom::try_from_str("process_flag").unwrap(),
-        2,
-        |proc, args| erlang::process_flag_2::native(proc, args[0], args[1]),
-    );
-
-    let send_2_module_function_arity = erlang::send_2::module_function_arity();
-    native.add_simple(
-        send_2_module_function_arity.function,
-        send_2_module_function_arity.arity as usize,
-        |proc, args| erlang::send_2::native(proc, args[0], args[1]),
-    );
-
-    native.add_simple(Atom::try_from_str("send").unwrap(), 3, |proc, args| {
-        erlang::send_2::native(proc, args[0], args[1])
-    });
-
-    native.add_simple(Atom::try_from_str("!").unwrap(), 2, |proc, args| {
-        erlang::send_2::native(proc, args[0], args[1])
-    });
-
-    native.add_simple(Atom::try_from_str("-").unwrap(), 2, |proc, args| {
-        erlang::subtract_2::native(proc, args[0], args[1])
-    });
-
-    native.add_simple(Atom::try_from_str("+").unwrap(), 2, |proc, args| {
-        erlang::add_2::native(proc, args[0], args[1])
-    });
+    // Overridden so that sends can be traced
+    for name in &["!", "send"] {
+        native.add_simple(Atom::try_from_str(name).unwrap(), 2, |proc, args| {
+            trace::send(proc, args[0], args[1]);
+            erlang::send_2::native(proc, args[0], args[1])
+        });
+    }
+
+    // Unlike the BEAM, all the events of a traced process are traced, so the flags other than
+    // `{tracer, Tracer}` only need to be valid. Like the BEAM, the caller is the default tracer.
+    native.add_simple(Atom::try_from_str("trace").unwrap(), 3, |proc, args| {
+        let pid = match args[0].decode()? {
+            TypedTerm::Pid(pid) => pid,
+            _ => return Err(anyhow!("process ({}) is not a local pid", args[0]).into()),
+        };
+        let on = match args[1].decode()? {
+            TypedTerm::Atom(how) if how.name() == "true" || how.name() == "false" => {
+                how.name() == "true"
+            }
+            _ => return Err(anyhow!("how ({}) is not a boolean", args[1]).into()),
+        };
+        let flags = match args[2].decode()? {
+            TypedTerm::Nil => Vec::new(),
+            TypedTerm::List(cons) => cons
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| anyhow!("flags ({}) is not a proper list", args[2]))?,
+            _ => return Err(anyhow!("flags ({}) is not a list", args[2]).into()),
+        };
+
+        let mut tracer = proc.pid();
+        for flag in flags {
+            match flag.decode()? {
+                TypedTerm::Atom(atom) if TRACE_FLAGS.contains(&atom.name()) => (),
+                TypedTerm::Tuple(tuple) if tuple.len() == 2 && tuple[0] == atom!("tracer") => {
+                    tracer = match tuple[1].decode()? {
+                        TypedTerm::Pid(pid) => pid,
+                        _ => return Err(anyhow!("tracer ({}) is not a local pid", tuple[1]).into()),
+                    }
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "flag ({}) is not supported, supported flags are {} and {{tracer, Pid}}",
+                        flag,
+                        TRACE_FLAGS.join(", ")
+                    )
+                    .into())
+                }
+            }
+        }
 
-    native.add_simple(Atom::try_from_str("self").unwrap(), 0, |proc, _args| {
-        Ok(proc.pid_term())
-    });
+        if on {
+            trace::set_tracer(Tracer::Process(tracer));
+        }
+        trace::trace_process(pid, on);
 
-    native.add_simple(
-        Atom::try_from_str("is_integer").unwrap(),
-        1,
-        |_proc, args| {
-            assert!(args.len() == 1);
-            Ok(erlang::is_integer_1::native(args[0]))
-        },
-    );
-    native.add_simple(Atom::try_from_str("is_list").unwrap(), 1, |_proc, args| {
-        Ok(erlang::is_list_1::native(args[0]))
-    });
-    native.add_simple(
-        Atom::try_from_str("is_binary").unwrap(),
-        1,
-        |_proc, args| Ok(erlang::is_binary_1::native(args[0])),
-    );
-    native.add_simple(Atom::try_from_str("is_atom").unwrap(), 1, |_proc, args| {
-        Ok(erlang::is_atom_1::native(args[0]))
-    });
-    native.add_simple(Atom::try_from_str("is_pid").unwrap(), 1, |_proc, args| {
-        Ok(erlang::is_pid_1::native(args[0]))
-    });
-    native.add_simple(
-        Atom::try_from_str("is_function").unwrap(),
-        1,
-        |_proc, args| Ok(erlang::is_function_1::native(args[0])),
-    );
-    native.add_simple(
-        Atom::try_from_str("is_function").unwrap(),
-        2,
-        |_proc, args| Ok(erlang::is_function_1::native(args[0])),
-    );
-    native.add_simple(Atom::try_from_str("is_tuple").unwrap(), 1, |_proc, args| {
-        Ok(erlang::is_tuple_1::native(args[0]))
-    });
-    native.add_simple(Atom::try_from_str("is_map").unwrap(), 1, |_proc, args| {
-        Ok(erlang::is_map_1::native(args[0]))
-    });
-    native.add_simple(
-        Atom::try_from_str("is_bitstring").unwrap(),
-        1,
-        |_proc, args| Ok(erlang::is_bitstring_1::native(args[0])),
-    );
-    native.add_simple(Atom::try_from_str("is_float").unwrap(), 1, |_proc, args| {
-        Ok(erlang::is_bitstring_1::native(args[0]))
+        // The number of processes whose flags were changed
+        Ok(proc.integer(1)?)
     });
 
-    native.add_simple(
-        Atom::try_from_str("monotonic_time").unwrap(),
-        0,
-        |proc, _args| erlang::monotonic_time_0::native(proc),
-    );
-
     native.add_yielding(Atom::try_from_str("apply").unwrap(), 3, |proc, args| {
         let inner_args = proc.cons(args[0], proc.cons(args[1], args[4])?)?;
         proc.stack_push(inner_args)?;
@@ -183,38 +117,35 @@ pub fn make_erlang() -> NativeModule {
         crate::code::apply(proc)
     });
 
-    native.add_simple(Atom::try_from_str("node").unwrap(), 0, |_proc, _args| {
-        Ok(erlang::node_0::native())
-    });
-    native.add_simple(Atom::try_from_str("node").unwrap(), 1, |_proc, _args| {
-        Ok(Atom::str_to_term("nonode@nohost"))
-    });
-    native.add_simple(Atom::try_from_str("whereis").unwrap(), 1, |_proc, args| {
-        erlang::whereis_1::native(args[0])
-    });
-
-    native.add_simple(
-        Atom::try_from_str("process_info").unwrap(),
-        2,
-        |proc, args| erlang::process_info_2::native(proc, args[0], args[1]),
-    );
+    // `hibernate/3` throws away the call stack, so `module:function` is called with the
+    // continuations of a spawned process's initial call instead of those of the caller
+    native.add_yielding(Atom::try_from_str("hibernate").unwrap(), 3, |proc, args| {
+        let inner_args = match with_continuations(proc, args[4]) {
+            Ok(inner_args) => inner_args,
+            Err(exception) => return code::result_from_exception(proc, 0, exception),
+        };
+        erlang::hibernate_3::place_frame_with_arguments(
+            proc,
+            Placement::Replace,
+            args[2],
+            args[3],
+            inner_args,
+        )?;
 
-    native.add_simple(Atom::try_from_str("get").unwrap(), 1, |proc, args| {
-        Ok(erlang::get_1::native(proc, args[0]))
-    });
-    native.add_simple(Atom::try_from_str("put").unwrap(), 2, |proc, args| {
-        Ok(proc.put(args[0], args[1])?)
+        Process::call_code(proc)
     });
 
-    native.add_simple(
-        Atom::try_from_str("convert_time_unit").unwrap(),
-        3,
-        |proc, args| erlang::convert_time_unit_3::native(proc, args[0], args[1], args[2]),
-    );
+    native
+}
 
-    native.add_simple(Atom::try_from_str("element").unwrap(), 2, |_proc, args| {
-        erlang::element_2::native(args[0], args[1])
-    });
+/// The `erlang:trace/3` flags for the events that are traced
+const TRACE_FLAGS: &[&str] = &["all", "call", "receive", "return_to", "send"];
 
-    native
+/// Prepends the continuations of the initial call of a spawned process to its arguments, so that
+/// it exits `normal` when the call returns and with the exception if it raises one
+fn with_continuations(proc: &Process, arguments: Term) -> exception::Result<Term> {
+    let ok = crate::code::return_clean_closure(proc)?;
+    let throw = crate::code::return_throw_closure(proc)?;
+
+    Ok(proc.cons(ok, proc.cons(throw, arguments)?)?)
 }
@@ -1,26 +1,50 @@
-use liblumen_alloc::erts::term::prelude::Atom;
+use std::convert::TryInto;
 
-use crate::module::NativeModule;
+use log::Level;
 
-macro_rules! trace {
-    ($($t:tt)*) => (lumen_runtime::system::io::puts(&format_args!($($t)*).to_string()))
-}
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::module::NativeModule;
+use crate::print;
 
 pub fn make_logger() -> NativeModule {
     let mut native = NativeModule::new(Atom::try_from_str("logger").unwrap());
 
-    native.add_simple(Atom::try_from_str("allow").unwrap(), 2, |_proc, _args| {
-        Ok(true.into())
+    native.add_simple(Atom::try_from_str("allow").unwrap(), 2, |_proc, args| {
+        Ok(level(args[0])
+            .map_or(false, |level| level <= log::max_level())
+            .into())
     });
 
     native.add_simple(
         Atom::try_from_str("macro_log").unwrap(),
         4,
         |_proc, args| {
-            trace!("{} {} {} {}", args[0], args[1], args[2], args[3]);
-            Ok(true.into())
+            let level = level(args[1]).unwrap_or(Level::Info);
+            log::log!(
+                level,
+                "{} {} {}",
+                print::term(args[2]),
+                print::term(args[3]),
+                print::term(args[0])
+            );
+            Ok(atom!("ok"))
         },
     );
 
     native
 }
+
+/// Maps the `logger` levels onto those of `log`, which has fewer
+fn level(term: Term) -> Option<Level> {
+    let atom: Atom = term.try_into().ok()?;
+    let level = match atom.name() {
+        "emergency" | "alert" | "critical" | "error" => Level::Error,
+        "warning" => Level::Warn,
+        "notice" | "info" => Level::Info,
+        "debug" => Level::Debug,
+        _ => return None,
+    };
+    Some(level)
+}
@@ -1,14 +1,11 @@
 mod erlang;
 pub use erlang::make_erlang;
 
-mod lists;
-pub use lists::make_lists;
-
-mod maps;
-pub use maps::make_maps;
-
 mod logger;
 pub use logger::make_logger;
 
 mod lumen_intrinsics;
 pub use lumen_intrinsics::make_lumen_intrinsics;
+
+mod timer;
+pub use timer::make_timer;
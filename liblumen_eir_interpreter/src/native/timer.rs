@@ -0,0 +1,86 @@
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::code;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use lumen_runtime::otp::erlang::{convert_time_unit_3, monotonic_time_0, subtract_2};
+
+use crate::exec::call_closure;
+use crate::module::NativeModule;
+
+/// The `timer` functions that call other functions, which `lumen_runtime` only implements with
+/// the process stack
+pub fn make_timer() -> NativeModule {
+    let mut native = NativeModule::new(module());
+
+    // `apply/3` is called with a return continuation that times it before returning
+    // `{Time, Value}` to the caller
+    native.add_yielding(Atom::try_from_str("tc").unwrap(), 3, |proc, args| {
+        let before = match monotonic_time_0::native(proc) {
+            Ok(before) => before,
+            Err(exception) => return code::result_from_exception(proc, 0, exception),
+        };
+        let return_tc = proc.anonymous_closure_with_env_from_slice(
+            module(),
+            0,
+            Default::default(),
+            Default::default(),
+            1,
+            Some(return_tc),
+            proc.pid().into(),
+            &[before, args[0]],
+        )?;
+
+        let inner_args = proc.cons(return_tc, proc.cons(args[1], args[4])?)?;
+        proc.stack_push(inner_args)?;
+
+        proc.stack_push(args[3])?;
+        proc.stack_push(args[2])?;
+
+        crate::code::apply(proc)
+    });
+
+    native
+}
+
+fn module() -> Atom {
+    Atom::try_from_str("timer").unwrap()
+}
+
+/// Calls the return continuation of `timer:tc/3` with `{Time, Value}`, where `Value` is returned
+/// by the applied function and `Time` is in microseconds
+fn return_tc(arc_process: &Arc<Process>) -> code::Result {
+    let argument_list = arc_process.stack_pop().unwrap();
+    let closure_term = arc_process.stack_pop().unwrap();
+
+    let value = match argument_list.decode().unwrap() {
+        TypedTerm::List(argument_cons) => argument_cons.head,
+        _ => panic!(),
+    };
+
+    let closure: Boxed<Closure> = closure_term.try_into().unwrap();
+    let before = closure.env_slice()[0];
+    let return_ok = closure.env_slice()[1];
+
+    match time_value(arc_process, before, value) {
+        Ok(time_value) => {
+            call_closure(arc_process, return_ok, &mut [time_value]);
+
+            Ok(())
+        }
+        Err(exception) => code::result_from_exception(arc_process, 0, exception),
+    }
+}
+
+fn time_value(process: &Process, before: Term, value: Term) -> exception::Result<Term> {
+    let after = monotonic_time_0::native(process)?;
+    let duration = subtract_2::native(process, after, before)?;
+    let time =
+        convert_time_unit_3::native(process, duration, atom!("native"), atom!("microsecond"))?;
+
+    Ok(process.tuple_from_slice(&[time, value])?)
+}
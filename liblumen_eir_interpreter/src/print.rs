@@ -0,0 +1,186 @@
+//! Prints terms the way the Erlang shell does, e.g. `{ok,"abc"}` rather than the
+//! `Display` implementations of the runtime terms, which are meant for debugging.
+use std::fmt::Write;
+
+use liblumen_alloc::erts::term::prelude::*;
+
+const KEYWORDS: &[&str] = &[
+    "after", "and", "andalso", "band", "begin", "bnot", "bor", "bsl", "bsr", "bxor", "case",
+    "catch", "cond", "div", "end", "fun", "if", "let", "not", "of", "or", "orelse", "receive",
+    "rem", "try", "when", "xor",
+];
+
+/// Formats a term as Erlang source
+pub fn term(term: Term) -> String {
+    let mut out = String::new();
+    write_term(&mut out, term);
+    out
+}
+
+/// Formats an exception along with its stacktrace
+pub fn exception(class: Term, reason: Term, stacktrace: Term) -> String {
+    let mut out = String::new();
+    write!(out, "** exception ").unwrap();
+    write_term(&mut out, class);
+    write!(out, ": ").unwrap();
+    write_term(&mut out, reason);
+    for frame in list_elements(stacktrace).unwrap_or_default() {
+        write!(out, "\n     in ").unwrap();
+        write_frame(&mut out, frame);
+    }
+    out
+}
+
+/// Formats an atom, quoting it only if necessary
+pub fn atom(atom: Atom) -> String {
+    let name = atom.name();
+    let mut chars = name.chars();
+    let unquoted = match chars.next() {
+        Some(c) if c.is_ascii_lowercase() => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+                && !KEYWORDS.contains(&name)
+        }
+        _ => false,
+    };
+    if unquoted {
+        name.to_string()
+    } else {
+        let mut quoted = String::with_capacity(name.len() + 2);
+        quoted.push('\'');
+        for c in name.chars() {
+            match c {
+                '\'' => quoted.push_str("\\'"),
+                '\\' => quoted.push_str("\\\\"),
+                _ => quoted.extend(c.escape_default()),
+            }
+        }
+        quoted.push('\'');
+        quoted
+    }
+}
+
+fn write_term(out: &mut String, term: Term) {
+    let typed_term = match term.decode() {
+        Ok(typed_term) => typed_term,
+        Err(_) => {
+            write!(out, "{:?}", term).unwrap();
+            return;
+        }
+    };
+    match typed_term {
+        TypedTerm::Atom(a) => out.push_str(&atom(a)),
+        TypedTerm::Nil => out.push_str("[]"),
+        TypedTerm::SmallInteger(small) => {
+            let i: isize = small.into();
+            write!(out, "{}", i).unwrap()
+        }
+        TypedTerm::Tuple(tuple) => {
+            out.push('{');
+            write_elements(out, tuple.iter().copied());
+            out.push('}');
+        }
+        TypedTerm::List(cons) => write_list(out, cons),
+        TypedTerm::Map(map) => {
+            // Sorted, so that the output doesn't depend on the hashing of the keys
+            let mut entries = map.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+            entries.sort();
+            out.push_str("#{");
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_term(out, key);
+                out.push_str(" => ");
+                write_term(out, value);
+            }
+            out.push('}');
+        }
+        other => write!(out, "{}", other).unwrap(),
+    }
+}
+
+fn write_elements<I: Iterator<Item = Term>>(out: &mut String, elements: I) {
+    for (i, element) in elements.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_term(out, element);
+    }
+}
+
+fn write_list(out: &mut String, cons: Boxed<Cons>) {
+    let mut elements = Vec::new();
+    let mut tail = None;
+    for result in cons.as_ref().into_iter() {
+        match result {
+            Ok(element) => elements.push(element),
+            Err(ImproperList { tail: improper }) => tail = Some(improper),
+        }
+    }
+
+    if tail.is_none() {
+        if let Some(string) = printable(&elements) {
+            write!(out, "{:?}", string).unwrap();
+            return;
+        }
+    }
+
+    out.push('[');
+    write_elements(out, elements.into_iter());
+    if let Some(tail) = tail {
+        out.push('|');
+        write_term(out, tail);
+    }
+    out.push(']');
+}
+
+/// Returns the list as a string if every element is a printable character
+fn printable(elements: &[Term]) -> Option<String> {
+    elements
+        .iter()
+        .map(|element| match element.decode() {
+            Ok(TypedTerm::SmallInteger(small)) => {
+                let i: isize = small.into();
+                std::char::from_u32(i as u32)
+                    .filter(|c| i >= 0 && (!c.is_control() || c.is_ascii_whitespace()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn list_elements(list: Term) -> Option<Vec<Term>> {
+    match list.decode().ok()? {
+        TypedTerm::Nil => Some(Vec::new()),
+        TypedTerm::List(cons) => cons.as_ref().into_iter().collect::<Result<_, _>>().ok(),
+        _ => None,
+    }
+}
+
+/// Formats a `{Module, Function, ArityOrArgs, Location}` stacktrace entry as `Module:Function/Arity`
+fn write_frame(out: &mut String, frame: Term) {
+    let elements = match frame.decode() {
+        Ok(TypedTerm::Tuple(tuple)) => tuple.iter().copied().collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    match elements.as_slice() {
+        [module, function, arity, ..] => {
+            write_term(out, *module);
+            out.push(':');
+            write_term(out, *function);
+            match list_elements(*arity) {
+                // The arguments were captured instead of the arity
+                Some(args) => {
+                    out.push('(');
+                    write_elements(out, args.into_iter());
+                    out.push(')');
+                }
+                None => {
+                    out.push('/');
+                    write_term(out, *arity);
+                }
+            }
+        }
+        _ => write_term(out, frame),
+    }
+}
@@ -0,0 +1,581 @@
+//! An interactive shell for evaluating Erlang expressions in the interpreter.
+//!
+//! Each expression sequence is compiled into the shell's evaluation module, which is reloaded
+//! for every sequence and has a single `eval` function. `eval` takes the values of the previously
+//! bound variables it uses and returns its result along with the values of the variables it
+//! binds. It is called by one long-lived evaluator process, so `self()`, its mailbox, links and
+//! monitors persist between evaluations, like in `erl`. The bound values are kept on the heap of a
+//! dedicated shell process between evaluations.
+pub(crate) mod scan;
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+
+use rustyline::error::ReadlineError;
+use rustyline::Editor;
+
+use libeir_diagnostics::{ColorChoice, Emitter, StandardStreamEmitter};
+
+use libeir_ir::Module;
+
+use libeir_passes::PassManager;
+
+use libeir_syntax_erl::ast::Module as ErlAstModule;
+use libeir_syntax_erl::{lower_module, ParseConfig, Parser, ParserError};
+
+use liblumen_alloc::atom;
+use liblumen_alloc::borrow::clone_to_process::CloneToProcess;
+use liblumen_alloc::erts::exception::AllocResult;
+use liblumen_alloc::erts::process::alloc::TermAlloc;
+use liblumen_alloc::erts::process::{Process, Status};
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::HeapFragment;
+
+use lumen_runtime::config::Command;
+use lumen_runtime::process;
+use lumen_runtime::scheduler::smp;
+
+use crate::call_result::call_erlang;
+use crate::print;
+use crate::VM;
+
+use self::scan::Exprs;
+
+const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = ".. ";
+
+/// The variable the result of the expressions is bound to in the generated module
+const RESULT_VAR: &str = "Shell@Result";
+
+/// The module run by the evaluator processes, which calls `eval` in the evaluation module it is
+/// sent and replies with its result or the exception it raised
+const EVALUATOR_MODULE: &str = "lumen_shell";
+const EVALUATOR_SOURCE: &str = "-module(lumen_shell).\n\
+                                -export([loop/1]).\n\
+                                loop(Shell) ->\n\
+                                receive\n\
+                                {'$lumen_shell_eval', Module, Args} ->\n\
+                                Reply = try {value, apply(Module, eval, Args)}\n\
+                                catch Class:Reason:Stacktrace ->\n\
+                                {exception, Class, Reason, Stacktrace}\n\
+                                end,\n\
+                                Shell ! {'$lumen_shell_reply', Reply},\n\
+                                loop(Shell)\n\
+                                end.\n";
+const EVAL_TAG: &str = "$lumen_shell_eval";
+const REPLY_TAG: &str = "$lumen_shell_reply";
+
+/// How often the shell checks for the evaluator's reply
+const REPLY_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Numbers the evaluation module of each shell, so shells don't reload each other's
+static SHELLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Runs the shell for the given command, compiling with `config`
+pub fn start(command: &Command, config: ParseConfig) -> Result<()> {
+    match command {
+        Command::Run => Ok(()),
+        Command::Shell => Shell::new(config)?.run(),
+        Command::RemoteShell(node) => Err(anyhow!(
+            "cannot connect to {}, distribution is not supported yet",
+            node
+        )),
+    }
+}
+
+/// The outcome of evaluating an expression sequence
+pub enum Evaluation {
+    Value(Term),
+    Exception {
+        class: Term,
+        reason: Term,
+        stacktrace: Term,
+    },
+    /// `q()` was called
+    Quit,
+}
+
+pub struct Shell {
+    config: ParseConfig,
+    process: Arc<Process>,
+    /// Evaluates the expressions, which see it as `self()`
+    evaluator: Arc<Process>,
+    /// The module each expression sequence is compiled into
+    eval_module: Atom,
+    bindings: Vec<(String, Term)>,
+}
+
+impl Shell {
+    pub fn new(config: ParseConfig) -> Result<Self> {
+        // Starts the VM and the scheduler threads, if not already started
+        let _ = &*VM;
+        let process = smp::runtime().spawn_init(0)?;
+
+        let evaluator_module = Atom::try_from_str(EVALUATOR_MODULE)?;
+        let loaded = VM
+            .modules
+            .read()
+            .unwrap()
+            .lookup_function(evaluator_module, Atom::try_from_str("loop")?, 1)
+            .is_some();
+        if !loaded {
+            let module = compile(&config, |parser| {
+                parser.parse_string::<&str, ErlAstModule>(EVALUATOR_SOURCE)
+            })?;
+            VM.modules.write().unwrap().register_erlang_module(module);
+        }
+
+        let evaluator = spawn_evaluator(&process)?;
+        let eval_module = Atom::try_from_str(&format!(
+            "lumen_shell_eval_{}",
+            SHELLS.fetch_add(1, Ordering::Relaxed)
+        ))?;
+
+        Ok(Self {
+            config,
+            process,
+            evaluator,
+            eval_module,
+            bindings: Vec::new(),
+        })
+    }
+
+    /// The variables bound so far, in the order they were first bound
+    pub fn bindings(&self) -> &[(String, Term)] {
+        &self.bindings
+    }
+
+    /// Reads, evaluates and prints expressions until `q()` or the end of input
+    pub fn run(&mut self) -> Result<()> {
+        let mut editor = Editor::<()>::new();
+        let mut input = String::new();
+        loop {
+            let prompt = if input.is_empty() {
+                PROMPT
+            } else {
+                CONTINUATION_PROMPT
+            };
+            match editor.readline(prompt) {
+                Ok(line) => {
+                    input.push_str(&line);
+                    input.push('\n');
+                }
+                // Like `erl`, an interrupt abandons the current input rather than exiting
+                Err(ReadlineError::Interrupted) => {
+                    input.clear();
+                    continue;
+                }
+                Err(ReadlineError::Eof) => return Ok(()),
+                Err(err) => return Err(err.into()),
+            }
+            if !scan::is_complete(&input) {
+                continue;
+            }
+
+            editor.add_history_entry(input.trim_end());
+            let evaluations = match self.eval(&input) {
+                Ok(evaluations) => evaluations,
+                Err(err) => {
+                    eprintln!("** {}", err);
+                    Vec::new()
+                }
+            };
+            input.clear();
+
+            for evaluation in evaluations {
+                match evaluation {
+                    Evaluation::Value(value) => println!("{}", print::term(value)),
+                    Evaluation::Exception {
+                        class,
+                        reason,
+                        stacktrace,
+                    } => eprintln!("{}", print::exception(class, reason, stacktrace)),
+                    Evaluation::Quit => return Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Evaluates each expression sequence in the input, which must be complete
+    ///
+    /// Evaluation stops early if `q()` is called or an expression sequence fails to compile;
+    /// an exception only stops the expression sequence which raised it.
+    pub fn eval(&mut self, input: &str) -> Result<Vec<Evaluation>> {
+        let sequences = scan::split(input).ok_or_else(|| anyhow!("incomplete input"))?;
+        let mut evaluations = Vec::with_capacity(sequences.len());
+        for exprs in sequences {
+            evaluations.push(self.eval_exprs(&exprs)?);
+            if let Some(Evaluation::Quit) = evaluations.last() {
+                break;
+            }
+        }
+        Ok(evaluations)
+    }
+
+    fn eval_exprs(&mut self, exprs: &Exprs) -> Result<Evaluation> {
+        if let Some(evaluation) = self.command(exprs.source)? {
+            return Ok(evaluation);
+        }
+
+        // Only variables which are already bound are passed in, any others are bound or unbound
+        // by the expressions themselves, which is reported when lowering
+        let params = exprs
+            .vars
+            .iter()
+            .filter_map(|var| {
+                self.bindings
+                    .iter()
+                    .find(|(name, _)| name == var)
+                    .map(|(name, value)| (name.clone(), *value))
+            })
+            .collect::<Vec<_>>();
+
+        let source = format!(
+            "-module('{module}').\n\
+             -export([eval/{arity}]).\n\
+             eval({params}) ->\n\
+             {result} = begin\n{source}\nend,\n\
+             {{{result}, [{bound}]}}.\n",
+            module = self.eval_module.name(),
+            arity = params.len(),
+            params = params
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            result = RESULT_VAR,
+            source = exprs.source,
+            bound = exprs.bound.join(", "),
+        );
+        let module = compile(&self.config, |parser| {
+            parser.parse_string::<&str, ErlAstModule>(&source)
+        })?;
+        // Funs made by earlier versions keep calling those versions
+        VM.modules.write().unwrap().register_erlang_module(module);
+
+        let args = params.iter().map(|(_, value)| *value).collect::<Vec<_>>();
+        self.send_eval(&args)?;
+
+        let reply = match self.receive_reply() {
+            Some(reply) => reply,
+            None => return self.restart_evaluator(),
+        };
+        match decode_reply(reply)
+            .ok_or_else(|| anyhow!("unexpected reply from the evaluator: {:?}", reply))?
+        {
+            Reply::Value(returned) => {
+                let (value, bound) = decode_eval_result(returned)
+                    .ok_or_else(|| anyhow!("unexpected result from the shell: {:?}", returned))?;
+                for (name, value) in exprs.bound.iter().zip(bound) {
+                    self.bind(name, value);
+                }
+                Ok(Evaluation::Value(value))
+            }
+            Reply::Exception {
+                class,
+                reason,
+                stacktrace,
+            } => Ok(Evaluation::Exception {
+                class,
+                reason,
+                stacktrace,
+            }),
+        }
+    }
+
+    /// Sends the evaluator the arguments to call the evaluation module with
+    fn send_eval(&self, args: &[Term]) -> Result<()> {
+        let tag = Atom::str_to_term(EVAL_TAG);
+        let eval_module = self.eval_module.encode()?;
+        // Besides the copies of the arguments, the list has 2 words for each argument and the
+        // tuple has a header and 3 elements
+        let words = args.iter().map(|arg| arg.size_in_words()).sum::<usize>() + 2 * args.len() + 4;
+
+        let mut non_null_heap_fragment = HeapFragment::new_from_word_size(words)?;
+        let heap_fragment = unsafe { non_null_heap_fragment.as_mut() };
+        let message = build_eval_message(heap_fragment, tag, eval_module, args)?;
+
+        self.evaluator
+            .send_heap_message(non_null_heap_fragment, message);
+        stop_waiting(&self.evaluator);
+
+        Ok(())
+    }
+
+    /// Waits for the evaluator to reply, returning `None` if it exited instead, such as when
+    /// killed by the expressions or a linked process
+    fn receive_reply(&self) -> Option<Term> {
+        let reply_tag = Atom::str_to_term(REPLY_TAG);
+
+        loop {
+            // Checked before the mailbox, as the evaluator may reply just before it exits
+            let exiting = self.evaluator.is_exiting();
+
+            while let Some(message) = self.process.mailbox.lock().borrow_mut().pop() {
+                // The message stays alive on the heap or in an attached heap fragment of the shell
+                // process
+                let data = *message.data();
+                if let Ok(TypedTerm::Tuple(tuple)) = data.decode() {
+                    if tuple.len() == 2 && tuple[0] == reply_tag {
+                        return Some(tuple[1]);
+                    }
+                }
+            }
+
+            if exiting {
+                return None;
+            }
+
+            thread::sleep(REPLY_POLL_INTERVAL);
+        }
+    }
+
+    /// Reports how the evaluator exited and replaces it, like `erl` does when its evaluator crashes
+    fn restart_evaluator(&mut self) -> Result<Evaluation> {
+        let evaluation = match *self.evaluator.status.read() {
+            Status::Exiting(ref exception) => Evaluation::Exception {
+                class: Atom::str_to_term(&exception.class().unwrap().to_string()),
+                reason: exception.reason().unwrap().clone_to_process(&self.process),
+                stacktrace: Term::NIL,
+            },
+            _ => unreachable!(),
+        };
+        self.evaluator = spawn_evaluator(&self.process)?;
+
+        Ok(evaluation)
+    }
+
+    /// Handles the shell commands, returning `None` if the source is not a shell command
+    fn command(&mut self, source: &str) -> Result<Option<Evaluation>> {
+        let (name, arg) = match parse_command(source) {
+            Some(command) => command,
+            None => return Ok(None),
+        };
+        let evaluation = match (name, arg) {
+            ("b", None) => {
+                for (name, value) in self.bindings.iter() {
+                    println!("{} = {}", name, print::term(*value));
+                }
+                Evaluation::Value(atom!("ok"))
+            }
+            ("c", Some(file)) => self.compile_and_load(file)?,
+            ("f", None) => {
+                self.bindings.clear();
+                Evaluation::Value(atom!("ok"))
+            }
+            ("f", Some(var)) => {
+                self.bindings.retain(|(name, _)| name != var);
+                Evaluation::Value(atom!("ok"))
+            }
+            ("q", None) => Evaluation::Quit,
+            _ => return Ok(None),
+        };
+        Ok(Some(evaluation))
+    }
+
+    /// Compiles a file and loads it into the module registry, replacing any previous version
+    fn compile_and_load(&mut self, file: &str) -> Result<Evaluation> {
+        let mut path = PathBuf::from(file);
+        if path.extension().is_none() {
+            path.set_extension("erl");
+        }
+        let module = match compile(&self.config, |parser| {
+            parser.parse_file::<&Path, ErlAstModule>(path.as_path())
+        }) {
+            Ok(module) => module,
+            // The diagnostics have already been printed
+            Err(_) => return Ok(Evaluation::Value(atom!("error"))),
+        };
+        let name = Atom::try_from_str(module.name().as_str())?;
+        VM.modules.write().unwrap().register_erlang_module(module);
+
+        let ok = self
+            .process
+            .tuple_from_slice(&[atom!("ok"), name.encode()?])?;
+        Ok(Evaluation::Value(ok))
+    }
+
+    fn bind(&mut self, name: &str, value: Term) {
+        match self.bindings.iter_mut().find(|(bound, _)| bound == name) {
+            Some(binding) => binding.1 = value,
+            None => self.bindings.push((name.to_string(), value)),
+        }
+    }
+}
+
+impl Drop for Shell {
+    fn drop(&mut self) {
+        process::exit_signal(
+            &self.process,
+            &self.evaluator,
+            atom!("kill"),
+            anyhow!("shell stopped").into(),
+        );
+    }
+}
+
+/// What the evaluator replied with
+enum Reply {
+    Value(Term),
+    Exception {
+        class: Term,
+        reason: Term,
+        stacktrace: Term,
+    },
+}
+
+fn build_eval_message(
+    heap_fragment: &mut HeapFragment,
+    tag: Term,
+    eval_module: Term,
+    args: &[Term],
+) -> AllocResult<Term> {
+    let args = args
+        .iter()
+        .map(|arg| arg.clone_to_heap(heap_fragment))
+        .collect::<AllocResult<Vec<_>>>()?;
+    let args = heap_fragment.list_from_slice(&args)?.encode().unwrap();
+
+    Ok(heap_fragment
+        .tuple_from_slice(&[tag, eval_module, args])?
+        .encode()
+        .unwrap())
+}
+
+/// Parses, lowers and optimizes a module with `config`, printing any diagnostics
+fn compile<F>(config: &ParseConfig, parse: F) -> Result<Module>
+where
+    F: FnOnce(&Parser) -> std::result::Result<ErlAstModule, Vec<ParserError>>,
+{
+    let parser = Parser::new(config.clone());
+    let emitter =
+        StandardStreamEmitter::new(ColorChoice::Auto).set_codemap(parser.config.codemap.clone());
+
+    let ast = match parse(&parser) {
+        Ok(ast) => ast,
+        Err(errs) => {
+            for err in errs.iter() {
+                emitter.diagnostic(&err.to_diagnostic()).unwrap();
+            }
+            return Err(anyhow!("parsing failed"));
+        }
+    };
+
+    let (res, messages) = lower_module(&parser.config.codemap.lock().unwrap(), &ast);
+    for err in messages.iter() {
+        emitter.diagnostic(&err.to_diagnostic()).unwrap();
+    }
+    let mut module = res.map_err(|_| anyhow!("compilation failed"))?;
+
+    let mut pass_manager = PassManager::default();
+    pass_manager.run(&mut module);
+
+    Ok(module)
+}
+
+/// Parses shell commands of the form `name()` or `name(Arg)`, where the argument is an atom,
+/// variable name or string
+fn parse_command(source: &str) -> Option<(&str, Option<&str>)> {
+    let open = source.find('(')?;
+    let name = source[..open].trim();
+    let arg = source[open + 1..].trim_end().strip_suffix(')')?.trim();
+    if !["b", "c", "f", "q"].contains(&name) {
+        return None;
+    }
+    if arg.is_empty() {
+        return Some((name, None));
+    }
+    let unquoted = arg
+        .strip_prefix('"')
+        .and_then(|arg| arg.strip_suffix('"'))
+        .or_else(|| {
+            arg.strip_prefix('\'')
+                .and_then(|arg| arg.strip_suffix('\''))
+        });
+    match unquoted {
+        Some(unquoted) => Some((name, Some(unquoted))),
+        // Anything more complex than a bare atom or variable is a regular function call
+        None if arg
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '@') =>
+        {
+            Some((name, Some(arg)))
+        }
+        None => None,
+    }
+}
+
+/// Splits the `{value, Returned}` and `{exception, Class, Reason, Stacktrace}` replies of the
+/// evaluator
+fn decode_reply(reply: Term) -> Option<Reply> {
+    let tuple = match reply.decode().ok()? {
+        TypedTerm::Tuple(tuple) => tuple,
+        _ => return None,
+    };
+    match tuple.len() {
+        2 if tuple[0] == atom!("value") => Some(Reply::Value(tuple[1])),
+        4 if tuple[0] == atom!("exception") => Some(Reply::Exception {
+            class: tuple[1],
+            reason: tuple[2],
+            stacktrace: tuple[3],
+        }),
+        _ => None,
+    }
+}
+
+/// Splits the `{Value, BoundValues}` tuple returned by the generated `eval` function
+fn decode_eval_result(returned: Term) -> Option<(Term, Vec<Term>)> {
+    let tuple = match returned.decode().ok()? {
+        TypedTerm::Tuple(tuple) => tuple,
+        _ => return None,
+    };
+    if tuple.len() != 2 {
+        return None;
+    }
+    let value = tuple.get_element(0usize).ok()?;
+    let bound = match tuple.get_element(1usize).ok()?.decode().ok()? {
+        TypedTerm::Nil => Vec::new(),
+        TypedTerm::List(cons) => cons
+            .as_ref()
+            .into_iter()
+            .collect::<std::result::Result<_, _>>()
+            .ok()?,
+        _ => return None,
+    };
+    Some((value, bound))
+}
+
+/// Spawns an evaluator process, which replies to `shell_process`
+fn spawn_evaluator(shell_process: &Arc<Process>) -> Result<Arc<Process>> {
+    let receiver = call_erlang(
+        shell_process.clone(),
+        Atom::try_from_str(EVALUATOR_MODULE)?,
+        Atom::try_from_str("loop")?,
+        &[shell_process.pid_term()],
+    );
+
+    Ok(receiver.process)
+}
+
+fn stop_waiting(arc_process: &Arc<Process>) {
+    let was_waiting = {
+        let mut status = arc_process.status.write();
+        if *status == Status::Waiting {
+            *status = Status::Runnable;
+            true
+        } else {
+            false
+        }
+    };
+
+    if was_waiting {
+        if let Some(scheduler) = arc_process.scheduler() {
+            scheduler.stop_waiting(arc_process);
+        }
+    }
+}
@@ -0,0 +1,285 @@
+//! A lightweight scanner over shell input.
+//!
+//! The shell needs to know where an expression sequence ends, and which variables it uses and
+//! binds, before the input is handed to the real parser. This only tracks enough of the Erlang
+//! token structure to answer those questions.
+
+/// An expression sequence terminated by a `.`
+#[derive(Debug, PartialEq)]
+pub struct Exprs<'a> {
+    /// The source of the expressions, without the terminating `.`
+    pub source: &'a str,
+    /// Every variable which occurs in the expressions, in order of first occurrence
+    pub vars: Vec<&'a str>,
+    /// The variables bound by top-level matches, e.g. `X` and `Y` in `{X, Y} = foo(), X + Y`
+    pub bound: Vec<&'a str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    Var(&'a str),
+    Atom(&'a str),
+    Punct(&'a str),
+    Dot,
+    Other,
+}
+
+/// Multi-character operators, longest first so that e.g. `=:=` is not scanned as `=`
+const OPERATORS: &[&str] = &[
+    "=:=", "=/=", "==", "=<", "=>", ":=", "/=", ">=", "<=", "<<", ">>", "->", "<-", "||", "++",
+    "--",
+];
+
+/// Returns `true` if the input ends with a terminating `.`, i.e. it can be evaluated
+pub fn is_complete(input: &str) -> bool {
+    match tokenize(input) {
+        Some(tokens) => tokens
+            .last()
+            .map(|(_, t)| *t == Token::Dot)
+            .unwrap_or(false),
+        None => false,
+    }
+}
+
+/// Splits complete input into its expression sequences
+///
+/// Returns `None` if the input is incomplete, e.g. it is missing the final `.`
+/// or ends in the middle of a string.
+pub fn split<'a>(input: &'a str) -> Option<Vec<Exprs<'a>>> {
+    let tokens = tokenize(input)?;
+    let mut result = Vec::new();
+    let mut start = 0;
+    let mut segment_start = 0;
+    for (i, (offset, token)) in tokens.iter().enumerate() {
+        if *token != Token::Dot {
+            continue;
+        }
+        let segment = &tokens[segment_start..i];
+        let source = input[start..*offset].trim();
+        if !source.is_empty() {
+            result.push(Exprs {
+                source,
+                vars: vars(segment),
+                bound: bound(segment),
+            });
+        }
+        start = offset + 1;
+        segment_start = i + 1;
+    }
+    if !input[start..].trim().is_empty() {
+        return None;
+    }
+    Some(result)
+}
+
+fn vars<'a>(tokens: &[(usize, Token<'a>)]) -> Vec<&'a str> {
+    let mut vars = Vec::new();
+    for (_, token) in tokens.iter() {
+        if let Token::Var(name) = *token {
+            if name != "_" && !vars.contains(&name) {
+                vars.push(name);
+            }
+        }
+    }
+    vars
+}
+
+/// Finds the variables in the patterns of top-level matches, i.e. those to the left of the
+/// last `=` outside of any brackets or blocks in each comma-separated expression
+fn bound<'a>(tokens: &[(usize, Token<'a>)]) -> Vec<&'a str> {
+    let mut bound = Vec::new();
+    let mut depth = 0usize;
+    let mut expr_start = 0;
+    let mut last_match = None;
+    for (i, (_, token)) in tokens.iter().enumerate() {
+        match *token {
+            Token::Punct("(") | Token::Punct("[") | Token::Punct("{") | Token::Punct("<<") => {
+                depth += 1
+            }
+            Token::Punct(")") | Token::Punct("]") | Token::Punct("}") | Token::Punct(">>") => {
+                depth = depth.saturating_sub(1)
+            }
+            Token::Atom("begin")
+            | Token::Atom("case")
+            | Token::Atom("if")
+            | Token::Atom("receive")
+            | Token::Atom("try") => depth += 1,
+            // `fun foo/1` and `fun m:f/1` have no `end`, only funs with clauses do
+            Token::Atom("fun") if opens_fun(&tokens[i + 1..]) => depth += 1,
+            Token::Atom("end") => depth = depth.saturating_sub(1),
+            Token::Punct("=") if depth == 0 => last_match = Some(i),
+            Token::Punct(",") if depth == 0 => {
+                push_bound(&tokens[expr_start..i], expr_start, last_match, &mut bound);
+                expr_start = i + 1;
+                last_match = None;
+            }
+            _ => (),
+        }
+    }
+    push_bound(&tokens[expr_start..], expr_start, last_match, &mut bound);
+    bound
+}
+
+fn push_bound<'a>(
+    expr: &[(usize, Token<'a>)],
+    expr_start: usize,
+    last_match: Option<usize>,
+    bound: &mut Vec<&'a str>,
+) {
+    if let Some(last_match) = last_match {
+        for name in vars(&expr[..last_match - expr_start]) {
+            if !bound.contains(&name) {
+                bound.push(name);
+            }
+        }
+    }
+}
+
+fn opens_fun(rest: &[(usize, Token)]) -> bool {
+    match rest {
+        [(_, Token::Punct("(")), ..] => true,
+        // A named fun, e.g. `fun Fact(0) -> 1; Fact(N) -> N * Fact(N - 1) end`
+        [(_, Token::Var(_)), (_, Token::Punct("(")), ..] => true,
+        _ => false,
+    }
+}
+
+/// Splits the input into tokens paired with their byte offset,
+/// returning `None` if the input ends inside a string, quoted atom or character literal
+fn tokenize<'a>(input: &'a str) -> Option<Vec<(usize, Token<'a>)>> {
+    let bytes = input.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+        match c {
+            b'%' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            _ if c.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'"' | b'\'' => {
+                // Quoted atoms are never keywords, so neither they nor strings affect nesting
+                i = skip_quoted(bytes, i + 1, c)?;
+                tokens.push((start, Token::Other));
+                continue;
+            }
+            b'$' => {
+                // Character literals, e.g. `$a`, `$.` or `$\n`
+                i += 1;
+                if i < bytes.len() && bytes[i] == b'\\' {
+                    i += 1;
+                }
+                if i >= bytes.len() {
+                    return None;
+                }
+                i += utf8_len(bytes[i]);
+                tokens.push((start, Token::Other));
+                continue;
+            }
+            b'0'..=b'9' => {
+                i = skip_number(bytes, i);
+                tokens.push((start, Token::Other));
+                continue;
+            }
+            _ if c == b'_' || c.is_ascii_alphabetic() || c >= 0x80 => {
+                while i < bytes.len()
+                    && (bytes[i] == b'_'
+                        || bytes[i] == b'@'
+                        || bytes[i].is_ascii_alphanumeric()
+                        || bytes[i] >= 0x80)
+                {
+                    i += 1;
+                }
+                let name = &input[start..i];
+                let token = if c == b'_' || c.is_ascii_uppercase() {
+                    Token::Var(name)
+                } else {
+                    Token::Atom(name)
+                };
+                tokens.push((start, token));
+                continue;
+            }
+            b'.' => {
+                // A `.` is only a terminator when followed by whitespace, a comment or the end
+                let next = bytes.get(i + 1);
+                let is_dot = match next {
+                    None => true,
+                    Some(b'%') => true,
+                    Some(n) => n.is_ascii_whitespace(),
+                };
+                i += 1;
+                tokens.push((
+                    start,
+                    if is_dot {
+                        Token::Dot
+                    } else {
+                        Token::Punct(".")
+                    },
+                ));
+                continue;
+            }
+            _ => (),
+        }
+        let rest = &input[i..];
+        let op: &str = match OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            Some(op) => op,
+            None => &rest[..utf8_len(c)],
+        };
+        i += op.len();
+        tokens.push((start, Token::Punct(op)));
+    }
+    Some(tokens)
+}
+
+/// Skips to just past the closing quote, returning `None` if there is none
+fn skip_quoted(bytes: &[u8], mut i: usize, quote: u8) -> Option<usize> {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Skips an integer or float, e.g. `10`, `16#ff`, `1.5` or `1.0e-10`
+fn skip_number(bytes: &[u8], mut i: usize) -> usize {
+    let digits = |bytes: &[u8], mut i: usize| {
+        while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+            i += 1;
+        }
+        i
+    };
+    i = digits(bytes, i);
+    if i < bytes.len() && bytes[i] == b'#' {
+        return digits(bytes, i + 1);
+    }
+    if i + 1 < bytes.len() && bytes[i] == b'.' && bytes[i + 1].is_ascii_digit() {
+        i = digits(bytes, i + 1);
+        // The exponent sign, the digits before it were consumed as alphanumerics
+        if i + 1 < bytes.len()
+            && (bytes[i - 1] == b'e' || bytes[i - 1] == b'E')
+            && (bytes[i] == b'-' || bytes[i] == b'+')
+        {
+            i = digits(bytes, i + 1);
+        }
+    }
+    i
+}
+
+fn utf8_len(first: u8) -> usize {
+    match first {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        _ => 4,
+    }
+}
@@ -1,8 +1,17 @@
-use super::VM;
+#[cfg(not(target_arch = "wasm32"))]
+mod shell;
+
+use super::trace::{self, Tracer};
+use super::{CallError, VM};
+
+use std::sync::{Mutex, MutexGuard};
+
+use lazy_static::lazy_static;
+use num_bigint::BigInt;
 
 use libeir_diagnostics::{ColorChoice, Emitter, StandardStreamEmitter};
 
-use libeir_ir::Module;
+use libeir_ir::{FunctionIdent, Module};
 
 use libeir_passes::PassManager;
 
@@ -14,6 +23,24 @@ use liblumen_alloc::erts::term::prelude::*;
 
 use lumen_runtime::scheduler::Scheduler;
 
+lazy_static! {
+    static ref TRACE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Gives a test sole use of the tracer, which is global, and turns tracing off once it is done,
+/// even if it fails
+struct TraceGuard(MutexGuard<'static, ()>);
+impl TraceGuard {
+    fn acquire() -> Self {
+        TraceGuard(TRACE_LOCK.lock().unwrap_or_else(|error| error.into_inner()))
+    }
+}
+impl Drop for TraceGuard {
+    fn drop(&mut self) {
+        trace::clear();
+    }
+}
+
 fn parse<T>(input: &str, config: ParseConfig) -> (T, Parser)
 where
     T: Parse<T>,
@@ -113,6 +140,38 @@ fib(X) -> fib(X - 1) + fib(X - 2).
     assert!(res.result == Ok(int));
 }
 
+#[test]
+fn runtime_bifs() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("runtime_bifs").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(runtime_bifs).
+
+run() ->
+    List = lists:reverse([1, 2, 3]),
+    {3, _} = lists:keyfind(3, 1, [{1, a}, {3, b}]),
+    b = maps:get(a, #{a => b}),
+    {Time, [3, 2, 1]} = timer:tc(lists, reverse, [[1, 2, 3]]),
+    true = is_integer(Time) andalso Time >= 0,
+    length(List) + tuple_size({a, b}).
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    let int = init_arc_process.integer(5).unwrap();
+    assert!(res.result == Ok(int));
+}
+
 #[test]
 fn exception_test() {
     &*VM;
@@ -142,6 +201,633 @@ a() -> 1 + a.
     }
 }
 
+#[test]
+fn constants() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("constants").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(constants).
+
+run() -> {1.5, 123456789012345678901234567890}.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    let big: BigInt = "123456789012345678901234567890".parse().unwrap();
+    let tuple = init_arc_process
+        .tuple_from_slice(&[
+            init_arc_process.float(1.5).unwrap(),
+            init_arc_process.integer(big).unwrap(),
+        ])
+        .unwrap();
+    assert!(res.result == Ok(tuple));
+}
+
+#[test]
+fn comparisons() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("comparisons").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(comparisons).
+
+compare(A, B) when A == B, A =< B, A >= B, A =:= B -> equal;
+compare(A, B) when A /= B, A < B, A =/= B -> less;
+compare(A, B) when A > B -> greater.
+
+loosely_equal(A, B) when A == B, A =/= B -> true;
+loosely_equal(_, _) -> false.
+
+run() ->
+    {equal, less, greater} = {compare(1, 1), compare(1, 2), compare(b, a)},
+    {true, false} = {loosely_equal(1, 1.0), loosely_equal(1, 1)},
+    ok.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    assert!(res.result == Ok(Atom::str_to_term("ok")));
+}
+
+#[test]
+fn map_update() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("map_update").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(map_update).
+
+update(M, K) -> M#{K := 2, b => 3}.
+
+run() ->
+    #{a := 2, b := 3} = update(#{a => 1}, a),
+    badkey = try update(#{a => 1}, c) of
+        _ -> ok
+    catch
+        error:{badkey, c} -> badkey
+    end,
+    badmap = try update(not_a_map, a) of
+        _ -> ok
+    catch
+        error:{badmap, not_a_map} -> badmap
+    end,
+    ok.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    assert!(res.result == Ok(Atom::str_to_term("ok")));
+}
+
+#[test]
+fn binaries() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("binaries").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(binaries).
+
+build(X) ->
+    <<1, X:4, 3:4, -X:16/signed-little, 1.5/float, \"abc\", 16#20AC/utf8, 16#1F600/utf16,
+      16#41/utf32-little, 1:12/little>>.
+
+run() ->
+    <<1, A:4, B:4, C:16/signed-little, F/float, S:3/binary, U8/utf8, U16/utf16,
+      U32/utf32-little, L:12/little>> = build(2),
+    {2, 3, -2, 1.5, <<\"abc\">>, 16#20AC, 16#1F600, 16#41, 1} = {A, B, C, F, S, U8, U16, U32, L},
+    <<_:3, Rest/bits>> = build(2),
+    Size = bit_size(Rest),
+    Size = bit_size(build(2)) - 3,
+    badarg = try <<(build(2))/binary>> of
+        _ -> ok
+    catch
+        error:badarg -> badarg
+    end,
+    ok.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    assert!(res.result == Ok(Atom::str_to_term("ok")));
+}
+
+#[test]
+fn receive_after_zero() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("receive_after_zero").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(receive_after_zero).
+
+run() ->
+    self() ! first,
+    timeout = receive second -> second after 0 -> timeout end,
+    first = receive first -> first after 0 -> timeout end,
+    timeout = receive _ -> message after 0 -> timeout end,
+    ok.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    assert!(res.result == Ok(Atom::str_to_term("ok")));
+}
+
+#[test]
+fn receive_after_timeout() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("receive_after_timeout").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(receive_after_timeout).
+
+run() ->
+    self() ! first,
+    timeout = receive second -> second after 10 -> timeout end,
+    first = receive first -> first after 10 -> timeout end,
+    self() ! second,
+    second = receive second -> second after 1000 -> timeout end,
+    timeout = receive _ -> message after 10 -> timeout end,
+    ok.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    assert!(res.result == Ok(Atom::str_to_term("ok")));
+}
+
+#[test]
+fn receive_invalid_timeout() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("receive_invalid_timeout").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(receive_invalid_timeout).
+
+id(X) -> X.
+
+run() ->
+    receive
+        message -> message
+    after id(soon) -> timeout
+    end.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    match res.result {
+        Err((class, reason, _)) => {
+            assert_eq!(class, Atom::str_to_term("error"));
+            assert_eq!(reason, Atom::str_to_term("timeout_value"));
+        }
+        Ok(value) => panic!("returned {}", value),
+    }
+}
+
+#[test]
+fn receive_new_reference() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("receive_new_reference").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    // The receives for `Ref` skip the messages from before it was made, which must still be
+    // received by later receives
+    let eir_mod = compile(
+        "
+-module(receive_new_reference).
+
+call(Message) ->
+    Ref = make_ref(),
+    self() ! {Ref, Message},
+    receive
+        {Ref, Reply} -> Reply
+    end.
+
+run() ->
+    self() ! before,
+    first = call(first),
+    First = make_ref(),
+    Second = make_ref(),
+    self() ! {First, first},
+    self() ! {Second, second},
+    first = receive {First, Reply} -> Reply end,
+    second = receive {Second, Reply2} -> Reply2 end,
+    before = receive Message -> Message end,
+    ok.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    assert!(res.result == Ok(Atom::str_to_term("ok")));
+}
+
+#[test]
+fn stacktrace() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("stacktrace").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(stacktrace).
+
+run() ->
+    try erlang:error(oops) of
+        _ -> ok
+    catch
+        error:oops:Stacktrace -> is_list(Stacktrace)
+    end.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    assert!(res.result == Ok(Atom::str_to_term("true")));
+}
+
+#[test]
+fn catchable_errors() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("catchable_errors").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(catchable_errors).
+
+one(1) -> one.
+
+id(X) -> X.
+
+catch_error(Fun) ->
+    try Fun() of
+        _ -> ok
+    catch
+        error:Reason -> Reason
+    end.
+
+run() ->
+    undef = catch_error(fun() -> catchable_errors_missing:f() end),
+    badarg = catch_error(fun() -> list_to_atom(id(1)) end),
+    function_clause = catch_error(fun() -> one(id(2)) end),
+    {badmatch, 2} = catch_error(fun() -> 1 = id(2) end),
+    {badkey, b} = catch_error(fun() -> (id(#{a => 1}))#{b := 2} end),
+    timeout_value = catch_error(fun() -> receive message -> message after id(soon) -> timeout end end),
+    bye = try exit(bye) of
+        _ -> ok
+    catch
+        exit:Reason -> Reason
+    end,
+    ok.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    assert!(res.result == Ok(Atom::str_to_term("ok")));
+}
+
+#[test]
+fn exits_propagate_through_links() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("exits_propagate_through_links").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(exits_propagate_through_links).
+
+crash() -> exit(crashed).
+
+run() ->
+    spawn_link(exits_propagate_through_links, crash, []),
+    receive
+        never -> ok
+    end.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    match res.result {
+        Err((class, reason, _)) => {
+            assert_eq!(class, Atom::str_to_term("exit"));
+            assert_eq!(reason, Atom::str_to_term("crashed"));
+        }
+        Ok(value) => panic!("returned {}", value),
+    }
+}
+
+#[test]
+fn calling_a_non_fun_raises_badfun() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("calling_a_non_fun_raises_badfun").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(calling_a_non_fun_raises_badfun).
+
+id(X) -> X.
+
+run() ->
+    Fun = id(1),
+    Fun().
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    match res.result {
+        Err((class, reason, _)) => {
+            assert_eq!(class, Atom::str_to_term("error"));
+            assert_eq!(crate::print::term(reason), "{badfun,1}");
+        }
+        Ok(value) => panic!("returned {}", value),
+    }
+}
+
+#[test]
+fn undefined_function_calls_error_handler() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("undefined_function_calls_error_handler").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(undefined_function_calls_error_handler).
+
+-export([undefined_function/3]).
+
+undefined_function(Module, Function, Args) -> {handled, Module, Function, Args}.
+
+run() ->
+    process_flag(error_handler, undefined_function_calls_error_handler),
+    missing:function(1, 2).
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    match res.result {
+        Ok(value) => assert_eq!(
+            crate::print::term(value),
+            "{handled,missing,function,[1,2]}"
+        ),
+        Err((class, reason, _)) => panic!("raised {}:{}", class, reason),
+    }
+}
+
+#[test]
+fn save_calls_saves_last_calls() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("save_calls_saves_last_calls").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(save_calls_saves_last_calls).
+
+-export([id/1]).
+
+id(X) -> X.
+
+run() ->
+    Self = self(),
+    process_flag(save_calls, 2),
+    save_calls_saves_last_calls:id(1),
+    save_calls_saves_last_calls:id(2),
+    process_info(Self, last_calls).
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    match res.result {
+        Ok(value) => assert_eq!(
+            crate::print::term(value),
+            "{last_calls,[{save_calls_saves_last_calls,id,1},{erlang,process_info,2}]}"
+        ),
+        Err((class, reason, _)) => panic!("raised {}:{}", class, reason),
+    }
+}
+
+#[test]
+fn vm_call() {
+    &*VM;
+
+    let eir_mod = compile(
+        "
+-module(vm_call).
+
+double(X) -> X * 2.
+
+fail() -> erlang:error(failed).
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let double = FunctionIdent::parse("vm_call:double/1").unwrap();
+    let four = VM.call(&double, &[VM.init.integer(2).unwrap()]).unwrap();
+    assert_eq!(four, VM.init.integer(4).unwrap());
+
+    let fail = FunctionIdent::parse("vm_call:fail/0").unwrap();
+    match VM.call(&fail, &[]) {
+        Err(CallError::Exception { class, reason, .. }) => {
+            assert_eq!(class, Atom::str_to_term("error"));
+            assert_eq!(reason, Atom::str_to_term("failed"));
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn trace_module_to_process() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("trace_module_to_process").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(trace_module_to_process).
+
+double(X) -> X * 2.
+
+work() -> double(21).
+
+run() ->
+    spawn(trace_module_to_process, work, []),
+    receive
+        {trace, _, call, {trace_module_to_process, double, [21]}} -> ok
+    end,
+    receive
+        {trace, _, return_from, {trace_module_to_process, double, 1}, 42} -> ok
+    end.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let _trace_guard = TraceGuard::acquire();
+
+    trace::set_tracer(Tracer::Process(init_arc_process.pid()));
+    trace::trace_module(module, true);
+    assert!(trace::is_enabled());
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    assert!(res.result == Ok(Atom::str_to_term("ok")));
+}
+
+#[test]
+fn trace_process_with_bif() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("trace_process_with_bif").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(trace_process_with_bif).
+
+work() ->
+    receive
+        go -> ok
+    end.
+
+run() ->
+    Pid = spawn(trace_process_with_bif, work, []),
+    1 = erlang:trace(Pid, true, ['receive']),
+    Pid ! go,
+    receive
+        {trace, Pid, 'receive', go} -> ok
+    end.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let _trace_guard = TraceGuard::acquire();
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    assert!(res.result == Ok(Atom::str_to_term("ok")));
+}
+
 #[test]
 fn fib_gc() {
     &*VM;
@@ -0,0 +1,129 @@
+use libeir_syntax_erl::ParseConfig;
+
+use liblumen_alloc::atom;
+
+use crate::shell::scan::{is_complete, split};
+use crate::shell::{Evaluation, Shell};
+
+#[test]
+fn incomplete_input() {
+    assert!(!is_complete("X = 1"));
+    assert!(!is_complete("X = \"a.\n"));
+    assert!(!is_complete("X = 1.5"));
+    assert!(!is_complete("X = #rec.field"));
+    assert!(is_complete("X = 1."));
+    assert!(is_complete("X = $. . % comment"));
+}
+
+#[test]
+fn top_level_matches_bind_variables() {
+    let exprs = split("{X, Y} = foo(Z), A = B = X + Y, A.").unwrap();
+    assert_eq!(exprs.len(), 1);
+    assert_eq!(exprs[0].source, "{X, Y} = foo(Z), A = B = X + Y, A");
+    assert_eq!(exprs[0].vars, vec!["X", "Y", "Z", "A", "B"]);
+    assert_eq!(exprs[0].bound, vec!["X", "Y", "A", "B"]);
+}
+
+#[test]
+fn nested_matches_do_not_bind_variables() {
+    let exprs = split("case foo() of X = 1 -> X end, F = fun (Y) -> Z = Y, Z end.").unwrap();
+    assert_eq!(exprs[0].bound, vec!["F"]);
+
+    let exprs = split("F = fun lists:reverse/1, X = F([1]).").unwrap();
+    assert_eq!(exprs[0].bound, vec!["F", "X"]);
+
+    let exprs = split("X == 1, Y =:= 2, #{a := 1} = M.").unwrap();
+    assert!(exprs[0].bound.is_empty());
+}
+
+#[test]
+fn multiple_expression_sequences() {
+    let exprs = split("X = 1. Y = X + 1.\n").unwrap();
+    let sources = exprs.iter().map(|e| e.source).collect::<Vec<_>>();
+    assert_eq!(sources, vec!["X = 1", "Y = X + 1"]);
+    assert!(split("X = 1. Y").is_none());
+}
+
+#[test]
+fn bindings_persist_between_evaluations() {
+    let mut shell = Shell::new(ParseConfig::default()).unwrap();
+
+    let evaluations = shell.eval("{X, Y} = {1, 2}. Z = X + Y.").unwrap();
+    assert_eq!(evaluations.len(), 2);
+    assert_eq!(shell.bindings().len(), 3);
+
+    let evaluations = shell.eval("Z * 2.").unwrap();
+    match evaluations.as_slice() {
+        [Evaluation::Value(value)] => assert_eq!(crate::print::term(*value), "6"),
+        _ => panic!("expected a value"),
+    }
+
+    shell.eval("f(Z).").unwrap();
+    assert_eq!(shell.bindings().len(), 2);
+
+    match shell.eval("q(). X.").unwrap().as_slice() {
+        [Evaluation::Quit] => (),
+        _ => panic!("expected q() to stop evaluation"),
+    }
+}
+
+#[test]
+fn exceptions_are_returned() {
+    let mut shell = Shell::new(ParseConfig::default()).unwrap();
+
+    match shell.eval("erlang:error(badarg).").unwrap().as_slice() {
+        [Evaluation::Exception { class, reason, .. }] => {
+            assert_eq!(*class, atom!("error"));
+            assert_eq!(*reason, atom!("badarg"));
+        }
+        _ => panic!("expected an exception"),
+    }
+}
+
+#[test]
+fn evaluator_persists_between_evaluations() {
+    let mut shell = Shell::new(ParseConfig::default()).unwrap();
+
+    shell.eval("Self = self(), self() ! hello.").unwrap();
+    match shell.eval("Self =:= self().").unwrap().as_slice() {
+        [Evaluation::Value(value)] => assert_eq!(*value, atom!("true")),
+        _ => panic!("expected a value"),
+    }
+    match shell
+        .eval("receive Message -> Message after 0 -> timeout end.")
+        .unwrap()
+        .as_slice()
+    {
+        [Evaluation::Value(value)] => assert_eq!(*value, atom!("hello")),
+        _ => panic!("expected the message sent by an earlier evaluation"),
+    }
+}
+
+#[test]
+fn funs_outlive_reloads_of_the_evaluation_module() {
+    let mut shell = Shell::new(ParseConfig::default()).unwrap();
+
+    shell.eval("F = fun (X) -> X + 1 end.").unwrap();
+    shell.eval("G = fun () -> ok end.").unwrap();
+    match shell.eval("F(1).").unwrap().as_slice() {
+        [Evaluation::Value(value)] => assert_eq!(crate::print::term(*value), "2"),
+        _ => panic!("expected a value"),
+    }
+}
+
+#[test]
+fn exited_evaluator_is_restarted() {
+    let mut shell = Shell::new(ParseConfig::default()).unwrap();
+
+    match shell.eval("exit(self(), kill).").unwrap().as_slice() {
+        [Evaluation::Exception { class, reason, .. }] => {
+            assert_eq!(*class, atom!("exit"));
+            assert_eq!(*reason, atom!("killed"));
+        }
+        _ => panic!("expected the evaluator to exit"),
+    }
+    match shell.eval("1 + 1.").unwrap().as_slice() {
+        [Evaluation::Value(value)] => assert_eq!(crate::print::term(*value), "2"),
+        _ => panic!("expected a value"),
+    }
+}
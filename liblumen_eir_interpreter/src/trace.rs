@@ -0,0 +1,314 @@
+//! Tracing of the calls, returns, sends and receives made by interpreted code.
+//!
+//! Nothing is traced until a module or process is turned on with `trace_module` or
+//! `trace_process`, or a process is turned on from Erlang with `erlang:trace/3`. Calls and returns are traced for traced modules and traced processes, while
+//! sends and receives are only traced for traced processes. Events are either logged with `log`
+//! or sent to a tracer process as the same messages as `erlang:trace/3`:
+//!
+//! * `{trace, Pid, call, {Module, Function, Arguments}}`
+//! * `{trace, Pid, return_from, {Module, Function, Arity}, Value}`
+//! * `{trace, Pid, send, Message, To}`
+//! * `{trace, Pid, 'receive', Message}`
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use lazy_static::lazy_static;
+use log::Level;
+
+use liblumen_alloc::atom;
+use liblumen_alloc::borrow::clone_to_process::CloneToProcess;
+use liblumen_alloc::erts::exception::AllocResult;
+use liblumen_alloc::erts::process::alloc::TermAlloc;
+use liblumen_alloc::erts::process::{Process, Status};
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::HeapFragment;
+
+use lumen_runtime::registry;
+use lumen_runtime::scheduler::Scheduler;
+
+use crate::print;
+
+/// Where trace events are delivered
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tracer {
+    Log(Level),
+    Process(Pid),
+}
+
+struct Config {
+    tracer: Tracer,
+    modules: HashSet<Atom>,
+    pids: HashSet<Pid>,
+}
+
+lazy_static! {
+    static ref CONFIG: RwLock<Config> = RwLock::new(Config {
+        tracer: Tracer::Log(Level::Trace),
+        modules: HashSet::new(),
+        pids: HashSet::new(),
+    });
+}
+
+/// Whether anything is traced, so that events are skipped without locking when nothing is
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Sets where trace events are delivered, which is `log` at the `trace` level by default
+pub fn set_tracer(tracer: Tracer) {
+    CONFIG.write().unwrap().tracer = tracer;
+}
+
+/// Turns tracing the calls to and returns from the functions of `module` on or off
+pub fn trace_module(module: Atom, on: bool) {
+    update(|config| {
+        if on {
+            config.modules.insert(module);
+        } else {
+            config.modules.remove(&module);
+        }
+    })
+}
+
+/// Turns tracing all the events of the process with `pid` on or off
+pub fn trace_process(pid: Pid, on: bool) {
+    update(|config| {
+        if on {
+            config.pids.insert(pid);
+        } else {
+            config.pids.remove(&pid);
+        }
+    })
+}
+
+/// Turns all tracing off and restores the default tracer
+pub fn clear() {
+    update(|config| {
+        config.tracer = Tracer::Log(Level::Trace);
+        config.modules.clear();
+        config.pids.clear();
+    })
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn update<F: FnOnce(&mut Config)>(f: F) {
+    let mut config = CONFIG.write().unwrap();
+    f(&mut config);
+    ENABLED.store(
+        !config.modules.is_empty() || !config.pids.is_empty(),
+        Ordering::SeqCst,
+    );
+}
+
+pub(crate) fn call(process: &Process, module: Atom, function: Atom, arguments: &[Term]) {
+    with_tracer(process, Some(module), |tracer| match tracer {
+        Tracer::Log(level) => log::log!(
+            level,
+            "{} call {}:{}({})",
+            process.pid(),
+            print::atom(module),
+            print::atom(function),
+            arguments
+                .iter()
+                .map(|argument| print::term(*argument))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        Tracer::Process(pid) => {
+            let words = 10 + words_for(arguments) + 2 * arguments.len();
+            send_to_tracer(pid, words, |heap| {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| argument.clone_to_heap(heap))
+                    .collect::<AllocResult<Vec<_>>>()?;
+                let arguments = match heap.list_from_slice(&arguments)? {
+                    Some(cons) => cons.encode().unwrap(),
+                    None => Term::NIL,
+                };
+                let mfa = heap.tuple_from_slice(&[
+                    module.encode().unwrap(),
+                    function.encode().unwrap(),
+                    arguments,
+                ])?;
+                message(
+                    heap,
+                    &[
+                        atom!("trace"),
+                        process.pid_term(),
+                        atom!("call"),
+                        mfa.encode().unwrap(),
+                    ],
+                )
+            })
+        }
+    })
+}
+
+pub(crate) fn return_from(
+    process: &Process,
+    module: Atom,
+    function: Atom,
+    arity: usize,
+    value: Term,
+) {
+    with_tracer(process, Some(module), |tracer| match tracer {
+        Tracer::Log(level) => log::log!(
+            level,
+            "{} returned from {}:{}/{} -> {}",
+            process.pid(),
+            print::atom(module),
+            print::atom(function),
+            arity,
+            print::term(value)
+        ),
+        Tracer::Process(pid) => {
+            let words = 11 + words_for(&[value]);
+            send_to_tracer(pid, words, |heap| {
+                let arity = heap.integer(arity)?;
+                let mfa = heap.tuple_from_slice(&[
+                    module.encode().unwrap(),
+                    function.encode().unwrap(),
+                    arity,
+                ])?;
+                let value = value.clone_to_heap(heap)?;
+                message(
+                    heap,
+                    &[
+                        atom!("trace"),
+                        process.pid_term(),
+                        atom!("return_from"),
+                        mfa.encode().unwrap(),
+                        value,
+                    ],
+                )
+            })
+        }
+    })
+}
+
+pub(crate) fn send(process: &Process, destination: Term, data: Term) {
+    with_tracer(process, None, |tracer| match tracer {
+        Tracer::Log(level) => log::log!(
+            level,
+            "{} sent {} to {}",
+            process.pid(),
+            print::term(data),
+            print::term(destination)
+        ),
+        Tracer::Process(pid) => {
+            let words = 6 + words_for(&[data, destination]);
+            send_to_tracer(pid, words, |heap| {
+                let data = data.clone_to_heap(heap)?;
+                let destination = destination.clone_to_heap(heap)?;
+                message(
+                    heap,
+                    &[
+                        atom!("trace"),
+                        process.pid_term(),
+                        atom!("send"),
+                        data,
+                        destination,
+                    ],
+                )
+            })
+        }
+    })
+}
+
+pub(crate) fn receive(process: &Process, data: Term) {
+    with_tracer(process, None, |tracer| match tracer {
+        Tracer::Log(level) => {
+            log::log!(level, "{} received {}", process.pid(), print::term(data))
+        }
+        Tracer::Process(pid) => {
+            let words = 5 + words_for(&[data]);
+            send_to_tracer(pid, words, |heap| {
+                let data = data.clone_to_heap(heap)?;
+                message(
+                    heap,
+                    &[atom!("trace"), process.pid_term(), atom!("receive"), data],
+                )
+            })
+        }
+    })
+}
+
+/// Calls `f` with the tracer if the event is traced, which it is if the process or the module
+/// of a call or return is traced and the process is not sensitive
+fn with_tracer<F: FnOnce(Tracer)>(process: &Process, module: Option<Atom>, f: F) {
+    // Sensitive processes are never traced, like in the BEAM
+    if !is_enabled() || process.is_sensitive() {
+        return;
+    }
+
+    let tracer = {
+        let config = CONFIG.read().unwrap();
+        let pid = process.pid();
+        let is_traced = config.pids.contains(&pid)
+            || module.map_or(false, |module| config.modules.contains(&module));
+        // The tracer's own events would trace themselves forever
+        if !is_traced || config.tracer == Tracer::Process(pid) {
+            return;
+        }
+        config.tracer
+    };
+
+    f(tracer)
+}
+
+fn words_for(terms: &[Term]) -> usize {
+    terms.iter().map(|term| term.size_in_words()).sum()
+}
+
+fn message(heap: &mut HeapFragment, elements: &[Term]) -> AllocResult<Term> {
+    Ok(heap.tuple_from_slice(elements)?.encode().unwrap())
+}
+
+/// Builds the message in a heap fragment of `words` and sends it to the tracer, if it is alive
+fn send_to_tracer<F>(tracer: Pid, words: usize, build: F)
+where
+    F: FnOnce(&mut HeapFragment) -> AllocResult<Term>,
+{
+    let arc_tracer = match registry::pid_to_process(&tracer) {
+        Some(arc_tracer) => arc_tracer,
+        None => return,
+    };
+
+    let mut fragment = match HeapFragment::new_from_word_size(words) {
+        Ok(fragment) => fragment,
+        Err(err) => {
+            log::warn!("could not allocate trace message: {:?}", err);
+            return;
+        }
+    };
+    match build(unsafe { fragment.as_mut() }) {
+        Ok(message) => {
+            arc_tracer.send_heap_message(fragment, message);
+            stop_waiting(&arc_tracer);
+        }
+        Err(err) => log::warn!("could not build trace message: {:?}", err),
+    }
+}
+
+fn stop_waiting(arc_process: &Arc<Process>) {
+    let was_waiting = {
+        let mut status = arc_process.status.write();
+        if *status == Status::Waiting {
+            *status = Status::Runnable;
+            true
+        } else {
+            false
+        }
+    };
+
+    if was_waiting {
+        if let Some(scheduler) = arc_process
+            .scheduler_id()
+            .and_then(|id| Scheduler::from_id(&id))
+        {
+            scheduler.stop_waiting(arc_process);
+        }
+    }
+}
@@ -1,17 +1,15 @@
-use std::rc::Rc;
 use std::sync::{Arc, RwLock};
 
 use libeir_ir::FunctionIdent;
 
 use liblumen_alloc::atom;
-use liblumen_alloc::erts::exception::RuntimeException;
-use liblumen_alloc::erts::process::{Process, Status};
+use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::*;
 
-use lumen_runtime::process::spawn::options::Options;
-use lumen_runtime::scheduler::{Scheduler, Spawned};
-use lumen_runtime::system;
+#[cfg(target_arch = "wasm32")]
+use lumen_runtime::scheduler::Scheduler;
 
+use super::call_result::{call_erlang, run_until_exit};
 use super::module::ModuleRegistry;
 
 pub struct VMState {
@@ -24,15 +22,21 @@ impl VMState {
     pub fn new() -> Self {
         lumen_runtime::otp::erlang::apply_3::set_code(crate::code::apply);
 
+        // The BIFs from `lumen_runtime` are already registered, these add to or replace them
         let mut modules = ModuleRegistry::new();
         modules.register_native_module(crate::native::make_erlang());
-        modules.register_native_module(crate::native::make_lists());
-        modules.register_native_module(crate::native::make_maps());
         modules.register_native_module(crate::native::make_logger());
         modules.register_native_module(crate::native::make_lumen_intrinsics());
+        modules.register_native_module(crate::native::make_timer());
 
-        let arc_scheduler = Scheduler::current();
-        let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+        // Processes run on the scheduler threads, which wasm32 doesn't have, so there they run on
+        // the scheduler of the main thread
+        #[cfg(not(target_arch = "wasm32"))]
+        let init_arc_process = lumen_runtime::scheduler::smp::runtime()
+            .spawn_init(0)
+            .unwrap();
+        #[cfg(target_arch = "wasm32")]
+        let init_arc_process = Scheduler::current().spawn_init(0).unwrap();
 
         VMState {
             modules: RwLock::new(modules),
@@ -41,84 +45,57 @@ impl VMState {
         }
     }
 
-    pub fn call(
-        &mut self,
-        fun: &FunctionIdent,
-        args: &[Term],
-    ) -> Result<Rc<Term>, (Rc<Term>, Rc<Term>, Rc<Term>)> {
-        let arc_scheduler = Scheduler::current();
-        let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
-
-        let module = Atom::try_from_str(&fun.module.as_str()).unwrap();
-        let function = Atom::try_from_str(&fun.name.as_str()).unwrap();
-        let arguments = init_arc_process
-            .list_from_slice(args)
-            // if not enough memory here, resize `spawn_init` heap
-            .unwrap();
+    /// Calls `fun` with `args` in a new process and waits until the process exits
+    ///
+    /// `args` must be on the heap of `init`, which is also where the result is kept.
+    pub fn call(&self, fun: &FunctionIdent, args: &[Term]) -> Result<Term, CallError> {
+        if fun.arity != args.len() {
+            return Err(CallError::Exception {
+                class: atom!("error"),
+                reason: atom!("badarity"),
+                stacktrace: Term::NIL,
+            });
+        }
+        let (module, function) = match (
+            Atom::try_from_str(&fun.module.as_str()),
+            Atom::try_from_str(&fun.name.as_str()),
+        ) {
+            (Ok(module), Ok(function)) => (module, function),
+            _ => {
+                return Err(CallError::Exception {
+                    class: atom!("error"),
+                    reason: atom!("badarg"),
+                    stacktrace: Term::NIL,
+                })
+            }
+        };
 
-        let mut options: Options = Default::default();
-        options.min_heap_size = Some(4 + 1000 * 2);
+        let recv = call_erlang(self.init.clone(), module, function, args);
+        let result = run_until_exit(recv).ok_or(CallError::Deadlock)?;
 
-        let Spawned {
-            arc_process: run_arc_process,
-            ..
-        } = Scheduler::spawn_apply_3(
-            &init_arc_process,
-            options,
-            module,
-            function,
-            arguments)
-            // if this fails  a bigger sized heap
-            .unwrap();
+        let mut heap = result.heap;
+        self.init.attach_fragment(unsafe { heap.as_mut() });
 
-        loop {
-            let ran = Scheduler::current().run_through(&run_arc_process);
-
-            match *run_arc_process.status.read() {
-                Status::Exiting(ref exception) => match exception {
-                    RuntimeException::Exit(err) => {
-                        let reason = err.reason();
-                        if reason != atom!("normal") {
-                            panic!("Process exited: {:?}", reason);
-                        } else {
-                            panic!("yay!");
-                        }
-                    }
-                    _ => {
-                        panic!(
-                            "Process exception: {:?}\n{:?}",
-                            exception,
-                            run_arc_process.stacktrace()
-                        );
-                    }
-                },
-                Status::Waiting => {
-                    if ran {
-                        system::io::puts(&format!(
-                            "WAITING Run queues len = {:?}",
-                            Scheduler::current().run_queues_len()
-                        ));
-                    } else {
-                        panic!(
-                            "{:?} did not run.  Deadlock likely in {:#?}",
-                            run_arc_process,
-                            Scheduler::current()
-                        );
-                    }
-                }
-                Status::Runnable => {
-                    system::io::puts(&format!(
-                        "RUNNABLE Run queues len = {:?}",
-                        Scheduler::current().run_queues_len()
-                    ));
-                }
-                Status::Running => {
-                    system::io::puts(&format!(
-                        "RUNNING Run queues len = {:?}",
-                        Scheduler::current().run_queues_len()
-                    ));
-                }
-            }
-        }
+        result
+            .result
+            .map_err(|(class, reason, stacktrace)| CallError::Exception {
+                class,
+                reason,
+                stacktrace,
+            })
     }
 }
+
+/// Why a call made with `VMState::call` did not return a value
+#[derive(Debug)]
+pub enum CallError {
+    /// The function raised an exception, or its process was exited by a linked process
+    Exception {
+        class: Term,
+        reason: Term,
+        stacktrace: Term,
+    },
+    /// The process is waiting for a message, but no other process can run to send one.  This is
+    /// only detected for processes run by the scheduler of the calling thread.
+    Deadlock,
+}
@@ -0,0 +1,373 @@
+//! A port mapper compatible with the Erlang Port Mapper Daemon (EPMD), which maps the names of the
+//! alive nodes on a host to the ports that they listen on, as described in
+//! http://erlang.org/doc/apps/erts/erl_dist_protocol.html#epmd-protocol
+//!
+//! It supports the requests that nodes and `epmd -names` make:
+//!
+//! * `ALIVE2_REQ` registers a node for as long as it keeps the connection open.  Like `epmd`, only
+//!   nodes connecting over loopback can register, so that other hosts can't register names.
+//! * `PORT_PLEASE2_REQ` looks up the port of a registered node.
+//! * `NAMES_REQ` lists the registered nodes.
+//!
+//! Both Lumen and BEAM (`erl -sname`) nodes can register with it, so they can connect to each other
+//! on a host without `epmd` running.
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub const ALIVE2_X_RESP: u8 = 118;
+pub const ALIVE2_REQ: u8 = 120;
+pub const ALIVE2_RESP: u8 = 121;
+pub const NAMES_REQ: u8 = 110;
+pub const PORT2_RESP: u8 = 119;
+pub const PORT_PLEASE2_REQ: u8 = 122;
+
+/// The port that nodes expect the port mapper on, unless `ERL_EPMD_PORT` is set
+pub const DEFAULT_PORT: u16 = 4369;
+
+/// Serves the port mapper protocol on `listener` from a thread of its own
+pub fn start(listener: TcpListener) -> io::Result<()> {
+    thread::Builder::new()
+        .name("epmd".to_string())
+        .spawn(move || serve(listener))?;
+
+    Ok(())
+}
+
+/// Serves the port mapper protocol on `listener` on the calling thread, with a thread for each
+/// connection, as registered nodes keep their connection open.
+pub fn serve(listener: TcpListener) {
+    let port = match listener.local_addr() {
+        Ok(addr) => addr.port(),
+        Err(_) => return,
+    };
+    let arc_registry = Arc::new(Registry {
+        port,
+        registered_by_name: Default::default(),
+        // Creations are never reused, so that the identifiers of a node's earlier incarnations can
+        // be told apart
+        next_creation: AtomicU32::new(1),
+    });
+
+    for stream in listener.incoming().filter_map(Result::ok) {
+        let arc_registry = arc_registry.clone();
+
+        thread::spawn(move || arc_registry.serve(stream));
+    }
+}
+
+// Private
+
+struct Registry {
+    port: u16,
+    registered_by_name: Mutex<HashMap<String, Registered>>,
+    next_creation: AtomicU32,
+}
+
+impl Registry {
+    fn alive2(&self, mut stream: TcpStream, bytes: &[u8]) -> io::Result<()> {
+        // Closed without a response, as `epmd` does for non-local peers
+        if !is_loopback(stream.peer_addr()?.ip()) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "only nodes on the host can register",
+            ));
+        }
+
+        // PortNo (2), NodeType (1), Protocol (1), HighestVersion (2), LowestVersion (2), Nlen (2)
+        if bytes.len() < 10 {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+
+        let port = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let highest_version = u16::from_be_bytes([bytes[4], bytes[5]]);
+        let name_len = u16::from_be_bytes([bytes[8], bytes[9]]) as usize;
+        let name = match bytes.get(10..10 + name_len) {
+            Some(name_bytes) => String::from_utf8_lossy(name_bytes).to_string(),
+            None => return Err(io::ErrorKind::InvalidData.into()),
+        };
+
+        let registered = {
+            let mut registered_by_name = self.registered_by_name.lock().unwrap();
+
+            if registered_by_name.contains_key(&name) {
+                false
+            } else {
+                registered_by_name.insert(
+                    name.clone(),
+                    Registered {
+                        port,
+                        rest: bytes[2..].to_vec(),
+                    },
+                );
+
+                true
+            }
+        };
+
+        // Refused registrations don't use up a creation
+        let (result, creation) = if registered {
+            (0, self.next_creation.fetch_add(1, Ordering::SeqCst))
+        } else {
+            (1, 0)
+        };
+
+        // Nodes that support version 6 of the handshake (OTP 23+) expect the 32-bit creation
+        let response = if 6 <= highest_version {
+            let mut response = vec![ALIVE2_X_RESP, result];
+            response.extend_from_slice(&creation.to_be_bytes());
+
+            response
+        } else {
+            let mut response = vec![ALIVE2_RESP, result];
+            response.extend_from_slice(&(creation as u16).to_be_bytes());
+
+            response
+        };
+
+        stream.write_all(&response)?;
+
+        if registered {
+            // The node stays registered until it closes the connection
+            let mut buffer = [0; 64];
+
+            while let Ok(len) = stream.read(&mut buffer) {
+                if len == 0 {
+                    break;
+                }
+            }
+
+            self.registered_by_name.lock().unwrap().remove(&name);
+        }
+
+        Ok(())
+    }
+
+    /// Lists the registered nodes in the format that `net_adm:names/0` parses
+    fn names(&self, mut stream: TcpStream) -> io::Result<()> {
+        let mut response = (self.port as u32).to_be_bytes().to_vec();
+
+        for (name, Registered { port, .. }) in self.registered_by_name.lock().unwrap().iter() {
+            response.extend_from_slice(format!("name {} at port {}\n", name, port).as_bytes());
+        }
+
+        stream.write_all(&response)
+    }
+
+    fn port_please2(&self, mut stream: TcpStream, name_bytes: &[u8]) -> io::Result<()> {
+        let name = String::from_utf8_lossy(name_bytes);
+
+        let response = match self.registered_by_name.lock().unwrap().get(name.as_ref()) {
+            Some(Registered { port, rest }) => {
+                let mut response = vec![PORT2_RESP, 0];
+                response.extend_from_slice(&port.to_be_bytes());
+                response.extend_from_slice(rest);
+
+                response
+            }
+            None => vec![PORT2_RESP, 1],
+        };
+
+        stream.write_all(&response)
+    }
+
+    fn serve(&self, mut stream: TcpStream) -> io::Result<()> {
+        let request = read_request(&mut stream)?;
+
+        match request.split_first() {
+            Some((&ALIVE2_REQ, bytes)) => self.alive2(stream, bytes),
+            Some((&NAMES_REQ, _)) => self.names(stream),
+            Some((&PORT_PLEASE2_REQ, bytes)) => self.port_please2(stream, bytes),
+            // Unsupported requests are closed without a response, as `epmd` does for unknown ones
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The `PORT2_RESP` fields of a registered node after its port, which are the same as those of
+/// its `ALIVE2_REQ`
+struct Registered {
+    port: u16,
+    rest: Vec<u8>,
+}
+
+/// Whether `ip` is a loopback address, including IPv4 ones mapped to IPv6
+fn is_loopback(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ipv4) => ipv4.is_loopback(),
+        IpAddr::V6(ipv6) => {
+            ipv6.is_loopback() || ipv6.to_ipv4().filter(Ipv4Addr::is_loopback).is_some()
+        }
+    }
+}
+
+/// Requests are prefixed by their length as a 16-bit big endian unsigned integer
+fn read_request(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let mut len_bytes = [0; 2];
+    stream.read_exact(&mut len_bytes)?;
+
+    let mut request = vec![0; u16::from_be_bytes(len_bytes) as usize];
+    stream.read_exact(&mut request)?;
+
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn registered_node_has_port_until_it_disconnects() {
+        let addr = start_on_any_port();
+
+        let (alive_stream, creation) = alive2(addr, "registered", 6, 12345);
+        assert_ne!(creation, 0);
+        assert_eq!(port_please2(addr, "registered"), Some(12345));
+
+        drop(alive_stream);
+
+        assert!(unregisters(addr, "registered"));
+    }
+
+    #[test]
+    fn names_lists_registered_nodes() {
+        let addr = start_on_any_port();
+        let _alive_stream = alive2(addr, "listed", 6, 23456);
+
+        let mut stream = request(addr, &[NAMES_REQ]);
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+
+        assert_eq!(&response[..4], &(addr.port() as u32).to_be_bytes());
+        assert_eq!(&response[4..], b"name listed at port 23456\n");
+    }
+
+    #[test]
+    fn name_can_only_be_registered_once() {
+        let addr = start_on_any_port();
+        let _alive_stream = alive2(addr, "once", 6, 34567);
+
+        let mut stream = request(addr, &alive2_request("once", 6, 45678));
+        let mut response = [0; 2];
+        stream.read_exact(&mut response).unwrap();
+
+        assert_eq!(response, [ALIVE2_X_RESP, 1]);
+    }
+
+    #[test]
+    fn refused_registration_does_not_use_creation() {
+        let addr = start_on_any_port();
+        let (_first_alive_stream, first_creation) = alive2(addr, "first", 6, 34568);
+
+        let mut stream = request(addr, &alive2_request("first", 6, 45679));
+        let mut response = [0; 2];
+        stream.read_exact(&mut response).unwrap();
+        assert_eq!(response, [ALIVE2_X_RESP, 1]);
+
+        let (_second_alive_stream, second_creation) = alive2(addr, "second", 6, 34569);
+
+        assert_eq!(second_creation, first_creation + 1);
+    }
+
+    #[test]
+    fn only_loopback_addresses_can_register() {
+        assert!(is_loopback("127.0.0.1".parse().unwrap()));
+        assert!(is_loopback("::1".parse().unwrap()));
+        assert!(is_loopback("::ffff:127.0.0.1".parse().unwrap()));
+
+        assert!(!is_loopback("192.168.0.1".parse().unwrap()));
+        assert!(!is_loopback("::ffff:192.168.0.1".parse().unwrap()));
+        assert!(!is_loopback("fe80::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn nodes_before_version_6_get_16_bit_creation() {
+        let addr = start_on_any_port();
+
+        let mut stream = request(addr, &alive2_request("old", 5, 56789));
+        let mut response = [0; 4];
+        stream.read_exact(&mut response).unwrap();
+
+        assert_eq!(&response[..2], &[ALIVE2_RESP, 0]);
+    }
+
+    fn alive2(addr: SocketAddr, name: &str, version: u16, port: u16) -> (TcpStream, u32) {
+        let mut stream = request(addr, &alive2_request(name, version, port));
+        let mut response = [0; 6];
+        stream.read_exact(&mut response).unwrap();
+
+        assert_eq!(&response[..2], &[ALIVE2_X_RESP, 0]);
+
+        let creation = u32::from_be_bytes([response[2], response[3], response[4], response[5]]);
+
+        (stream, creation)
+    }
+
+    fn alive2_request(name: &str, version: u16, port: u16) -> Vec<u8> {
+        let mut request = vec![ALIVE2_REQ];
+        request.extend_from_slice(&port.to_be_bytes());
+        // normal node over TCP/IPv4
+        request.extend_from_slice(&[77, 0]);
+        request.extend_from_slice(&version.to_be_bytes());
+        request.extend_from_slice(&version.to_be_bytes());
+        request.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        request.extend_from_slice(name.as_bytes());
+        // no extra
+        request.extend_from_slice(&[0, 0]);
+
+        request
+    }
+
+    /// Whether `name` is unregistered before the deadline, as the registry only removes the name
+    /// once it reads the end of the connection
+    fn unregisters(addr: SocketAddr, name: &str) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+
+        while port_please2(addr, name).is_some() {
+            if deadline <= Instant::now() {
+                return false;
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        true
+    }
+
+    fn port_please2(addr: SocketAddr, name: &str) -> Option<u16> {
+        let mut port_please2_request = vec![PORT_PLEASE2_REQ];
+        port_please2_request.extend_from_slice(name.as_bytes());
+
+        let mut stream = request(addr, &port_please2_request);
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+
+        match response[..2] {
+            [PORT2_RESP, 0] => Some(u16::from_be_bytes([response[2], response[3]])),
+            _ => None,
+        }
+    }
+
+    fn request(addr: SocketAddr, request: &[u8]) -> TcpStream {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let mut byte_vec = (request.len() as u16).to_be_bytes().to_vec();
+        byte_vec.extend_from_slice(request);
+        stream.write_all(&byte_vec).unwrap();
+
+        stream
+    }
+
+    fn start_on_any_port() -> SocketAddr {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        start(listener).unwrap();
+
+        addr
+    }
+}
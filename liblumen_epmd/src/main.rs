@@ -0,0 +1,42 @@
+//! Runs the port mapper in the foreground, like `epmd`, for hosts without Erlang/OTP installed.
+//!
+//! The port is `-port PORT`, then `ERL_EPMD_PORT`, then 4369, as for `epmd`.
+use std::env;
+use std::net::{Ipv4Addr, TcpListener};
+use std::process;
+
+fn main() {
+    let port = match port() {
+        Ok(port) => port,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(1);
+        }
+    };
+
+    match TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)) {
+        Ok(listener) => liblumen_epmd::serve(listener),
+        Err(error) => {
+            eprintln!("can't listen on port {}: {}", port, error);
+            process::exit(1);
+        }
+    }
+}
+
+fn port() -> Result<u16, String> {
+    let mut args = env::args().skip(1);
+
+    match args.next() {
+        Some(ref flag) if flag == "-port" => match args.next() {
+            Some(port_string) => port_string
+                .parse()
+                .map_err(|_| format!("port ({}) is not a port number", port_string)),
+            None => Err("-port needs a port number".to_string()),
+        },
+        Some(arg) => Err(format!("unsupported argument ({})", arg)),
+        None => Ok(env::var("ERL_EPMD_PORT")
+            .ok()
+            .and_then(|port_string| port_string.parse().ok())
+            .unwrap_or(liblumen_epmd::DEFAULT_PORT)),
+    }
+}
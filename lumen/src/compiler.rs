@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::convert::From;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
@@ -20,15 +20,42 @@ pub fn dispatch<'a>(args: &'a ArgMatches) -> Result<()> {
 }
 
 /// Create a CompilerSettings struct from ArgMatches produced by clap
-fn configure<'a>(args: &'a ArgMatches) -> Result<CompilerSettings> {
+pub fn configure<'a>(args: &'a ArgMatches) -> Result<CompilerSettings> {
     let codemap = Arc::new(Mutex::new(CodeMap::new()));
-    let file_type = value_t!(args, "compiler", FileType).unwrap_or_else(|e| e.exit());
-    let source_dir = args.value_of_os("path").map(PathBuf::from).unwrap();
-    let output_dir = args.value_of_os("output").map(PathBuf::from).unwrap();
+    // The shell only takes the arguments which affect parsing, so it has no sources to load
+    let file_type = if args.is_present("compiler") {
+        value_t!(args, "compiler", FileType).unwrap_or_else(|e| e.exit())
+    } else {
+        FileType::Erlang
+    };
+    let source_dir = match args.value_of_os("path") {
+        Some(path) => PathBuf::from(path),
+        None => std::env::current_dir()?,
+    };
+    // Only `compile` accepts an output directory, other subcommands use the default
+    let output_dir = match args.value_of_os("output") {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::current_dir()?.join("_build/target"),
+    };
     let warnings_as_errors = args.is_present("warnings-as-errors");
-    let no_warn = args.is_present("no_warn");
+    let no_warn = args.is_present("no-warnings");
     let verbosity = Verbosity::from_level(args.occurrences_of("verbose") as isize);
-    let include_path = VecDeque::new();
+    let defines = match args.values_of("define") {
+        None => HashMap::new(),
+        Some(values) => values
+            .map(|define| parse_define(&codemap, define))
+            .collect::<Result<_, _>>()?,
+    };
+    let mut include_path = match args.values_of_os("include") {
+        None => VecDeque::new(),
+        Some(values) => values.map(PathBuf::from).collect(),
+    };
+    // Like erlc, the directory containing the sources is always searched for includes
+    if source_dir.is_dir() {
+        include_path.push_back(source_dir.clone());
+    } else if let Some(parent) = source_dir.parent() {
+        include_path.push_back(parent.to_path_buf());
+    }
     let mut code_path = match args.values_of_os("prepend-path") {
         None => Vec::new(),
         Some(values) => values.map(PathBuf::from).collect(),
@@ -38,16 +65,34 @@ fn configure<'a>(args: &'a ArgMatches) -> Result<CompilerSettings> {
         Some(values) => values.map(PathBuf::from).collect(),
     };
     code_path.append(&mut append_dirs);
+    let jobs = if args.is_present("jobs") {
+        value_t!(args, "jobs", usize).unwrap_or_else(|e| e.exit())
+    } else {
+        num_cpus::get()
+    };
+    // The cache belongs to the output it describes, so it lives in the output directory unless
+    // placed elsewhere explicitly
+    let cache_dir = if args.is_present("no-cache") {
+        None
+    } else {
+        match args.value_of_os("cache-dir") {
+            Some(dir) => Some(PathBuf::from(dir)),
+            None => Some(output_dir.join(".cache")),
+        }
+    };
     Ok(CompilerSettings {
         file_type,
         color: ColorChoice::Auto,
         source_dir,
         output_dir,
+        defines,
         warnings_as_errors,
         no_warn,
         verbosity,
         code_path,
         include_path,
+        jobs,
+        cache_dir,
         codemap,
     })
 }
@@ -1,5 +1,8 @@
 mod compiler;
+mod runner;
 
+use std::ffi::OsString;
+use std::path::Path;
 use std::process;
 
 use clap::{crate_description, crate_name, crate_version};
@@ -7,6 +10,15 @@ use clap::{App, Arg, ArgMatches, SubCommand};
 
 use libeir_diagnostics::{ColorChoice, Emitter, StandardStreamEmitter};
 use liblumen_compiler::CompilerError;
+use liblumen_eir_interpreter::shell;
+use lumen_runtime::config::Command;
+
+#[cfg(feature = "codegen")]
+const COMPILE_ABOUT: &str = "Compiles Erlang to an executable or shared library";
+/// Without code generation `lumen compile` can only report that it is unsupported
+#[cfg(not(feature = "codegen"))]
+const COMPILE_ABOUT: &str = "Compiles Erlang to an executable or shared library \
+                             (unavailable, as lumen was built without the `codegen` feature)";
 
 fn main() -> anyhow::Result<()> {
     human_panic::setup_panic!();
@@ -23,25 +35,8 @@ fn main() -> anyhow::Result<()> {
         .about(crate_description!())
         .subcommand(
             SubCommand::with_name("compile")
-                .about("Compiles Erlang to an executable or shared library")
-                .arg(
-                    Arg::with_name("path")
-                        .help("The path to the file or directory of files you wish to compile")
-                        .index(1)
-                        .takes_value(true)
-                        .value_name("FILE_OR_DIR")
-                        .default_value_os(cwd.as_os_str())
-                        .required(true),
-                )
-                .arg(
-                    Arg::with_name("compiler")
-                        .help("The type of compiler to use")
-                        .takes_value(true)
-                        .value_name("TYPE")
-                        .possible_values(&["beam", "erl"])
-                        .default_value("erl")
-                        .required(true),
-                )
+                .about(COMPILE_ABOUT)
+                .args(&source_args(&cwd))
                 .arg(
                     Arg::with_name("output")
                         .help("The directory to place compiler output")
@@ -51,51 +46,60 @@ fn main() -> anyhow::Result<()> {
                         .default_value_os(output_dir.as_os_str()),
                 )
                 .arg(
-                    Arg::with_name("define")
-                        .help("Define a macro, e.g. -DTEST")
-                        .short("D")
-                        .long("define")
-                        .value_name("NAME")
-                        .takes_value(true)
-                        .multiple(true),
-                )
-                .arg(
-                    Arg::with_name("warnings-as-errors")
-                        .help("Causes the compiler to treat all warnings as errors")
-                        .long("warnings-as-errors"),
+                    Arg::with_name("jobs")
+                        .help("The number of modules to compile in parallel, defaults to the number of CPUs")
+                        .short("j")
+                        .long("jobs")
+                        .value_name("N")
+                        .takes_value(true),
                 )
                 .arg(
-                    Arg::with_name("no-warnings")
-                        .help("Disable warnings")
-                        .long("no-warnings")
-                        .conflicts_with("warnings-as-errors"),
+                    Arg::with_name("cache-dir")
+                        .help("The directory to keep the build cache in, defaults to .cache in the output directory")
+                        .long("cache-dir")
+                        .value_name("DIR")
+                        .takes_value(true),
                 )
                 .arg(
-                    Arg::with_name("verbose")
-                        .help("Set verbosity level")
-                        .short("v")
-                        .multiple(true),
-                )
+                    Arg::with_name("no-cache")
+                        .help("Recompile all modules, even if they are unchanged since the last build")
+                        .long("no-cache"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("Runs Erlang in the interpreter, without compiling to native code")
+                .args(&source_args(&cwd))
                 .arg(
-                    Arg::with_name("append-path")
-                        .help("Appends a path to the code path")
-                        .short("pz")
-                        .long("append-path")
-                        .value_name("PATH")
+                    Arg::with_name("start")
+                        .help("The function to call, with the arguments as a list of strings if any are given")
+                        .short("s")
+                        .long("start")
+                        .value_name("MODULE:FUNCTION")
                         .takes_value(true)
-                        .multiple(true),
+                        .required(true),
                 )
                 .arg(
-                    Arg::with_name("prepend-path")
-                        .help("Prepends a path to the code path")
-                        .short("pa")
-                        .long("prepend-path")
-                        .value_name("PATH")
-                        .takes_value(true)
+                    Arg::with_name("args")
+                        .help("Arguments passed to the start function")
+                        .index(2)
+                        .value_name("ARGS")
                         .multiple(true),
                 ),
         )
-        .get_matches();
+        .subcommand(
+            SubCommand::with_name("shell")
+                .about("Starts an interactive shell backed by the interpreter")
+                .args(&parse_args())
+                .arg(
+                    Arg::with_name("remote")
+                        .help("Connects to the shell of a remote node instead")
+                        .long("remote")
+                        .value_name("NODE")
+                        .takes_value(true),
+                ),
+        )
+        .get_matches_from(args());
 
     // Handle success/failure
     if let Err(err) = self::dispatch(matches) {
@@ -114,10 +118,105 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Returns the arguments shared by the subcommands which load Erlang sources
+fn source_args<'a, 'b>(cwd: &'a Path) -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("path")
+            .help("The path to the file or directory of files you wish to load")
+            .index(1)
+            .takes_value(true)
+            .value_name("FILE_OR_DIR")
+            .default_value_os(cwd.as_os_str())
+            .required(true),
+        Arg::with_name("compiler")
+            .help("The type of compiler to use, `erl` also compiles any .beam files")
+            .takes_value(true)
+            .value_name("TYPE")
+            .possible_values(&["beam", "erl"])
+            .default_value("erl")
+            .required(true),
+    ]
+    .into_iter()
+    .chain(parse_args())
+    .collect()
+}
+
+/// Returns the arguments which configure how Erlang sources are parsed, which the shell also
+/// takes for compiling with `c/1`
+fn parse_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("define")
+            .help("Define a macro, e.g. -DTEST or -DVSN=1")
+            .short("D")
+            .long("define")
+            .value_name("NAME[=VALUE]")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+        Arg::with_name("include")
+            .help("Adds a directory to search for included files")
+            .short("I")
+            .long("include")
+            .value_name("DIR")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+        Arg::with_name("warnings-as-errors")
+            .help("Causes the compiler to treat all warnings as errors")
+            .long("warnings-as-errors"),
+        Arg::with_name("no-warnings")
+            .help("Disable warnings")
+            .long("no-warnings")
+            .conflicts_with("warnings-as-errors"),
+        Arg::with_name("verbose")
+            .help("Set verbosity level")
+            .short("v")
+            .multiple(true),
+        Arg::with_name("append-path")
+            .help("Appends a path to the code path (-pz)")
+            .long("append-path")
+            .value_name("PATH")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+        Arg::with_name("prepend-path")
+            .help("Prepends a path to the code path (-pa)")
+            .long("prepend-path")
+            .value_name("PATH")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+    ]
+}
+
+/// Returns the command-line arguments with the erlc-style `-pa`/`-pz` flags,
+/// which clap cannot express as short flags, rewritten to their long forms
+fn args() -> Vec<OsString> {
+    std::env::args_os()
+        .map(|arg| match arg.to_str() {
+            Some("-pa") => OsString::from("--prepend-path"),
+            Some("-pz") => OsString::from("--append-path"),
+            _ => arg,
+        })
+        .collect()
+}
+
 #[inline]
 fn dispatch(matches: ArgMatches) -> anyhow::Result<()> {
     match matches.subcommand() {
         ("compile", Some(args)) => compiler::dispatch(&args),
+        ("run", Some(args)) => {
+            let status = runner::dispatch(&args)?;
+            process::exit(status)
+        }
+        ("shell", Some(args)) => {
+            let command = match args.value_of("remote") {
+                Some(node) => Command::RemoteShell(node.to_string()),
+                None => Command::Shell,
+            };
+            let config = compiler::configure(&args)?;
+            shell::start(&command, config.into())
+        }
         _ => Ok(()),
     }
 }
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use clap::ArgMatches;
+
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_compiler::Compiler;
+use liblumen_eir_interpreter::call_result::call_run_erlang;
+use liblumen_eir_interpreter::print;
+use liblumen_eir_interpreter::VM;
+
+use super::compiler::configure;
+
+/// Loads the sources into the interpreter and calls the start function,
+/// returning the exit status derived from how it returned
+pub fn dispatch<'a>(args: &'a ArgMatches) -> Result<i32> {
+    let (module, function) = parse_start(args.value_of("start").unwrap())?;
+
+    let config = configure(args)?;
+    let mut compiler = Compiler::new(config);
+    let modules = compiler.lower_modules()?;
+
+    // Starts the VM and the scheduler threads, the native modules are registered at this point
+    let init_arc_process = Arc::clone(&VM.init);
+    {
+        let mut registry = VM.modules.write().unwrap();
+        for module in modules {
+            registry.register_erlang_module(module);
+        }
+    }
+
+    // Like `erl -run`, arguments are passed as a single list of strings
+    let arguments = match args.values_of("args") {
+        None => Vec::new(),
+        Some(values) => {
+            let strings = values
+                .map(|arg| init_arc_process.charlist_from_str(arg))
+                .collect::<Result<Vec<_>, _>>()?;
+            vec![init_arc_process.list_from_slice(&strings)?]
+        }
+    };
+
+    let result = call_run_erlang(init_arc_process, module, function, &arguments);
+
+    Ok(exit_status(&result.result))
+}
+
+/// Parses `module:function`
+fn parse_start(start: &str) -> Result<(Atom, Atom)> {
+    let mut parts = start.splitn(2, ':');
+    match (parts.next(), parts.next()) {
+        (Some(module), Some(function)) if !module.is_empty() && !function.is_empty() => {
+            let module = Atom::try_from_str(module)?;
+            let function = Atom::try_from_str(function)?;
+            Ok((module, function))
+        }
+        _ => Err(anyhow!(
+            "invalid start function '{}', expected MODULE:FUNCTION",
+            start
+        )),
+    }
+}
+
+/// Like `erl`, returning normally or exiting with `normal` or `shutdown` is a success,
+/// any other exception is reported and results in a non-zero exit status
+fn exit_status(result: &Result<Term, (Term, Term, Term)>) -> i32 {
+    match result {
+        Ok(_) => 0,
+        Err((class, reason, _)) if *class == atom!("exit") && is_clean_exit(*reason) => 0,
+        Err((class, reason, stacktrace)) => {
+            eprintln!("{}", print::exception(*class, *reason, *stacktrace));
+            1
+        }
+    }
+}
+
+fn is_clean_exit(reason: Term) -> bool {
+    if reason == atom!("normal") || reason == atom!("shutdown") {
+        return true;
+    }
+    match reason.decode() {
+        Ok(TypedTerm::Tuple(tuple)) => {
+            tuple.len() == 2 && tuple.get_element(0usize).ok() == Some(atom!("shutdown"))
+        }
+        _ => false,
+    }
+}
@@ -1,5 +1,6 @@
 pub mod anonymous;
 pub mod export;
+pub mod native;
 
 use alloc::sync::Arc;
 
@@ -0,0 +1,59 @@
+//! Every `#[native_implemented_function]` declared with `native_functions!` in `crate::otp` is
+//! listed here, so that other runtimes, such as the interpreter, can call the BIFs directly with
+//! their arguments instead of through the process stack like `code`.
+//!
+//! BIFs which call other functions, such as `timer:tc/3`, only have `code`, so runtimes have to
+//! bridge those themselves.
+use alloc::sync::Arc;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::Arity;
+
+use crate::otp;
+
+pub type Apply = fn(&Arc<Process>, &[Term]) -> exception::Result<Term>;
+
+pub struct NativeFunction {
+    // Atoms can't be created in a `const` context, so the names are only looked up when needed
+    module: fn() -> Atom,
+    function: fn() -> Atom,
+    pub arity: Arity,
+    /// Calls the native function with its arguments, which must number `arity`
+    pub apply: Apply,
+}
+
+impl NativeFunction {
+    pub fn new(module: fn() -> Atom, function: fn() -> Atom, arity: Arity, apply: Apply) -> Self {
+        Self {
+            module,
+            function,
+            arity,
+            apply,
+        }
+    }
+
+    pub fn module(&self) -> Atom {
+        (self.module)()
+    }
+
+    pub fn function(&self) -> Atom {
+        (self.function)()
+    }
+}
+
+lazy_static! {
+    static ref NATIVE_FUNCTIONS: Vec<NativeFunction> = {
+        let mut native_functions = otp::erlang::native_functions();
+        native_functions.extend(otp::lists::native_functions());
+        native_functions.extend(otp::maps::native_functions());
+
+        native_functions
+    };
+}
+
+/// Iterates over every native function, in no particular order
+pub fn iter() -> impl Iterator<Item = &'static NativeFunction> {
+    NATIVE_FUNCTIONS.iter()
+}
@@ -88,14 +88,14 @@ impl Config {
             .arg(Arg::with_name("name")
                      .long("name")
                      .global(true)
-                     .help("The secret cookie to use in distributed mode\n\
-                            If one is not provided, one will be generated for you in ~/.erlang.cookie")
+                     .help("The name of the node in distributed mode")
                      .takes_value(true)
                      .validator(is_valid_node_name))
             .arg(Arg::with_name("cookie")
                      .long("cookie")
                      .global(true)
-                     .help("The secret cookie to use in distributed mode")
+                     .help("The secret cookie to use in distributed mode\n\
+                            If one is not provided, the one in ~/.erlang.cookie is used, which is generated if needed")
                      .takes_value(true)
                      .env("COOKIE"))
             .arg(Arg::with_name("extra")
@@ -1,2 +1,19 @@
+//! The distribution connects the running node to other nodes, so that their processes can send
+//! each other messages and link, monitor and exit signals.
+//!
+//! It is started by making the running node alive with a name, which registers it with the port
+//! mapper (EPMD) of its host.  If no port mapper is running, `liblumen_epmd` is started in the
+//! node.
+mod connection;
+mod control;
+pub mod cookie;
+mod epmd;
 pub mod external_term_format;
+mod handshake;
 pub mod nodes;
+
+pub use connection::{
+    connected_nodes, demonitor, exit, is_connected, link, monitor, propagate_exit, send,
+    send_to_name, start, unlink,
+};
+pub use control::Identifier;
@@ -0,0 +1,1358 @@
+//! The connections of the running node to other nodes, over which local processes and processes on
+//! the other nodes send each other messages and link, monitor and exit signals.
+//!
+//! After the handshake, nodes send each other packets prefixed by their length as a 32-bit big
+//! endian unsigned integer.  Empty packets are ticks that keep idle connections alive, while the
+//! others are a control message followed by the message of sends, each with its own version.
+//!
+//! Connections are made in the background, so that processes don't wait for the handshake.  Until
+//! a connection is established, the packets sent over it are queued, and if it can't be
+//! established, its links and monitors get `noconnection`.
+//!
+//! Each connection has a thread reading its packets.  Their terms are decoded into a process of
+//! the connection, which is never scheduled, and are copied to the local processes they are
+//! delivered to.  Each connection also has a thread writing the packets sent over it, so that the
+//! processes sending them never wait for the other node.
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::net::{Ipv4Addr, Shutdown, TcpListener, TcpStream};
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::*;
+use hashbrown::{HashMap, HashSet};
+
+use liblumen_core::locks::{Condvar, Mutex, RwLock};
+
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::exception::{
+    AllocResult, ArcError, InternalException, InternalResult, RuntimeException, SystemException,
+};
+use liblumen_alloc::erts::process::alloc::Heap;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::{Pid as LocalPid, *};
+use liblumen_alloc::erts::Node;
+use liblumen_alloc::CloneToProcess;
+
+use crate::distribution::control::{Control, Identifier, MonitorReference};
+use crate::distribution::epmd::{self, Registration};
+use crate::distribution::external_term_format::{term, version, Pid};
+use crate::distribution::handshake::{self, Peer, Status};
+use crate::distribution::nodes::{self, node};
+use crate::otp::erlang::term_to_binary;
+use crate::process;
+use crate::registry;
+use crate::scheduler::Scheduled;
+use crate::system::host;
+
+/// Makes the running node alive as `name`, so that nodes sharing `cookie` can connect to it.
+///
+/// Without a host, `name` is on the short name of the host, as with `erl -sname`.
+pub fn start(name: &str, cookie: String) -> io::Result<()> {
+    let (alive_name, host) = match name.find('@') {
+        Some(index) => (&name[..index], name[index + 1..].to_string()),
+        None => (name, host::name::short()),
+    };
+    let node_name = Atom::try_from_str(format!("{}@{}", alive_name, host))
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "node name is too long"))?;
+
+    let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    let registration = epmd::register(alive_name, listener.local_addr()?.port())?;
+
+    *RW_LOCK_OPTION_COOKIE.write() = Some(cookie);
+    node::set_alive(node_name, registration.creation);
+
+    thread::Builder::new()
+        .name("distribution".to_string())
+        .spawn(move || accept(listener, registration))?;
+
+    Ok(())
+}
+
+/// The names of the nodes with established connections, in no particular order
+pub fn connected_nodes() -> Vec<Atom> {
+    RW_LOCK_ARC_CONNECTION_BY_NAME
+        .read()
+        .iter()
+        .filter(|(_, arc_connection)| match *arc_connection.stream.lock() {
+            Stream::Connected(_) => true,
+            _ => false,
+        })
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+pub fn demonitor(process: &Process, reference: &Reference) -> bool {
+    let arc_connection_vec = arc_connection_vec();
+
+    for arc_connection in arc_connection_vec {
+        // separate from `if let` below so that the lock is not held while sending
+        let option_monitor = arc_connection.monitor_by_reference.lock().remove(reference);
+
+        if let Some(Monitor { identifier, .. }) = option_monitor {
+            arc_connection.send_control(
+                process,
+                Control::DemonitorP {
+                    from: Pid::Local(process.pid()),
+                    to: identifier,
+                    reference: MonitorReference::Local(*reference),
+                },
+                None,
+            );
+
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Sends an exit signal with `reason` from `process` to `to`, like `exit(To, Reason)`
+pub fn exit(process: &Process, to: &ExternalPid, reason: Term) {
+    if let Ok(arc_connection) = connect(to.arc_node().name()) {
+        arc_connection.send_control(
+            process,
+            Control::Exit2 {
+                from: Pid::Local(process.pid()),
+                to: Pid::External(to.clone()),
+                reason,
+            },
+            None,
+        );
+    }
+}
+
+pub fn is_connected(node: &Atom) -> bool {
+    RW_LOCK_ARC_CONNECTION_BY_NAME.read().contains_key(node)
+}
+
+/// Links `process` to `to`.
+///
+/// Returns `false` if the node of `to` can't be connected to, because the running node is not
+/// alive or the name of the node has no host.  If the connection can't be established,
+/// `process` gets an exit signal with `noconnection` instead.
+pub fn link(process: &Process, to: &ExternalPid) -> bool {
+    match connect(to.arc_node().name()) {
+        Ok(arc_connection) => {
+            arc_connection
+                .link_set
+                .lock()
+                .insert((process.pid(), to.clone()));
+            arc_connection.send_control(
+                process,
+                Control::Link {
+                    from: Pid::Local(process.pid()),
+                    to: Pid::External(to.clone()),
+                },
+                None,
+            );
+
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Monitors `identifier` on `node` for `process` with `reference`.
+///
+/// Returns `false` if `node` can't be connected to, because the running node is not alive or the
+/// name of `node` has no host.  If the connection can't be established, `process` gets
+/// `{'DOWN', Reference, process, Identifier, noconnection}` instead.
+pub fn monitor(
+    process: &Process,
+    reference: Reference,
+    identifier: Identifier,
+    node: Atom,
+) -> bool {
+    match connect(node) {
+        Ok(arc_connection) => {
+            arc_connection.monitor_by_reference.lock().insert(
+                reference,
+                Monitor {
+                    monitoring_pid: process.pid(),
+                    identifier: identifier.clone(),
+                },
+            );
+            arc_connection.send_control(
+                process,
+                Control::MonitorP {
+                    from: Pid::Local(process.pid()),
+                    to: identifier,
+                    reference: MonitorReference::Local(reference),
+                },
+                None,
+            );
+
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Sends the exit of `process` to the processes on other nodes that are linked to or monitor it,
+/// and demonitors the processes on other nodes that it monitors.
+pub fn propagate_exit(process: &Process, exception: &RuntimeException) {
+    let pid = process.pid();
+    let reason = exception.reason().unwrap_or_else(|| atom!("system_error"));
+
+    for arc_connection in arc_connection_vec() {
+        let linked_vec: Vec<ExternalPid> = {
+            let mut link_set = arc_connection.link_set.lock();
+            let linked_vec: Vec<ExternalPid> = link_set
+                .iter()
+                .filter(|(local_pid, _)| *local_pid == pid)
+                .map(|(_, external_pid)| external_pid.clone())
+                .collect();
+
+            for external_pid in &linked_vec {
+                link_set.remove(&(pid, external_pid.clone()));
+            }
+
+            linked_vec
+        };
+
+        for external_pid in linked_vec {
+            arc_connection.send_control(
+                process,
+                Control::Exit {
+                    from: Pid::Local(pid),
+                    to: Pid::External(external_pid),
+                    reason,
+                },
+                None,
+            );
+        }
+
+        let monitored_vec: Vec<Monitored> = {
+            let mut monitored_vec = arc_connection.monitored_vec.lock();
+            let (exited_monitored_vec, other_monitored_vec) = monitored_vec
+                .drain(..)
+                .partition(|monitored| monitored.monitored_pid == pid);
+            *monitored_vec = other_monitored_vec;
+
+            exited_monitored_vec
+        };
+
+        for Monitored {
+            identifier,
+            monitoring_pid,
+            reference,
+            ..
+        } in monitored_vec
+        {
+            arc_connection.send_control(
+                process,
+                Control::MonitorPExit {
+                    from: identifier,
+                    to: Pid::External(monitoring_pid),
+                    reference,
+                    reason,
+                },
+                None,
+            );
+        }
+
+        let monitor_vec: Vec<(Reference, Monitor)> = {
+            let mut monitor_by_reference = arc_connection.monitor_by_reference.lock();
+            let reference_vec: Vec<Reference> = monitor_by_reference
+                .iter()
+                .filter(|(_, monitor)| monitor.monitoring_pid == pid)
+                .map(|(reference, _)| *reference)
+                .collect();
+
+            reference_vec
+                .into_iter()
+                .filter_map(|reference| {
+                    monitor_by_reference
+                        .remove(&reference)
+                        .map(|monitor| (reference, monitor))
+                })
+                .collect()
+        };
+
+        for (reference, Monitor { identifier, .. }) in monitor_vec {
+            arc_connection.send_control(
+                process,
+                Control::DemonitorP {
+                    from: Pid::Local(pid),
+                    to: identifier,
+                    reference: MonitorReference::Local(reference),
+                },
+                None,
+            );
+        }
+    }
+}
+
+/// Sends `message` from `process` to `to`, connecting to its node if needed.
+///
+/// Like sending to a local process that is not alive, `message` is dropped if the node can't be
+/// connected to.
+pub fn send(process: &Process, to: &ExternalPid, message: Term) {
+    if let Ok(arc_connection) = connect(to.arc_node().name()) {
+        arc_connection.send_control(
+            process,
+            Control::Send {
+                to: Pid::External(to.clone()),
+            },
+            Some(message),
+        );
+    }
+}
+
+/// Sends `message` from `process` to the process registered as `name` on `node`, connecting to
+/// `node` if needed.
+///
+/// `message` is dropped if `node` can't be connected to.
+pub fn send_to_name(process: &Process, name: Atom, node: Atom, message: Term) {
+    if let Ok(arc_connection) = connect(node) {
+        arc_connection.send_control(
+            process,
+            Control::RegSend {
+                from: Pid::Local(process.pid()),
+                to_name: name,
+            },
+            Some(message),
+        );
+    }
+}
+
+/// Unlinks `process` from `to`, if they are linked
+pub fn unlink(process: &Process, to: &ExternalPid) {
+    let option_arc_connection = RW_LOCK_ARC_CONNECTION_BY_NAME
+        .read()
+        .get(&to.arc_node().name())
+        .cloned();
+
+    if let Some(arc_connection) = option_arc_connection {
+        let linked = arc_connection
+            .link_set
+            .lock()
+            .remove(&(process.pid(), to.clone()));
+
+        if linked {
+            let id = arc_connection.next_unlink_id.fetch_add(1, Ordering::SeqCst);
+
+            arc_connection.send_control(
+                process,
+                Control::UnlinkId {
+                    id,
+                    from: Pid::Local(process.pid()),
+                    to: Pid::External(to.clone()),
+                },
+                None,
+            );
+        }
+    }
+}
+
+// Private
+
+/// Connecting, which includes asking the port mapper for the port of the node and the handshake,
+/// gives up after this long, like `net_setuptime` of `erl`
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(7);
+
+/// Nodes tick when they haven't sent anything for a quarter of the net tick time, so connections
+/// silent for longer are dead
+const NET_TICK_TIME: Duration = Duration::from_secs(60);
+const TICK_INTERVAL: Duration = Duration::from_secs(15);
+
+// Tag of packets without an atom cache, which is only used with `DFLAG_DIST_HDR_ATOM_CACHE`
+const PASS_THROUGH: u8 = 112;
+
+// Words for the terms of a received packet, beyond those of the decoded terms, such as for
+// building `{'EXIT', From, Reason}` and `{'DOWN', Reference, process, Identifier, Info}`
+const PROCESS_EXTRA_WORDS: usize = 64;
+
+struct Connection {
+    arc_node: Arc<Node>,
+    stream: Mutex<Stream>,
+    /// Packets waiting for the connection's writer thread, which starts once the connection is
+    /// established
+    packet_queue: Mutex<VecDeque<Vec<u8>>>,
+    packet_queue_condvar: Condvar,
+    /// Links between local processes and processes on the other node
+    link_set: Mutex<HashSet<(LocalPid, ExternalPid)>>,
+    /// Monitors by local processes of processes on the other node
+    monitor_by_reference: Mutex<HashMap<Reference, Monitor>>,
+    /// Monitors by processes on the other node of local processes
+    monitored_vec: Mutex<Vec<Monitored>>,
+    next_unlink_id: AtomicU64,
+}
+
+impl Connection {
+    fn new(arc_node: Arc<Node>, stream: Stream) -> Self {
+        Self {
+            arc_node,
+            stream: Mutex::new(stream),
+            packet_queue: Default::default(),
+            packet_queue_condvar: Condvar::new(),
+            link_set: Default::default(),
+            monitor_by_reference: Default::default(),
+            monitored_vec: Default::default(),
+            // 0 is reserved for the old `UNLINK`
+            next_unlink_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Establishes the pending connection with `stream`.  The packets sent while it was pending
+    /// stay queued for the writer thread.
+    ///
+    /// Returns `stream` back if the connection is not pending.
+    fn adopt(&self, stream: TcpStream) -> Result<(), TcpStream> {
+        let mut locked_stream = self.stream.lock();
+
+        match *locked_stream {
+            Stream::Pending => {
+                *locked_stream = Stream::Connected(stream);
+
+                Ok(())
+            }
+            _ => Err(stream),
+        }
+    }
+
+    /// Closes the connection if it is still pending, so that it isn't established in the
+    /// meantime.
+    ///
+    /// Returns whether the connection was pending, in which case it needs to be disconnected.
+    fn close_pending(&self) -> bool {
+        let mut stream = self.stream.lock();
+
+        match *stream {
+            Stream::Pending => {
+                *stream = Stream::Closed;
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn disconnect(&self) {
+        let name = self.arc_node.name();
+
+        // write scope
+        {
+            let mut arc_connection_by_name = RW_LOCK_ARC_CONNECTION_BY_NAME.write();
+
+            // a connection that lost the race to connect is not registered
+            if let Some(arc_connection) = arc_connection_by_name.get(&name) {
+                if ptr::eq(arc_connection.as_ref(), self) {
+                    arc_connection_by_name.remove(&name);
+                }
+            }
+        }
+
+        if let Stream::Connected(stream) = mem::replace(&mut *self.stream.lock(), Stream::Closed) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+
+        // Wakes the writer thread, so that it sees the connection is closed
+        self.packet_queue.lock().clear();
+        self.packet_queue_condvar.notify_all();
+
+        let link_vec: Vec<(LocalPid, ExternalPid)> = self.link_set.lock().drain().collect();
+        let monitor_vec: Vec<(Reference, Monitor)> =
+            self.monitor_by_reference.lock().drain().collect();
+        self.monitored_vec.lock().clear();
+
+        let noconnection = atom!("noconnection");
+
+        // Sized so that the terms of all the signals fit
+        if let Ok(process) =
+            process::init(PROCESS_EXTRA_WORDS * (link_vec.len() + monitor_vec.len()))
+        {
+            for (local_pid, external_pid) in link_vec {
+                if let Some(arc_process) = registry::pid_to_process(&local_pid) {
+                    let _ = exit_signal(&process, &external_pid, &arc_process, noconnection, false);
+                }
+            }
+
+            for (reference, monitor) in monitor_vec {
+                let _ = self.down(&process, &reference, &monitor, noconnection);
+            }
+        }
+    }
+
+    /// Sends `{'DOWN', Reference, process, Identifier, Info}` for `monitor`
+    fn down(
+        &self,
+        process: &Process,
+        reference: &Reference,
+        monitor: &Monitor,
+        info: Term,
+    ) -> AllocResult<()> {
+        if let Some(monitoring_arc_process) = registry::pid_to_process(&monitor.monitoring_pid) {
+            let reference_term = reference.clone_to_process(process);
+            let identifier_term = match &monitor.identifier {
+                Identifier::Pid(pid) => pid.clone_to_process(process),
+                Identifier::Name(name) => process.tuple_from_slice(&[
+                    name.encode().unwrap(),
+                    self.arc_node.name().encode().unwrap(),
+                ])?,
+            };
+            let message = process.tuple_from_slice(&[
+                atom!("DOWN"),
+                reference_term,
+                atom!("process"),
+                identifier_term,
+                info,
+            ])?;
+
+            deliver(&monitoring_arc_process, message)?;
+        }
+
+        Ok(())
+    }
+
+    /// Receives a packet that is not a tick, decoding its terms into `process`, which is
+    /// collected first if its heap doesn't have room for them
+    fn receive(&self, process: &Process, packet: &[u8]) -> InternalResult<()> {
+        let bytes = match packet.split_first() {
+            Some((&PASS_THROUGH, bytes)) => bytes,
+            _ => return Err(anyhow!("packet does not start with pass through").into()),
+        };
+
+        // Terms are never bigger than 2 words for each of their bytes, such as for the cons cells
+        // of `STRING_EXT`, so more words are needed only for the terms built while receiving.
+        let mut words = 2 * bytes.len() + PROCESS_EXTRA_WORDS;
+
+        loop {
+            // The terms of earlier packets were copied to the processes they were delivered to,
+            // so nothing on the heap is live
+            let heap_available = process.acquire_heap().heap_available();
+
+            if heap_available < words {
+                process.garbage_collect(words, &mut []).map_err(|gc_err| {
+                    anyhow!("could not collect decoding process: {:?}", gc_err)
+                })?;
+            }
+
+            match self.receive_in(process, bytes) {
+                Err(InternalException::System(SystemException::Alloc(_))) => words *= 2,
+                result => return result,
+            }
+        }
+    }
+
+    fn receive_in(&self, process: &Process, bytes: &[u8]) -> InternalResult<()> {
+        let (control, after_control_bytes) = Control::decode(process, bytes)?;
+
+        match control {
+            Control::Send { to } => {
+                let message = decode_message(process, after_control_bytes)?;
+
+                if let Pid::Local(local_pid) = to {
+                    if let Some(arc_process) = registry::pid_to_process(&local_pid) {
+                        deliver(&arc_process, message)?;
+                    }
+                }
+            }
+            Control::RegSend { to_name, .. } => {
+                let message = decode_message(process, after_control_bytes)?;
+
+                if let Some(arc_process) = registry::atom_to_process(&to_name) {
+                    deliver(&arc_process, message)?;
+                }
+            }
+            Control::Link {
+                from: Pid::External(from),
+                to: Pid::Local(to),
+            } => {
+                if registry::pid_to_process(&to).is_some() {
+                    self.link_set.lock().insert((to, from));
+                } else {
+                    // Like linking to a local process that is not alive
+                    self.send_control(
+                        process,
+                        Control::Exit {
+                            from: Pid::Local(to),
+                            to: Pid::External(from),
+                            reason: atom!("noproc"),
+                        },
+                        None,
+                    );
+                }
+            }
+            Control::Unlink {
+                from: Pid::External(from),
+                to: Pid::Local(to),
+            } => {
+                self.link_set.lock().remove(&(to, from));
+            }
+            Control::UnlinkId { id, from, to } => {
+                if let (Pid::External(external_pid), Pid::Local(local_pid)) = (&from, &to) {
+                    self.link_set
+                        .lock()
+                        .remove(&(*local_pid, external_pid.clone()));
+                }
+
+                self.send_control(
+                    process,
+                    Control::UnlinkIdAck {
+                        id,
+                        from: to,
+                        to: from,
+                    },
+                    None,
+                );
+            }
+            Control::UnlinkIdAck { .. } => (),
+            Control::Exit {
+                from: Pid::External(from),
+                to: Pid::Local(to),
+                reason,
+            } => {
+                // Only linked processes get the exit signals of links
+                if self.link_set.lock().remove(&(to, from.clone())) {
+                    if let Some(arc_process) = registry::pid_to_process(&to) {
+                        exit_signal(process, &from, &arc_process, reason, false)?;
+                    }
+                }
+            }
+            Control::Exit2 {
+                from: Pid::External(from),
+                to: Pid::Local(to),
+                reason,
+            } => {
+                if let Some(arc_process) = registry::pid_to_process(&to) {
+                    exit_signal(process, &from, &arc_process, reason, true)?;
+                }
+            }
+            Control::MonitorP {
+                from: Pid::External(from),
+                to,
+                reference,
+            } => {
+                let option_monitored_arc_process = match &to {
+                    Identifier::Pid(Pid::Local(local_pid)) => registry::pid_to_process(local_pid),
+                    Identifier::Name(name) => registry::atom_to_process(name),
+                    Identifier::Pid(Pid::External(_)) => None,
+                };
+
+                match option_monitored_arc_process {
+                    Some(monitored_arc_process) => self.monitored_vec.lock().push(Monitored {
+                        monitored_pid: monitored_arc_process.pid(),
+                        identifier: to,
+                        monitoring_pid: from,
+                        reference,
+                    }),
+                    None => self.send_control(
+                        process,
+                        Control::MonitorPExit {
+                            from: to,
+                            to: Pid::External(from),
+                            reference,
+                            reason: atom!("noproc"),
+                        },
+                        None,
+                    ),
+                }
+            }
+            Control::DemonitorP { reference, .. } => {
+                self.monitored_vec
+                    .lock()
+                    .retain(|monitored| monitored.reference != reference);
+            }
+            Control::MonitorPExit {
+                reference: MonitorReference::Local(reference),
+                reason,
+                ..
+            } => {
+                // separate from `if let` below so that the lock is not held while delivering
+                let option_monitor = self.monitor_by_reference.lock().remove(&reference);
+
+                if let Some(monitor) = option_monitor {
+                    self.down(process, &reference, &monitor, reason)?;
+                }
+            }
+            _ => return Err(anyhow!("control message has pids of unexpected nodes").into()),
+        }
+
+        Ok(())
+    }
+
+    /// Queues `control` followed by `option_message` encoded with `process` for the writer
+    /// thread.
+    fn send_control(&self, process: &Process, control: Control, option_message: Option<Term>) {
+        let packet = packet(process, control, option_message);
+
+        // Like messages to processes that are not alive, packets of closed connections are
+        // dropped
+        if let Stream::Closed = *self.stream.lock() {
+            return;
+        }
+
+        self.packet_queue.lock().push_back(packet);
+        self.packet_queue_condvar.notify_one();
+    }
+}
+
+/// A monitor by a local process of a process on the other node
+struct Monitor {
+    monitoring_pid: LocalPid,
+    identifier: Identifier,
+}
+
+/// A monitor by a process on the other node of a local process
+struct Monitored {
+    monitored_pid: LocalPid,
+    /// How the monitoring process identified the local process
+    identifier: Identifier,
+    monitoring_pid: ExternalPid,
+    reference: MonitorReference,
+}
+
+enum Stream {
+    /// Connecting in the background, while the packets sent in the meantime are queued
+    Pending,
+    /// Only used to shut down the connection, as the reader and writer threads have their own
+    /// clones of the stream
+    Connected(TcpStream),
+    Closed,
+}
+
+fn accept(listener: TcpListener, _registration: Registration) {
+    for stream in listener.incoming().filter_map(Result::ok) {
+        // Each handshake is on its own thread, so that slow nodes don't hold up others
+        thread::spawn(move || accept_handshake(stream));
+    }
+}
+
+fn accept_handshake(mut stream: TcpStream) -> io::Result<()> {
+    let cookie = cookie()?;
+    let arc_node = node::arc_node();
+    let name = arc_node.name();
+
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CONNECT_TIMEOUT))?;
+
+    let peer = handshake::accept(
+        &mut stream,
+        name.name(),
+        arc_node.creation(),
+        &cookie,
+        |peer_name| status(name, peer_name),
+    )?;
+
+    // The peer only connects again to replace a connection that it lost
+    establish(stream, peer, true)
+}
+
+fn arc_connection_vec() -> Vec<Arc<Connection>> {
+    RW_LOCK_ARC_CONNECTION_BY_NAME
+        .read()
+        .values()
+        .cloned()
+        .collect()
+}
+
+/// The connection to the node `name`, which is connected to in the background if needed
+fn connect(name: Atom) -> io::Result<Arc<Connection>> {
+    if let Some(arc_connection) = RW_LOCK_ARC_CONNECTION_BY_NAME.read().get(&name) {
+        return Ok(arc_connection.clone());
+    }
+
+    let cookie = cookie()?;
+    let name_str = name.name();
+
+    let (alive_name, host) = match name_str.find('@') {
+        Some(index) => (
+            name_str[..index].to_string(),
+            name_str[index + 1..].to_string(),
+        ),
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("node name ({}) has no host", name_str),
+            ))
+        }
+    };
+
+    // write scope
+    let arc_connection = {
+        let mut arc_connection_by_name = RW_LOCK_ARC_CONNECTION_BY_NAME.write();
+
+        // Another process may have started connecting since the read lock was released
+        if let Some(arc_connection) = arc_connection_by_name.get(&name) {
+            return Ok(arc_connection.clone());
+        }
+
+        let arc_connection = Arc::new(Connection::new(
+            nodes::atom_to_arc_node_or_insert(&name),
+            Stream::Pending,
+        ));
+        arc_connection_by_name.insert(name, arc_connection.clone());
+
+        arc_connection
+    };
+
+    let connecting_arc_connection = arc_connection.clone();
+
+    if let Err(error) = thread::Builder::new()
+        .name(format!("distribution-{}-connect", name_str))
+        .spawn(move || connect_pending(connecting_arc_connection, &host, &alive_name, &cookie))
+    {
+        arc_connection.close_pending();
+        arc_connection.disconnect();
+
+        return Err(error);
+    }
+
+    Ok(arc_connection)
+}
+
+/// Runs on the thread of the pending `arc_connection` until it is established, or disconnects it
+/// if it can't be.
+fn connect_pending(arc_connection: Arc<Connection>, host: &str, alive_name: &str, cookie: &str) {
+    match handshake_connect(host, alive_name, cookie) {
+        Ok((stream, peer)) => {
+            if establish(stream, peer, false).is_ok() {
+                return;
+            }
+        }
+        // The node is connecting to the running node at the same time, and that connection
+        // establishes this one once its handshake is accepted
+        Err(ref error) if error.kind() == io::ErrorKind::AlreadyExists => {
+            thread::sleep(CONNECT_TIMEOUT)
+        }
+        Err(_) => (),
+    }
+
+    if arc_connection.close_pending() {
+        arc_connection.disconnect();
+    }
+}
+
+fn cookie() -> io::Result<String> {
+    RW_LOCK_OPTION_COOKIE.read().clone().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotConnected,
+            "distribution is not started, so the node is not alive",
+        )
+    })
+}
+
+fn decode_message(process: &Process, bytes: &[u8]) -> InternalResult<Term> {
+    let after_version_bytes = version::check(bytes)?;
+    let (message, _) = term::decode_tagged(process, false, after_version_bytes)?;
+
+    Ok(message)
+}
+
+/// Delivers `message` to `to`, copying it out of the process that decoded it
+fn deliver(to: &Process, message: Term) -> AllocResult<()> {
+    if to.send_from_other(message)? {
+        if let Some(arc_scheduler) = to.scheduler() {
+            arc_scheduler.stop_waiting(to);
+        }
+    }
+
+    Ok(())
+}
+
+/// Establishes the connection with `stream` to `peer`, adopting the pending connection to `peer`,
+/// so that the packets sent while connecting are sent.
+///
+/// If a connection to `peer` was established in the meantime, it is replaced with `replace`, and
+/// otherwise `stream` is shut down and the existing connection is kept.
+fn establish(stream: TcpStream, peer: Peer, replace: bool) -> io::Result<()> {
+    let name = Atom::try_from_str(&peer.name)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "node name is too long"))?;
+
+    let arc_node = nodes::atom_to_arc_node_or_insert(&name);
+    arc_node.set_creation(peer.creation);
+
+    stream.set_read_timeout(Some(NET_TICK_TIME))?;
+    stream.set_write_timeout(Some(NET_TICK_TIME))?;
+    let read_stream = stream.try_clone()?;
+    let write_stream = stream.try_clone()?;
+
+    // write scope
+    let (arc_connection, option_replaced_arc_connection) = {
+        let mut arc_connection_by_name = RW_LOCK_ARC_CONNECTION_BY_NAME.write();
+        let option_existing_arc_connection = arc_connection_by_name.get(&name).cloned();
+
+        let unadopted_stream = match &option_existing_arc_connection {
+            Some(existing_arc_connection) => existing_arc_connection.adopt(stream),
+            None => Err(stream),
+        };
+
+        match unadopted_stream {
+            Ok(()) => (option_existing_arc_connection.unwrap(), None),
+            Err(stream) => {
+                if option_existing_arc_connection.is_some() && !replace {
+                    let _ = stream.shutdown(Shutdown::Both);
+
+                    return Ok(());
+                }
+
+                let arc_connection = Arc::new(Connection::new(arc_node, Stream::Connected(stream)));
+                arc_connection_by_name.insert(name, arc_connection.clone());
+
+                (arc_connection, option_existing_arc_connection)
+            }
+        }
+    };
+
+    if let Some(replaced_arc_connection) = option_replaced_arc_connection {
+        replaced_arc_connection.disconnect();
+    }
+
+    let reading_arc_connection = arc_connection.clone();
+    let writing_arc_connection = arc_connection.clone();
+
+    let spawned = thread::Builder::new()
+        .name(format!("distribution-{}", name.name()))
+        .spawn(move || read(reading_arc_connection, read_stream))
+        .and_then(|_| {
+            thread::Builder::new()
+                .name(format!("distribution-{}-write", name.name()))
+                .spawn(move || write(writing_arc_connection, write_stream))
+        });
+
+    if let Err(error) = spawned {
+        arc_connection.disconnect();
+
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+/// Sends an exit signal with `reason` from `from` on the other node to `to`.  Only exit signals
+/// from `exit/2`, and not from links, exit `to` with `killed` for `kill` even if it traps exits.
+fn exit_signal(
+    process: &Process,
+    from: &ExternalPid,
+    to: &Process,
+    reason: Term,
+    kill_untrappable: bool,
+) -> AllocResult<()> {
+    let source: ArcError = anyhow!("exit from {} on {}", from, from.arc_node().name()).into();
+
+    if kill_untrappable && reason == atom!("kill") {
+        process::exit_with_reason(to, atom!("killed"), source);
+    } else if to.traps_exit() {
+        let from_term = from.clone_to_process(process);
+        let message = process.tuple_from_slice(&[atom!("EXIT"), from_term, reason])?;
+
+        deliver(to, message)?;
+    } else if reason != atom!("normal") {
+        process::exit_with_reason(to, reason, source);
+    }
+
+    Ok(())
+}
+
+/// Connects to the node `alive_name` on `host` and completes the handshake, giving up on each step
+/// after the connect timeout
+fn handshake_connect(host: &str, alive_name: &str, cookie: &str) -> io::Result<(TcpStream, Peer)> {
+    let port = epmd::port_please(host, alive_name, CONNECT_TIMEOUT)?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("node ({}@{}) is not alive", alive_name, host),
+        )
+    })?;
+
+    let mut stream = epmd::connect_timeout(host, port, CONNECT_TIMEOUT)?;
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CONNECT_TIMEOUT))?;
+
+    let arc_node = node::arc_node();
+    let peer = handshake::connect(
+        &mut stream,
+        arc_node.name().name(),
+        arc_node.creation(),
+        cookie,
+    )?;
+
+    Ok((stream, peer))
+}
+
+/// The packet of `control` followed by `option_message` encoded with `process`
+fn packet(process: &Process, control: Control, option_message: Option<Term>) -> Vec<u8> {
+    let mut packet = vec![PASS_THROUGH];
+    packet.append(&mut control.encode(process));
+
+    if let Some(message) = option_message {
+        packet.append(&mut term_to_binary::term_to_byte_vec(
+            process,
+            &Default::default(),
+            message,
+        ));
+    }
+
+    packet
+}
+
+/// Runs on the connection's thread, receiving packets until the connection is closed or silent for
+/// longer than the net tick time.
+fn read(arc_connection: Arc<Connection>, mut stream: TcpStream) {
+    // The terms of every packet are decoded into this one process, so that packets don't each
+    // need a process of their own
+    if let Ok(process) = process::init(PROCESS_EXTRA_WORDS) {
+        while let Ok(packet) = read_packet(&mut stream) {
+            // Empty packets are ticks
+            if !packet.is_empty() {
+                // Like messages to processes that are not alive, packets that can't be received
+                // are dropped
+                let _ = arc_connection.receive(&process, &packet);
+            }
+        }
+    }
+
+    arc_connection.disconnect();
+}
+
+fn read_packet(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let mut len_bytes = [0; 4];
+    stream.read_exact(&mut len_bytes)?;
+
+    let mut packet = vec![0; u32::from_be_bytes(len_bytes) as usize];
+    stream.read_exact(&mut packet)?;
+
+    Ok(packet)
+}
+
+/// The status of the handshake of `peer_name` connecting to the running node, `name`
+fn status(name: Atom, peer_name: &str) -> Status {
+    let option_arc_connection = Atom::try_from_str(peer_name)
+        .ok()
+        .and_then(|peer| RW_LOCK_ARC_CONNECTION_BY_NAME.read().get(&peer).cloned());
+
+    match option_arc_connection {
+        Some(arc_connection) => match *arc_connection.stream.lock() {
+            // Only the connection of the node with the greater name continues
+            Stream::Pending => {
+                if name.name() < peer_name {
+                    Status::OkSimultaneous
+                } else {
+                    Status::Nok
+                }
+            }
+            Stream::Connected(_) => Status::Alive,
+            Stream::Closed => Status::Ok,
+        },
+        None => Status::Ok,
+    }
+}
+
+/// Runs on the connection's writer thread, writing the queued packets, or a tick when nothing was
+/// sent for the tick interval, until the connection is closed.
+///
+/// If a packet can't be written, the connection is shut down, so that its reader thread
+/// disconnects it.
+fn write(arc_connection: Arc<Connection>, mut stream: TcpStream) {
+    loop {
+        // packet_queue.lock() scope
+        let packet = {
+            let mut packet_queue = arc_connection.packet_queue.lock();
+
+            loop {
+                if let Stream::Closed = *arc_connection.stream.lock() {
+                    return;
+                }
+
+                match packet_queue.pop_front() {
+                    Some(packet) => break packet,
+                    None => {
+                        if arc_connection
+                            .packet_queue_condvar
+                            .wait_for(&mut packet_queue, TICK_INTERVAL)
+                            .timed_out()
+                        {
+                            // Empty packets are ticks
+                            break Vec::new();
+                        }
+                    }
+                }
+            }
+        };
+
+        if write_packet(&mut stream, &packet).is_err() {
+            let _ = stream.shutdown(Shutdown::Both);
+
+            return;
+        }
+    }
+}
+
+fn write_packet(stream: &mut TcpStream, packet: &[u8]) -> io::Result<()> {
+    let mut byte_vec = Vec::with_capacity(4 + packet.len());
+    byte_vec.extend_from_slice(&(packet.len() as u32).to_be_bytes());
+    byte_vec.extend_from_slice(packet);
+
+    stream.write_all(&byte_vec)
+}
+
+lazy_static! {
+    static ref RW_LOCK_ARC_CONNECTION_BY_NAME: RwLock<HashMap<Atom, Arc<Connection>>> =
+        Default::default();
+    static ref RW_LOCK_OPTION_COOKIE: RwLock<Option<String>> = Default::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::convert::TryInto;
+    use std::env;
+    use std::process::Command;
+    use std::time::Instant;
+
+    use crate::process::SchedulerDependentAlloc;
+    use crate::test::has_message;
+
+    // Making the running node alive changes the node of every local pid, so the nodes are connected
+    // in a test process of their own
+    #[test]
+    fn with_peer_node_over_loopback_sends_signals() {
+        let epmd_port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let status = Command::new(env::current_exe().unwrap())
+            .args(&[
+                "--exact",
+                "distribution::connection::tests::peer_node_over_loopback",
+                "--ignored",
+            ])
+            .env(PEER_NODE_OVER_LOOPBACK, "true")
+            .env("ERL_EPMD_PORT", epmd_port.to_string())
+            .status()
+            .unwrap();
+
+        assert!(status.success());
+    }
+
+    #[test]
+    #[ignore]
+    fn peer_node_over_loopback() {
+        // Only run in the test process of `with_peer_node_over_loopback_sends_signals`
+        if env::var_os(PEER_NODE_OVER_LOOPBACK).is_none() {
+            return;
+        }
+
+        start("local@127.0.0.1", COOKIE.to_string()).unwrap();
+
+        // The peer node is played by the test over a listener of its own, with a process to encode
+        // and decode its terms
+        let peer_listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let _peer_registration =
+            epmd::register("peer", peer_listener.local_addr().unwrap().port()).unwrap();
+        let peer_name = Atom::try_from_str(PEER_NAME).unwrap();
+        let peer_pid =
+            ExternalPid::new(nodes::atom_to_arc_node_or_insert(&peer_name), 1, 0).unwrap();
+        let peer_process = process::test_init();
+        let peer_pid_term = peer_pid.clone_to_process(&peer_process);
+
+        let arc_process = process::test(&peer_process);
+        arc_process.trap_exit(true);
+        let local_pid = Pid::Local(arc_process.pid());
+
+        // Signals sent while connecting are sent once connected
+        assert!(link(&arc_process, &peer_pid));
+
+        let message = Atom::str_to_term("to_peer");
+        send(&arc_process, &peer_pid, message);
+
+        let mut peer_stream = accept_peer(&peer_listener);
+
+        match receive_control(&peer_process, &mut peer_stream) {
+            (Control::Link { from, to }, _) => {
+                assert_eq!(from, local_pid);
+                assert_eq!(to, Pid::External(peer_pid.clone()));
+            }
+            (control, _) => panic!("expected link, got {:?}", control),
+        }
+
+        match receive_control(&peer_process, &mut peer_stream) {
+            (Control::Send { to }, after_control_bytes) => {
+                assert_eq!(to, Pid::External(peer_pid.clone()));
+                assert_eq!(
+                    decode_message(&peer_process, &after_control_bytes).unwrap(),
+                    message
+                );
+            }
+            (control, _) => panic!("expected send, got {:?}", control),
+        }
+
+        // send
+        let peer_message = Atom::str_to_term("from_peer");
+        send_peer_control(
+            &peer_process,
+            &mut peer_stream,
+            Control::Send {
+                to: local_pid.clone(),
+            },
+            Some(peer_message),
+        );
+
+        assert!(receives(&arc_process, peer_message));
+        assert_eq!(connected_nodes(), vec![peer_name]);
+
+        // link exit
+        let exit_reason = Atom::str_to_term("peer_exit");
+        send_peer_control(
+            &peer_process,
+            &mut peer_stream,
+            Control::Exit {
+                from: Pid::External(peer_pid.clone()),
+                to: local_pid.clone(),
+                reason: exit_reason,
+            },
+            None,
+        );
+
+        assert!(receives(
+            &arc_process,
+            peer_process
+                .tuple_from_slice(&[atom!("EXIT"), peer_pid_term, exit_reason])
+                .unwrap()
+        ));
+
+        // monitor DOWN
+        let reference = monitor_peer(&arc_process, &peer_pid);
+
+        let monitor_reference = match receive_control(&peer_process, &mut peer_stream) {
+            (
+                Control::MonitorP {
+                    from, reference, ..
+                },
+                _,
+            ) => {
+                assert_eq!(from, local_pid);
+
+                reference
+            }
+            (control, _) => panic!("expected monitor, got {:?}", control),
+        };
+
+        let down_reason = Atom::str_to_term("peer_down");
+        send_peer_control(
+            &peer_process,
+            &mut peer_stream,
+            Control::MonitorPExit {
+                from: Identifier::Pid(Pid::External(peer_pid.clone())),
+                to: local_pid.clone(),
+                reference: monitor_reference,
+                reason: down_reason,
+            },
+            None,
+        );
+
+        assert!(receives(
+            &arc_process,
+            down(&peer_process, reference, peer_pid_term, down_reason)
+        ));
+
+        // noconnection on disconnect
+        assert!(link(&arc_process, &peer_pid));
+        let disconnected_reference = monitor_peer(&arc_process, &peer_pid);
+
+        // the link and monitor are received before disconnecting
+        receive_control(&peer_process, &mut peer_stream);
+        receive_control(&peer_process, &mut peer_stream);
+
+        peer_stream.shutdown(Shutdown::Both).unwrap();
+
+        let noconnection = atom!("noconnection");
+
+        assert!(receives(
+            &arc_process,
+            peer_process
+                .tuple_from_slice(&[atom!("EXIT"), peer_pid_term, noconnection])
+                .unwrap()
+        ));
+        assert!(receives(
+            &arc_process,
+            down(
+                &peer_process,
+                disconnected_reference,
+                peer_pid_term,
+                noconnection
+            )
+        ));
+        assert!(!is_connected(&peer_name));
+        assert!(connected_nodes().is_empty());
+    }
+
+    const COOKIE: &str = "cookie";
+    const PEER_NAME: &str = "peer@127.0.0.1";
+    const PEER_NODE_OVER_LOOPBACK: &str = "LUMEN_PEER_NODE_OVER_LOOPBACK";
+
+    fn accept_peer(listener: &TcpListener) -> TcpStream {
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.set_read_timeout(Some(CONNECT_TIMEOUT)).unwrap();
+
+        let peer = handshake::accept(&mut stream, PEER_NAME, 1, COOKIE, |_| Status::Ok).unwrap();
+        assert_eq!(peer.name, "local@127.0.0.1");
+
+        stream
+    }
+
+    fn down(process: &Process, reference: Term, pid: Term, info: Term) -> Term {
+        process
+            .tuple_from_slice(&[atom!("DOWN"), reference, atom!("process"), pid, info])
+            .unwrap()
+    }
+
+    fn monitor_peer(arc_process: &Process, peer_pid: &ExternalPid) -> Term {
+        let reference = arc_process.next_reference().unwrap();
+        let reference_boxed: Boxed<Reference> = reference.try_into().unwrap();
+
+        assert!(monitor(
+            arc_process,
+            *reference_boxed.as_ref(),
+            Identifier::Pid(Pid::External(peer_pid.clone())),
+            peer_pid.arc_node().name()
+        ));
+
+        reference
+    }
+
+    /// Whether `process` receives `data` before the connect timeout
+    fn receives(process: &Process, data: Term) -> bool {
+        let deadline = Instant::now() + CONNECT_TIMEOUT;
+
+        while !has_message(process, data) {
+            if deadline <= Instant::now() {
+                return false;
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        true
+    }
+
+    /// The next control message that the peer receives, skipping ticks, and the bytes after it
+    fn receive_control(process: &Process, stream: &mut TcpStream) -> (Control, Vec<u8>) {
+        loop {
+            let packet = read_packet(stream).unwrap();
+
+            if let Some((&PASS_THROUGH, bytes)) = packet.split_first() {
+                let (control, after_control_bytes) = Control::decode(process, bytes).unwrap();
+
+                return (control, after_control_bytes.to_vec());
+            }
+        }
+    }
+
+    fn send_peer_control(
+        process: &Process,
+        stream: &mut TcpStream,
+        control: Control,
+        option_message: Option<Term>,
+    ) {
+        write_packet(stream, &packet(process, control, option_message)).unwrap();
+    }
+}
@@ -0,0 +1,526 @@
+//! The control messages that connected nodes send each other, as described in
+//! http://erlang.org/doc/apps/erts/erl_dist_protocol.html#control-messages
+//!
+//! Only the control messages for the flags that the running node sets in the handshake are
+//! supported.
+use std::convert::TryInto;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception::InternalResult;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::distribution::external_term_format::{term, try_split_at, version, Pid, Tag};
+use crate::distribution::nodes::node;
+use crate::otp::erlang::term_to_binary;
+
+#[derive(Debug)]
+pub enum Control {
+    Link {
+        from: Pid,
+        to: Pid,
+    },
+    /// Followed by the message
+    Send {
+        to: Pid,
+    },
+    Exit {
+        from: Pid,
+        to: Pid,
+        reason: Term,
+    },
+    Unlink {
+        from: Pid,
+        to: Pid,
+    },
+    /// Followed by the message
+    RegSend {
+        from: Pid,
+        to_name: Atom,
+    },
+    Exit2 {
+        from: Pid,
+        to: Pid,
+        reason: Term,
+    },
+    MonitorP {
+        from: Pid,
+        to: Identifier,
+        reference: MonitorReference,
+    },
+    DemonitorP {
+        from: Pid,
+        to: Identifier,
+        reference: MonitorReference,
+    },
+    MonitorPExit {
+        from: Identifier,
+        to: Pid,
+        reference: MonitorReference,
+        reason: Term,
+    },
+    UnlinkId {
+        id: u64,
+        from: Pid,
+        to: Pid,
+    },
+    UnlinkIdAck {
+        id: u64,
+        from: Pid,
+        to: Pid,
+    },
+}
+
+impl Control {
+    /// Decodes the control message at the start of `bytes` into `process`, returning the bytes
+    /// after it, which are the message for `Send` and `RegSend`.
+    pub fn decode<'a>(process: &Process, bytes: &'a [u8]) -> InternalResult<(Self, &'a [u8])> {
+        let after_version_bytes = version::check(bytes)?;
+        let after_len_bytes = match Tag::decode(after_version_bytes)? {
+            (Tag::SmallTuple, after_tag_bytes) => try_split_at(after_tag_bytes, 1)?.1,
+            (tag, _) => {
+                return Err(anyhow!("control message is a {} instead of a small tuple", tag).into())
+            }
+        };
+
+        let mut decoder = Decoder {
+            process,
+            bytes: after_len_bytes,
+        };
+
+        let control = match decoder.u64()? {
+            LINK => Control::Link {
+                from: decoder.pid()?,
+                to: decoder.pid()?,
+            },
+            SEND => {
+                // Unused
+                decoder.term()?;
+
+                Control::Send { to: decoder.pid()? }
+            }
+            EXIT => Control::Exit {
+                from: decoder.pid()?,
+                to: decoder.pid()?,
+                reason: decoder.term()?,
+            },
+            UNLINK => Control::Unlink {
+                from: decoder.pid()?,
+                to: decoder.pid()?,
+            },
+            REG_SEND => {
+                let from = decoder.pid()?;
+                // Unused
+                decoder.term()?;
+
+                Control::RegSend {
+                    from,
+                    to_name: decoder.atom()?,
+                }
+            }
+            EXIT2 => Control::Exit2 {
+                from: decoder.pid()?,
+                to: decoder.pid()?,
+                reason: decoder.term()?,
+            },
+            MONITOR_P => Control::MonitorP {
+                from: decoder.pid()?,
+                to: decoder.identifier()?,
+                reference: decoder.reference()?,
+            },
+            DEMONITOR_P => Control::DemonitorP {
+                from: decoder.pid()?,
+                to: decoder.identifier()?,
+                reference: decoder.reference()?,
+            },
+            MONITOR_P_EXIT => Control::MonitorPExit {
+                from: decoder.identifier()?,
+                to: decoder.pid()?,
+                reference: decoder.reference()?,
+                reason: decoder.term()?,
+            },
+            UNLINK_ID => Control::UnlinkId {
+                id: decoder.u64()?,
+                from: decoder.pid()?,
+                to: decoder.pid()?,
+            },
+            UNLINK_ID_ACK => Control::UnlinkIdAck {
+                id: decoder.u64()?,
+                from: decoder.pid()?,
+                to: decoder.pid()?,
+            },
+            operation => {
+                return Err(anyhow!("unsupported control message operation ({})", operation).into())
+            }
+        };
+
+        Ok((control, decoder.bytes))
+    }
+
+    /// Encodes the control message with the version, using `process` to encode `reason`s
+    pub fn encode(&self, process: &Process) -> Vec<u8> {
+        let mut byte_vec = vec![version::NUMBER];
+
+        match self {
+            Control::Link { from, to } => {
+                append_tuple_header(&mut byte_vec, 3);
+                append_u64(&mut byte_vec, LINK);
+                append_pid(&mut byte_vec, from);
+                append_pid(&mut byte_vec, to);
+            }
+            Control::Send { to } => {
+                append_tuple_header(&mut byte_vec, 3);
+                append_u64(&mut byte_vec, SEND);
+                append_unused(&mut byte_vec);
+                append_pid(&mut byte_vec, to);
+            }
+            Control::Exit { from, to, reason } => {
+                append_tuple_header(&mut byte_vec, 4);
+                append_u64(&mut byte_vec, EXIT);
+                append_pid(&mut byte_vec, from);
+                append_pid(&mut byte_vec, to);
+                append_term(&mut byte_vec, process, *reason);
+            }
+            Control::Unlink { from, to } => {
+                append_tuple_header(&mut byte_vec, 3);
+                append_u64(&mut byte_vec, UNLINK);
+                append_pid(&mut byte_vec, from);
+                append_pid(&mut byte_vec, to);
+            }
+            Control::RegSend { from, to_name } => {
+                append_tuple_header(&mut byte_vec, 4);
+                append_u64(&mut byte_vec, REG_SEND);
+                append_pid(&mut byte_vec, from);
+                append_unused(&mut byte_vec);
+                byte_vec.append(&mut term_to_binary::atom_to_byte_vec(*to_name));
+            }
+            Control::Exit2 { from, to, reason } => {
+                append_tuple_header(&mut byte_vec, 4);
+                append_u64(&mut byte_vec, EXIT2);
+                append_pid(&mut byte_vec, from);
+                append_pid(&mut byte_vec, to);
+                append_term(&mut byte_vec, process, *reason);
+            }
+            Control::MonitorP {
+                from,
+                to,
+                reference,
+            } => {
+                append_tuple_header(&mut byte_vec, 4);
+                append_u64(&mut byte_vec, MONITOR_P);
+                append_pid(&mut byte_vec, from);
+                append_identifier(&mut byte_vec, to);
+                append_reference(&mut byte_vec, reference);
+            }
+            Control::DemonitorP {
+                from,
+                to,
+                reference,
+            } => {
+                append_tuple_header(&mut byte_vec, 4);
+                append_u64(&mut byte_vec, DEMONITOR_P);
+                append_pid(&mut byte_vec, from);
+                append_identifier(&mut byte_vec, to);
+                append_reference(&mut byte_vec, reference);
+            }
+            Control::MonitorPExit {
+                from,
+                to,
+                reference,
+                reason,
+            } => {
+                append_tuple_header(&mut byte_vec, 5);
+                append_u64(&mut byte_vec, MONITOR_P_EXIT);
+                append_identifier(&mut byte_vec, from);
+                append_pid(&mut byte_vec, to);
+                append_reference(&mut byte_vec, reference);
+                append_term(&mut byte_vec, process, *reason);
+            }
+            Control::UnlinkId { id, from, to } => {
+                append_tuple_header(&mut byte_vec, 4);
+                append_u64(&mut byte_vec, UNLINK_ID);
+                append_u64(&mut byte_vec, *id);
+                append_pid(&mut byte_vec, from);
+                append_pid(&mut byte_vec, to);
+            }
+            Control::UnlinkIdAck { id, from, to } => {
+                append_tuple_header(&mut byte_vec, 4);
+                append_u64(&mut byte_vec, UNLINK_ID_ACK);
+                append_u64(&mut byte_vec, *id);
+                append_pid(&mut byte_vec, from);
+                append_pid(&mut byte_vec, to);
+            }
+        }
+
+        byte_vec
+    }
+}
+
+/// A monitored process is identified by either its pid or its registered name on its node
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Identifier {
+    Pid(Pid),
+    Name(Atom),
+}
+
+/// The references of monitors by the running node are decoded, but those of other nodes are kept
+/// encoded, so that they are sent back exactly as they were received.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum MonitorReference {
+    Local(Reference),
+    Remote(Vec<u8>),
+}
+
+// Private
+
+const LINK: u64 = 1;
+const SEND: u64 = 2;
+const EXIT: u64 = 3;
+const UNLINK: u64 = 4;
+const REG_SEND: u64 = 6;
+const EXIT2: u64 = 8;
+const MONITOR_P: u64 = 19;
+const DEMONITOR_P: u64 = 20;
+const MONITOR_P_EXIT: u64 = 21;
+const UNLINK_ID: u64 = 35;
+const UNLINK_ID_ACK: u64 = 36;
+
+struct Decoder<'a, 'b> {
+    process: &'b Process,
+    bytes: &'a [u8],
+}
+
+impl<'a, 'b> Decoder<'a, 'b> {
+    fn atom(&mut self) -> InternalResult<Atom> {
+        let term = self.term()?;
+
+        term.try_into()
+            .with_context(|| format!("{} is not an atom", term))
+            .map_err(From::from)
+    }
+
+    fn identifier(&mut self) -> InternalResult<Identifier> {
+        let term = self.term()?;
+
+        match term.decode()? {
+            TypedTerm::Atom(atom) => Ok(Identifier::Name(atom)),
+            TypedTerm::Pid(pid) => Ok(Identifier::Pid(Pid::Local(pid))),
+            TypedTerm::ExternalPid(external_pid) => Ok(Identifier::Pid(Pid::External(
+                external_pid.as_ref().clone(),
+            ))),
+            _ => Err(anyhow!("{} is neither a pid nor a registered name", term).into()),
+        }
+    }
+
+    fn pid(&mut self) -> InternalResult<Pid> {
+        let term = self.term()?;
+
+        match term.decode()? {
+            TypedTerm::Pid(pid) => Ok(Pid::Local(pid)),
+            TypedTerm::ExternalPid(external_pid) => {
+                Ok(Pid::External(external_pid.as_ref().clone()))
+            }
+            _ => Err(anyhow!("{} is not a pid", term).into()),
+        }
+    }
+
+    fn reference(&mut self) -> InternalResult<MonitorReference> {
+        let (tag, after_tag_bytes) = Tag::decode(self.bytes)?;
+
+        let creation_byte_len = match tag {
+            Tag::NewerReference => 4,
+            Tag::NewReference => 1,
+            _ => return Err(anyhow!("{} is not a reference", tag).into()),
+        };
+
+        let (len_bytes, after_len_bytes) = try_split_at(after_tag_bytes, 2)?;
+        let id_byte_len = 4 * (u16::from_be_bytes([len_bytes[0], len_bytes[1]]) as usize);
+
+        let (node, after_node_bytes) = term::decode_tagged(self.process, false, after_len_bytes)?;
+        let (_, after_reference_bytes) =
+            try_split_at(after_node_bytes, creation_byte_len + id_byte_len)?;
+        let reference_byte_len = self.bytes.len() - after_reference_bytes.len();
+
+        let reference = match tag {
+            Tag::NewerReference if node == node::term() => {
+                let (reference, _) = term::decode_tagged(self.process, false, self.bytes)?;
+                let reference_reference: Boxed<Reference> = reference
+                    .try_into()
+                    .with_context(|| format!("{} is not a local reference", reference))?;
+
+                MonitorReference::Local(*reference_reference.as_ref())
+            }
+            _ => MonitorReference::Remote(self.bytes[..reference_byte_len].to_vec()),
+        };
+
+        self.bytes = after_reference_bytes;
+
+        Ok(reference)
+    }
+
+    fn term(&mut self) -> InternalResult<Term> {
+        let (term, after_term_bytes) = term::decode_tagged(self.process, false, self.bytes)?;
+        self.bytes = after_term_bytes;
+
+        Ok(term)
+    }
+
+    fn u64(&mut self) -> InternalResult<u64> {
+        let term = self.term()?;
+
+        term.try_into()
+            .with_context(|| format!("{} is not a non-negative integer", term))
+            .map_err(From::from)
+    }
+}
+
+fn append_identifier(byte_vec: &mut Vec<u8>, identifier: &Identifier) {
+    match identifier {
+        Identifier::Pid(pid) => append_pid(byte_vec, pid),
+        Identifier::Name(name) => byte_vec.append(&mut term_to_binary::atom_to_byte_vec(*name)),
+    }
+}
+
+fn append_pid(byte_vec: &mut Vec<u8>, pid: &Pid) {
+    match pid {
+        Pid::Local(local_pid) => term_to_binary::append_pid(
+            byte_vec,
+            node::arc_node(),
+            local_pid.number() as u32,
+            local_pid.serial() as u32,
+        ),
+        Pid::External(external_pid) => term_to_binary::append_pid(
+            byte_vec,
+            external_pid.arc_node(),
+            external_pid.number() as u32,
+            external_pid.serial() as u32,
+        ),
+    }
+}
+
+fn append_reference(byte_vec: &mut Vec<u8>, reference: &MonitorReference) {
+    match reference {
+        MonitorReference::Local(local_reference) => {
+            term_to_binary::append_reference(byte_vec, local_reference)
+        }
+        MonitorReference::Remote(bytes) => byte_vec.extend_from_slice(bytes),
+    }
+}
+
+fn append_term(byte_vec: &mut Vec<u8>, process: &Process, term: Term) {
+    let versioned_byte_vec = term_to_binary::term_to_byte_vec(process, &Default::default(), term);
+
+    byte_vec.extend_from_slice(&versioned_byte_vec[1..]);
+}
+
+fn append_tuple_header(byte_vec: &mut Vec<u8>, len: u8) {
+    byte_vec.push(Tag::SmallTuple.into());
+    byte_vec.push(len);
+}
+
+/// As `SMALL_INTEGER_EXT`, `INTEGER_EXT` or `SMALL_BIG_EXT`, whichever is the smallest
+fn append_u64(byte_vec: &mut Vec<u8>, u: u64) {
+    if u <= (std::u8::MAX as u64) {
+        byte_vec.push(Tag::SmallInteger.into());
+        byte_vec.push(u as u8);
+    } else if u <= (std::i32::MAX as u64) {
+        byte_vec.push(Tag::Integer.into());
+        byte_vec.extend_from_slice(&(u as i32).to_be_bytes());
+    } else {
+        let little_endian_bytes = u.to_le_bytes();
+        let len = little_endian_bytes
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .unwrap()
+            + 1;
+
+        byte_vec.push(Tag::SmallBig.into());
+        byte_vec.push(len as u8);
+        // positive
+        byte_vec.push(0);
+        byte_vec.extend_from_slice(&little_endian_bytes[..len]);
+    }
+}
+
+/// The unused element of `SEND` and `REG_SEND`, which was once the cookie, is the empty atom
+fn append_unused(byte_vec: &mut Vec<u8>) {
+    byte_vec.append(&mut term_to_binary::atom_to_byte_vec(
+        Atom::try_from_str("").unwrap(),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::convert::TryInto;
+
+    use crate::process::{self, SchedulerDependentAlloc};
+
+    #[test]
+    fn unlink_id_with_big_id_round_trips() {
+        let arc_process = process::test_init();
+        let from = Pid::Local(arc_process.pid());
+        let to = Pid::Local(process::test(&arc_process).pid());
+        let id = u64::max_value();
+
+        let bytes = Control::UnlinkId {
+            id,
+            from: from.clone(),
+            to: to.clone(),
+        }
+        .encode(&arc_process);
+
+        match Control::decode(&arc_process, &bytes).unwrap() {
+            (
+                Control::UnlinkId {
+                    id: decoded_id,
+                    from: decoded_from,
+                    to: decoded_to,
+                },
+                rest,
+            ) => {
+                assert_eq!(decoded_id, id);
+                assert_eq!(decoded_from, from);
+                assert_eq!(decoded_to, to);
+                assert!(rest.is_empty());
+            }
+            (control, _) => panic!("decoded {:?}", control),
+        }
+    }
+
+    #[test]
+    fn monitor_p_with_name_round_trips() {
+        let arc_process = process::test_init();
+        let from = Pid::Local(arc_process.pid());
+        let to = Identifier::Name(Atom::try_from_str("registered").unwrap());
+        let reference_boxed: Boxed<Reference> =
+            arc_process.next_reference().unwrap().try_into().unwrap();
+        let reference = MonitorReference::Local(*reference_boxed.as_ref());
+
+        let bytes = Control::MonitorP {
+            from: from.clone(),
+            to: to.clone(),
+            reference: reference.clone(),
+        }
+        .encode(&arc_process);
+
+        match Control::decode(&arc_process, &bytes).unwrap() {
+            (
+                Control::MonitorP {
+                    from: decoded_from,
+                    to: decoded_to,
+                    reference: decoded_reference,
+                },
+                rest,
+            ) => {
+                assert_eq!(decoded_from, from);
+                assert_eq!(decoded_to, to);
+                assert_eq!(decoded_reference, reference);
+                assert!(rest.is_empty());
+            }
+            (control, _) => panic!("decoded {:?}", control),
+        }
+    }
+}
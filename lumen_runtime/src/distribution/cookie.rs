@@ -0,0 +1,61 @@
+//! The cookie nodes share to connect to each other, which defaults to the one in
+//! `~/.erlang.cookie`, like `erl`.
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// The cookie in `~/.erlang.cookie`, which is generated if the file does not exist
+pub fn home() -> io::Result<String> {
+    let path = path()?;
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => Ok(contents.trim().to_string()),
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => {
+            let cookie = generate();
+            write(&path, &cookie)?;
+
+            Ok(cookie)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+// Private
+
+// Like `erl`, 20 uppercase letters
+const LEN: usize = 20;
+
+fn generate() -> String {
+    (0..LEN)
+        .map(|_| (b'A' + rand::random::<u8>() % 26) as char)
+        .collect()
+}
+
+fn path() -> io::Result<PathBuf> {
+    env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join(".erlang.cookie"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "HOME is not set"))
+}
+
+// Like `erl`, only the owner can read the cookie
+#[cfg(unix)]
+fn write(path: &PathBuf, cookie: &str) -> io::Result<()> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o400)
+        .open(path)?
+        .write_all(cookie.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn write(path: &PathBuf, cookie: &str) -> io::Result<()> {
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?
+        .write_all(cookie.as_bytes())
+}
@@ -0,0 +1,164 @@
+//! A client of the Erlang Port Mapper Daemon (EPMD), which maps the names of the alive nodes on a
+//! host to the ports that they listen on, as described in
+//! http://erlang.org/doc/apps/erts/erl_dist_protocol.html#epmd-protocol
+//!
+//! When no port mapper is running on the host, `liblumen_epmd` is started in-process, so that
+//! nodes can be alive without any external services.
+use std::env;
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use liblumen_epmd::{
+    ALIVE2_REQ, ALIVE2_RESP, ALIVE2_X_RESP, DEFAULT_PORT, PORT2_RESP, PORT_PLEASE2_REQ,
+};
+
+use super::handshake;
+
+/// The open connection to the port mapper, which keeps the node registered until it is dropped
+pub struct Registration {
+    _stream: TcpStream,
+    pub creation: u32,
+}
+
+/// Connects to `port` on `host`, giving up on each of its addresses after `timeout`
+pub fn connect_timeout(host: &str, port: u16, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last_error = io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("host ({}) has no addresses", host),
+    );
+
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(error) => last_error = error,
+        }
+    }
+
+    Err(last_error)
+}
+
+/// The port that the node `alive_name` listens on at `host`, if it is alive.
+///
+/// Gives up if the port mapper does not respond within `timeout`.
+pub fn port_please(host: &str, alive_name: &str, timeout: Duration) -> io::Result<Option<u16>> {
+    let mut stream = connect_timeout(host, port(), timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let mut request = vec![PORT_PLEASE2_REQ];
+    request.extend_from_slice(alive_name.as_bytes());
+    write_request(&mut stream, &request)?;
+
+    let mut header = [0; 2];
+    stream.read_exact(&mut header)?;
+
+    match header {
+        [PORT2_RESP, 0] => {
+            let mut port_bytes = [0; 2];
+            stream.read_exact(&mut port_bytes)?;
+
+            Ok(Some(u16::from_be_bytes(port_bytes)))
+        }
+        [PORT2_RESP, _] => Ok(None),
+        [tag, _] => Err(unexpected_response(tag)),
+    }
+}
+
+/// Registers the node `alive_name` as listening on `listen_port`, starting a port mapper in-process
+/// if none is running.
+pub fn register(alive_name: &str, listen_port: u16) -> io::Result<Registration> {
+    let port = port();
+
+    let mut stream = match TcpStream::connect((Ipv4Addr::LOCALHOST, port)) {
+        Ok(stream) => stream,
+        Err(ref error) if error.kind() == io::ErrorKind::ConnectionRefused => {
+            match TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)) {
+                Ok(listener) => liblumen_epmd::start(listener)?,
+                // Another node on the host started its port mapper in the meantime
+                Err(ref error) if error.kind() == io::ErrorKind::AddrInUse => (),
+                Err(error) => return Err(error),
+            }
+
+            TcpStream::connect((Ipv4Addr::LOCALHOST, port))?
+        }
+        Err(error) => return Err(error),
+    };
+
+    let mut request = vec![ALIVE2_REQ];
+    request.extend_from_slice(&listen_port.to_be_bytes());
+    request.push(NODE_TYPE_NORMAL);
+    request.push(PROTOCOL_TCP_IPV4);
+    request.extend_from_slice(&handshake::VERSION.to_be_bytes());
+    request.extend_from_slice(&handshake::VERSION.to_be_bytes());
+    append_u16_len_bytes(&mut request, alive_name.as_bytes());
+    // no extra
+    append_u16_len_bytes(&mut request, &[]);
+    write_request(&mut stream, &request)?;
+
+    let mut header = [0; 2];
+    stream.read_exact(&mut header)?;
+
+    let creation = match header {
+        [ALIVE2_X_RESP, 0] => {
+            let mut creation_bytes = [0; 4];
+            stream.read_exact(&mut creation_bytes)?;
+
+            u32::from_be_bytes(creation_bytes)
+        }
+        [ALIVE2_RESP, 0] => {
+            let mut creation_bytes = [0; 2];
+            stream.read_exact(&mut creation_bytes)?;
+
+            u16::from_be_bytes(creation_bytes) as u32
+        }
+        [ALIVE2_X_RESP, result] | [ALIVE2_RESP, result] => {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!(
+                    "port mapper refused to register {} (result {})",
+                    alive_name, result
+                ),
+            ))
+        }
+        [tag, _] => return Err(unexpected_response(tag)),
+    };
+
+    Ok(Registration {
+        _stream: stream,
+        creation,
+    })
+}
+
+// Private
+
+const NODE_TYPE_NORMAL: u8 = 77;
+const PROTOCOL_TCP_IPV4: u8 = 0;
+
+fn append_u16_len_bytes(byte_vec: &mut Vec<u8>, bytes: &[u8]) {
+    byte_vec.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+    byte_vec.extend_from_slice(bytes);
+}
+
+/// `ERL_EPMD_PORT` overrides the default port, as it does for `erl`
+fn port() -> u16 {
+    env::var("ERL_EPMD_PORT")
+        .ok()
+        .and_then(|port_string| port_string.parse().ok())
+        .unwrap_or(DEFAULT_PORT)
+}
+
+fn unexpected_response(tag: u8) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("unexpected port mapper response ({})", tag),
+    )
+}
+
+/// Requests are prefixed by their length as a 16-bit big endian unsigned integer
+fn write_request(stream: &mut TcpStream, request: &[u8]) -> io::Result<()> {
+    let mut byte_vec = Vec::with_capacity(2 + request.len());
+    append_u16_len_bytes(&mut byte_vec, request);
+
+    stream.write_all(&byte_vec)
+}
@@ -4,8 +4,11 @@ mod atom_utf8;
 mod big;
 mod binary;
 mod bit_binary;
+pub mod compressed;
 mod export;
 mod f64;
+mod float;
+mod function;
 mod i32;
 mod integer;
 mod isize;
@@ -14,8 +17,12 @@ mod map;
 mod new_float;
 mod new_function;
 mod new_pid;
+mod new_port;
+mod new_reference;
 mod newer_reference;
 mod pid;
+mod port;
+mod reference;
 mod sign;
 mod small_atom;
 mod small_atom_utf8;
@@ -25,7 +32,6 @@ pub mod term;
 mod tuple;
 mod u16;
 mod u32;
-mod u64;
 mod u8;
 pub mod version;
 
@@ -88,6 +94,7 @@ impl From<DecodeError> for InternalException {
 pub enum Tag {
     NewFloat = 70,
     BitBinary = 77,
+    Compressed = 80,
     AtomCacheReference = 82,
     NewPID = 88,
     NewPort = 89,
@@ -134,6 +141,7 @@ impl Display for Tag {
     }
 }
 
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum Pid {
     Local(LocalPid),
     External(ExternalPid),
@@ -169,7 +177,7 @@ impl Pid {
         Ok(pid)
     }
 
-    fn clone_to_process(&self, process: &Process) -> Term {
+    pub(crate) fn clone_to_process(&self, process: &Process) -> Term {
         match self {
             Pid::Local(local_pid) => local_pid.clone().into(),
             Pid::External(external_pid) => external_pid.clone_to_process(process),
@@ -188,13 +196,22 @@ impl Into<Creator> for Pid {
 
 // Private
 
+/// Atom cache references index the atom cache of a distribution header, which is only used when
+/// `DFLAG_DIST_HDR_ATOM_CACHE` is negotiated, which `crate::distribution` does not do.  Outside of
+/// distribution there is no atom cache to reference.
+fn atom_cache_reference_error() -> InternalException {
+    anyhow!("atom cache references are not supported without an atom cache").into()
+}
+
 fn decode_vec_term<'a>(
     process: &Process,
     safe: bool,
     bytes: &'a [u8],
     len: usize,
 ) -> InternalResult<(Vec<Term>, &'a [u8])> {
-    let mut element_vec: Vec<Term> = Vec::with_capacity(len);
+    // Each element is at least 1 byte, so a `len` that is more than the bytes is malformed, and
+    // must not be used to reserve memory
+    let mut element_vec: Vec<Term> = Vec::with_capacity(len.min(bytes.len()));
     let mut remaining_bytes = bytes;
 
     for _ in 0..len {
@@ -6,7 +6,7 @@ use anyhow::*;
 use liblumen_alloc::erts::exception::InternalResult;
 use liblumen_alloc::erts::term::prelude::*;
 
-use super::{atom_utf8, small_atom_utf8, u16, DecodeError, Tag};
+use super::{atom_cache_reference_error, atom_utf8, small_atom_utf8, u16, DecodeError, Tag};
 use crate::distribution::external_term_format::try_split_at;
 
 pub fn atom_bytes_to_term_bytes((atom, bytes): (Atom, &[u8])) -> (Term, &[u8]) {
@@ -54,7 +54,7 @@ pub fn decode_tagged<'a>(safe: bool, bytes: &'a [u8]) -> InternalResult<(Atom, &
 
     match tag {
         Tag::Atom => decode_atom(safe, after_tag_bytes),
-        Tag::AtomCacheReference => unimplemented!("{:?}", tag),
+        Tag::AtomCacheReference => Err(atom_cache_reference_error()),
         Tag::AtomUTF8 => atom_utf8::decode_atom(safe, after_tag_bytes),
         Tag::SmallAtomUTF8 => small_atom_utf8::decode_atom(safe, after_tag_bytes),
         _ => Err(DecodeError::UnexpectedTag { tag, backtrace: Backtrace::capture() }).context("An atom tag (ATOM_EXT, ATOM_CACHE_REF, ATOM_UTF8_EXT, or SMALL_ATOM_UTF8_EXT) is expected").map_err(|error| error.into()),
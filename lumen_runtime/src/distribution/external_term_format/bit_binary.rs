@@ -1,3 +1,5 @@
+use anyhow::*;
+
 use liblumen_alloc::erts::exception::InternalResult;
 use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::erts::Process;
@@ -9,7 +11,22 @@ pub fn decode<'a>(process: &Process, bytes: &'a [u8]) -> InternalResult<(Term, &
     let len_usize = len_u32 as usize;
 
     let (partial_byte_bit_len, after_partial_byte_bit_len_bytes) = u8::decode(after_len_bytes)?;
-    assert!(0 < partial_byte_bit_len);
+
+    if !(1..=8).contains(&partial_byte_bit_len) || len_usize == 0 {
+        return Err(anyhow!(
+            "bit binary has {} bytes and {} bits in its last byte, but needs at least 1 byte and 1 to 8 bits",
+            len_usize,
+            partial_byte_bit_len
+        )
+        .into());
+    }
+
+    // All 8 bits of the last byte are used in a binary
+    let (full_byte_len, partial_byte_bit_len) = if partial_byte_bit_len == 8 {
+        (len_usize, 0)
+    } else {
+        (len_usize - 1, partial_byte_bit_len)
+    };
 
     try_split_at(after_partial_byte_bit_len_bytes, len_usize).and_then(
         |(data_bytes, after_data_bytes)| {
@@ -18,7 +35,7 @@ pub fn decode<'a>(process: &Process, bytes: &'a [u8]) -> InternalResult<(Term, &
                 original,
                 0,
                 0,
-                len_usize - 1,
+                full_byte_len,
                 partial_byte_bit_len,
             )?;
 
@@ -0,0 +1,52 @@
+use anyhow::*;
+use flate2::{Decompress, FlushDecompress, Status};
+
+use liblumen_alloc::erts::exception::InternalResult;
+
+use super::u32;
+
+/// The uncompressed tagged term inside the `Compressed` envelope and the bytes after the envelope.
+///
+/// > UncompressedSize - A 4 byte big-endian unsigned integer.
+/// > Zlib-compressedData - Data that is compressed using zlib.
+pub fn decode(bytes: &[u8]) -> InternalResult<(Vec<u8>, &[u8])> {
+    let (uncompressed_size_u32, after_uncompressed_size_bytes) = u32::decode(bytes)?;
+    let uncompressed_size = uncompressed_size_u32 as usize;
+
+    // Don't trust `uncompressed_size` for the allocation when there isn't enough compressed data
+    // to ever reach it.
+    let capacity = uncompressed_size.min(
+        after_uncompressed_size_bytes
+            .len()
+            .saturating_mul(DEFLATE_MAX_RATIO),
+    );
+    let mut uncompressed_byte_vec = Vec::with_capacity(capacity);
+    let mut decompress = Decompress::new(true);
+
+    let status = decompress
+        .decompress_vec(
+            after_uncompressed_size_bytes,
+            &mut uncompressed_byte_vec,
+            FlushDecompress::Finish,
+        )
+        .context("compressed data is not zlib")?;
+
+    match status {
+        Status::StreamEnd if uncompressed_byte_vec.len() == uncompressed_size => {
+            let compressed_len = decompress.total_in() as usize;
+
+            Ok((
+                uncompressed_byte_vec,
+                &after_uncompressed_size_bytes[compressed_len..],
+            ))
+        }
+        _ => Err(anyhow!(
+            "compressed data does not uncompress to the uncompressed size ({})",
+            uncompressed_size
+        )
+        .into()),
+    }
+}
+
+// A DEFLATE stream cannot expand more than this
+const DEFLATE_MAX_RATIO: usize = 1032;
@@ -0,0 +1,26 @@
+use std::str;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception::InternalResult;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::Process;
+
+use super::try_split_at;
+
+/// `FLOAT_EXT`, which is the float formatted with `"%.20e"` and padded with NULs to 31 bytes
+pub fn decode<'a>(process: &Process, bytes: &'a [u8]) -> InternalResult<(Term, &'a [u8])> {
+    let (float_bytes, after_float_bytes) = try_split_at(bytes, LEN)?;
+
+    let float_str = str::from_utf8(float_bytes)
+        .context("float bytes are not ASCII")?
+        .trim_end_matches('\0');
+    let f: f64 = float_str
+        .parse()
+        .with_context(|| format!("float string ({:?}) is not a float", float_str))?;
+    let float = process.float(f)?;
+
+    Ok((float, after_float_bytes))
+}
+
+const LEN: usize = 31;
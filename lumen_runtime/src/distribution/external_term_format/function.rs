@@ -0,0 +1,50 @@
+use std::convert::TryInto;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception::InternalResult;
+use liblumen_alloc::erts::term::closure::{Index, OldUnique};
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::Process;
+
+use super::{atom, decode_vec_term, isize, u32, Pid};
+
+/// `FUN_EXT`, which older BEAM releases used for anonymous functions.
+///
+/// Unlike `NEW_FUN_EXT`, it has neither the arity nor the MD5 of the module, so they are decoded
+/// as `0` and zeroes, and there is no code to call, but the function can still be compared and sent
+/// back to the node that encoded it.
+pub fn decode<'a>(
+    process: &Process,
+    safe: bool,
+    bytes: &'a [u8],
+) -> InternalResult<(Term, &'a [u8])> {
+    let (num_free, after_num_free_bytes) = u32::decode(bytes)?;
+    let (creator, after_creator_bytes) = Pid::decode(safe, after_num_free_bytes)?;
+    let (module, after_module_bytes) = atom::decode_tagged(safe, after_creator_bytes)?;
+
+    let (index_isize, after_index_bytes) = isize::decode(after_module_bytes)?;
+    let index: Index = index_isize
+        .try_into()
+        .with_context(|| format!("index ({}) is negative", index_isize))?;
+
+    let (old_uniq, after_old_uniq_bytes) = isize::decode(after_index_bytes)?;
+    let old_unique = old_uniq as OldUnique;
+
+    let env_len: usize = num_free as usize;
+    let (env_vec, after_vec_term_bytes) =
+        decode_vec_term(process, safe, after_old_uniq_bytes, env_len)?;
+
+    let closure = process.anonymous_closure_with_env_from_slice(
+        module,
+        index,
+        old_unique,
+        Default::default(),
+        0,
+        None,
+        creator.into(),
+        &env_vec,
+    )?;
+
+    Ok((closure, after_vec_term_bytes))
+}
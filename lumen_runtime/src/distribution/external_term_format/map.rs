@@ -13,7 +13,10 @@ pub fn decode<'a>(
 ) -> InternalResult<(Term, &'a [u8])> {
     let (pair_len_u32, after_len_bytes) = u32::decode(bytes)?;
     let pair_len_usize = pair_len_u32 as usize;
-    let mut hash_map: HashMap<Term, Term> = HashMap::with_capacity(pair_len_usize);
+    // Each pair is at least 2 bytes, so a length that is more than the bytes is malformed, and must
+    // not be used to reserve memory
+    let mut hash_map: HashMap<Term, Term> =
+        HashMap::with_capacity(pair_len_usize.min(after_len_bytes.len() / 2));
     let mut remaining_bytes = after_len_bytes;
 
     for _ in 0..pair_len_usize {
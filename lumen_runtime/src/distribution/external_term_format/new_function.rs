@@ -1,5 +1,7 @@
 use std::convert::TryInto;
 
+use anyhow::*;
+
 use liblumen_alloc::erts::exception::InternalResult;
 use liblumen_alloc::erts::term::closure::OldUnique;
 use liblumen_alloc::erts::term::prelude::*;
@@ -23,7 +25,10 @@ pub fn decode<'a>(
     let (module, after_module_bytes) = atom::decode_tagged(safe, after_num_free_bytes)?;
 
     let (old_index, after_old_index_bytes) = isize::decode(after_module_bytes)?;
-    assert_eq!(old_index, index as isize);
+
+    if old_index != index as isize {
+        return Err(anyhow!("old index ({}) is not index ({})", old_index, index).into());
+    }
 
     let (old_uniq, after_old_uniq_bytes) = isize::decode(after_old_index_bytes)?;
     let old_unique = old_uniq as OldUnique;
@@ -34,10 +39,17 @@ pub fn decode<'a>(
     let (env_vec, after_vec_term_bytes) =
         decode_vec_term(process, safe, after_creator_bytes, env_len)?;
 
-    assert_eq!(
-        bytes.len() - after_vec_term_bytes.len(),
-        total_byte_len as usize
-    );
+    // `Size` includes itself
+    let byte_len = bytes.len() - after_vec_term_bytes.len();
+
+    if byte_len != total_byte_len as usize {
+        return Err(anyhow!(
+            "size ({}) is not byte length ({})",
+            total_byte_len,
+            byte_len
+        )
+        .into());
+    }
 
     let option_code = code::anonymous::get(&module, &index, &old_unique, &uniq, &arity);
 
@@ -0,0 +1,25 @@
+use std::mem;
+
+use liblumen_alloc::erts::exception::InternalResult;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::Process;
+
+use super::{arc_node, reference, try_split_at, u16, u8};
+
+pub fn decode<'a>(
+    process: &Process,
+    safe: bool,
+    bytes: &'a [u8],
+) -> InternalResult<(Term, &'a [u8])> {
+    let (u32_len_u16, after_len_bytes) = u16::decode(bytes)?;
+    let len_usize = (u32_len_u16 as usize) * mem::size_of::<u32>();
+
+    let (arc_node, after_node_bytes) = arc_node::decode(safe, after_len_bytes)?;
+    // TODO use creation to differentiate respawned nodes
+    let (_creation, after_creation_bytes) = u8::decode(after_node_bytes)?;
+
+    let (id_bytes, after_id_bytes) = try_split_at(after_creation_bytes, len_usize)?;
+    let reference = reference::id_bytes_to_term(process, arc_node, id_bytes)?;
+
+    Ok((reference, after_id_bytes))
+}
@@ -4,10 +4,7 @@ use liblumen_alloc::erts::exception::InternalResult;
 use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::erts::Process;
 
-use crate::distribution::nodes::node;
-
-use super::{arc_node, u16, u32, u64};
-use crate::distribution::external_term_format::try_split_at;
+use super::{arc_node, reference, try_split_at, u16, u32};
 
 pub fn decode<'a>(
     process: &Process,
@@ -21,17 +18,8 @@ pub fn decode<'a>(
     // TODO use creation to differentiate respawned nodes
     let (_creation, after_creation_bytes) = u32::decode(after_node_bytes)?;
 
-    try_split_at(after_creation_bytes, len_usize).and_then(|(id_bytes, after_id_bytes)| {
-        if arc_node == node::arc_node() {
-            let (scheduler_id_u32, after_scheduler_id_bytes) = u32::decode(id_bytes)?;
-            let (number_u64, _) = u64::decode(after_scheduler_id_bytes)?;
-
-            let reference =
-                process.reference_from_scheduler(scheduler_id_u32.into(), number_u64)?;
+    let (id_bytes, after_id_bytes) = try_split_at(after_creation_bytes, len_usize)?;
+    let reference = reference::id_bytes_to_term(process, arc_node, id_bytes)?;
 
-            Ok((reference, after_id_bytes))
-        } else {
-            unimplemented!()
-        }
-    })
+    Ok((reference, after_id_bytes))
 }
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception::InternalResult;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::{Node, Process};
+
+use crate::distribution::nodes::node;
+
+use super::{arc_node, u32, u8};
+
+/// `PORT_EXT`
+pub fn decode<'a>(
+    process: &Process,
+    safe: bool,
+    bytes: &'a [u8],
+) -> InternalResult<(Term, &'a [u8])> {
+    let (arc_node, after_node_bytes) = arc_node::decode(safe, bytes)?;
+    let (id, after_id_bytes) = u32::decode(after_node_bytes)?;
+    // TODO use creation to differentiate respawned nodes
+    let (_creation, after_creation_bytes) = u8::decode(after_id_bytes)?;
+
+    let port = id_to_term(process, arc_node, id)?;
+
+    Ok((port, after_creation_bytes))
+}
+
+pub fn id_to_term(process: &Process, arc_node: Arc<Node>, id: u32) -> InternalResult<Term> {
+    let number = id as usize;
+
+    if Port::NUMBER_MAX < number {
+        return Err(anyhow!(
+            "port id ({}) is greater than the maximum ({})",
+            number,
+            Port::NUMBER_MAX
+        )
+        .into());
+    }
+
+    let port = unsafe { Port::from_raw(number) };
+
+    let term = if arc_node == node::arc_node() {
+        port.encode().unwrap()
+    } else {
+        process.external_port(arc_node, port)?
+    };
+
+    Ok(term)
+}
@@ -0,0 +1,96 @@
+use std::mem;
+use std::sync::Arc;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception::InternalResult;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::{Node, Process};
+
+use crate::distribution::nodes::node;
+
+use super::{arc_node, try_split_at, u32, u8};
+
+/// `REFERENCE_EXT`, which has a single ID word
+pub fn decode<'a>(
+    process: &Process,
+    safe: bool,
+    bytes: &'a [u8],
+) -> InternalResult<(Term, &'a [u8])> {
+    let (arc_node, after_node_bytes) = arc_node::decode(safe, bytes)?;
+    let (id_bytes, after_id_bytes) = try_split_at(after_node_bytes, mem::size_of::<u32>())?;
+    // TODO use creation to differentiate respawned nodes
+    let (_creation, after_creation_bytes) = u8::decode(after_id_bytes)?;
+
+    let reference = id_bytes_to_term(process, arc_node, id_bytes)?;
+
+    Ok((reference, after_creation_bytes))
+}
+
+/// The reference of `arc_node` with the ID words in `id_bytes`.
+///
+/// References are encoded with the scheduler ID as the first word and the number as the next two.
+/// Other nodes also make references with up to 2 more ID words, such as their pid-refs, which are
+/// kept so that the references are the same when sent back.
+pub fn id_bytes_to_term(
+    process: &Process,
+    arc_node: Arc<Node>,
+    id_bytes: &[u8],
+) -> InternalResult<Term> {
+    let (scheduler_id_u32, after_scheduler_id_bytes) = u32::decode(id_bytes)?;
+
+    let (number, after_number_bytes): (u64, &[u8]) =
+        match after_scheduler_id_bytes.len() / mem::size_of::<u32>() {
+            0 => (0, after_scheduler_id_bytes),
+            1 => {
+                let (number_u32, after_number_bytes) = u32::decode(after_scheduler_id_bytes)?;
+
+                (number_u32 as u64, after_number_bytes)
+            }
+            _ => {
+                let (high_u32, after_high_bytes) = u32::decode(after_scheduler_id_bytes)?;
+                let (low_u32, after_low_bytes) = u32::decode(after_high_bytes)?;
+
+                (
+                    ((high_u32 as u64) << 32) | (low_u32 as u64),
+                    after_low_bytes,
+                )
+            }
+        };
+
+    let extra_ids_len = after_number_bytes.len() / mem::size_of::<u32>();
+
+    if ExternalReference::EXTRA_IDS_MAX < extra_ids_len {
+        return Err(anyhow!(
+            "reference has {} ID words, but at most {} are supported",
+            3 + extra_ids_len,
+            3 + ExternalReference::EXTRA_IDS_MAX
+        )
+        .into());
+    }
+
+    let mut extra_ids = Vec::with_capacity(extra_ids_len);
+    let mut after_extra_ids_bytes = after_number_bytes;
+
+    for _ in 0..extra_ids_len {
+        let (extra_id, after_extra_id_bytes) = u32::decode(after_extra_ids_bytes)?;
+        extra_ids.push(extra_id);
+        after_extra_ids_bytes = after_extra_id_bytes;
+    }
+
+    let reference = if arc_node == node::arc_node() {
+        if !extra_ids.is_empty() {
+            return Err(anyhow!(
+                "reference has {} ID words, but this node only makes references with 3",
+                3 + extra_ids_len
+            )
+            .into());
+        }
+
+        process.reference_from_scheduler(scheduler_id_u32.into(), number)?
+    } else {
+        process.external_reference(arc_node, scheduler_id_u32.into(), number, &extra_ids)?
+    };
+
+    Ok(reference)
+}
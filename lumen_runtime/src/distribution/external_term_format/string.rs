@@ -1,7 +1,3 @@
-use std::str;
-
-use anyhow::*;
-
 use liblumen_alloc::erts::exception::InternalResult;
 use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::erts::Process;
@@ -15,8 +11,9 @@ pub fn decode<'a>(process: &Process, bytes: &'a [u8]) -> InternalResult<(Term, &
 
     try_split_at(after_len_bytes, len_usize).and_then(
         |(character_bytes, after_characters_bytes)| {
-            let s = str::from_utf8(character_bytes).context("string is not UTF-8")?;
-            let charlist = process.charlist_from_str(s)?;
+            // Each byte is a character, so it is Latin-1 and not UTF-8
+            let s: String = character_bytes.iter().map(|byte| *byte as char).collect();
+            let charlist = process.charlist_from_str(&s)?;
 
             Ok((charlist, after_characters_bytes))
         },
@@ -0,0 +1,400 @@
+//! The handshake that connects two nodes sharing a cookie, as described in
+//! http://erlang.org/doc/apps/erts/erl_dist_protocol.html#distribution-handshake
+//!
+//! Only version 6 of the handshake, introduced in OTP 23 and mandatory since OTP 25, is supported.
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+
+/// The highest and lowest version of the handshake, as registered with the port mapper
+pub const VERSION: u16 = 6;
+
+/// The name, flags and creation of the node on the other side of the handshake
+pub struct Peer {
+    pub name: String,
+    pub flags: u64,
+    pub creation: u32,
+}
+
+/// How the accepting node continues the handshake, which depends on its connections to the peer
+pub enum Status {
+    /// Not connected to the peer
+    Ok,
+    /// Also connecting to the peer, but the peer's name is greater, so only this handshake
+    /// continues
+    OkSimultaneous,
+    /// Also connecting to the peer, and its own name is greater, so only its own handshake
+    /// continues
+    Nok,
+    /// Already connected to the peer, which decides whether this handshake replaces that
+    /// connection
+    Alive,
+}
+
+/// Accepts the handshake of the node that connected to `name` with `stream`, with the status that
+/// `status` returns for the name of the peer
+pub fn accept<S: FnOnce(&str) -> Status>(
+    stream: &mut TcpStream,
+    name: &str,
+    creation: u32,
+    cookie: &str,
+    status: S,
+) -> io::Result<Peer> {
+    // recv_name
+    let name_message = read_message(stream)?;
+    let (flags, peer_creation, peer_name) = match name_message.split_first() {
+        Some((&SEND_NAME_TAG, bytes)) if 14 <= bytes.len() => {
+            let flags = u64_at(bytes, 0);
+            let peer_creation = u32_at(bytes, 8);
+            let peer_name = name_at(bytes, 12)?;
+
+            (flags, peer_creation, peer_name)
+        }
+        _ => {
+            write_message(stream, b"snot_allowed")?;
+
+            return Err(invalid_data("expected send_name of version 6"));
+        }
+    };
+
+    // send_status
+    match status(&peer_name) {
+        Status::Ok => write_message(stream, b"sok")?,
+        Status::OkSimultaneous => write_message(stream, b"sok_simultaneous")?,
+        Status::Nok => {
+            write_message(stream, b"snok")?;
+
+            return Err(already_connecting(&peer_name));
+        }
+        Status::Alive => {
+            write_message(stream, b"salive")?;
+
+            // recv_status
+            match read_message(stream)?.split_first() {
+                Some((&STATUS_TAG, b"true")) => (),
+                Some((&STATUS_TAG, b"false")) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("{} is already connected", peer_name),
+                    ))
+                }
+                _ => return Err(invalid_data("expected status true or false")),
+            }
+        }
+    }
+
+    // send_challenge
+    let challenge: u32 = rand::random();
+    let mut challenge_message = vec![SEND_NAME_TAG];
+    challenge_message.extend_from_slice(&FLAGS.to_be_bytes());
+    challenge_message.extend_from_slice(&challenge.to_be_bytes());
+    challenge_message.extend_from_slice(&creation.to_be_bytes());
+    append_name(&mut challenge_message, name);
+    write_message(stream, &challenge_message)?;
+
+    // recv_challenge_reply
+    let reply_message = read_message(stream)?;
+    let peer_challenge = match reply_message.split_first() {
+        Some((&CHALLENGE_REPLY_TAG, bytes)) if bytes.len() == 20 => {
+            if bytes[4..] != digest(challenge, cookie) {
+                return Err(not_allowed(&peer_name));
+            }
+
+            u32_at(bytes, 0)
+        }
+        _ => return Err(invalid_data("expected challenge_reply")),
+    };
+
+    // send_challenge_ack
+    let mut ack_message = vec![CHALLENGE_ACK_TAG];
+    ack_message.extend_from_slice(&digest(peer_challenge, cookie));
+    write_message(stream, &ack_message)?;
+
+    Ok(Peer {
+        name: peer_name,
+        flags,
+        creation: peer_creation,
+    })
+}
+
+/// Connects `name` to the node that `stream` is connected to.
+///
+/// If the node is connecting to `name` at the same time and only its own handshake continues, the
+/// error is `io::ErrorKind::AlreadyExists`.
+pub fn connect(
+    stream: &mut TcpStream,
+    name: &str,
+    creation: u32,
+    cookie: &str,
+) -> io::Result<Peer> {
+    // send_name
+    let mut name_message = vec![SEND_NAME_TAG];
+    name_message.extend_from_slice(&FLAGS.to_be_bytes());
+    name_message.extend_from_slice(&creation.to_be_bytes());
+    append_name(&mut name_message, name);
+    write_message(stream, &name_message)?;
+
+    // recv_status
+    let status_message = read_message(stream)?;
+
+    match status_message.split_first() {
+        Some((&STATUS_TAG, b"ok")) | Some((&STATUS_TAG, b"ok_simultaneous")) => (),
+        // Only connections that are not established are connected, so the node's connection is
+        // stale and is replaced
+        Some((&STATUS_TAG, b"alive")) => write_message(stream, b"strue")?,
+        Some((&STATUS_TAG, b"nok")) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "node is connecting at the same time",
+            ))
+        }
+        Some((&STATUS_TAG, status)) => {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("handshake status ({})", String::from_utf8_lossy(status)),
+            ))
+        }
+        _ => return Err(invalid_data("expected status")),
+    }
+
+    // recv_challenge
+    let challenge_message = read_message(stream)?;
+    let (flags, challenge, peer_creation, peer_name) = match challenge_message.split_first() {
+        Some((&SEND_NAME_TAG, bytes)) if 18 <= bytes.len() => (
+            u64_at(bytes, 0),
+            u32_at(bytes, 8),
+            u32_at(bytes, 12),
+            name_at(bytes, 16)?,
+        ),
+        _ => return Err(invalid_data("expected challenge of version 6")),
+    };
+
+    // send_challenge_reply
+    let own_challenge: u32 = rand::random();
+    let mut reply_message = vec![CHALLENGE_REPLY_TAG];
+    reply_message.extend_from_slice(&own_challenge.to_be_bytes());
+    reply_message.extend_from_slice(&digest(challenge, cookie));
+    write_message(stream, &reply_message)?;
+
+    // recv_challenge_ack
+    let ack_message = read_message(stream)?;
+
+    match ack_message.split_first() {
+        Some((&CHALLENGE_ACK_TAG, peer_digest)) if peer_digest == digest(own_challenge, cookie) => {
+            Ok(Peer {
+                name: peer_name,
+                flags,
+                creation: peer_creation,
+            })
+        }
+        _ => Err(not_allowed(&peer_name)),
+    }
+}
+
+// Private
+
+const CHALLENGE_ACK_TAG: u8 = b'a';
+const CHALLENGE_REPLY_TAG: u8 = b'r';
+const SEND_NAME_TAG: u8 = b'N';
+const STATUS_TAG: u8 = b's';
+
+// DFLAG_* from
+// http://erlang.org/doc/apps/erts/erl_dist_protocol.html#distribution-flags
+const PUBLISHED: u64 = 0x1;
+const EXTENDED_REFERENCES: u64 = 0x4;
+const DIST_MONITOR: u64 = 0x8;
+const FUN_TAGS: u64 = 0x10;
+const DIST_MONITOR_NAME: u64 = 0x20;
+const NEW_FUN_TAGS: u64 = 0x80;
+const EXTENDED_PIDS_PORTS: u64 = 0x100;
+const EXPORT_PTR_TAG: u64 = 0x200;
+const BIT_BINARIES: u64 = 0x400;
+const NEW_FLOATS: u64 = 0x800;
+const SMALL_ATOM_TAGS: u64 = 0x4000;
+const UTF8_ATOMS: u64 = 0x10000;
+const MAP_TAG: u64 = 0x20000;
+const BIG_CREATION: u64 = 0x40000;
+const HANDSHAKE_23: u64 = 0x100_0000;
+const UNLINK_ID: u64 = 0x200_0000;
+const V4_NC: u64 = 0x4_0000_0000;
+
+/// The flags of the running node, which include all flags that are mandatory since OTP 26
+const FLAGS: u64 = PUBLISHED
+    | EXTENDED_REFERENCES
+    | DIST_MONITOR
+    | FUN_TAGS
+    | DIST_MONITOR_NAME
+    | NEW_FUN_TAGS
+    | EXTENDED_PIDS_PORTS
+    | EXPORT_PTR_TAG
+    | BIT_BINARIES
+    | NEW_FLOATS
+    | SMALL_ATOM_TAGS
+    | UTF8_ATOMS
+    | MAP_TAG
+    | BIG_CREATION
+    | HANDSHAKE_23
+    | UNLINK_ID
+    | V4_NC;
+
+fn already_connecting(peer_name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("already connecting to {}", peer_name),
+    )
+}
+
+fn append_name(byte_vec: &mut Vec<u8>, name: &str) {
+    byte_vec.extend_from_slice(&(name.len() as u16).to_be_bytes());
+    byte_vec.extend_from_slice(name.as_bytes());
+}
+
+/// MD5 of the cookie followed by the challenge in decimal
+fn digest(challenge: u32, cookie: &str) -> [u8; 16] {
+    let mut input = cookie.as_bytes().to_vec();
+    input.extend_from_slice(challenge.to_string().as_bytes());
+
+    md5::compute(input).0
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn name_at(bytes: &[u8], index: usize) -> io::Result<String> {
+    let len = u16::from_be_bytes([bytes[index], bytes[index + 1]]) as usize;
+
+    match bytes.get(index + 2..index + 2 + len) {
+        Some(name_bytes) => String::from_utf8(name_bytes.to_vec())
+            .map_err(|_| invalid_data("node name is not UTF-8")),
+        None => Err(invalid_data("node name is truncated")),
+    }
+}
+
+fn not_allowed(peer_name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!("{} does not share the cookie", peer_name),
+    )
+}
+
+/// Handshake messages are prefixed by their length as a 16-bit big endian unsigned integer
+fn read_message(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let mut len_bytes = [0; 2];
+    stream.read_exact(&mut len_bytes)?;
+
+    let mut message = vec![0; u16::from_be_bytes(len_bytes) as usize];
+    stream.read_exact(&mut message)?;
+
+    Ok(message)
+}
+
+fn u32_at(bytes: &[u8], index: usize) -> u32 {
+    let mut u32_bytes = [0; 4];
+    u32_bytes.copy_from_slice(&bytes[index..index + 4]);
+
+    u32::from_be_bytes(u32_bytes)
+}
+
+fn u64_at(bytes: &[u8], index: usize) -> u64 {
+    let mut u64_bytes = [0; 8];
+    u64_bytes.copy_from_slice(&bytes[index..index + 8]);
+
+    u64::from_be_bytes(u64_bytes)
+}
+
+fn write_message(stream: &mut TcpStream, message: &[u8]) -> io::Result<()> {
+    let mut byte_vec = Vec::with_capacity(2 + message.len());
+    byte_vec.extend_from_slice(&(message.len() as u16).to_be_bytes());
+    byte_vec.extend_from_slice(message);
+
+    stream.write_all(&byte_vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn with_same_cookie_connects() {
+        let (accepted, connected) = handshake("cookie", "cookie", Status::Ok);
+
+        let accepted_peer = accepted.unwrap();
+        assert_eq!(accepted_peer.name, "connecting@localhost");
+        assert_eq!(accepted_peer.creation, 2);
+
+        let connected_peer = connected.unwrap();
+        assert_eq!(connected_peer.name, "accepting@localhost");
+        assert_eq!(connected_peer.creation, 1);
+    }
+
+    #[test]
+    fn with_different_cookie_is_not_allowed() {
+        let (accepted, connected) = handshake("cookie", "other_cookie", Status::Ok);
+
+        assert_eq!(
+            accepted.err().unwrap().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+        assert!(connected.is_err());
+    }
+
+    #[test]
+    fn with_ok_simultaneous_status_connects() {
+        let (accepted, connected) = handshake("cookie", "cookie", Status::OkSimultaneous);
+
+        assert!(accepted.is_ok());
+        assert!(connected.is_ok());
+    }
+
+    #[test]
+    fn with_nok_status_does_not_connect() {
+        let (accepted, connected) = handshake("cookie", "cookie", Status::Nok);
+
+        assert_eq!(accepted.err().unwrap().kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(
+            connected.err().unwrap().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+    }
+
+    #[test]
+    fn with_alive_status_replaces_connection() {
+        let (accepted, connected) = handshake("cookie", "cookie", Status::Alive);
+
+        assert_eq!(accepted.unwrap().name, "connecting@localhost");
+        assert_eq!(connected.unwrap().name, "accepting@localhost");
+    }
+
+    fn handshake(
+        accepting_cookie: &'static str,
+        connecting_cookie: &'static str,
+        status: Status,
+    ) -> (io::Result<Peer>, io::Result<Peer>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let accepting = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            accept(
+                &mut stream,
+                "accepting@localhost",
+                1,
+                accepting_cookie,
+                |peer_name| {
+                    assert_eq!(peer_name, "connecting@localhost");
+
+                    status
+                },
+            )
+        });
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let connected = connect(&mut stream, "connecting@localhost", 2, connecting_cookie);
+
+        (accepting.join().unwrap(), connected)
+    }
+}
@@ -1,6 +1,7 @@
 pub mod node;
 
 use std::backtrace::Backtrace;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use hashbrown::HashMap;
@@ -20,14 +21,27 @@ pub fn atom_to_arc_node(atom: &Atom) -> Option<Arc<Node>> {
         .map(|ref_arc_node| ref_arc_node.clone())
 }
 
-pub fn try_atom_to_arc_node(atom: &Atom) -> Result<Arc<Node>, NodeNotFound> {
-    match atom_to_arc_node(atom) {
-        Some(arc_node) => Ok(arc_node),
-        None => Err(NodeNotFound::Name {
-            name: atom.clone(),
-            backtrace: Backtrace::capture(),
-        }),
+/// The node named `atom`, which is added if it is not known yet, as the pids, ports and
+/// references of a node can be decoded before connecting to it.  Its creation is unknown until it
+/// is connected to.
+pub fn atom_to_arc_node_or_insert(atom: &Atom) -> Arc<Node> {
+    if let Some(arc_node) = atom_to_arc_node(atom) {
+        return arc_node;
     }
+
+    let mut arc_node_by_id = RW_LOCK_ARC_NODE_BY_ID.write();
+    let mut arc_node_by_name = RW_LOCK_ARC_NODE_BY_NAME.write();
+
+    // Another thread may have added the node between the read and write locks
+    arc_node_by_name
+        .entry(*atom)
+        .or_insert_with(|| {
+            let arc_node = Arc::new(Node::new(next_id(), *atom, 0));
+            arc_node_by_id.insert(arc_node.id(), arc_node.clone());
+
+            arc_node
+        })
+        .clone()
 }
 
 pub fn id_to_arc_node(id: &usize) -> Option<Arc<Node>> {
@@ -47,8 +61,6 @@ pub fn try_id_to_arc_node(id: &usize) -> Result<Arc<Node>, NodeNotFound> {
     }
 }
 
-// TODO make non-test-only once distribution connection is implemented
-#[cfg(all(not(target_arch = "wasm32"), test))]
 pub fn insert(arc_node: Arc<Node>) {
     let id = arc_node.id();
     let name = arc_node.name();
@@ -76,10 +88,23 @@ pub fn insert(arc_node: Arc<Node>) {
         .unwrap_none();
 }
 
+/// The id for a `Node` that is not yet known.  Ids are never reused, so that an `ExternalPid` of
+/// a disconnected node never refers to the node's next incarnation.
+pub fn next_id() -> usize {
+    NEXT_ID.fetch_add(1, Ordering::SeqCst)
+}
+
+/// Changes the name of `arc_node`, which is only done to the running node when it becomes alive
+pub fn rename(arc_node: &Arc<Node>, name: Atom) {
+    let mut arc_node_by_name = RW_LOCK_ARC_NODE_BY_NAME.write();
+
+    arc_node_by_name.remove(&arc_node.name());
+    arc_node.set_name(name);
+    arc_node_by_name.insert(name, arc_node.clone());
+}
+
 #[derive(Debug, Error)]
 pub enum NodeNotFound {
-    #[error("No node with name ({name})")]
-    Name { name: Atom, backtrace: Backtrace },
     #[error("No node with id ({id})")]
     ID { id: usize, backtrace: Backtrace },
 }
@@ -100,6 +125,9 @@ impl From<NodeNotFound> for RuntimeException {
     }
 }
 
+// Starts after the id of the running node
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
 lazy_static! {
     static ref RW_LOCK_ARC_NODE_BY_ID: RwLock<HashMap<usize, Arc<Node>>> = {
         let mut hash_map = HashMap::new();
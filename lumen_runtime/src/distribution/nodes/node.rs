@@ -21,6 +21,17 @@ pub fn id() -> usize {
     ARC_NODE.id()
 }
 
+pub fn is_alive() -> bool {
+    atom() != dead_atom()
+}
+
+/// Makes the running node alive as `name` with the `creation` assigned by the port mapper when the
+/// distribution is started.
+pub fn set_alive(name: Atom, creation: u32) {
+    super::rename(&ARC_NODE, name);
+    ARC_NODE.set_creation(creation);
+}
+
 pub fn term() -> Term {
     atom().encode().unwrap()
 }
@@ -43,13 +43,14 @@ mod binary;
 pub mod binary_to_string;
 // `pub` or `examples/spawn-chain`
 pub mod code;
-mod config;
+pub mod config;
 mod context;
 mod distribution;
 pub mod future;
 mod logging;
 mod number;
 pub mod otp;
+mod port;
 pub mod process;
 mod proplist;
 // `pub` or `examples/spawn-chain`
@@ -67,8 +68,8 @@ mod term;
 #[cfg(test)]
 mod test;
 pub mod time;
-// Public so that external code can all `timer::expire` to expire timers
-mod timer;
+// `pub` for the `receive ... after` timeouts of compiled code and the interpreter
+pub mod timer;
 
 use self::config::Config;
 use self::logging::Logger;
@@ -107,7 +108,7 @@ cfg_if! {
 /// The main entry point for the runtime, it is invoked by the platform-specific shims found above
 pub fn main(name: &str, version: &str, argv: Vec<String>) -> anyhow::Result<()> {
     // Load configuration
-    let _config = Config::from_argv(name.to_string(), version.to_string(), argv)?;
+    let config = Config::from_argv(name.to_string(), version.to_string(), argv)?;
 
     // This bus is used to receive signals across threads in the system
     let mut bus: Bus<break_handler::Signal> = Bus::new(1);
@@ -119,6 +120,20 @@ pub fn main(name: &str, version: &str, argv: Vec<String>) -> anyhow::Result<()>
     // Start logger
     Logger::init(Level::Info).expect("Unexpected failure initializing logger");
 
+    // Start a scheduler thread for each logical CPU to run processes on
+    #[cfg(not(target_arch = "wasm32"))]
+    log::info!("started {} schedulers", scheduler::smp::runtime().len());
+
+    // Make the node alive, so that other nodes can connect to it
+    if let Some(node_name) = &config.name {
+        let cookie = match &config.cookie {
+            Some(cookie) => cookie.clone(),
+            None => distribution::cookie::home()?,
+        };
+
+        distribution::start(node_name, cookie)?;
+    }
+
     // TEMP: Blocking loop which waits for user input
     loop {
         let _ = rx1.recv()?;
@@ -9,6 +9,8 @@ mod exception;
 #[macro_use]
 mod integer;
 #[macro_use]
+mod native;
+#[macro_use]
 mod number;
 #[macro_use]
 mod support;
@@ -0,0 +1,25 @@
+/// Declares the modules of an OTP module's native functions, which must each be a
+/// `#[native_implemented_function]`, and lists them in `native_functions()` for
+/// `crate::code::native`.
+///
+/// Declaring and listing the modules in one place means a native function can't be left out of
+/// the table, while a module that isn't a native function fails to compile.
+macro_rules! native_functions {
+    ($($native_module:ident),* $(,)?) => {
+        $(pub mod $native_module;)*
+
+        /// The native functions of this module
+        pub(crate) fn native_functions() -> Vec<crate::code::native::NativeFunction> {
+            vec![
+                $(
+                    crate::code::native::NativeFunction::new(
+                        module,
+                        $native_module::function,
+                        $native_module::ARITY,
+                        $native_module::apply,
+                    ),
+                )*
+            ]
+        }
+    };
+}
@@ -1,193 +1,213 @@
 //! Mirrors [erlang](http://erlang::org/doc/man/erlang::html) module
 
-pub mod abs_1;
-pub mod add_2;
-pub mod and_2;
-pub mod andalso_2;
-pub mod append_element_2;
 pub mod apply_2;
 pub mod apply_3;
-pub mod are_equal_after_conversion_2;
-pub mod are_exactly_equal_2;
-pub mod are_exactly_not_equal_2;
-pub mod are_not_equal_after_conversion_2;
-pub mod atom_to_binary_2;
-pub mod atom_to_list_1;
-pub mod band_2;
 pub mod base;
-pub mod binary_part_2;
-pub mod binary_part_3;
-pub mod binary_to_atom_2;
-pub mod binary_to_existing_atom_2;
-pub mod binary_to_float_1;
-pub mod binary_to_integer_1;
-pub mod binary_to_integer_2;
-pub mod binary_to_list_1;
-pub mod binary_to_list_3;
-pub mod binary_to_term_1;
-pub mod binary_to_term_2;
-pub mod bit_size_1;
-pub mod bitstring_to_list_1;
-pub mod bnot_1;
-pub mod bor_2;
-pub mod bsl_2;
-pub mod bsr_2;
-pub mod bxor_2;
-pub mod byte_size_1;
-pub mod cancel_timer_1;
-pub mod cancel_timer_2;
-pub mod ceil_1;
 mod charlist_to_string;
-pub mod concatenate_2;
-pub mod convert_time_unit_3;
-pub mod date_0;
-pub mod delete_element_2;
-pub mod demonitor_1;
-pub mod demonitor_2;
-pub mod div_2;
-pub mod divide_2;
-pub mod element_2;
-pub mod erase_0;
-pub mod erase_1;
-pub mod error_1;
-pub mod error_2;
-pub mod exit_1;
-pub mod float_1;
-pub mod float_to_binary_1;
-pub mod float_to_binary_2;
-pub mod float_to_list_1;
-pub mod float_to_list_2;
 mod float_to_string;
-pub mod floor_1;
-pub mod function_exported_3;
-pub mod get_0;
-pub mod get_1;
-pub mod get_keys_0;
-pub mod get_keys_1;
-pub mod get_stacktrace_0;
-pub mod group_leader_0;
-pub mod group_leader_2;
-pub mod hd_1;
-pub mod insert_element_3;
-pub mod integer_to_binary_1;
-pub mod integer_to_binary_2;
-pub mod integer_to_list_1;
-pub mod integer_to_list_2;
+pub mod hibernate_3;
 mod integer_to_string;
 mod iolist_or_binary;
-pub mod iolist_size_1;
-pub mod iolist_to_binary_1;
-pub mod iolist_to_iovec_1;
-pub mod is_alive_0;
-pub mod is_atom_1;
-pub mod is_binary_1;
-pub mod is_bitstring_1;
-pub mod is_boolean_1;
-pub mod is_equal_or_less_than_2;
-pub mod is_float_1;
-pub mod is_function_1;
-pub mod is_function_2;
-pub mod is_greater_than_2;
-pub mod is_greater_than_or_equal_2;
-pub mod is_integer_1;
-pub mod is_less_than_2;
-pub mod is_list_1;
-pub mod is_map_1;
 pub mod is_map_key_2;
-pub mod is_number_1;
-pub mod is_pid_1;
-pub mod is_process_alive_1;
-pub mod is_record_2;
-pub mod is_record_3;
-pub mod is_reference_1;
-pub mod is_tuple_1;
-pub mod length_1;
-pub mod link_1;
-pub mod list_to_atom_1;
-pub mod list_to_binary_1;
-pub mod list_to_bitstring_1;
-pub mod list_to_existing_atom_1;
-pub mod list_to_float_1;
-pub mod list_to_integer_1;
-pub mod list_to_integer_2;
-pub mod list_to_pid_1;
 mod list_to_string;
-pub mod list_to_tuple_1;
-pub mod localtime_0;
-pub mod make_ref_0;
-pub mod make_tuple_2;
-pub mod make_tuple_3;
-pub mod map_get_2;
-pub mod map_size_1;
-pub mod max_2;
-pub mod min_2;
-pub mod monitor_2;
-pub mod monotonic_time_0;
-pub mod monotonic_time_1;
-pub mod multiply_2;
-pub mod negate_1;
-pub mod node_0;
-pub mod not_1;
-pub mod now_0;
-pub mod number_or_badarith_1;
 mod number_to_integer;
-pub mod or_2;
-pub mod orelse_2;
-pub mod process_flag_2;
-pub mod process_info_2;
-pub mod put_2;
-pub mod raise_3;
-pub mod read_timer_1;
-pub mod read_timer_2;
-pub mod register_2;
-pub mod registered_0;
-pub mod rem_2;
-pub mod round_1;
-pub mod self_0;
-pub mod send_2;
-pub mod send_3;
-pub mod send_after_3;
-pub mod send_after_4;
-pub mod setelement_3;
-pub mod size_1;
-pub mod spawn_1;
-pub mod spawn_3;
 mod spawn_apply_1;
 mod spawn_apply_3;
-pub mod spawn_link_1;
-pub mod spawn_link_3;
-pub mod spawn_monitor_1;
-pub mod spawn_monitor_3;
-pub mod spawn_opt_2;
-pub mod spawn_opt_4;
-pub mod split_binary_2;
-pub mod start_timer_3;
-pub mod start_timer_4;
 mod string_to_float;
 mod string_to_integer;
-pub mod subtract_2;
-pub mod subtract_list_2;
-pub mod system_time_0;
-pub mod system_time_1;
 mod term_to_binary;
-pub mod term_to_binary_1;
-pub mod throw_1;
-pub mod time_0;
-pub mod time_offset_0;
-pub mod time_offset_1;
-pub mod timestamp_0;
-pub mod tl_1;
-pub mod trunc_1;
-pub mod tuple_size_1;
-pub mod tuple_to_list_1;
 mod unique_integer;
-pub mod unique_integer_0;
-pub mod unique_integer_1;
-pub mod universaltime_0;
-pub mod unlink_1;
-pub mod unregister_1;
-pub mod whereis_1;
-pub mod xor_2;
+
+native_functions! {
+    abs_1,
+    add_2,
+    and_2,
+    andalso_2,
+    append_element_2,
+    are_equal_after_conversion_2,
+    are_exactly_equal_2,
+    are_exactly_not_equal_2,
+    are_not_equal_after_conversion_2,
+    atom_to_binary_2,
+    atom_to_list_1,
+    band_2,
+    binary_part_2,
+    binary_part_3,
+    binary_to_atom_2,
+    binary_to_existing_atom_2,
+    binary_to_float_1,
+    binary_to_integer_1,
+    binary_to_integer_2,
+    binary_to_list_1,
+    binary_to_list_3,
+    binary_to_term_1,
+    binary_to_term_2,
+    bit_size_1,
+    bitstring_to_list_1,
+    bnot_1,
+    bor_2,
+    bsl_2,
+    bsr_2,
+    bxor_2,
+    byte_size_1,
+    cancel_timer_1,
+    cancel_timer_2,
+    ceil_1,
+    concatenate_2,
+    convert_time_unit_3,
+    date_0,
+    delete_element_2,
+    demonitor_1,
+    demonitor_2,
+    div_2,
+    divide_2,
+    element_2,
+    erase_0,
+    erase_1,
+    error_1,
+    error_2,
+    exit_1,
+    exit_2,
+    float_1,
+    float_to_binary_1,
+    float_to_binary_2,
+    float_to_list_1,
+    float_to_list_2,
+    floor_1,
+    function_exported_3,
+    garbage_collect_0,
+    garbage_collect_1,
+    get_0,
+    get_1,
+    get_keys_0,
+    get_keys_1,
+    get_stacktrace_0,
+    group_leader_0,
+    group_leader_2,
+    hd_1,
+    insert_element_3,
+    integer_to_binary_1,
+    integer_to_binary_2,
+    integer_to_list_1,
+    integer_to_list_2,
+    iolist_size_1,
+    iolist_to_binary_1,
+    iolist_to_iovec_1,
+    is_alive_0,
+    is_atom_1,
+    is_binary_1,
+    is_bitstring_1,
+    is_boolean_1,
+    is_equal_or_less_than_2,
+    is_float_1,
+    is_function_1,
+    is_function_2,
+    is_greater_than_2,
+    is_greater_than_or_equal_2,
+    is_integer_1,
+    is_less_than_2,
+    is_list_1,
+    is_map_1,
+    is_number_1,
+    is_pid_1,
+    is_process_alive_1,
+    is_record_2,
+    is_record_3,
+    is_reference_1,
+    is_tuple_1,
+    length_1,
+    link_1,
+    list_to_atom_1,
+    list_to_binary_1,
+    list_to_bitstring_1,
+    list_to_existing_atom_1,
+    list_to_float_1,
+    list_to_integer_1,
+    list_to_integer_2,
+    list_to_pid_1,
+    list_to_tuple_1,
+    localtime_0,
+    make_ref_0,
+    make_tuple_2,
+    make_tuple_3,
+    map_get_2,
+    map_size_1,
+    max_2,
+    min_2,
+    monitor_2,
+    monotonic_time_0,
+    monotonic_time_1,
+    multiply_2,
+    negate_1,
+    node_0,
+    node_1,
+    nodes_0,
+    not_1,
+    now_0,
+    number_or_badarith_1,
+    open_port_2,
+    or_2,
+    orelse_2,
+    pid_to_list_1,
+    port_close_1,
+    port_command_2,
+    port_connect_2,
+    process_flag_2,
+    process_info_1,
+    process_info_2,
+    processes_0,
+    put_2,
+    raise_3,
+    read_timer_1,
+    read_timer_2,
+    register_2,
+    registered_0,
+    rem_2,
+    round_1,
+    self_0,
+    send_2,
+    send_3,
+    send_after_3,
+    send_after_4,
+    setelement_3,
+    size_1,
+    spawn_1,
+    spawn_3,
+    spawn_link_1,
+    spawn_link_3,
+    spawn_monitor_1,
+    spawn_monitor_3,
+    spawn_opt_2,
+    spawn_opt_4,
+    split_binary_2,
+    start_timer_3,
+    start_timer_4,
+    statistics_1,
+    subtract_2,
+    subtract_list_2,
+    system_info_1,
+    system_time_0,
+    system_time_1,
+    term_to_binary_1,
+    term_to_binary_2,
+    throw_1,
+    time_0,
+    time_offset_0,
+    time_offset_1,
+    timestamp_0,
+    tl_1,
+    trunc_1,
+    tuple_size_1,
+    tuple_to_list_1,
+    unique_integer_0,
+    unique_integer_1,
+    universaltime_0,
+    unlink_1,
+    unregister_1,
+    whereis_1,
+    xor_2,
+    yield_0,
+}
 
 use core::convert::TryInto;
 
@@ -1,10 +1,17 @@
-use proptest::prop_assert_eq;
-use proptest::strategy::Just;
+use proptest::arbitrary::any;
+use proptest::sample::Index;
+use proptest::strategy::{Just, Strategy};
+use proptest::test_runner::TestCaseResult;
+use proptest::{prop_assert, prop_assert_eq};
 
+use liblumen_alloc::erts::exception;
 use liblumen_alloc::erts::process::Process;
-use liblumen_alloc::erts::term::prelude::{Atom, Term};
+use liblumen_alloc::erts::term::prelude::{
+    AlignedBinary, Atom, Encode, Encoded, Port, Term, TypedTerm,
+};
 
 use crate::otp::erlang::binary_to_term_1::native;
+use crate::otp::erlang::term_to_binary_1;
 use crate::test::strategy;
 
 #[test]
@@ -169,6 +176,213 @@ fn with_binary_encoding_small_atom_utf8_returns_atom() {
     );
 }
 
+#[test]
+fn with_binary_encoding_float_returns_float() {
+    with_binary_returns_term(
+        // :erlang.term_to_binary(1.0, minor_version: 0)
+        vec![
+            131, 99, 49, 46, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48,
+            48, 101, 43, 48, 48, 0, 0, 0, 0, 0,
+        ],
+        |process| process.float(1.0).unwrap(),
+    );
+}
+
+#[test]
+fn with_binary_encoding_reference_returns_reference() {
+    with_binary_returns_term(
+        // REFERENCE_EXT for scheduler 1 on `nonode@nohost`
+        vec![
+            131, 101, 100, 0, 13, 110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115, 116,
+            0, 0, 0, 1, 0,
+        ],
+        |process| process.reference_from_scheduler(1.into(), 0).unwrap(),
+    );
+}
+
+#[test]
+fn with_binary_encoding_new_reference_returns_reference() {
+    with_binary_returns_term(
+        // NEW_REFERENCE_EXT for scheduler 1 and number 2 on `nonode@nohost`
+        vec![
+            131, 114, 0, 3, 100, 0, 13, 110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115,
+            116, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2,
+        ],
+        |process| process.reference_from_scheduler(1.into(), 2).unwrap(),
+    );
+}
+
+#[test]
+fn with_binary_encoding_newer_reference_with_5_id_words_returns_reference_with_all_id_words() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                strategy::term::binary::containing_bytes(
+                    // NEWER_REFERENCE_EXT of a pid-ref with ID words 1 to 5 on `node@external`
+                    vec![
+                        131, 90, 0, 5, 100, 0, 13, 110, 111, 100, 101, 64, 101, 120, 116, 101, 114,
+                        110, 97, 108, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4,
+                        0, 0, 0, 5,
+                    ],
+                    arc_process.clone(),
+                ),
+            )
+        },
+        |(arc_process, binary)| {
+            let reference = native(&arc_process, binary).unwrap();
+
+            match reference.decode().unwrap() {
+                TypedTerm::ExternalReference(external_reference) => {
+                    let scheduler_id_u32: u32 = external_reference.scheduler_id().into();
+
+                    prop_assert_eq!(scheduler_id_u32, 1);
+                    prop_assert_eq!(external_reference.number(), (2_u64 << 32) | 3);
+                    prop_assert_eq!(external_reference.extra_ids(), &[4, 5][..]);
+                }
+                typed_term => prop_assert!(false, "{:?} is not an external reference", typed_term),
+            }
+
+            // The extra ID words are kept when the reference is sent back
+            let reencoded = term_to_binary_1::native(&arc_process, reference).unwrap();
+
+            prop_assert_eq!(native(&arc_process, reencoded), Ok(reference));
+
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn with_binary_encoding_newer_reference_with_more_than_5_id_words_errors_badarg() {
+    with_binary_errors_badarg(
+        vec![
+            131, 90, 0, 6, 100, 0, 13, 110, 111, 100, 101, 64, 101, 120, 116, 101, 114, 110, 97,
+            108, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0, 5, 0, 0, 0,
+            6,
+        ],
+        "reference has 6 ID words, but at most 5 are supported",
+    );
+}
+
+#[test]
+fn with_binary_encoding_port_returns_port() {
+    with_binary_returns_term(
+        // PORT_EXT for port 5 on `nonode@nohost`
+        vec![
+            131, 102, 100, 0, 13, 110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115, 116,
+            0, 0, 0, 5, 0,
+        ],
+        |_| unsafe { Port::from_raw(5) }.encode().unwrap(),
+    );
+}
+
+#[test]
+fn with_binary_encoding_new_port_returns_port() {
+    with_binary_returns_term(
+        // NEW_PORT_EXT for port 5 on `nonode@nohost`
+        vec![
+            131, 89, 100, 0, 13, 110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115, 116, 0,
+            0, 0, 5, 0, 0, 0, 0,
+        ],
+        |_| unsafe { Port::from_raw(5) }.encode().unwrap(),
+    );
+}
+
+#[test]
+fn with_binary_encoding_compressed_returns_term() {
+    with_binary_returns_term(
+        // :erlang.term_to_binary(<<0::8000>>, [:compressed])
+        vec![
+            131, 80, 0, 0, 3, 237, 120, 156, 203, 101, 96, 96, 126, 193, 48, 10, 70, 193, 40, 24,
+            246, 0, 0, 71, 7, 1, 89,
+        ],
+        |process| process.binary_from_bytes(&[0; 1000]).unwrap(),
+    );
+}
+
+#[test]
+fn with_binary_encoding_compressed_with_wrong_uncompressed_size_errors_badarg() {
+    with_binary_errors_badarg(
+        vec![
+            131, 80, 0, 0, 3, 236, 120, 156, 203, 101, 96, 96, 126, 193, 48, 10, 70, 193, 40, 24,
+            246, 0, 0, 71, 7, 1, 89,
+        ],
+        "does not uncompress to the uncompressed size (1004)",
+    );
+}
+
+#[test]
+fn with_binary_encoding_atom_cache_reference_errors_badarg() {
+    with_binary_errors_badarg(
+        // ATOM_CACHE_REF without a distribution header
+        vec![131, 82, 0],
+        "atom cache references are not supported",
+    );
+}
+
+#[test]
+fn with_binary_encoding_bit_binary_with_more_than_8_bits_errors_badarg() {
+    with_binary_errors_badarg(
+        vec![131, 77, 0, 0, 0, 1, 9, 0],
+        "needs at least 1 byte and 1 to 8 bits",
+    );
+}
+
+#[test]
+fn with_random_bytes_returns_term_or_errors_badarg() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                proptest::collection::vec(any::<u8>(), 0..64).prop_map(move |mut byte_vec| {
+                    // With the version, so that the bytes are decoded as terms
+                    byte_vec.insert(0, 131);
+
+                    arc_process.binary_from_bytes(&byte_vec).unwrap()
+                }),
+            )
+        },
+        |(arc_process, binary)| prop_assert_term_or_badarg(native(&arc_process, binary)),
+    );
+}
+
+#[test]
+fn with_truncated_encoding_returns_term_or_errors_badarg() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                strategy::term(arc_process.clone()),
+                any::<Index>(),
+            )
+        },
+        |(arc_process, term, index)| {
+            let binary = term_to_binary_1::native(&arc_process, term).unwrap();
+            let byte_vec: Vec<u8> = match binary.decode().unwrap() {
+                TypedTerm::HeapBinary(heap_binary) => heap_binary.as_bytes().to_vec(),
+                TypedTerm::ProcBin(process_binary) => process_binary.as_bytes().to_vec(),
+                typed_term => panic!("{:?} is not a binary", typed_term),
+            };
+            let truncated_len = index.index(byte_vec.len());
+            let truncated = arc_process
+                .binary_from_bytes(&byte_vec[..truncated_len])
+                .unwrap();
+
+            prop_assert_term_or_badarg(native(&arc_process, truncated))
+        },
+    );
+}
+
+/// Malformed binaries can decode to any term or error with any source, as long as they don't panic
+fn prop_assert_term_or_badarg(result: exception::Result<Term>) -> TestCaseResult {
+    if result.is_err() {
+        prop_assert_badarg!(result, "");
+    }
+
+    Ok(())
+}
+
 fn with_binary_returns_term<T>(byte_vec: Vec<u8>, term: T)
 where
     T: Fn(&Process) -> Term,
@@ -187,3 +401,19 @@ where
         },
     );
 }
+
+fn with_binary_errors_badarg(byte_vec: Vec<u8>, expected_substring: &'static str) {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                strategy::term::binary::containing_bytes(byte_vec.clone(), arc_process.clone()),
+            )
+        },
+        |(arc_process, binary)| {
+            prop_assert_badarg!(native(&arc_process, binary), expected_substring);
+
+            Ok(())
+        },
+    );
+}
@@ -17,7 +17,7 @@ use liblumen_alloc::erts::term::prelude::*;
 use lumen_runtime_macros::native_implemented_function;
 
 use crate::binary::to_term::Options;
-use crate::distribution::external_term_format::{term, version};
+use crate::distribution::external_term_format::{compressed, term, version, Tag};
 
 macro_rules! maybe_aligned_maybe_binary_try_into_term {
     ($process:expr, $options:expr, $binary:expr, $ident:expr) => {
@@ -70,8 +70,24 @@ fn versioned_tagged_bytes_try_into_term(
     bytes: &[u8],
 ) -> exception::Result<Term> {
     let after_version_bytes = version::check(bytes)?;
-    let (term, after_term_bytes) =
-        term::decode_tagged(process, options.existing, after_version_bytes)?;
+    let (term, after_term_bytes) = match Tag::decode(after_version_bytes)? {
+        (Tag::Compressed, after_tag_bytes) => {
+            let (uncompressed_bytes, after_compressed_bytes) = compressed::decode(after_tag_bytes)?;
+            let (term, after_term_bytes) =
+                term::decode_tagged(process, options.existing, &uncompressed_bytes)?;
+
+            if !after_term_bytes.is_empty() {
+                return Err(anyhow!(
+                    "compressed data has {} bytes after the term",
+                    after_term_bytes.len()
+                )
+                .into());
+            }
+
+            (term, after_compressed_bytes)
+        }
+        _ => term::decode_tagged(process, options.existing, after_version_bytes)?,
+    };
 
     if options.used {
         let used_byte_len = bytes.len() - after_term_bytes.len();
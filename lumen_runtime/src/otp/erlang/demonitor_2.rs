@@ -15,6 +15,7 @@ use liblumen_alloc::erts::term::prelude::*;
 
 use lumen_runtime_macros::native_implemented_function;
 
+use crate::distribution;
 use crate::otp::erlang::demonitor_2::options::Options;
 use crate::process::monitor::is_down;
 use crate::registry::pid_to_process;
@@ -34,7 +35,7 @@ pub(in crate::otp::erlang) fn demonitor(
     reference: &Reference,
     Options { flush, info }: Options,
 ) -> exception::Result<Term> {
-    match monitoring_process.demonitor(reference) {
+    let demonitored = match monitoring_process.demonitor(reference) {
         Some(monitored_pid) => {
             match pid_to_process(&monitored_pid) {
                 Some(monitored_arc_proces) => match monitored_arc_proces.demonitored(reference) {
@@ -44,25 +45,28 @@ pub(in crate::otp::erlang) fn demonitor(
                 None => (),
             }
 
-            if flush {
-                let flushed = self::flush(monitoring_process, reference);
-
-                if info && flushed {
-                    Ok(false.into())
-                } else {
-                    Ok(true.into())
-                }
-            } else {
-                Ok(true.into())
-            }
+            true
         }
-        None => {
-            if info {
+        // Monitors of processes on other nodes are kept by the connection to their node
+        None => distribution::demonitor(monitoring_process, reference),
+    };
+
+    if demonitored {
+        if flush {
+            let flushed = self::flush(monitoring_process, reference);
+
+            if info && flushed {
                 Ok(false.into())
             } else {
                 Ok(true.into())
             }
+        } else {
+            Ok(true.into())
         }
+    } else if info {
+        Ok(false.into())
+    } else {
+        Ok(true.into())
     }
 }
 
@@ -0,0 +1,98 @@
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{atom, exit};
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::distribution;
+use crate::port;
+use crate::process;
+use crate::registry::pid_to_process;
+
+#[native_implemented_function(exit/2)]
+pub fn native(process: &Process, pid_or_port: Term, reason: Term) -> exception::Result<Term> {
+    match pid_or_port.decode()? {
+        TypedTerm::Pid(pid) => {
+            if pid == process.pid() {
+                exit_self(process, reason)
+            } else {
+                // Like the BEAM, exit signals to processes that are not alive are ignored
+                if let Some(pid_arc_process) = pid_to_process(&pid) {
+                    process::exit_signal(
+                        process,
+                        &pid_arc_process,
+                        reason,
+                        anyhow!("exit from {}", process).into(),
+                    );
+                }
+
+                Ok(true.into())
+            }
+        }
+        // A port closes on any exit signal, except `normal`, as ports don't trap exits.  Its linked
+        // processes are then sent an exit signal with the same reason, except `kill`, which, like
+        // for processes, becomes `killed`.
+        TypedTerm::Port(port_port) => {
+            if reason != atom!("normal") {
+                let port_reason = if reason == atom!("kill") {
+                    atom!("killed")
+                } else {
+                    reason
+                };
+
+                port::exit(
+                    port_port,
+                    port_reason,
+                    anyhow!("exit from {}", process).into(),
+                );
+            }
+
+            Ok(true.into())
+        }
+        // Like the BEAM, exit signals to nodes that can't be connected to are dropped
+        TypedTerm::ExternalPid(external_pid) => {
+            distribution::exit(process, &external_pid, reason);
+
+            Ok(true.into())
+        }
+        // Like the BEAM, exit signals can't be sent to ports on other nodes
+        TypedTerm::ExternalPort(_) => Err(anyhow!(
+            "pid_or_port ({}) is a port on another node, which can't be sent exit signals",
+            pid_or_port
+        )
+        .into()),
+        _ => Err(TypeError)
+            .context(format!(
+                "pid_or_port ({}) is neither a pid nor a port",
+                pid_or_port
+            ))
+            .map_err(From::from),
+    }
+}
+
+/// An exit signal to the calling process takes effect immediately, so unlike `exit_signal`, the
+/// exit is returned to stop the rest of the calling code from running.  Unlike other processes,
+/// `normal` exits the calling process if it does not trap exits.
+fn exit_self(process: &Process, reason: Term) -> exception::Result<Term> {
+    if reason == atom!("kill") {
+        Err(exit!(atom!("killed"), anyhow!("exit(self(), kill)").into()).into())
+    } else if process.traps_exit() {
+        let exit_message =
+            process.tuple_from_slice(&[atom!("EXIT"), process.pid_term(), reason])?;
+        process.send_from_self(exit_message);
+
+        Ok(true.into())
+    } else {
+        Err(exit!(reason, anyhow!("exit(self(), {})", reason).into()).into())
+    }
+}
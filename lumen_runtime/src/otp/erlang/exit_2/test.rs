@@ -0,0 +1,163 @@
+mod with_linked_processes;
+
+use std::sync::Arc;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::process::{Process, ProcessFlags, Status};
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{atom, exit};
+
+use crate::otp::erlang::exit_2::native;
+use crate::process;
+use crate::scheduler::{with_process, with_process_arc, Scheduler};
+use crate::test::{external_arc_node, has_message};
+
+#[test]
+fn without_pid_or_port_errors_badarg() {
+    with_process(|process| {
+        assert_badarg!(
+            native(process, atom!("pid"), atom!("normal")),
+            "is neither a pid nor a port"
+        );
+    });
+}
+
+#[test]
+fn with_external_port_errors_badarg() {
+    with_process(|process| {
+        let external_port = process
+            .external_port(external_arc_node(), Port::next())
+            .unwrap();
+
+        assert_badarg!(
+            native(process, external_port, atom!("normal")),
+            "is a port on another node"
+        );
+    });
+}
+
+#[test]
+fn with_non_existent_pid_returns_true() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, Pid::next_term(), atom!("abnormal")),
+            Ok(true.into())
+        );
+    });
+}
+
+#[test]
+fn with_self_and_kill_exits_killed_even_if_trapping_exits() {
+    with_process(|process| {
+        process.set_flags(ProcessFlags::TrapExit);
+
+        assert_eq!(
+            native(process, process.pid_term(), atom!("kill")),
+            Err(exit!(atom!("killed"), anyhow!("Test").into()).into())
+        );
+    });
+}
+
+#[test]
+fn with_self_and_normal_exits_normal() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, process.pid_term(), atom!("normal")),
+            Err(exit!(atom!("normal"), anyhow!("Test").into()).into())
+        );
+    });
+}
+
+#[test]
+fn with_self_trapping_exits_sends_exit_message() {
+    with_process(|process| {
+        process.set_flags(ProcessFlags::TrapExit);
+        let reason = atom!("abnormal");
+
+        assert_eq!(native(process, process.pid_term(), reason), Ok(true.into()));
+
+        assert!(has_message(
+            process,
+            process
+                .tuple_from_slice(&[atom!("EXIT"), process.pid_term(), reason])
+                .unwrap()
+        ));
+    });
+}
+
+#[test]
+fn with_other_process_and_normal_does_not_exit_process() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = process::test(&arc_process);
+
+        assert_eq!(
+            native(&arc_process, other_arc_process.pid_term(), atom!("normal")),
+            Ok(true.into())
+        );
+
+        assert!(Scheduler::current().run_through(&other_arc_process));
+
+        assert!(!other_arc_process.is_exiting());
+    });
+}
+
+#[test]
+fn with_other_process_and_abnormal_exits_process_with_reason() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = process::test(&arc_process);
+        let reason = atom!("abnormal");
+
+        assert_eq!(
+            native(&arc_process, other_arc_process.pid_term(), reason),
+            Ok(true.into())
+        );
+
+        assert_eq!(exit_reason(&other_arc_process), Some(reason));
+        assert!(!arc_process.is_exiting());
+    });
+}
+
+#[test]
+fn with_other_process_trapping_exits_sends_exit_message() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = process::test(&arc_process);
+        other_arc_process.set_flags(ProcessFlags::TrapExit);
+        let reason = atom!("normal");
+
+        assert_eq!(
+            native(&arc_process, other_arc_process.pid_term(), reason),
+            Ok(true.into())
+        );
+
+        assert!(!other_arc_process.is_exiting());
+        assert!(has_message(
+            &other_arc_process,
+            other_arc_process
+                .tuple_from_slice(&[atom!("EXIT"), arc_process.pid_term(), reason])
+                .unwrap()
+        ));
+    });
+}
+
+#[test]
+fn with_other_process_trapping_exits_and_kill_exits_process_killed() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = process::test(&arc_process);
+        other_arc_process.set_flags(ProcessFlags::TrapExit);
+
+        assert_eq!(
+            native(&arc_process, other_arc_process.pid_term(), atom!("kill")),
+            Ok(true.into())
+        );
+
+        assert_eq!(exit_reason(&other_arc_process), Some(atom!("killed")));
+    });
+}
+
+pub fn exit_reason(process: &Process) -> Option<Term> {
+    match *process.status.read() {
+        Status::Exiting(ref exception) => exception.reason(),
+        _ => None,
+    }
+}
@@ -0,0 +1,98 @@
+use super::*;
+
+#[test]
+fn with_abnormal_exits_linked_chain_with_reason() {
+    with_process_arc(|arc_process| {
+        let (first, second, third) = linked_chain(&arc_process);
+        let reason = atom!("abnormal");
+
+        assert_eq!(
+            native(&arc_process, first.pid_term(), reason),
+            Ok(true.into())
+        );
+
+        assert!(Scheduler::current().run_through(&first));
+        assert!(Scheduler::current().run_through(&second));
+
+        assert_eq!(exit_reason(&first), Some(reason));
+        assert_eq!(exit_reason(&second), Some(reason));
+        assert_eq!(exit_reason(&third), Some(reason));
+    });
+}
+
+#[test]
+fn with_kill_exits_linked_chain_killed() {
+    with_process_arc(|arc_process| {
+        let (first, second, third) = linked_chain(&arc_process);
+
+        assert_eq!(
+            native(&arc_process, first.pid_term(), atom!("kill")),
+            Ok(true.into())
+        );
+
+        assert!(Scheduler::current().run_through(&first));
+        assert!(Scheduler::current().run_through(&second));
+
+        assert_eq!(exit_reason(&first), Some(atom!("killed")));
+        assert_eq!(exit_reason(&second), Some(atom!("killed")));
+        assert_eq!(exit_reason(&third), Some(atom!("killed")));
+    });
+}
+
+#[test]
+fn with_normal_does_not_exit_linked_chain() {
+    with_process_arc(|arc_process| {
+        let (first, second, third) = linked_chain(&arc_process);
+
+        assert_eq!(
+            native(&arc_process, first.pid_term(), atom!("normal")),
+            Ok(true.into())
+        );
+
+        assert!(Scheduler::current().run_through(&first));
+
+        assert!(!first.is_exiting());
+        assert!(!second.is_exiting());
+        assert!(!third.is_exiting());
+    });
+}
+
+#[test]
+fn with_linked_process_trapping_exits_stops_exit_at_that_process() {
+    with_process_arc(|arc_process| {
+        let (first, second, third) = linked_chain(&arc_process);
+        second.set_flags(ProcessFlags::TrapExit);
+
+        assert_eq!(
+            native(&arc_process, first.pid_term(), atom!("kill")),
+            Ok(true.into())
+        );
+
+        assert!(Scheduler::current().run_through(&first));
+
+        assert_eq!(exit_reason(&first), Some(atom!("killed")));
+        assert!(!second.is_exiting());
+        assert!(has_message(
+            &second,
+            second
+                .tuple_from_slice(&[atom!("EXIT"), first.pid_term(), atom!("killed")])
+                .unwrap()
+        ));
+
+        assert!(Scheduler::current().run_through(&second));
+
+        assert!(!third.is_exiting());
+    });
+}
+
+/// `first` linked to `second` linked to `third`
+fn linked_chain(parent_process: &Process) -> (Arc<Process>, Arc<Process>, Arc<Process>) {
+    let first = process::test(parent_process);
+    let second = process::test(parent_process);
+    let third = process::test(parent_process);
+
+    first.link(&second);
+    second.link(&third);
+
+    (first, second, third)
+}
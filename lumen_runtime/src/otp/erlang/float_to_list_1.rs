@@ -13,7 +13,7 @@ use lumen_runtime_macros::native_implemented_function;
 
 use crate::otp::erlang::float_to_string::float_to_string;
 
-#[native_implemented_function(float_to_binary/1)]
+#[native_implemented_function(float_to_list/1)]
 pub fn native(process: &Process, float: Term) -> exception::Result<Term> {
     float_to_string(float, Default::default())
         .map_err(|error| error.into())
@@ -0,0 +1,35 @@
+use std::convert::TryInto;
+
+use liblumen_alloc::erts::process::gc;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::garbage_collect_0::native;
+use crate::scheduler::with_process;
+
+#[test]
+fn returns_true_after_collecting() {
+    with_process(|process| {
+        let (collections_before, _) = gc::statistics();
+
+        assert_eq!(native(process), Ok(true.into()));
+
+        let (collections_after, _) = gc::statistics();
+
+        assert!(collections_before < collections_after);
+    });
+}
+
+#[test]
+fn keeps_stack_terms_live() {
+    with_process(|process| {
+        let list = process.charlist_from_str("live").unwrap();
+        process.stack_push(list).unwrap();
+
+        assert_eq!(native(process), Ok(true.into()));
+
+        let moved_cons: Boxed<Cons> = process.stack_peek(1).unwrap().try_into().unwrap();
+        let moved_string: String = moved_cons.try_into().unwrap();
+
+        assert_eq!(moved_string, "live");
+    });
+}
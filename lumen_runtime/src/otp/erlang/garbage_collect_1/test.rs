@@ -0,0 +1,60 @@
+use proptest::strategy::Just;
+
+use liblumen_alloc::erts::process::ProcessFlags;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::garbage_collect_1::native;
+use crate::process;
+use crate::scheduler::{with_process_arc, Scheduler};
+use crate::test::strategy;
+
+#[test]
+fn without_pid_errors_badarg() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                strategy::term::is_not_pid(arc_process.clone()),
+            )
+        },
+        |(arc_process, pid)| {
+            prop_assert_is_not_local_pid!(native(&arc_process, pid), pid);
+
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn with_self_returns_true() {
+    with_process_arc(|arc_process| {
+        assert_eq!(
+            native(&arc_process, arc_process.pid_term()),
+            Ok(true.into())
+        );
+    });
+}
+
+#[test]
+fn without_process_returns_false() {
+    with_process_arc(|arc_process| {
+        assert_eq!(native(&arc_process, Pid::next_term()), Ok(false.into()));
+    });
+}
+
+#[test]
+fn with_other_process_returns_true_and_collects_before_it_next_runs() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = process::test(&arc_process);
+
+        assert_eq!(
+            native(&arc_process, other_arc_process.pid_term()),
+            Ok(true.into())
+        );
+        assert!(other_arc_process.are_flags_set(ProcessFlags::ForceGC));
+
+        assert!(Scheduler::current().run_through(&other_arc_process));
+
+        assert!(!other_arc_process.are_flags_set(ProcessFlags::ForceGC));
+    });
+}
@@ -0,0 +1,96 @@
+mod label_1;
+
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::sync::Arc;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::code::stack::frame::{Frame, Placement};
+use liblumen_alloc::erts::process::{code, Process};
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::ModuleFunctionArity;
+
+use crate::process;
+
+pub fn place_frame_with_arguments(
+    process: &Process,
+    placement: Placement,
+    module: Term,
+    function: Term,
+    arguments: Term,
+) -> code::Result {
+    process.stack_push(arguments)?;
+    process.stack_push(function)?;
+    process.stack_push(module)?;
+    process.place_frame(frame(), placement);
+
+    Ok(())
+}
+
+// Private
+
+/// Throws away the call stack, so that `module:function(arguments...)` is the only call left, and
+/// shrinks the heap to only the terms still live.  The process then waits in `label_1` until it
+/// receives a message.
+fn code(arc_process: &Arc<Process>) -> code::Result {
+    arc_process.reduce();
+
+    let module = arc_process.stack_peek(1).unwrap();
+    let function = arc_process.stack_peek(2).unwrap();
+    let arguments = arc_process.stack_peek(3).unwrap();
+
+    if let Err(exception) = check(module, function, arguments) {
+        return code::result_from_exception(arc_process, 3, exception);
+    }
+
+    arc_process.stack_popn(arc_process.stack_used());
+
+    while 0 < arc_process.code_stack_len() {
+        arc_process.pop_code_stack();
+    }
+
+    label_1::place_frame_with_arguments(arc_process, Placement::Push, module, function, arguments)?;
+
+    // `module`, `function` and `arguments` are only on the stack now, which is a root of the
+    // collection, so `label_1` reads where the collection moved them.
+    match process::garbage_collect(arc_process) {
+        Ok(()) => Process::call_code(arc_process),
+        Err(exception) => code::result_from_exception(arc_process, 0, exception),
+    }
+}
+
+fn check(module: Term, function: Term, arguments: Term) -> exception::Result<()> {
+    term_try_into_atom!(module)?;
+    term_try_into_atom!(function)?;
+
+    if arguments.decode()?.is_proper_list() {
+        Ok(())
+    } else {
+        Err(TypeError)
+            .with_context(|| format!("arguments ({}) must be a proper list", arguments))
+            .map_err(From::from)
+    }
+}
+
+fn frame() -> Frame {
+    Frame::new(module_function_arity(), code)
+}
+
+fn function() -> Atom {
+    Atom::try_from_str("hibernate").unwrap()
+}
+
+fn module_function_arity() -> Arc<ModuleFunctionArity> {
+    Arc::new(ModuleFunctionArity {
+        module: super::module(),
+        function: function(),
+        arity: 3,
+    })
+}
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use liblumen_alloc::erts::process::code::stack::frame::{Frame, Placement};
+use liblumen_alloc::erts::process::{code, Process};
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::apply_3;
+
+/// ```elixir
+/// # label 1
+/// # pushed to stack: (module, function, arguments)
+/// # returned from call: N/A
+/// # full stack: (module, function, arguments)
+/// # returns: value
+/// receive # waits for a message without taking it out of the mailbox
+/// apply(module, function, arguments)
+/// ```
+pub fn place_frame_with_arguments(
+    process: &Process,
+    placement: Placement,
+    module: Term,
+    function: Term,
+    arguments: Term,
+) -> code::Result {
+    assert!(module.is_atom());
+    assert!(function.is_atom());
+    assert!(
+        arguments.is_list(),
+        "arguments ({:?}) are not a list",
+        arguments
+    );
+    process.stack_push(arguments)?;
+    process.stack_push(function)?;
+    process.stack_push(module)?;
+    process.place_frame(frame(), placement);
+
+    Ok(())
+}
+
+// Private
+
+fn code(arc_process: &Arc<Process>) -> code::Result {
+    arc_process.reduce();
+
+    // Stays on this frame, so that it is run again when a message wakes the process
+    if arc_process.wait_for_message() {
+        return Ok(());
+    }
+
+    let module = arc_process.stack_peek(1).unwrap();
+    let function = arc_process.stack_peek(2).unwrap();
+    let arguments = arc_process.stack_peek(3).unwrap();
+
+    arc_process.stack_popn(3);
+
+    apply_3::place_frame_with_arguments(
+        arc_process,
+        Placement::Replace,
+        module,
+        function,
+        arguments,
+    )?;
+
+    Process::call_code(arc_process)
+}
+
+fn frame() -> Frame {
+    Frame::new(super::module_function_arity(), code)
+}
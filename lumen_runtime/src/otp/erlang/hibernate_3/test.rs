@@ -0,0 +1,121 @@
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::process::code::stack::frame::Placement;
+use liblumen_alloc::erts::process::Status;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::{self, hibernate_3};
+use crate::process;
+use crate::scheduler::{with_process_arc, Scheduler};
+
+#[test]
+fn without_atom_module_exits_with_badarg() {
+    with_process_arc(|arc_process| {
+        let module = arc_process.integer(0).unwrap();
+
+        hibernate_3::place_frame_with_arguments(
+            &arc_process,
+            Placement::Replace,
+            module,
+            atom!("self"),
+            Term::NIL,
+        )
+        .unwrap();
+
+        assert!(Scheduler::current().run_through(&arc_process));
+
+        match *arc_process.status.read() {
+            Status::Exiting(ref exception) => {
+                assert_eq!(exception.reason(), Some(atom!("badarg")));
+            }
+            ref status => panic!("Process status ({:?}) is not exiting", status),
+        }
+    });
+}
+
+#[test]
+fn without_proper_list_arguments_exits_with_badarg() {
+    with_process_arc(|arc_process| {
+        let arguments = arc_process.cons(atom!("first"), atom!("tail")).unwrap();
+
+        hibernate_3::place_frame_with_arguments(
+            &arc_process,
+            Placement::Replace,
+            atom!("erlang"),
+            atom!("self"),
+            arguments,
+        )
+        .unwrap();
+
+        assert!(Scheduler::current().run_through(&arc_process));
+
+        match *arc_process.status.read() {
+            Status::Exiting(ref exception) => {
+                assert_eq!(exception.reason(), Some(atom!("badarg")));
+            }
+            ref status => panic!("Process status ({:?}) is not exiting", status),
+        }
+    });
+}
+
+#[test]
+fn without_message_waits_with_only_the_call_left() {
+    with_process_arc(|arc_process| {
+        hibernate_3::place_frame_with_arguments(
+            &arc_process,
+            Placement::Replace,
+            atom!("erlang"),
+            atom!("self"),
+            Term::NIL,
+        )
+        .unwrap();
+
+        assert!(Scheduler::current().run_through(&arc_process));
+
+        assert_eq!(*arc_process.status.read(), Status::Waiting);
+        assert_eq!(arc_process.code_stack_len(), 1);
+        // module, function, and arguments
+        assert_eq!(arc_process.stack_used(), 3);
+    });
+}
+
+#[test]
+fn with_message_calls_module_function_with_arguments_without_receiving_message() {
+    with_process_arc(|arc_process| {
+        erlang::self_0::export();
+
+        hibernate_3::place_frame_with_arguments(
+            &arc_process,
+            Placement::Replace,
+            atom!("erlang"),
+            atom!("self"),
+            Term::NIL,
+        )
+        .unwrap();
+
+        let scheduler = Scheduler::current();
+
+        assert!(scheduler.run_through(&arc_process));
+        assert_eq!(*arc_process.status.read(), Status::Waiting);
+
+        let sender_arc_process = process::test(&arc_process);
+        let message = atom!("message");
+
+        assert_eq!(
+            erlang::send_2::native(&sender_arc_process, arc_process.pid_term(), message),
+            Ok(message)
+        );
+        assert_eq!(*arc_process.status.read(), Status::Runnable);
+
+        assert!(scheduler.run_through(&arc_process));
+
+        // `self/0` returned to the empty call stack, so the process exited `normal`
+        match *arc_process.status.read() {
+            Status::Exiting(ref exception) => {
+                assert_eq!(exception.reason(), Some(atom!("normal")));
+            }
+            ref status => panic!("Process status ({:?}) is not exiting", status),
+        }
+
+        assert_eq!(arc_process.mailbox.lock().borrow().len(), 1);
+    });
+}
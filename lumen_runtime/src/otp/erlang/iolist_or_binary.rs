@@ -48,6 +48,12 @@ pub fn native(
 }
 
 pub fn to_binary(process: &Process, name: &'static str, value: Term) -> exception::Result<Term> {
+    let byte_vec = to_bytes(name, value)?;
+
+    Ok(process.binary_from_bytes(byte_vec.as_slice()).unwrap())
+}
+
+pub fn to_bytes(name: &'static str, value: Term) -> exception::Result<Vec<u8>> {
     let mut byte_vec: Vec<u8> = Vec::new();
     let mut stack: Vec<Term> = vec![value];
 
@@ -110,7 +116,7 @@ pub fn to_binary(process: &Process, name: &'static str, value: Term) -> exceptio
         }
     }
 
-    Ok(process.binary_from_bytes(byte_vec.as_slice()).unwrap())
+    Ok(byte_vec)
 }
 
 fn element_context(name: &'static str, value: Term, element: Term) -> String {
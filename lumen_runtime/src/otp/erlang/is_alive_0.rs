@@ -5,8 +5,10 @@ use liblumen_alloc::erts::term::prelude::Term;
 
 use lumen_runtime_macros::native_implemented_function;
 
-/// Distribution is not supported at this time.  Always returns `false`.
+use crate::distribution::nodes::node;
+
+/// `true` once the distribution is started with `--name`
 #[native_implemented_function(is_alive/0)]
 pub fn native() -> Term {
-    false.into()
+    node::is_alive().into()
 }
@@ -7,13 +7,15 @@ mod test;
 
 use anyhow::*;
 
-use liblumen_alloc::error;
 use liblumen_alloc::erts::exception;
 use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{atom, error, exit};
 
 use lumen_runtime_macros::native_implemented_function;
 
+use crate::distribution;
+use crate::port;
 use crate::registry::pid_to_process;
 
 #[native_implemented_function(link/1)]
@@ -37,8 +39,38 @@ fn native(process: &Process, pid_or_port: Term) -> exception::Result<Term> {
                 }
             }
         }
-        TypedTerm::Port(_) => unimplemented!(),
-        TypedTerm::ExternalPid(_) => unimplemented!(),
+        TypedTerm::Port(port) => {
+            if port::link(process, port) {
+                Ok(true.into())
+            } else {
+                Err(error!(
+                    Atom::str_to_term("noproc"),
+                    anyhow!("port ({}) is not open", port).into()
+                )
+                .into())
+            }
+        }
+        TypedTerm::ExternalPid(external_pid) => {
+            if distribution::link(process, &external_pid) {
+                Ok(true.into())
+            } else if process.traps_exit() {
+                // Like the link being broken by the connection going down immediately
+                let exit_message = process.tuple_from_slice(&[
+                    atom!("EXIT"),
+                    pid_or_port,
+                    atom!("noconnection"),
+                ])?;
+                process.send_from_self(exit_message);
+
+                Ok(true.into())
+            } else {
+                Err(exit!(
+                    atom!("noconnection"),
+                    anyhow!("node of pid ({}) can't be connected to", external_pid).into()
+                )
+                .into())
+            }
+        }
         TypedTerm::ExternalPort(_) => unimplemented!(),
         _ => Err(TypeError)
             .context(format!(
@@ -17,6 +17,7 @@ use liblumen_alloc::erts::term::prelude::*;
 use lumen_runtime_macros::native_implemented_function;
 
 use crate::context::*;
+use crate::distribution::{self, external_term_format, Identifier};
 use crate::otp::erlang::node_0;
 use crate::process::{self, SchedulerDependentAlloc};
 use crate::registry;
@@ -46,7 +47,12 @@ fn monitor_process_identifier(
     match process_identifier.decode()? {
         TypedTerm::Atom(atom) => monitor_process_registered_name(process, process_identifier, atom),
         TypedTerm::Pid(pid) => monitor_process_pid(process, process_identifier, pid),
-        TypedTerm::ExternalPid(_) => unimplemented!(),
+        TypedTerm::ExternalPid(external_pid) => monitor_process_remote(
+            process,
+            process_identifier,
+            Identifier::Pid(external_term_format::Pid::External(external_pid.as_ref().clone())),
+            external_pid.arc_node().name(),
+        ),
         TypedTerm::Tuple(tuple) => monitor_process_tuple(process, process_identifier, &tuple),
         _ => Err(TypeError)
             .context(PROCESS_IDENTIFIER_CONTEXT)
@@ -65,6 +71,33 @@ fn monitor_process_identifier_noproc(
     Ok(monitor_reference)
 }
 
+fn monitor_process_remote(
+    process: &Process,
+    process_identifier: Term,
+    identifier: Identifier,
+    node: Atom,
+) -> exception::Result<Term> {
+    let reference = process.next_reference()?;
+    let reference_reference: Boxed<Reference> = reference.try_into().unwrap();
+
+    if !distribution::monitor(
+        process,
+        reference_reference.as_ref().clone(),
+        identifier,
+        node,
+    ) {
+        let noconnection_message = down_message(
+            process,
+            reference,
+            process_identifier,
+            atom!("noconnection"),
+        )?;
+        process.send_from_self(noconnection_message);
+    }
+
+    Ok(reference)
+}
+
 fn monitor_process_pid(
     process: &Process,
     process_identifier: Term,
@@ -113,7 +146,7 @@ const PROCESS_IDENTIFIER_CONTEXT: &str =
 
 fn monitor_process_tuple(
     process: &Process,
-    _process_identifier: Term,
+    process_identifier: Term,
     tuple: &Tuple,
 ) -> exception::Result<Term> {
     if tuple.len() == 2 {
@@ -125,13 +158,14 @@ fn monitor_process_tuple(
         if node == node_0::native() {
             monitor_process_registered_name(process, registered_name, registered_name_atom)
         } else {
-            let _: Atom = term_try_into_atom!(node)?;
-
-            unimplemented!(
-                "node ({:?}) is not the local node ({:?})",
-                node,
-                node_0::native()
-            );
+            let node_atom: Atom = term_try_into_atom!(node)?;
+
+            monitor_process_remote(
+                process,
+                process_identifier,
+                Identifier::Name(registered_name_atom),
+                node_atom,
+            )
         }
     } else {
         Err(anyhow!(PROCESS_IDENTIFIER_CONTEXT).into())
@@ -0,0 +1,36 @@
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::term::prelude::*;
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::distribution::nodes::node;
+
+/// The name of the node that `pid_port_or_reference` is from
+#[native_implemented_function(node/1)]
+pub fn native(pid_port_or_reference: Term) -> exception::Result<Term> {
+    let arc_node = match pid_port_or_reference.decode()? {
+        TypedTerm::Pid(_) | TypedTerm::Port(_) | TypedTerm::Reference(_) => return Ok(node::term()),
+        TypedTerm::ExternalPid(external_pid) => external_pid.arc_node(),
+        TypedTerm::ExternalPort(external_port) => external_port.arc_node(),
+        TypedTerm::ExternalReference(external_reference) => external_reference.arc_node(),
+        _ => {
+            return Err(TypeError)
+                .context(format!(
+                    "pid_port_or_reference ({}) is not a pid, port or reference",
+                    pid_port_or_reference
+                ))
+                .map_err(From::from)
+        }
+    };
+
+    Ok(arc_node.name().encode()?)
+}
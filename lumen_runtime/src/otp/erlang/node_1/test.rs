@@ -0,0 +1,53 @@
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::distribution::nodes::node;
+use crate::otp::erlang::node_1::native;
+use crate::scheduler::with_process;
+use crate::test::external_arc_node;
+
+#[test]
+fn without_pid_port_or_reference_errors_badarg() {
+    assert_badarg!(
+        native(atom!("node")),
+        "pid_port_or_reference (node) is not a pid, port or reference"
+    );
+}
+
+#[test]
+fn with_local_pid_port_or_reference_returns_running_node() {
+    with_process(|process| {
+        assert_eq!(native(process.pid_term()), Ok(node::term()));
+        assert_eq!(native(Port::next().encode().unwrap()), Ok(node::term()));
+        assert_eq!(native(process.next_reference().unwrap()), Ok(node::term()));
+    });
+}
+
+#[test]
+fn with_external_pid_port_or_reference_returns_their_node() {
+    with_process(|process| {
+        let arc_node = external_arc_node();
+        let node_name = arc_node.name().encode().unwrap();
+
+        assert_eq!(
+            native(process.external_pid(arc_node.clone(), 2, 3).unwrap()),
+            Ok(node_name)
+        );
+        assert_eq!(
+            native(
+                process
+                    .external_port(arc_node.clone(), Port::next())
+                    .unwrap()
+            ),
+            Ok(node_name)
+        );
+        assert_eq!(
+            native(
+                process
+                    .external_reference(arc_node, 0_u32.into(), 1, &[])
+                    .unwrap()
+            ),
+            Ok(node_name)
+        );
+    });
+}
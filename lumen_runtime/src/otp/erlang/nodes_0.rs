@@ -0,0 +1,21 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::distribution;
+
+/// The names of the nodes connected to the running node, not including the running node
+#[native_implemented_function(nodes/0)]
+pub fn native(process: &Process) -> exception::Result<Term> {
+    let names: Vec<Term> = distribution::connected_nodes()
+        .iter()
+        .map(|name| name.encode().unwrap())
+        .collect();
+
+    process.list_from_slice(&names).map_err(From::from)
+}
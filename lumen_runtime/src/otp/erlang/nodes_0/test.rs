@@ -0,0 +1,20 @@
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::distribution::nodes::node;
+use crate::otp::erlang::nodes_0::native;
+use crate::scheduler::with_process;
+
+#[test]
+fn does_not_include_running_node() {
+    with_process(|process| {
+        let nodes = native(process).unwrap();
+
+        let node_vec: Vec<Term> = match nodes.decode().unwrap() {
+            TypedTerm::Nil => Vec::new(),
+            TypedTerm::List(cons) => cons.into_iter().map(Result::unwrap).collect(),
+            _ => panic!("nodes ({}) is not a list", nodes),
+        };
+
+        assert!(!node_vec.contains(&node::term()));
+    });
+}
@@ -0,0 +1,44 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::convert::TryInto;
+use std::io;
+
+use anyhow::*;
+
+use liblumen_alloc::error;
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::port;
+
+#[native_implemented_function(open_port/2)]
+pub fn native(process: &Process, port_name: Term, port_settings: Term) -> exception::Result<Term> {
+    let name: port::Name = port_name.try_into()?;
+    let options: port::Options = port_settings.try_into()?;
+
+    match port::open(process, name, options) {
+        Ok(port) => Ok(port.encode()?),
+        // Like the BEAM, the reason is the POSIX error code that most closely describes the error
+        Err(err) => {
+            let reason = match err.kind() {
+                io::ErrorKind::NotFound => "enoent",
+                io::ErrorKind::PermissionDenied => "eacces",
+                _ => {
+                    return Err(err)
+                        .with_context(|| format!("port_name ({}) could not be opened", port_name))
+                        .map_err(From::from)
+                }
+            };
+
+            Err(error!(
+                Atom::str_to_term(reason),
+                anyhow!("port_name ({}) could not be opened: {}", port_name, err).into()
+            )
+            .into())
+        }
+    }
+}
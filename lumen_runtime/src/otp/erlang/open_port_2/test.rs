@@ -0,0 +1,225 @@
+use anyhow::*;
+
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{atom, error};
+
+use crate::otp::erlang::open_port_2::native;
+use crate::scheduler::with_process;
+use crate::test::poll_ports_until_message;
+
+#[test]
+fn without_tuple_port_name_errors_badarg() {
+    with_process(|process| {
+        assert_badarg!(
+            native(process, atom!("cat"), Term::NIL),
+            "supported port names are"
+        );
+    });
+}
+
+#[test]
+fn with_spawn_driver_errors_badarg() {
+    with_process(|process| {
+        let port_name = process
+            .tuple_from_slice(&[
+                atom!("spawn_driver"),
+                process.charlist_from_str("efile").unwrap(),
+            ])
+            .unwrap();
+
+        assert_badarg!(native(process, port_name, Term::NIL), "is not loaded");
+    });
+}
+
+#[test]
+fn with_unsupported_option_errors_badarg() {
+    with_process(|process| {
+        let port_settings = process.list_from_slice(&[atom!("unsupported")]).unwrap();
+
+        assert_badarg!(
+            native(
+                process,
+                spawn_executable(process, "/bin/echo"),
+                port_settings
+            ),
+            "supported options are"
+        );
+    });
+}
+
+#[test]
+fn with_non_existent_executable_errors_enoent() {
+    with_process(|process| {
+        assert_eq!(
+            native(
+                process,
+                spawn_executable(process, "/non/existent/executable"),
+                Term::NIL
+            ),
+            Err(error!(atom!("enoent"), anyhow!("Test").into()).into())
+        );
+    });
+}
+
+#[test]
+fn with_spawn_executable_sends_output_as_data_list() {
+    with_process(|process| {
+        let port_settings = process
+            .list_from_slice(&[args(process, &["hello"])])
+            .unwrap();
+
+        let port = native(
+            process,
+            spawn_executable(process, "/bin/echo"),
+            port_settings,
+        )
+        .unwrap();
+
+        let data = process.charlist_from_str("hello\n").unwrap();
+
+        assert!(poll_ports_until_message(
+            process,
+            message(process, port, atom!("data"), data)
+        ));
+    });
+}
+
+#[test]
+fn with_binary_sends_output_as_data_binary() {
+    with_process(|process| {
+        let port_settings = process
+            .list_from_slice(&[args(process, &["hello"]), atom!("binary")])
+            .unwrap();
+
+        let port = native(
+            process,
+            spawn_executable(process, "/bin/echo"),
+            port_settings,
+        )
+        .unwrap();
+
+        let data = process.binary_from_str("hello\n").unwrap();
+
+        assert!(poll_ports_until_message(
+            process,
+            message(process, port, atom!("data"), data)
+        ));
+    });
+}
+
+#[test]
+fn with_spawn_runs_command_with_arguments() {
+    with_process(|process| {
+        let port_name = process
+            .tuple_from_slice(&[
+                atom!("spawn"),
+                process.binary_from_str("echo spawned").unwrap(),
+            ])
+            .unwrap();
+
+        let port = native(process, port_name, Term::NIL).unwrap();
+
+        let data = process.charlist_from_str("spawned\n").unwrap();
+
+        assert!(poll_ports_until_message(
+            process,
+            message(process, port, atom!("data"), data)
+        ));
+    });
+}
+
+#[test]
+fn with_exit_status_sends_exit_status() {
+    with_process(|process| {
+        let port_settings = process
+            .list_from_slice(&[args(process, &["-c", "exit 3"]), atom!("exit_status")])
+            .unwrap();
+
+        let port = native(process, spawn_executable(process, "/bin/sh"), port_settings).unwrap();
+
+        assert!(poll_ports_until_message(
+            process,
+            message(
+                process,
+                port,
+                atom!("exit_status"),
+                process.integer(3).unwrap()
+            )
+        ));
+    });
+}
+
+#[test]
+fn with_eof_sends_eof() {
+    with_process(|process| {
+        let port_settings = process
+            .list_from_slice(&[args(process, &["-c", "true"]), atom!("eof")])
+            .unwrap();
+
+        let port = native(process, spawn_executable(process, "/bin/sh"), port_settings).unwrap();
+
+        assert!(poll_ports_until_message(
+            process,
+            process.tuple_from_slice(&[port, atom!("eof")]).unwrap()
+        ));
+    });
+}
+
+#[test]
+fn with_env_sets_environment_of_program() {
+    with_process(|process| {
+        let variable = process
+            .tuple_from_slice(&[
+                process.charlist_from_str("PORT_TEST").unwrap(),
+                process.charlist_from_str("set").unwrap(),
+            ])
+            .unwrap();
+        let port_settings = process
+            .list_from_slice(&[
+                args(process, &["-c", "echo $PORT_TEST"]),
+                process
+                    .tuple_from_slice(&[
+                        atom!("env"),
+                        process.list_from_slice(&[variable]).unwrap(),
+                    ])
+                    .unwrap(),
+            ])
+            .unwrap();
+
+        let port = native(process, spawn_executable(process, "/bin/sh"), port_settings).unwrap();
+
+        let data = process.charlist_from_str("set\n").unwrap();
+
+        assert!(poll_ports_until_message(
+            process,
+            message(process, port, atom!("data"), data)
+        ));
+    });
+}
+
+fn args(process: &Process, args: &[&str]) -> Term {
+    let arg_terms: Vec<Term> = args
+        .iter()
+        .map(|arg| process.charlist_from_str(arg).unwrap())
+        .collect();
+
+    process
+        .tuple_from_slice(&[atom!("args"), process.list_from_slice(&arg_terms).unwrap()])
+        .unwrap()
+}
+
+fn message(process: &Process, port: Term, tag: Term, value: Term) -> Term {
+    let info = process.tuple_from_slice(&[tag, value]).unwrap();
+
+    process.tuple_from_slice(&[port, info]).unwrap()
+}
+
+fn spawn_executable(process: &Process, file_name: &str) -> Term {
+    process
+        .tuple_from_slice(&[
+            atom!("spawn_executable"),
+            process.charlist_from_str(file_name).unwrap(),
+        ])
+        .unwrap()
+}
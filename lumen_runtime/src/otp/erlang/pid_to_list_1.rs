@@ -0,0 +1,34 @@
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::context::*;
+use crate::distribution::nodes;
+
+/// The inverse of `list_to_pid/1`, so local pids include the local node id instead of the `0` of
+/// their `Display`.
+#[native_implemented_function(pid_to_list/1)]
+pub fn native(process: &Process, pid: Term) -> exception::Result<Term> {
+    let string = match pid.decode().unwrap() {
+        TypedTerm::Pid(pid) => format!("<{}.{}.{}>", nodes::node::id(), pid.number(), pid.serial()),
+        TypedTerm::ExternalPid(external_pid) => external_pid.to_string(),
+        _ => {
+            return Err(TypeError)
+                .context(term_is_not_pid("pid", pid))
+                .map_err(From::from)
+        }
+    };
+
+    process.charlist_from_str(&string).map_err(From::from)
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use proptest::strategy::Just;
+
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::Node;
+
+use crate::distribution::nodes;
+use crate::otp::erlang::{list_to_pid_1, pid_to_list_1::native};
+use crate::scheduler::with_process;
+use crate::test::strategy;
+
+#[test]
+fn without_pid_errors_badarg() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                strategy::term::is_not_pid(arc_process.clone()),
+            )
+        },
+        |(arc_process, pid)| {
+            prop_assert_is_not_type!(native(&arc_process, pid), pid, "a pid");
+
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn with_local_pid_returns_list_with_local_node_id() {
+    with_process(|process| {
+        let pid = Pid::make_term(1, 2).unwrap();
+        let string = format!("<{}.1.2>", nodes::node::id());
+
+        assert_eq!(
+            native(process, pid),
+            Ok(process.charlist_from_str(&string).unwrap())
+        );
+    });
+}
+
+#[test]
+fn with_local_pid_is_inverse_of_list_to_pid() {
+    with_process(|process| {
+        let pid = process.pid_term();
+        let list = native(process, pid).unwrap();
+
+        assert_eq!(list_to_pid_1::native(process, list), Ok(pid));
+    });
+}
+
+#[test]
+fn with_external_pid_returns_list_with_node_id() {
+    with_process(|process| {
+        let arc_node = Arc::new(Node::new(5, Atom::try_from_str("5@external").unwrap(), 0));
+        let pid = process.external_pid(arc_node, 3, 4).unwrap();
+
+        assert_eq!(
+            native(process, pid),
+            Ok(process.charlist_from_str("<5.3.4>").unwrap())
+        );
+    });
+}
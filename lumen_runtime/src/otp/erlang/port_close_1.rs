@@ -0,0 +1,24 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::otp::erlang::port_command_2::term_try_into_port;
+use crate::port;
+
+#[native_implemented_function(port_close/1)]
+pub fn native(_process: &Process, port: Term) -> exception::Result<Term> {
+    let port_port = term_try_into_port(port)?;
+
+    if port::close(port_port) {
+        Ok(true.into())
+    } else {
+        Err(anyhow!("port ({}) is not open", port).into())
+    }
+}
@@ -0,0 +1,55 @@
+use std::time::{Duration, Instant};
+
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::port_close_1::native;
+use crate::port;
+use crate::scheduler::with_process;
+
+#[test]
+fn without_port_errors_badarg() {
+    with_process(|process| {
+        assert_badarg!(native(process, process.pid_term()), "is not a port");
+    });
+}
+
+#[test]
+fn with_open_port_returns_true_and_closes_port() {
+    with_process(|process| {
+        let port_port = port::open(
+            process,
+            port::Name::SpawnExecutable("/bin/cat".to_owned()),
+            Default::default(),
+        )
+        .unwrap();
+        let port = port_port.encode().unwrap();
+
+        assert_eq!(native(process, port), Ok(true.into()));
+        assert_eq!(port::connected(port_port), None);
+
+        assert_badarg!(native(process, port), "is not open");
+    });
+}
+
+#[test]
+fn with_running_program_kills_program() {
+    with_process(|process| {
+        let port_port = port::open(
+            process,
+            port::Name::SpawnExecutable("/bin/sleep".to_owned()),
+            port::Options {
+                args: vec!["60".to_owned()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let port = port_port.encode().unwrap();
+
+        let start = Instant::now();
+
+        assert_eq!(native(process, port), Ok(true.into()));
+        // Closing doesn't wait for the program to exit
+        assert!(start.elapsed() < Duration::from_secs(30));
+        assert_eq!(port::connected(port_port), None);
+    });
+}
@@ -0,0 +1,34 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::otp::erlang::iolist_or_binary;
+use crate::port;
+
+#[native_implemented_function(port_command/2)]
+pub fn native(_process: &Process, port: Term, data: Term) -> exception::Result<Term> {
+    let port_port = term_try_into_port(port)?;
+    let bytes = iolist_or_binary::to_bytes("data", data)?;
+
+    if port::command(port_port, &bytes) {
+        Ok(true.into())
+    } else {
+        Err(anyhow!("port ({}) is not open for output", port).into())
+    }
+}
+
+pub(in crate::otp::erlang) fn term_try_into_port(port: Term) -> exception::Result<Port> {
+    match port.decode()? {
+        TypedTerm::Port(port_port) => Ok(port_port),
+        _ => Err(TypeError)
+            .context(format!("port ({}) is not a port", port))
+            .map_err(From::from),
+    }
+}
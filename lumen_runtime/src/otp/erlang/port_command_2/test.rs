@@ -0,0 +1,69 @@
+use std::convert::TryInto;
+
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::port_command_2::native;
+use crate::port;
+use crate::scheduler::with_process;
+use crate::test::poll_ports_until_message;
+
+#[test]
+fn without_port_errors_badarg() {
+    with_process(|process| {
+        assert_badarg!(
+            native(process, process.pid_term(), Term::NIL),
+            "is not a port"
+        );
+    });
+}
+
+#[test]
+fn with_port_writes_data_to_input_of_program() {
+    with_process(|process| {
+        let port = cat(process);
+        let data = process
+            .list_from_slice(&[
+                process.charlist_from_str("hello ").unwrap(),
+                process.binary_from_str("world").unwrap(),
+            ])
+            .unwrap();
+
+        assert_eq!(native(process, port, data), Ok(true.into()));
+
+        let output = process.charlist_from_str("hello world").unwrap();
+        let info = process.tuple_from_slice(&[atom!("data"), output]).unwrap();
+
+        assert!(poll_ports_until_message(
+            process,
+            process.tuple_from_slice(&[port, info]).unwrap()
+        ));
+    });
+}
+
+#[test]
+fn with_closed_port_errors_badarg() {
+    with_process(|process| {
+        let port = cat(process);
+        let port_port: Port = port.try_into().unwrap();
+
+        assert!(port::close(port_port));
+
+        assert_badarg!(
+            native(process, port, process.binary_from_str("data").unwrap()),
+            "is not open"
+        );
+    });
+}
+
+fn cat(process: &Process) -> Term {
+    port::open(
+        process,
+        port::Name::SpawnExecutable("/bin/cat".to_owned()),
+        Default::default(),
+    )
+    .unwrap()
+    .encode()
+    .unwrap()
+}
@@ -0,0 +1,34 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::otp::erlang::port_command_2::term_try_into_port;
+use crate::port;
+
+/// Like the BEAM, the new connected process is linked to the port, while the old one stays linked
+/// until it calls `unlink/1`.
+#[native_implemented_function(port_connect/2)]
+pub fn native(_process: &Process, port: Term, pid: Term) -> exception::Result<Term> {
+    let port_port = term_try_into_port(port)?;
+    let pid_pid: Pid = match pid.decode()? {
+        TypedTerm::Pid(pid_pid) => pid_pid,
+        _ => {
+            return Err(TypeError)
+                .context(format!("pid ({}) is not a local pid", pid))
+                .map_err(From::from)
+        }
+    };
+
+    if port::connect(port_port, pid_pid) {
+        Ok(true.into())
+    } else {
+        Err(anyhow!("port ({}) is not open", port).into())
+    }
+}
@@ -0,0 +1,60 @@
+use std::convert::TryInto;
+
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::port_connect_2::native;
+use crate::port;
+use crate::process;
+use crate::scheduler::with_process_arc;
+use crate::test::{has_message, poll_ports_until_message};
+
+#[test]
+fn without_pid_errors_badarg() {
+    with_process_arc(|arc_process| {
+        let port = cat(&arc_process);
+
+        assert_badarg!(
+            native(&arc_process, port, atom!("pid")),
+            "is not a local pid"
+        );
+    });
+}
+
+#[test]
+fn with_pid_sends_data_to_new_connected_process() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = process::test(&arc_process);
+        let port = cat(&arc_process);
+        let port_port: Port = port.try_into().unwrap();
+
+        assert_eq!(
+            native(&arc_process, port, other_arc_process.pid_term()),
+            Ok(true.into())
+        );
+        assert_eq!(port::connected(port_port), Some(other_arc_process.pid()));
+
+        assert!(port::command(port_port, b"connected"));
+
+        let data = other_arc_process.charlist_from_str("connected").unwrap();
+        let info = other_arc_process
+            .tuple_from_slice(&[atom!("data"), data])
+            .unwrap();
+        let message = other_arc_process.tuple_from_slice(&[port, info]).unwrap();
+
+        assert!(poll_ports_until_message(&other_arc_process, message));
+        assert!(!has_message(&arc_process, message));
+    });
+}
+
+fn cat(process: &Process) -> Term {
+    port::open(
+        process,
+        port::Name::SpawnExecutable("/bin/cat".to_owned()),
+        Default::default(),
+    )
+    .unwrap()
+    .encode()
+    .unwrap()
+}
@@ -5,29 +5,85 @@
 #[cfg(all(not(target_arch = "wasm32"), test))]
 mod test;
 
+use std::convert::TryInto;
+
 use anyhow::*;
 
 use liblumen_alloc::erts::exception;
-use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::process::alloc::next_heap_size;
+use liblumen_alloc::erts::process::{Priority, Process, ProcessFlags};
 use liblumen_alloc::erts::term::prelude::*;
 
 use lumen_runtime_macros::native_implemented_function;
 
 use crate::context::*;
+use crate::process::spawn::options::{MaxHeapSize, MessageQueueData};
+use crate::scheduler::Scheduled;
+
+/// The most calls `save_calls` can save
+const MAX_SAVE_CALLS: usize = 10_000;
 
 #[native_implemented_function(process_flag/2)]
 pub fn native(process: &Process, flag: Term, value: Term) -> exception::Result<Term> {
     let flag_atom = term_try_into_atom!(flag)?;
 
     match flag_atom.name() {
-        "error_handler" => unimplemented!(),
-        "max_heap_size" => unimplemented!(),
-        "message_queue_data" => unimplemented!(),
-        "min_bin_vheap_size" => unimplemented!(),
-        "min_heap_size" => unimplemented!(),
-        "priority" => unimplemented!(),
-        "save_calls" => unimplemented!(),
-        "sensitive" => unimplemented!(),
+        "error_handler" => {
+            let module = term_try_into_atom("error_handler value", value)?;
+
+            Ok(process.set_error_handler(module).encode()?)
+        }
+        "max_heap_size" => {
+            let max_heap_size: MaxHeapSize = value.try_into().context("max_heap_size value")?;
+
+            Ok(max_heap_size.put(process).to_term(process)?)
+        }
+        "message_queue_data" => {
+            let message_queue_data: MessageQueueData =
+                value.try_into().context("message_queue_data value")?;
+
+            Ok(message_queue_data.put(process).into())
+        }
+        "min_bin_vheap_size" => {
+            let min_bin_vheap_size = term_try_into_words("min_bin_vheap_size value", value)?;
+
+            Ok(process.integer(process.set_min_bin_vheap_size(min_bin_vheap_size))?)
+        }
+        "min_heap_size" => {
+            let min_heap_size = term_try_into_words("min_heap_size value", value)?;
+
+            Ok(process.integer(process.set_min_heap_size(next_heap_size(min_heap_size)))?)
+        }
+        "priority" => {
+            let priority: Priority = value.try_into().context("priority value")?;
+            let old_priority = process.set_priority(priority);
+
+            if old_priority != priority {
+                if let Some(scheduler) = process.scheduler() {
+                    scheduler.reprioritize(process, old_priority);
+                }
+            }
+
+            Ok(old_priority.into())
+        }
+        "save_calls" => {
+            let save_calls: usize = value
+                .try_into()
+                .ok()
+                .filter(|save_calls| *save_calls <= MAX_SAVE_CALLS)
+                .with_context(|| {
+                    term_is_not_type("save_calls value", value, "an integer in 0-10000")
+                })?;
+
+            Ok(process.integer(process.set_save_calls(save_calls))?)
+        }
+        "sensitive" => {
+            let value_bool: bool = term_try_into_bool("sensitive value", value)?;
+
+            Ok(process
+                .put_flag(ProcessFlags::Sensitive, value_bool)
+                .into())
+        }
         "trap_exit" => {
             let value_bool: bool = term_try_into_bool("trap_exit value", value)?;
 
@@ -36,3 +92,9 @@ pub fn native(process: &Process, flag: Term, value: Term) -> exception::Result<T
         name => Err(TryAtomFromTermError(name)).context("supported flags are error_handler, max_heap_size, message_queue_data, min_bin_vheap_size, min_heap_size, priority, save_calls, sensitive, and trap_exit").map_err(From::from),
     }
 }
+
+fn term_try_into_words(name: &str, value: Term) -> anyhow::Result<usize> {
+    value
+        .try_into()
+        .with_context(|| term_is_not_non_negative_integer(name, value))
+}
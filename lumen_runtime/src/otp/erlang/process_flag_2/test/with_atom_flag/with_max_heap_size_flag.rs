@@ -0,0 +1,72 @@
+use super::*;
+
+use liblumen_alloc::erts::process::ProcessFlags;
+
+#[test]
+fn with_size_returns_old_max_heap_size_map() {
+    with_process(|process| {
+        let size = process.integer(1_000_000).unwrap();
+
+        assert_eq!(
+            native(process, flag(), size),
+            Ok(max_heap_size_map(process, 0, true, true))
+        );
+        assert_eq!(
+            native(process, flag(), size),
+            Ok(max_heap_size_map(process, 1_000_000, true, true))
+        );
+        assert_eq!(process.max_heap_size(), 1_000_000);
+    });
+}
+
+#[test]
+fn with_map_sets_size_kill_and_error_logger() {
+    with_process(|process| {
+        let value = process
+            .map_from_slice(&[
+                (
+                    Atom::str_to_term("size"),
+                    process.integer(2_000_000).unwrap(),
+                ),
+                (Atom::str_to_term("kill"), false.into()),
+                (Atom::str_to_term("error_logger"), false.into()),
+            ])
+            .unwrap();
+
+        assert_eq!(
+            native(process, flag(), value),
+            Ok(max_heap_size_map(process, 0, true, true))
+        );
+        assert_eq!(process.max_heap_size(), 2_000_000);
+        assert!(!process.are_flags_set(ProcessFlags::MaxHeapSizeKill));
+        assert!(!process.are_flags_set(ProcessFlags::MaxHeapSizeErrorLogger));
+    });
+}
+
+#[test]
+fn with_map_with_unsupported_key_errors_badarg() {
+    with_process(|process| {
+        let value = process
+            .map_from_slice(&[(Atom::str_to_term("limit"), process.integer(1).unwrap())])
+            .unwrap();
+
+        assert_badarg!(
+            native(process, flag(), value),
+            "max_heap_size is either size in words or a map with keys size"
+        );
+    });
+}
+
+fn flag() -> Term {
+    Atom::str_to_term("max_heap_size")
+}
+
+fn max_heap_size_map(process: &Process, size: usize, kill: bool, error_logger: bool) -> Term {
+    process
+        .map_from_slice(&[
+            (Atom::str_to_term("error_logger"), error_logger.into()),
+            (Atom::str_to_term("kill"), kill.into()),
+            (Atom::str_to_term("size"), process.integer(size).unwrap()),
+        ])
+        .unwrap()
+}
@@ -0,0 +1,46 @@
+use super::*;
+
+use crate::process;
+use crate::test::has_message;
+
+#[test]
+fn with_message_queue_data_returns_old_message_queue_data() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, flag(), Atom::str_to_term("off_heap")),
+            Ok(Atom::str_to_term("on_heap"))
+        );
+        assert_eq!(
+            native(process, flag(), Atom::str_to_term("on_heap")),
+            Ok(Atom::str_to_term("off_heap"))
+        );
+    });
+}
+
+#[test]
+fn with_off_heap_messages_from_other_processes_are_not_copied_to_heap() {
+    with_process(|process| {
+        let other_arc_process = process::test(process);
+
+        assert_eq!(
+            native(process, flag(), Atom::str_to_term("off_heap")),
+            Ok(Atom::str_to_term("on_heap"))
+        );
+
+        let message = other_arc_process
+            .tuple_from_slice(&[Atom::str_to_term("message"), other_arc_process.pid_term()])
+            .unwrap();
+        let total_heap_size_before = process.total_heap_size();
+        let heap_size_before = process.heap_size();
+
+        process.send_from_other(message).unwrap();
+
+        assert!(has_message(process, message));
+        assert_eq!(process.heap_size(), heap_size_before);
+        assert!(total_heap_size_before < process.total_heap_size());
+    });
+}
+
+fn flag() -> Term {
+    Atom::str_to_term("message_queue_data")
+}
@@ -0,0 +1,32 @@
+use super::*;
+
+use liblumen_alloc::erts::process::alloc::next_heap_size;
+
+#[test]
+fn without_non_negative_integer_errors_badarg() {
+    with_process(|process| {
+        let value = process.integer(-1).unwrap();
+
+        assert_badarg!(
+            native(process, flag(), value),
+            "is not a non-negative integer"
+        );
+    });
+}
+
+#[test]
+fn with_non_negative_integer_returns_old_min_heap_size_and_rounds_up_to_heap_size() {
+    with_process(|process| {
+        let old_min_heap_size = process.min_heap_size();
+
+        assert_eq!(
+            native(process, flag(), process.integer(1_000).unwrap()),
+            Ok(process.integer(old_min_heap_size).unwrap())
+        );
+        assert_eq!(process.min_heap_size(), next_heap_size(1_000));
+    });
+}
+
+fn flag() -> Term {
+    Atom::str_to_term("min_heap_size")
+}
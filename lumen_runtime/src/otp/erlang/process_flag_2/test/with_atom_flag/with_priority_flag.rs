@@ -0,0 +1,56 @@
+use super::*;
+
+use liblumen_alloc::erts::process::Priority;
+
+use crate::process;
+use crate::scheduler::Scheduled;
+
+#[test]
+fn without_priority_errors_badarg() {
+    with_process(|process| {
+        assert_badarg!(
+            native(process, flag(), Atom::str_to_term("urgent")),
+            "supported priorities are low, normal, high, or max"
+        );
+    });
+}
+
+#[test]
+fn with_priority_returns_old_priority() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, flag(), Atom::str_to_term("low")),
+            Ok(Atom::str_to_term("normal"))
+        );
+        assert_eq!(
+            native(process, flag(), Atom::str_to_term("max")),
+            Ok(Atom::str_to_term("low"))
+        );
+        assert_eq!(process.priority(), Priority::Max);
+    });
+}
+
+#[test]
+fn with_run_queued_process_moves_process_to_run_queue_for_new_priority() {
+    let arc_process = process::test(&process::test_init());
+    let arc_scheduler = arc_process.scheduler().unwrap();
+
+    assert!(arc_scheduler.is_run_queued(&arc_process));
+
+    let high_run_queue_len_before = arc_scheduler.run_queue_len(Priority::High);
+
+    assert_eq!(
+        native(&arc_process, flag(), Atom::str_to_term("high")),
+        Ok(Atom::str_to_term("normal"))
+    );
+
+    assert!(arc_scheduler.is_run_queued(&arc_process));
+    assert_eq!(
+        arc_scheduler.run_queue_len(Priority::High),
+        high_run_queue_len_before + 1
+    );
+}
+
+fn flag() -> Term {
+    Atom::str_to_term("priority")
+}
@@ -0,0 +1,44 @@
+use super::*;
+
+use crate::otp::erlang::process_info_2;
+use crate::process;
+
+#[test]
+fn without_boolean_value_errors_badarg() {
+    with_process(|process| {
+        let value = Atom::str_to_term("yes");
+
+        assert_badarg!(native(process, flag(), value), "is not a boolean");
+    });
+}
+
+#[test]
+fn with_true_hides_messages_from_process_info() {
+    with_process(|parent_process| {
+        let arc_process = process::test(parent_process);
+        let message = Atom::str_to_term("secret");
+        arc_process.send_from_self(message);
+
+        assert_eq!(native(&arc_process, flag(), true.into()), Ok(false.into()));
+
+        let item = Atom::str_to_term("messages");
+
+        assert_eq!(
+            process_info_2::native(parent_process, arc_process.pid_term(), item),
+            Ok(parent_process.tuple_from_slice(&[item, Term::NIL]).unwrap())
+        );
+
+        assert_eq!(native(&arc_process, flag(), false.into()), Ok(true.into()));
+
+        assert_eq!(
+            process_info_2::native(parent_process, arc_process.pid_term(), item),
+            Ok(parent_process
+                .tuple_from_slice(&[item, parent_process.list_from_slice(&[message]).unwrap()])
+                .unwrap())
+        );
+    });
+}
+
+fn flag() -> Term {
+    Atom::str_to_term("sensitive")
+}
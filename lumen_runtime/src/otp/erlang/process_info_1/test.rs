@@ -0,0 +1,101 @@
+use std::convert::TryInto;
+
+use proptest::strategy::Just;
+use proptest::test_runner::{Config, TestRunner};
+
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::process_info_1::native;
+use crate::scheduler::with_process_arc;
+use crate::test::{registered_name, strategy};
+use crate::{process, registry};
+
+#[test]
+fn without_local_pid_errors_badarg() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                strategy::term::is_not_local_pid(arc_process.clone()),
+            )
+        },
+        |(arc_process, pid)| {
+            prop_assert_is_not_local_pid!(native(&arc_process, pid), pid);
+
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn without_process_returns_undefined() {
+    with_process_arc(|arc_process| {
+        assert_eq!(
+            native(&arc_process, Pid::next_term()),
+            Ok(Atom::str_to_term("undefined"))
+        );
+    });
+}
+
+#[test]
+fn without_registered_name_returns_default_items() {
+    with_process_arc(|parent_arc_process| {
+        let arc_process = process::test(&parent_arc_process);
+
+        assert_eq!(
+            item_names(native(&parent_arc_process, arc_process.pid_term()).unwrap()),
+            DEFAULT_ITEM_NAMES
+        );
+    });
+}
+
+#[test]
+fn with_registered_name_returns_registered_name_before_default_items() {
+    with_process_arc(|arc_process| {
+        let registered_name = registered_name();
+
+        assert!(registry::put_atom_to_process(
+            registered_name.try_into().unwrap(),
+            arc_process.clone()
+        ));
+
+        let mut expected = vec!["registered_name"];
+        expected.extend_from_slice(DEFAULT_ITEM_NAMES);
+
+        assert_eq!(
+            item_names(native(&arc_process, arc_process.pid_term()).unwrap()),
+            expected
+        );
+    });
+}
+
+const DEFAULT_ITEM_NAMES: &[&str] = &[
+    "current_function",
+    "initial_call",
+    "status",
+    "message_queue_len",
+    "links",
+    "dictionary",
+    "trap_exit",
+    "error_handler",
+    "priority",
+    "group_leader",
+    "total_heap_size",
+    "heap_size",
+    "stack_size",
+    "reductions",
+    "garbage_collection",
+];
+
+fn item_names(info: Term) -> Vec<&'static str> {
+    let cons: Boxed<Cons> = info.try_into().unwrap();
+
+    cons.into_iter()
+        .map(|result| {
+            let tuple: Boxed<Tuple> = result.unwrap().try_into().unwrap();
+            let item: Atom = tuple[0].try_into().unwrap();
+
+            item.name()
+        })
+        .collect()
+}
@@ -1,3 +1,5 @@
+mod with_item_list;
+mod with_messages;
 mod with_registered_name;
 
 use super::*;
@@ -30,12 +32,70 @@ fn without_supported_item_errors_badarg() {
     });
 }
 
+const SUPPORTED_ITEMS: &[&str] = &[
+    "backtrace",
+    "binary",
+    "catchlevel",
+    "current_function",
+    "current_location",
+    "current_stacktrace",
+    "dictionary",
+    "error_handler",
+    "garbage_collection",
+    "garbage_collection_info",
+    "group_leader",
+    "heap_size",
+    "initial_call",
+    "links",
+    "last_calls",
+    "memory",
+    "message_queue_len",
+    "messages",
+    "min_heap_size",
+    "min_bin_vheap_size",
+    "monitored_by",
+    "monitors",
+    "message_queue_data",
+    "priority",
+    "reductions",
+    "registered_name",
+    "sequential_trace_token",
+    "stack_size",
+    "status",
+    "suspending",
+    "total_heap_size",
+    "trace",
+    "trap_exit",
+];
+
 fn unsupported_item_atom() -> BoxedStrategy<Term> {
     strategy::atom()
-        .prop_filter("Item cannot be supported", |atom| match atom.name() {
-            "registered_name" => false,
-            _ => true,
+        .prop_filter("Item cannot be supported", |atom| {
+            !SUPPORTED_ITEMS.contains(&atom.name())
         })
         .prop_map(|atom| atom.encode().unwrap())
         .boxed()
 }
+
+#[test]
+fn with_untracked_item_returns_empty_value_of_item_type() {
+    with_process_arc(|arc_process| {
+        let pid = arc_process.pid_term();
+        let zero = arc_process.integer(0).unwrap();
+
+        for (name, value) in &[
+            ("binary", Term::NIL),
+            ("catchlevel", zero),
+            ("sequential_trace_token", Term::NIL),
+            ("suspending", Term::NIL),
+            ("trace", zero),
+        ] {
+            let item = Atom::str_to_term(name);
+
+            assert_eq!(
+                native(&arc_process, pid, item),
+                Ok(arc_process.tuple_from_slice(&[item, *value]).unwrap())
+            );
+        }
+    });
+}
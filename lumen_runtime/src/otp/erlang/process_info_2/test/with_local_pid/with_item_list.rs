@@ -0,0 +1,71 @@
+use super::*;
+
+use crate::process;
+
+#[test]
+fn with_empty_list_returns_empty_list() {
+    with_process_arc(|arc_process| {
+        assert_eq!(
+            native(&arc_process, arc_process.pid_term(), Term::NIL),
+            Ok(Term::NIL)
+        );
+    });
+}
+
+#[test]
+fn with_unsupported_item_errors_badarg() {
+    with_process_arc(|arc_process| {
+        let item_list = arc_process
+            .list_from_slice(&[
+                Atom::str_to_term("trap_exit"),
+                Atom::str_to_term("unsupported"),
+            ])
+            .unwrap();
+
+        assert_badarg!(
+            native(&arc_process, arc_process.pid_term(), item_list),
+            "supported items are backtrace, binary, catchlevel, current_function, \
+             current_location, current_stacktrace, dictionary, error_handler, \
+             garbage_collection, garbage_collection_info, group_leader, heap_size, \
+             initial_call, links, last_calls, memory, message_queue_len, messages, \
+             min_heap_size, min_bin_vheap_size, monitored_by, monitors, \
+             message_queue_data, priority, reductions, registered_name, \
+             sequential_trace_token, stack_size, status, suspending, \
+             total_heap_size, trace, trap_exit"
+        );
+    });
+}
+
+#[test]
+fn with_items_returns_item_value_tuples_in_order() {
+    with_process_arc(|parent_arc_process| {
+        let arc_process = process::test(&parent_arc_process);
+        let registered_name = Atom::str_to_term("registered_name");
+        let trap_exit = Atom::str_to_term("trap_exit");
+        let priority = Atom::str_to_term("priority");
+        let links = Atom::str_to_term("links");
+        let item_list = parent_arc_process
+            .list_from_slice(&[registered_name, trap_exit, priority, links])
+            .unwrap();
+
+        assert_eq!(
+            native(&parent_arc_process, arc_process.pid_term(), item_list),
+            Ok(parent_arc_process
+                .list_from_slice(&[
+                    parent_arc_process
+                        .tuple_from_slice(&[registered_name, Term::NIL])
+                        .unwrap(),
+                    parent_arc_process
+                        .tuple_from_slice(&[trap_exit, false.into()])
+                        .unwrap(),
+                    parent_arc_process
+                        .tuple_from_slice(&[priority, Atom::str_to_term("normal")])
+                        .unwrap(),
+                    parent_arc_process
+                        .tuple_from_slice(&[links, Term::NIL])
+                        .unwrap(),
+                ])
+                .unwrap())
+        );
+    });
+}
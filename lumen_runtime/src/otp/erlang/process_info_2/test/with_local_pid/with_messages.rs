@@ -0,0 +1,46 @@
+use super::*;
+
+use crate::process;
+
+#[test]
+fn message_queue_len_counts_messages() {
+    with_process_arc(|arc_process| {
+        let item = Atom::str_to_term("message_queue_len");
+
+        arc_process.send_from_self(Atom::str_to_term("first"));
+        arc_process.send_from_self(Atom::str_to_term("second"));
+
+        assert_eq!(
+            native(&arc_process, arc_process.pid_term(), item),
+            Ok(arc_process
+                .tuple_from_slice(&[item, arc_process.integer(2).unwrap()])
+                .unwrap())
+        );
+    });
+}
+
+#[test]
+fn messages_of_other_process_are_copied_to_process() {
+    with_process_arc(|parent_arc_process| {
+        let other_arc_process = process::test(&parent_arc_process);
+        let item = Atom::str_to_term("messages");
+        let message = other_arc_process
+            .tuple_from_slice(&[
+                Atom::str_to_term("message"),
+                other_arc_process.integer(1).unwrap(),
+            ])
+            .unwrap();
+
+        other_arc_process.send_from_self(message);
+
+        assert_eq!(
+            native(&parent_arc_process, other_arc_process.pid_term(), item),
+            Ok(parent_arc_process
+                .tuple_from_slice(&[
+                    item,
+                    parent_arc_process.list_from_slice(&[message]).unwrap()
+                ])
+                .unwrap())
+        );
+    });
+}
@@ -0,0 +1,30 @@
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::registry;
+
+#[native_implemented_function(processes/0)]
+pub fn native(process: &Process) -> exception::Result<Term> {
+    let mut pid_vec: Vec<Pid> = registry::processes()
+        .iter()
+        .map(|arc_process| arc_process.pid())
+        .collect();
+    pid_vec.sort();
+
+    let pid_term_vec: Vec<Term> = pid_vec
+        .into_iter()
+        .map(|pid| pid.encode().unwrap())
+        .collect();
+
+    process.list_from_slice(&pid_term_vec).map_err(From::from)
+}
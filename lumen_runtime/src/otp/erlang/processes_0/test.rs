@@ -0,0 +1,52 @@
+use std::convert::TryInto;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::processes_0::native;
+use crate::process;
+use crate::scheduler::with_process_arc;
+
+#[test]
+fn includes_self_and_other_processes() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = process::test(&arc_process);
+
+        let pids = pids(native(&arc_process).unwrap());
+
+        assert!(pids.contains(&arc_process.pid_term()));
+        assert!(pids.contains(&other_arc_process.pid_term()));
+    });
+}
+
+#[test]
+fn excludes_exiting_processes() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = process::test(&arc_process);
+        other_arc_process.exit_normal(anyhow!("Test").into());
+
+        let pids = pids(native(&arc_process).unwrap());
+
+        assert!(!pids.contains(&other_arc_process.pid_term()));
+    });
+}
+
+#[test]
+fn is_sorted() {
+    with_process_arc(|arc_process| {
+        process::test(&arc_process);
+
+        let pids = pids(native(&arc_process).unwrap());
+        let mut sorted_pids = pids.clone();
+        sorted_pids.sort();
+
+        assert_eq!(pids, sorted_pids);
+    });
+}
+
+fn pids(list: Term) -> Vec<Term> {
+    let cons: Boxed<Cons> = list.try_into().unwrap();
+
+    cons.into_iter().map(|result| result.unwrap()).collect()
+}
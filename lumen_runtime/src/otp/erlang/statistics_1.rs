@@ -0,0 +1,101 @@
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::{self, gc, Process};
+use liblumen_alloc::erts::term::prelude::*;
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::scheduler::Scheduler;
+use crate::time::{monotonic, runtime};
+
+#[native_implemented_function(statistics/1)]
+pub fn native(process: &Process, item: Term) -> exception::Result<Term> {
+    let item_atom = term_try_into_atom!(item)?;
+
+    match item_atom.name() {
+        "context_switches" => {
+            let context_switches: u64 = Scheduler::all()
+                .iter()
+                .map(|scheduler| scheduler.context_switches())
+                .sum();
+
+            pair(process, context_switches, 0)
+        }
+        "garbage_collection" => {
+            let (collections, words_reclaimed) = gc::statistics();
+
+            process
+                .tuple_from_slice(&[
+                    process.integer(collections)?,
+                    process.integer(words_reclaimed)?,
+                    process.integer(0)?,
+                ])
+                .map_err(From::from)
+        }
+        "reductions" => total_and_since_last_call(
+            process,
+            process::total_reductions(),
+            &LAST_REDUCTIONS,
+        ),
+        "run_queue" => {
+            let runnable_len: usize = Scheduler::all()
+                .iter()
+                .map(|scheduler| scheduler.runnable_len())
+                .sum();
+
+            Ok(process.integer(runnable_len)?)
+        }
+        "runtime" => total_and_since_last_call(
+            process,
+            runtime::time_in_milliseconds(),
+            &LAST_RUNTIME,
+        ),
+        "wall_clock" => total_and_since_last_call(
+            process,
+            monotonic::time_in_milliseconds(),
+            &LAST_WALL_CLOCK,
+        ),
+        name => Err(TryAtomFromTermError(name))
+            .context("supported items are context_switches, garbage_collection, reductions, run_queue, runtime, and wall_clock")
+            .map_err(From::from),
+    }
+}
+
+// The totals as of the last call for each item, as `statistics` returns both the total and how
+// much it changed since the last call.
+static LAST_REDUCTIONS: AtomicU64 = AtomicU64::new(0);
+static LAST_RUNTIME: AtomicU64 = AtomicU64::new(0);
+static LAST_WALL_CLOCK: AtomicU64 = AtomicU64::new(0);
+
+fn pair(process: &Process, first: u64, second: u64) -> exception::Result<Term> {
+    process
+        .tuple_from_slice(&[process.integer(first)?, process.integer(second)?])
+        .map_err(From::from)
+}
+
+fn total_and_since_last_call(
+    process: &Process,
+    total: u64,
+    last: &AtomicU64,
+) -> exception::Result<Term> {
+    // Concurrent calls can read the totals out of order, so `last` only moves forward and the
+    // call that read the older total sees no change
+    let previous = last.fetch_max(total, Ordering::SeqCst);
+    let since_last_call = if previous < total {
+        total - previous
+    } else {
+        0
+    };
+
+    pair(process, total, since_last_call)
+}
@@ -0,0 +1,162 @@
+use std::convert::TryInto;
+
+use anyhow::*;
+
+use proptest::strategy::{BoxedStrategy, Just, Strategy};
+
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::statistics_1::native;
+use crate::process;
+use crate::scheduler::{with_process, with_process_arc, Scheduler};
+use crate::test::strategy;
+
+#[test]
+fn without_atom_item_errors_badarg() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                strategy::term::is_not_atom(arc_process.clone()),
+            )
+        },
+        |(arc_process, item)| {
+            prop_assert_is_not_atom!(native(&arc_process, item), item);
+
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn without_supported_item_errors_badarg() {
+    run!(
+        |arc_process| (Just(arc_process.clone()), unsupported_item_atom()),
+        |(arc_process, item)| {
+            prop_assert_badarg!(native(&arc_process, item), "supported items are context_switches, garbage_collection, reductions, run_queue, runtime, and wall_clock");
+
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn with_context_switches_counts_processes_run() {
+    with_process_arc(|arc_process| {
+        let (before, _) = pair(native(&arc_process, atom!("context_switches")).unwrap());
+
+        assert!(Scheduler::current().run_through(&arc_process));
+
+        let (after, zero) = pair(native(&arc_process, atom!("context_switches")).unwrap());
+
+        assert!(before < after);
+        assert_eq!(zero, 0);
+    });
+}
+
+#[test]
+fn with_garbage_collection_counts_collections() {
+    with_process(|process| {
+        let before: Boxed<Tuple> = native(process, atom!("garbage_collection"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        process.garbage_collect(0, &mut []).unwrap();
+
+        let after: Boxed<Tuple> = native(process, atom!("garbage_collection"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        assert_eq!(after.len(), 3);
+
+        let collections_before: u64 = before[0].try_into().unwrap();
+        let collections_after: u64 = after[0].try_into().unwrap();
+
+        assert!(collections_before < collections_after);
+        assert_eq!(after[2], process.integer(0).unwrap());
+    });
+}
+
+#[test]
+fn with_reductions_includes_reductions_of_process() {
+    with_process_arc(|arc_process| {
+        assert!(Scheduler::current().run_through(&arc_process));
+
+        let (total, _) = pair(native(&arc_process, atom!("reductions")).unwrap());
+
+        assert!(0 < total);
+    });
+}
+
+#[test]
+fn with_reductions_includes_reductions_of_exited_processes() {
+    with_process_arc(|parent_arc_process| {
+        let arc_process = process::test(&parent_arc_process);
+
+        assert!(Scheduler::current().run_through(&arc_process));
+
+        let (before, _) = pair(native(&parent_arc_process, atom!("reductions")).unwrap());
+
+        arc_process.exit_normal(anyhow!("test").into());
+
+        let (after, since_last_call) =
+            pair(native(&parent_arc_process, atom!("reductions")).unwrap());
+
+        assert!(before <= after);
+        assert!(since_last_call <= after);
+    });
+}
+
+#[test]
+fn with_run_queue_returns_non_negative_integer() {
+    with_process(|process| {
+        let run_queue: Result<usize, _> = native(process, atom!("run_queue")).unwrap().try_into();
+
+        assert!(run_queue.is_ok());
+    });
+}
+
+#[test]
+fn with_wall_clock_returns_total_and_time_since_last_call() {
+    with_process(|process| {
+        let (first_total, _) = pair(native(process, atom!("wall_clock")).unwrap());
+        let (second_total, since_last_call) = pair(native(process, atom!("wall_clock")).unwrap());
+
+        assert!(first_total <= second_total);
+        assert!(since_last_call <= second_total);
+    });
+}
+
+#[test]
+fn with_runtime_returns_total_and_time_since_last_call() {
+    with_process(|process| {
+        let (total, since_last_call) = pair(native(process, atom!("runtime")).unwrap());
+
+        assert!(since_last_call <= total);
+    });
+}
+
+fn pair(term: Term) -> (u64, u64) {
+    let tuple: Boxed<Tuple> = term.try_into().unwrap();
+
+    assert_eq!(tuple.len(), 2);
+
+    (tuple[0].try_into().unwrap(), tuple[1].try_into().unwrap())
+}
+
+fn unsupported_item_atom() -> BoxedStrategy<Term> {
+    strategy::term::atom()
+        .prop_filter("Cannot be a supported item name", |atom| {
+            let atom_atom: Atom = (*atom).try_into().unwrap();
+
+            match atom_atom.name() {
+                "context_switches" | "garbage_collection" | "reductions" | "run_queue"
+                | "runtime" | "wall_clock" => false,
+                _ => true,
+            }
+        })
+        .boxed()
+}
@@ -0,0 +1,66 @@
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::env;
+use std::mem;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::registry;
+use crate::scheduler::Scheduler;
+use crate::system::host::cpus;
+
+/// The OTP release whose BIFs and distribution protocol the runtime implements, which is the first
+/// with version 6 of the distribution handshake
+const OTP_RELEASE: &str = "23";
+
+/// The ERTS version of `OTP_RELEASE`, as code compares `version` against ERTS versions, not
+/// against Lumen's own version
+const ERTS_VERSION: &str = "11.0";
+
+#[native_implemented_function(system_info/1)]
+pub fn native(process: &Process, item: Term) -> exception::Result<Term> {
+    let item_atom = term_try_into_atom!(item)?;
+
+    let term = match item_atom.name() {
+        "atom_count" => process.integer(Atom::count())?,
+        "logical_processors" => process.integer(cpus::num_logical())?,
+        "os_type" => {
+            let family = match env::consts::FAMILY {
+                "windows" => "win32",
+                "" => "unknown",
+                family => family,
+            };
+            let name = match env::consts::OS {
+                "macos" => "darwin",
+                "windows" => "nt",
+                name => name,
+            };
+
+            process.tuple_from_slice(&[Atom::str_to_term(family), Atom::str_to_term(name)])?
+        }
+        "otp_release" => process.charlist_from_str(OTP_RELEASE)?,
+        "process_count" => process.integer(registry::processes().len())?,
+        "process_limit" => process.integer(Pid::NUMBER_MAX + 1)?,
+        "schedulers" => process.integer(Scheduler::all().len())?,
+        "version" => process.charlist_from_str(ERTS_VERSION)?,
+        "wordsize" => process.integer(mem::size_of::<usize>())?,
+        name => {
+            return Err(TryAtomFromTermError(name))
+                .context("supported items are atom_count, logical_processors, os_type, otp_release, process_count, process_limit, schedulers, version, and wordsize")
+                .map_err(From::from)
+        }
+    };
+
+    Ok(term)
+}
@@ -0,0 +1,169 @@
+use std::convert::TryInto;
+
+use proptest::strategy::{BoxedStrategy, Just, Strategy};
+
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::system_info_1::native;
+use crate::scheduler::with_process;
+use crate::test::strategy;
+
+#[test]
+fn without_atom_item_errors_badarg() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                strategy::term::is_not_atom(arc_process.clone()),
+            )
+        },
+        |(arc_process, item)| {
+            prop_assert_is_not_atom!(native(&arc_process, item), item);
+
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn without_supported_item_errors_badarg() {
+    run!(
+        |arc_process| (Just(arc_process.clone()), unsupported_item_atom()),
+        |(arc_process, item)| {
+            prop_assert_badarg!(native(&arc_process, item), "supported items are atom_count, logical_processors, os_type, otp_release, process_count, process_limit, schedulers, version, and wordsize");
+
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn with_atom_count_returns_number_of_atoms() {
+    with_process(|process| {
+        let before: usize = native(process, atom!("atom_count"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        Atom::from_str("system_info_1_with_atom_count_returns_number_of_atoms");
+
+        let after: usize = native(process, atom!("atom_count"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        assert!(before < after);
+    });
+}
+
+#[test]
+fn with_logical_processors_returns_positive_integer() {
+    with_process(|process| {
+        let logical_processors: usize = native(process, atom!("logical_processors"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        assert!(0 < logical_processors);
+    });
+}
+
+#[test]
+fn with_os_type_returns_family_and_name() {
+    with_process(|process| {
+        let os_type: Boxed<Tuple> = native(process, atom!("os_type"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        assert_eq!(os_type.len(), 2);
+        assert!(os_type[0].is_atom());
+        assert!(os_type[1].is_atom());
+
+        if cfg!(unix) {
+            assert_eq!(os_type[0], atom!("unix"));
+        }
+    });
+}
+
+#[test]
+fn with_otp_release_returns_string() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, atom!("otp_release")),
+            Ok(process.charlist_from_str("23").unwrap())
+        );
+    });
+}
+
+#[test]
+fn with_process_count_counts_live_processes() {
+    with_process(|process| {
+        let process_count: usize = native(process, atom!("process_count"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        // `process` and its parent
+        assert!(2 <= process_count);
+    });
+}
+
+#[test]
+fn with_process_limit_returns_number_of_pids() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, atom!("process_limit")),
+            Ok(process.integer(Pid::NUMBER_MAX + 1).unwrap())
+        );
+    });
+}
+
+#[test]
+fn with_schedulers_includes_current_scheduler() {
+    with_process(|process| {
+        let schedulers: usize = native(process, atom!("schedulers"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        assert!(1 <= schedulers);
+    });
+}
+
+#[test]
+fn with_version_returns_erts_version_of_otp_release() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, atom!("version")),
+            Ok(process.charlist_from_str("11.0").unwrap())
+        );
+    });
+}
+
+#[test]
+fn with_wordsize_returns_bytes_per_word() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, atom!("wordsize")),
+            Ok(process.integer(std::mem::size_of::<usize>()).unwrap())
+        );
+    });
+}
+
+fn unsupported_item_atom() -> BoxedStrategy<Term> {
+    strategy::term::atom()
+        .prop_filter("Cannot be a supported item name", |atom| {
+            let atom_atom: Atom = (*atom).try_into().unwrap();
+
+            match atom_atom.name() {
+                "atom_count" | "logical_processors" | "os_type" | "otp_release"
+                | "process_count" | "process_limit" | "schedulers" | "version" | "wordsize" => {
+                    false
+                }
+                _ => true,
+            }
+        })
+        .boxed()
+}
@@ -2,9 +2,11 @@ mod options;
 
 use std::collections::VecDeque;
 use std::convert::TryInto;
+use std::io::Write;
 use std::mem;
 use std::sync::Arc;
 
+use flate2::write::ZlibEncoder;
 use num_bigint::{BigInt, Sign};
 
 use liblumen_alloc::erts::exception;
@@ -21,6 +23,7 @@ use options::*;
 
 pub fn term_to_binary(process: &Process, term: Term, options: Options) -> exception::Result<Term> {
     let byte_vec = term_to_byte_vec(process, &options, term);
+    let byte_vec = compress(byte_vec, &options.compression);
 
     process
         .binary_from_bytes(&byte_vec)
@@ -29,14 +32,8 @@ pub fn term_to_binary(process: &Process, term: Term, options: Options) -> except
 
 // Private
 
-// TODO implement creation rotation
-// > A 32-bit big endian unsigned integer. All identifiers originating from the same node
-// > incarnation must have identical Creation values. This makes it possible to separate identifiers
-// > from old (crashed) nodes from a new one. The value zero should be avoided for normal operations
-// > as it is used as a wild card for debug purpose (like a pid returned by erlang:list_to_pid/1).
-const CREATION: u8 = 0;
-
-const NEWER_REFERENCE_EXT_MAX_U32_LEN: usize = 3;
+const FLOAT_EXT_LEN: usize = 31;
+const NEWER_REFERENCE_EXT_MAX_U32_LEN: usize = 5;
 
 const SMALL_INTEGER_EXT_MIN: isize = std::u8::MIN as isize;
 const SMALL_INTEGER_EXT_MAX: isize = std::u8::MAX as isize;
@@ -92,7 +89,55 @@ fn append_creator(byte_vec: &mut Vec<u8>, creator: &Creator) {
     }
 }
 
-fn append_pid(byte_vec: &mut Vec<u8>, arc_node: Arc<Node>, id: u32, serial: u32) {
+/// > A finite float (i.e. not inf, -inf or NaN) is stored in string format. The format used in
+/// > sprintf to format the float is "%.20e" (there are more bytes allocated than necessary).
+fn append_float_ext(byte_vec: &mut Vec<u8>, f: f64) {
+    push_tag(byte_vec, Tag::Float);
+
+    let rust_formatted = format!("{:.20e}", f);
+    let (mantissa, e_exponent) = rust_formatted.split_at(rust_formatted.find('e').unwrap());
+    let exponent: i32 = e_exponent[1..].parse().unwrap();
+    // Unlike Rust, C always signs the exponent and pads it to at least 2 digits
+    let sign = if exponent < 0 { '-' } else { '+' };
+    let c_formatted = format!("{}e{}{:02}", mantissa, sign, exponent.abs());
+
+    let mut float_ext_byte_vec = c_formatted.into_bytes();
+    float_ext_byte_vec.resize(FLOAT_EXT_LEN, 0);
+    byte_vec.append(&mut float_ext_byte_vec);
+}
+
+fn append_newer_reference(
+    byte_vec: &mut Vec<u8>,
+    arc_node: Arc<Node>,
+    scheduler_id_u32: u32,
+    number: u64,
+    extra_ids: &[u32],
+) {
+    push_tag(byte_vec, Tag::NewerReference);
+
+    let u32_byte_len = mem::size_of::<u32>();
+    let len_usize =
+        (mem::size_of::<u32>() + mem::size_of::<u64>()) / u32_byte_len + extra_ids.len();
+    // > Len - A 16-bit big endian unsigned integer not larger than 5.
+    assert!(len_usize <= NEWER_REFERENCE_EXT_MAX_U32_LEN);
+    append_usize_as_u16(byte_vec, len_usize);
+
+    byte_vec.extend_from_slice(&atom_to_byte_vec(arc_node.name()));
+
+    // > A 32-bit big endian unsigned integer. All identifiers originating from the same node
+    // > incarnation must have identical Creation values.
+    byte_vec.extend_from_slice(&arc_node.creation().to_be_bytes());
+
+    byte_vec.extend_from_slice(&scheduler_id_u32.to_be_bytes());
+    byte_vec.extend_from_slice(&number.to_be_bytes());
+
+    for extra_id in extra_ids {
+        byte_vec.extend_from_slice(&extra_id.to_be_bytes());
+    }
+}
+
+// `pub(crate)` for the control messages of `crate::distribution`, which have no version
+pub(crate) fn append_pid(byte_vec: &mut Vec<u8>, arc_node: Arc<Node>, id: u32, serial: u32) {
     let creation = arc_node.creation();
 
     let tag = if creation <= (std::u8::MAX as u32) {
@@ -114,6 +159,26 @@ fn append_pid(byte_vec: &mut Vec<u8>, arc_node: Arc<Node>, id: u32, serial: u32)
     };
 }
 
+fn append_port(byte_vec: &mut Vec<u8>, arc_node: Arc<Node>, port: Port) {
+    push_tag(byte_vec, Tag::NewPort);
+
+    byte_vec.extend_from_slice(&atom_to_byte_vec(arc_node.name()));
+    // `Port::NUMBER_MAX` fits in the 28 bits of `ID`
+    byte_vec.extend_from_slice(&(port.number() as u32).to_be_bytes());
+    byte_vec.extend_from_slice(&arc_node.creation().to_be_bytes());
+}
+
+// `pub(crate)` for the control messages of `crate::distribution`, which have no version
+pub(crate) fn append_reference(byte_vec: &mut Vec<u8>, reference: &Reference) {
+    append_newer_reference(
+        byte_vec,
+        arc_node(),
+        reference.scheduler_id().into(),
+        reference.number(),
+        &[],
+    );
+}
+
 fn append_usize_as_u16(byte_vec: &mut Vec<u8>, len_usize: usize) {
     assert!(len_usize <= (std::u16::MAX as usize));
     let len_u16 = len_usize as u16;
@@ -126,27 +191,9 @@ fn append_usize_as_u32(byte_vec: &mut Vec<u8>, len_usize: usize) {
     byte_vec.extend_from_slice(&len_u32.to_be_bytes());
 }
 
-fn atom_to_byte_vec(atom: Atom) -> Vec<u8> {
-    let bytes = atom.name().as_bytes();
-    let len_usize = bytes.len();
-    let mut byte_vec: Vec<u8> = Vec::new();
-
-    if bytes.iter().all(|byte| byte.is_ascii()) {
-        push_tag(&mut byte_vec, Tag::Atom);
-        append_usize_as_u16(&mut byte_vec, len_usize);
-    } else if len_usize <= SMALL_ATOM_UTF8_EXT_MAX_LEN {
-        push_tag(&mut byte_vec, Tag::SmallAtomUTF8);
-
-        let len_u8 = len_usize as u8;
-        byte_vec.push(len_u8);
-    } else {
-        push_tag(&mut byte_vec, Tag::AtomUTF8);
-        append_usize_as_u16(&mut byte_vec, len_usize);
-    }
-
-    byte_vec.extend_from_slice(bytes);
-
-    byte_vec
+// `pub(crate)` for the control messages of `crate::distribution`, which have no version
+pub(crate) fn atom_to_byte_vec(atom: Atom) -> Vec<u8> {
+    minor_version_atom_to_byte_vec(&Default::default(), atom)
 }
 
 // Tail is the final tail  of the list; it is NIL_EXT for a proper list, but can be any type if the
@@ -168,11 +215,72 @@ fn cons_to_element_vec_tail(cons: &Cons) -> (Vec<Term>, Term) {
     (element_vec, tail)
 }
 
+/// Wraps the tagged term in `byte_vec` in a `Compressed` envelope if `compression` is on and it
+/// makes the binary smaller.
+fn compress(byte_vec: Vec<u8>, compression: &Compression) -> Vec<u8> {
+    match compression.0 {
+        0 => byte_vec,
+        level => {
+            let tagged_term_bytes = &byte_vec[1..];
+
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::new(level as u32));
+            // Writes to a `Vec` cannot fail
+            encoder.write_all(tagged_term_bytes).unwrap();
+            let mut compressed_byte_vec = encoder.finish().unwrap();
+
+            // VERSION, TAG, and the UncompressedSize
+            let envelope_len = 1 + 1 + mem::size_of::<u32>();
+
+            if envelope_len + compressed_byte_vec.len() < byte_vec.len() {
+                let mut envelope_byte_vec =
+                    Vec::with_capacity(envelope_len + compressed_byte_vec.len());
+                envelope_byte_vec.push(version::NUMBER);
+                push_tag(&mut envelope_byte_vec, Tag::Compressed);
+                append_usize_as_u32(&mut envelope_byte_vec, tagged_term_bytes.len());
+                envelope_byte_vec.append(&mut compressed_byte_vec);
+
+                envelope_byte_vec
+            } else {
+                byte_vec
+            }
+        }
+    }
+}
+
+/// > `{minor_version, 2}` - Option `{minor_version, 2}` also forces atoms to be encoded using
+/// > UTF-8 (`ATOM_UTF8_EXT` or `SMALL_ATOM_UTF8_EXT`)
+///
+/// Lower minor versions only use UTF-8 for atoms that aren't ASCII. The node names of pids, ports,
+/// and references always use the default minor version.
+fn minor_version_atom_to_byte_vec(minor_version: &MinorVersion, atom: Atom) -> Vec<u8> {
+    let bytes = atom.name().as_bytes();
+    let len_usize = bytes.len();
+    let mut byte_vec: Vec<u8> = Vec::new();
+
+    if minor_version.0 < 2 && bytes.iter().all(|byte| byte.is_ascii()) {
+        push_tag(&mut byte_vec, Tag::Atom);
+        append_usize_as_u16(&mut byte_vec, len_usize);
+    } else if len_usize <= SMALL_ATOM_UTF8_EXT_MAX_LEN {
+        push_tag(&mut byte_vec, Tag::SmallAtomUTF8);
+
+        let len_u8 = len_usize as u8;
+        byte_vec.push(len_u8);
+    } else {
+        push_tag(&mut byte_vec, Tag::AtomUTF8);
+        append_usize_as_u16(&mut byte_vec, len_usize);
+    }
+
+    byte_vec.extend_from_slice(bytes);
+
+    byte_vec
+}
+
 fn push_tag(byte_vec: &mut Vec<u8>, tag: Tag) {
     byte_vec.push(tag.into());
 }
 
-fn term_to_byte_vec(process: &Process, options: &Options, term: Term) -> Vec<u8> {
+// `pub(crate)` for the messages of `crate::distribution`, which have the version
+pub(crate) fn term_to_byte_vec(process: &Process, options: &Options, term: Term) -> Vec<u8> {
     let mut stack = VecDeque::new();
     stack.push_front(term);
 
@@ -181,7 +289,10 @@ fn term_to_byte_vec(process: &Process, options: &Options, term: Term) -> Vec<u8>
     while let Some(front_term) = stack.pop_front() {
         match front_term.decode().unwrap() {
             TypedTerm::Atom(atom) => {
-                byte_vec.extend_from_slice(&atom_to_byte_vec(atom));
+                byte_vec.extend_from_slice(&minor_version_atom_to_byte_vec(
+                    &options.minor_version,
+                    atom,
+                ));
             }
             TypedTerm::List(cons) => {
                 match try_cons_to_string_ext_byte_vec(&cons) {
@@ -239,15 +350,26 @@ fn term_to_byte_vec(process: &Process, options: &Options, term: Term) -> Vec<u8>
             TypedTerm::Float(float) => {
                 let float_f64: f64 = float.into();
 
-                push_tag(&mut byte_vec, Tag::NewFloat);
-                byte_vec.extend_from_slice(&float_f64.to_be_bytes());
+                // > `{minor_version, 0}` - Causes floats to be encoded in a textual format.
+                if options.minor_version.0 == 0 {
+                    append_float_ext(&mut byte_vec, float_f64);
+                } else {
+                    push_tag(&mut byte_vec, Tag::NewFloat);
+                    byte_vec.extend_from_slice(&float_f64.to_be_bytes());
+                }
             }
             TypedTerm::Closure(closure) => {
                 match closure.definition() {
                     Definition::Export { function } => {
                         push_tag(&mut byte_vec, Tag::Export);
-                        byte_vec.append(&mut atom_to_byte_vec(closure.module()));
-                        byte_vec.append(&mut atom_to_byte_vec(*function));
+                        byte_vec.append(&mut minor_version_atom_to_byte_vec(
+                            &options.minor_version,
+                            closure.module(),
+                        ));
+                        byte_vec.append(&mut minor_version_atom_to_byte_vec(
+                            &options.minor_version,
+                            *function,
+                        ));
                         try_append_isize_as_small_integer_or_integer(
                             &mut byte_vec,
                             closure.arity() as isize,
@@ -271,7 +393,10 @@ fn term_to_byte_vec(process: &Process, options: &Options, term: Term) -> Vec<u8>
                         let env_len_u32: u32 = closure.env_len().try_into().unwrap();
                         sized_byte_vec.extend_from_slice(&env_len_u32.to_be_bytes());
 
-                        sized_byte_vec.append(&mut atom_to_byte_vec(module_function_arity.module));
+                        sized_byte_vec.append(&mut minor_version_atom_to_byte_vec(
+                            &options.minor_version,
+                            module_function_arity.module,
+                        ));
 
                         // > [index] encoded using SMALL_INTEGER_EXT or INTEGER_EXT.
                         try_append_isize_as_small_integer_or_integer(
@@ -318,9 +443,17 @@ fn term_to_byte_vec(process: &Process, options: &Options, term: Term) -> Vec<u8>
                 let len_usize = map.len();
                 append_usize_as_u32(&mut byte_vec, len_usize);
 
-                for (key, value) in map.iter() {
-                    stack.push_front(*value);
-                    stack.push_front(*key);
+                if options.deterministic {
+                    // Pushed in reverse, so that the pairs are popped in the term order of the keys
+                    for key in map.sorted_keys().into_iter().rev() {
+                        stack.push_front(map.get(key).unwrap());
+                        stack.push_front(key);
+                    }
+                } else {
+                    for (key, value) in map.iter() {
+                        stack.push_front(*value);
+                        stack.push_front(*key);
+                    }
                 }
             }
             TypedTerm::HeapBinary(heap_bin) => {
@@ -352,25 +485,27 @@ fn term_to_byte_vec(process: &Process, options: &Options, term: Term) -> Vec<u8>
 
                 byte_vec.extend_from_slice(proc_bin.as_bytes());
             }
+            TypedTerm::Port(port) => {
+                append_port(&mut byte_vec, arc_node(), port);
+            }
+            TypedTerm::ExternalPort(external_port) => {
+                append_port(
+                    &mut byte_vec,
+                    external_port.arc_node(),
+                    external_port.port(),
+                );
+            }
             TypedTerm::Reference(reference) => {
-                let scheduler_id_u32: u32 = reference.scheduler_id().into();
-                let number: u64 = reference.number().into();
-
-                push_tag(&mut byte_vec, Tag::NewerReference);
-
-                let u32_byte_len = mem::size_of::<u32>();
-                let len_usize = (mem::size_of::<u32>() + mem::size_of::<u64>()) / u32_byte_len;
-                // > Len - A 16-bit big endian unsigned integer not larger than 3.
-                assert!(len_usize <= NEWER_REFERENCE_EXT_MAX_U32_LEN);
-                append_usize_as_u16(&mut byte_vec, len_usize);
-
-                byte_vec.extend_from_slice(&atom_to_byte_vec(node::atom()));
-
-                let creation_u32 = CREATION as u32;
-                byte_vec.extend_from_slice(&creation_u32.to_be_bytes());
-
-                byte_vec.extend_from_slice(&scheduler_id_u32.to_be_bytes());
-                byte_vec.extend_from_slice(&number.to_be_bytes());
+                append_reference(&mut byte_vec, &reference);
+            }
+            TypedTerm::ExternalReference(external_reference) => {
+                append_newer_reference(
+                    &mut byte_vec,
+                    external_reference.arc_node(),
+                    external_reference.scheduler_id().into(),
+                    external_reference.number(),
+                    external_reference.extra_ids(),
+                );
             }
             TypedTerm::SubBinary(subbinary) => {
                 if subbinary.is_binary() {
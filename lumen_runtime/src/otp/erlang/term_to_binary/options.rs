@@ -3,14 +3,19 @@ mod minor_version;
 
 use std::convert::{TryFrom, TryInto};
 
+use anyhow::*;
+
 use liblumen_alloc::erts::term::prelude::*;
 
-use compression::*;
-use minor_version::*;
+use crate::proplist::TryPropListFromTermError;
+
+pub use compression::*;
+pub use minor_version::*;
 
 pub struct Options {
-    compression: Compression,
-    minor_version: MinorVersion,
+    pub compression: Compression,
+    pub deterministic: bool,
+    pub minor_version: MinorVersion,
 }
 
 impl Default for Options {
@@ -18,13 +23,14 @@ impl Default for Options {
         Self {
             // No compression is done (it is the same as giving no compressed option)
             compression: Compression(0),
+            deterministic: false,
             minor_version: Default::default(),
         }
     }
 }
 
 impl Options {
-    fn put_option_term(&mut self, option: Term) -> Result<&Self, TryFromTermError> {
+    fn put_option_term(&mut self, option: Term) -> anyhow::Result<&Self> {
         match option.decode().unwrap() {
             TypedTerm::Atom(atom) => match atom.name() {
                 "compressed" => {
@@ -32,44 +38,48 @@ impl Options {
 
                     Ok(self)
                 }
-                name => Err(TryFromTermError::AtomName(name)),
+                "deterministic" => {
+                    self.deterministic = true;
+
+                    Ok(self)
+                }
+                name => Err(TryPropListFromTermError::AtomName(name).into()),
             },
             TypedTerm::Tuple(tuple) => {
                 if tuple.len() == 2 {
                     let atom: Atom = tuple[0]
                         .try_into()
-                        .map_err(|_| TryFromTermError::KeywordKeyType)?;
+                        .map_err(|_| TryPropListFromTermError::KeywordKeyType)?;
 
                     match atom.name() {
                         "compressed" => {
-                            self.compression = tuple[1]
-                                .try_into()
-                                .map_err(|_| TryFromTermError::CompressedType)?;
+                            self.compression = tuple[1].try_into().context("compressed")?;
 
                             Ok(self)
                         }
                         "minor_version" => {
-                            self.minor_version = tuple[1]
-                                .try_into()
-                                .map_err(|_| TryFromTermError::MinorVersionType)?;
+                            self.minor_version = tuple[1].try_into().context("minor_version")?;
 
                             Ok(self)
                         }
-                        name => Err(TryFromTermError::KeywordKeyName(name)),
+                        name => Err(TryPropListFromTermError::KeywordKeyName(name).into()),
                     }
                 } else {
-                    Err(TryFromTermError::TupleLen)
+                    Err(TryPropListFromTermError::TupleNotPair.into())
                 }
             }
-            _ => Err(TryFromTermError::ElementType),
+            _ => Err(TryPropListFromTermError::PropertyType.into()),
         }
     }
 }
 
+const SUPPORTED_OPTIONS_CONTEXT: &str = "supported options are :compressed, \
+     {:compressed, level :: 0..9}, :deterministic, and {:minor_version, version :: 0..2}";
+
 impl TryFrom<Term> for Options {
-    type Error = TryFromTermError;
+    type Error = anyhow::Error;
 
-    fn try_from(term: Term) -> Result<Self, Self::Error> {
+    fn try_from(term: Term) -> anyhow::Result<Self> {
         let mut options: Options = Default::default();
         let mut options_term = term;
 
@@ -77,24 +87,15 @@ impl TryFrom<Term> for Options {
             match options_term.decode().unwrap() {
                 TypedTerm::Nil => return Ok(options),
                 TypedTerm::List(cons) => {
-                    options.put_option_term(cons.head)?;
+                    options
+                        .put_option_term(cons.head)
+                        .context(SUPPORTED_OPTIONS_CONTEXT)?;
                     options_term = cons.tail;
 
                     continue;
                 }
-                _ => return Err(TryFromTermError::Type),
+                _ => return Err(ImproperListError).context(SUPPORTED_OPTIONS_CONTEXT),
             };
         }
     }
 }
-
-pub enum TryFromTermError {
-    AtomName(&'static str),
-    CompressedType,
-    MinorVersionType,
-    KeywordKeyType,
-    KeywordKeyName(&'static str),
-    ElementType,
-    TupleLen,
-    Type,
-}
@@ -1,5 +1,7 @@
 use std::convert::{TryFrom, TryInto};
 
+use anyhow::*;
+
 use liblumen_alloc::erts::term::prelude::*;
 
 pub struct Compression(pub u8);
@@ -17,29 +19,22 @@ impl Default for Compression {
 }
 
 impl TryFrom<Term> for Compression {
-    type Error = TryFromTermError;
+    type Error = anyhow::Error;
 
-    fn try_from(term: Term) -> Result<Self, Self::Error> {
-        let term_u8: u8 = term.try_into()?;
+    fn try_from(term: Term) -> anyhow::Result<Self> {
+        let term_u8: u8 = term
+            .try_into()
+            .context("compression level must be an integer in 0..9")?;
 
-        if Self::MIN_U8 <= term_u8 && term_u8 <= Self::MAX_U8 {
+        if (Self::MIN_U8..=Self::MAX_U8).contains(&term_u8) {
             Ok(Self(term_u8))
         } else {
-            Err(TryFromTermError::OutOfRange)
-        }
-    }
-}
-
-pub enum TryFromTermError {
-    OutOfRange,
-    Type,
-}
-
-impl From<TryIntoIntegerError> for TryFromTermError {
-    fn from(error: TryIntoIntegerError) -> Self {
-        match error {
-            TryIntoIntegerError::Type => TryFromTermError::Type,
-            TryIntoIntegerError::OutOfRange => TryFromTermError::OutOfRange,
+            Err(anyhow!(
+                "compression level ({}) must be in {}..{}",
+                term_u8,
+                Self::MIN_U8,
+                Self::MAX_U8
+            ))
         }
     }
 }
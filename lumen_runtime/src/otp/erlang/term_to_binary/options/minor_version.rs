@@ -1,8 +1,10 @@
 use std::convert::{TryFrom, TryInto};
 
+use anyhow::*;
+
 use liblumen_alloc::erts::term::prelude::*;
 
-pub struct MinorVersion(u8);
+pub struct MinorVersion(pub u8);
 
 impl MinorVersion {
     const MIN_U8: u8 = 0;
@@ -16,29 +18,22 @@ impl Default for MinorVersion {
 }
 
 impl TryFrom<Term> for MinorVersion {
-    type Error = TryFromTermError;
+    type Error = anyhow::Error;
 
-    fn try_from(term: Term) -> Result<Self, Self::Error> {
-        let term_u8: u8 = term.try_into()?;
+    fn try_from(term: Term) -> anyhow::Result<Self> {
+        let term_u8: u8 = term
+            .try_into()
+            .context("minor version must be an integer in 0..2")?;
 
-        if Self::MIN_U8 <= term_u8 && term_u8 <= Self::MAX_U8 {
+        if (Self::MIN_U8..=Self::MAX_U8).contains(&term_u8) {
             Ok(Self(term_u8))
         } else {
-            Err(TryFromTermError::OutOfRange)
-        }
-    }
-}
-
-pub enum TryFromTermError {
-    OutOfRange,
-    Type,
-}
-
-impl From<TryIntoIntegerError> for TryFromTermError {
-    fn from(error: TryIntoIntegerError) -> Self {
-        match error {
-            TryIntoIntegerError::Type => TryFromTermError::Type,
-            TryIntoIntegerError::OutOfRange => TryFromTermError::OutOfRange,
+            Err(anyhow!(
+                "minor version ({}) must be in {}..{}",
+                term_u8,
+                Self::MIN_U8,
+                Self::MAX_U8
+            ))
         }
     }
 }
@@ -1,5 +1,5 @@
 use proptest::strategy::Just;
-use proptest::{prop_assert, prop_assert_eq};
+use proptest::{prop_assert, prop_assert_eq, prop_oneof};
 
 use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::scheduler;
@@ -29,6 +29,30 @@ fn roundtrips_through_binary_to_term() {
     );
 }
 
+#[test]
+fn with_port_or_reference_roundtrips_through_binary_to_term() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                prop_oneof![
+                    strategy::term::port::local(),
+                    strategy::term::port::external(arc_process.clone()),
+                    strategy::term::local_reference(arc_process.clone()),
+                    strategy::term::external_reference(arc_process),
+                ],
+            )
+        },
+        |(arc_process, term)| {
+            let binary = native(&arc_process, term).unwrap();
+
+            prop_assert_eq!(binary_to_term_1::native(&arc_process, binary), Ok(term));
+
+            Ok(())
+        },
+    );
+}
+
 // NEW_FLOAT_EXT (70)
 #[test]
 fn with_negative_float_returns_new_float_ext() {
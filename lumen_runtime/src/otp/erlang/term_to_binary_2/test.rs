@@ -0,0 +1,280 @@
+use proptest::prop_assert_eq;
+use proptest::strategy::Just;
+
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang;
+use crate::otp::erlang::binary_to_term_1;
+use crate::otp::erlang::term_to_binary_2::native;
+use crate::scheduler::with_process;
+use crate::test::strategy;
+
+#[test]
+fn without_proper_list_options_errors_badarg() {
+    with_process(|process| {
+        let term = Atom::str_to_term("term");
+        let options = Atom::str_to_term("compressed");
+
+        assert_badarg!(
+            native(process, term, options),
+            "supported options are :compressed"
+        );
+    });
+}
+
+#[test]
+fn with_compression_level_greater_than_9_errors_badarg() {
+    with_process(|process| {
+        let term = Atom::str_to_term("term");
+        let options = compressed_level_options(process, 10);
+
+        assert_badarg!(native(process, term, options), "compression level (10)");
+    });
+}
+
+#[test]
+fn with_compressed_roundtrips_through_binary_to_term() {
+    run!(
+        |arc_process| (Just(arc_process.clone()), strategy::term(arc_process)),
+        |(arc_process, term)| {
+            let options = compressed_options(&arc_process);
+            let binary = native(&arc_process, term, options).unwrap();
+
+            prop_assert_eq!(binary_to_term_1::native(&arc_process, binary), Ok(term));
+
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn with_compressed_with_compressible_term_returns_compressed_envelope() {
+    with_process(|process| {
+        let term = process.binary_from_bytes(&[0; 1000]).unwrap();
+        let options = compressed_options(process);
+
+        let binary = native(process, term, options).unwrap();
+        let split_binary_tuple =
+            erlang::split_binary_2::native(process, binary, process.integer(6).unwrap()).unwrap();
+        let prefix =
+            erlang::element_2::native(process.integer(1).unwrap(), split_binary_tuple).unwrap();
+
+        // VERSION, COMPRESSED, and UncompressedSize of 1 (BINARY_EXT) + 4 (Len) + 1000 bytes
+        assert_eq!(
+            prefix,
+            process.binary_from_bytes(&[131, 80, 0, 0, 3, 237]).unwrap()
+        );
+        assert_eq!(binary_to_term_1::native(process, binary), Ok(term));
+    });
+}
+
+#[test]
+fn with_compressed_with_incompressible_term_returns_uncompressed() {
+    with_process(|process| {
+        let term = Atom::str_to_term("a");
+
+        assert_eq!(
+            native(process, term, compressed_options(process)),
+            native(process, term, Term::NIL)
+        );
+    });
+}
+
+#[test]
+fn with_compressed_level_0_returns_uncompressed() {
+    with_process(|process| {
+        let term = process.binary_from_bytes(&[0; 1000]).unwrap();
+
+        assert_eq!(
+            native(process, term, compressed_level_options(process, 0)),
+            native(process, term, Term::NIL)
+        );
+    });
+}
+
+#[test]
+fn with_minor_version_0_with_float_returns_float_ext() {
+    with_process(|process| {
+        let options = minor_version_options(process, 0);
+
+        assert_eq!(
+            native(process, process.float(1.0).unwrap(), options),
+            Ok(process
+                .binary_from_bytes(&[
+                    131, 99, 49, 46, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48, 48,
+                    48, 48, 48, 48, 48, 101, 43, 48, 48, 0, 0, 0, 0, 0
+                ])
+                .unwrap())
+        );
+    });
+}
+
+#[test]
+fn with_minor_version_0_with_float_roundtrips_through_binary_to_term() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                strategy::term::float(arc_process.clone()),
+            )
+        },
+        |(arc_process, term)| {
+            let options = minor_version_options(&arc_process, 0);
+            let binary = native(&arc_process, term, options).unwrap();
+
+            prop_assert_eq!(binary_to_term_1::native(&arc_process, binary), Ok(term));
+
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn with_minor_version_1_with_float_returns_new_float_ext() {
+    with_process(|process| {
+        let options = minor_version_options(process, 1);
+
+        assert_eq!(
+            native(process, process.float(1.0).unwrap(), options),
+            Ok(process
+                .binary_from_bytes(&[131, 70, 63, 240, 0, 0, 0, 0, 0, 0])
+                .unwrap())
+        );
+    });
+}
+
+#[test]
+fn with_minor_version_1_with_ascii_atom_returns_atom_ext() {
+    with_process(|process| {
+        let options = minor_version_options(process, 1);
+
+        assert_eq!(
+            native(process, Atom::str_to_term("atom"), options),
+            Ok(process
+                .binary_from_bytes(&[131, 100, 0, 4, 97, 116, 111, 109])
+                .unwrap())
+        );
+    });
+}
+
+#[test]
+fn with_minor_version_2_with_ascii_atom_returns_small_atom_utf8_ext() {
+    with_process(|process| {
+        let options = minor_version_options(process, 2);
+
+        assert_eq!(
+            native(process, Atom::str_to_term("atom"), options),
+            Ok(process
+                .binary_from_bytes(&[131, 119, 4, 97, 116, 111, 109])
+                .unwrap())
+        );
+    });
+}
+
+#[test]
+fn with_minor_version_greater_than_2_errors_badarg() {
+    with_process(|process| {
+        let term = Atom::str_to_term("term");
+        let options = minor_version_options(process, 3);
+
+        assert_badarg!(native(process, term, options), "minor version (3)");
+    });
+}
+
+#[test]
+fn with_deterministic_with_map_returns_pairs_in_key_order() {
+    with_process(|process| {
+        let map = process
+            .map_from_slice(&[
+                (process.integer(3).unwrap(), process.integer(3).unwrap()),
+                (process.integer(1).unwrap(), process.integer(1).unwrap()),
+                (process.integer(2).unwrap(), process.integer(2).unwrap()),
+            ])
+            .unwrap();
+        let options = process
+            .list_from_slice(&[Atom::str_to_term("deterministic")])
+            .unwrap();
+
+        assert_eq!(
+            native(process, map, options),
+            Ok(process
+                .binary_from_bytes(&[
+                    131, 116, 0, 0, 0, 3, 97, 1, 97, 1, 97, 2, 97, 2, 97, 3, 97, 3
+                ])
+                .unwrap())
+        );
+    });
+}
+
+#[test]
+fn with_deterministic_with_map_with_equal_integer_and_float_keys_returns_integer_key_first() {
+    with_process(|process| {
+        let integer_pair = (process.integer(1).unwrap(), process.integer(1).unwrap());
+        let float_pair = (process.float(1.0).unwrap(), process.integer(2).unwrap());
+        let options = process
+            .list_from_slice(&[Atom::str_to_term("deterministic")])
+            .unwrap();
+        let expected = process
+            .binary_from_bytes(&[
+                131, 116, 0, 0, 0, 2, 97, 1, 97, 1, 70, 63, 240, 0, 0, 0, 0, 0, 0, 97, 2,
+            ])
+            .unwrap();
+
+        for pairs in &[[integer_pair, float_pair], [float_pair, integer_pair]] {
+            let map = process.map_from_slice(pairs).unwrap();
+
+            assert_eq!(native(process, map, options), Ok(expected));
+        }
+    });
+}
+
+#[test]
+fn with_deterministic_with_map_roundtrips_through_binary_to_term() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                strategy::term::map(arc_process.clone()),
+            )
+        },
+        |(arc_process, term)| {
+            let options = arc_process
+                .list_from_slice(&[Atom::str_to_term("deterministic")])
+                .unwrap();
+            let binary = native(&arc_process, term, options).unwrap();
+
+            prop_assert_eq!(binary_to_term_1::native(&arc_process, binary), Ok(term));
+
+            Ok(())
+        },
+    );
+}
+
+fn compressed_level_options(process: &Process, level: u8) -> Term {
+    process
+        .list_from_slice(&[process
+            .tuple_from_slice(&[
+                Atom::str_to_term("compressed"),
+                process.integer(level).unwrap(),
+            ])
+            .unwrap()])
+        .unwrap()
+}
+
+fn compressed_options(process: &Process) -> Term {
+    process
+        .list_from_slice(&[Atom::str_to_term("compressed")])
+        .unwrap()
+}
+
+fn minor_version_options(process: &Process, minor_version: u8) -> Term {
+    process
+        .list_from_slice(&[process
+            .tuple_from_slice(&[
+                Atom::str_to_term("minor_version"),
+                process.integer(minor_version).unwrap(),
+            ])
+            .unwrap()])
+        .unwrap()
+}
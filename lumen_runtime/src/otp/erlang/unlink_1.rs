@@ -13,6 +13,8 @@ use liblumen_alloc::erts::term::prelude::*;
 
 use lumen_runtime_macros::native_implemented_function;
 
+use crate::distribution;
+use crate::port;
 use crate::registry::pid_to_process;
 
 #[native_implemented_function(unlink/1)]
@@ -32,8 +34,16 @@ fn native(process: &Process, pid_or_port: Term) -> exception::Result<Term> {
                 Ok(true.into())
             }
         }
-        TypedTerm::Port(_) => unimplemented!(),
-        TypedTerm::ExternalPid(_) => unimplemented!(),
+        TypedTerm::Port(port) => {
+            port::unlink(process, port);
+
+            Ok(true.into())
+        }
+        TypedTerm::ExternalPid(external_pid) => {
+            distribution::unlink(process, &external_pid);
+
+            Ok(true.into())
+        }
         TypedTerm::ExternalPort(_) => unimplemented!(),
         _ => Err(TypeError)
             .context(format!(
@@ -1,10 +1,12 @@
 //! Mirrors [lists](http://erlang.org/doc/man/lists.html) module
 
-pub mod keyfind_3;
-pub mod keymember_3;
-pub mod member_2;
-pub mod reverse_1;
-pub mod reverse_2;
+native_functions! {
+    keyfind_3,
+    keymember_3,
+    member_2,
+    reverse_1,
+    reverse_2,
+}
 
 use liblumen_alloc::erts::term::prelude::Atom;
 
@@ -1,15 +1,17 @@
-pub mod find_2;
-pub mod from_list_1;
-pub mod get_2;
-pub mod get_3;
-pub mod is_key_2;
-pub mod keys_1;
-pub mod merge_2;
-pub mod put_3;
-pub mod remove_2;
-pub mod take_2;
-pub mod update_3;
-pub mod values_1;
+native_functions! {
+    find_2,
+    from_list_1,
+    get_2,
+    get_3,
+    is_key_2,
+    keys_1,
+    merge_2,
+    put_3,
+    remove_2,
+    take_2,
+    update_3,
+    values_1,
+}
 
 use liblumen_alloc::erts::term::prelude::Atom;
 
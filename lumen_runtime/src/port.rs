@@ -0,0 +1,632 @@
+//! Ports run external programs and exchange their standard I/O with the connected process.
+//!
+//! Each open port has a thread reading the program's output, which queues events for the scheduler
+//! that opened the port and wakes it.  The scheduler then delivers the events as messages to the
+//! connected process when it `poll`s its ports:
+//!
+//! * `{Port, {data, Data}}` for output, as a list of bytes or, with the `binary` option, a binary
+//! * `{Port, eof}` when the output is closed, only with the `eof` option
+//! * `{Port, {exit_status, Status}}` when the program exits, only with the `exit_status` option
+//!
+//! Data from `port_command/2` is queued for another thread of the port that writes it to the
+//! program, so that a program that doesn't read its input never blocks a scheduler.
+//!
+//! Like the BEAM, a port is linked to the process that opened it.  When a port closes, its linked
+//! processes are sent an exit signal, which they receive as `{'EXIT', Port, Reason}` when trapping
+//! exits.  When the connected process exits, the port is closed.
+//!
+//! Only external programs can be run.  `{spawn_driver, Command}` is not supported, as there are no
+//! linked-in drivers to load.
+mod options;
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::*;
+use hashbrown::{HashMap, HashSet};
+
+use liblumen_core::locks::{Condvar, Mutex, RwLock};
+
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::exception::{AllocResult, ArcError, RuntimeException};
+use liblumen_alloc::erts::process::alloc::TermAlloc;
+use liblumen_alloc::erts::process::{Process, Status};
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{CloneToProcess, HeapFragment};
+
+use crate::process;
+use crate::registry;
+use crate::scheduler::{Scheduled, Scheduler, ID};
+
+pub use options::*;
+
+const READ_BUFFER_SIZE: usize = 4096;
+/// How often the port's thread checks whether the program exited after it closed its output
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Spawns the program for `name` with `options` and connects its port to `process`, which is
+/// linked to the port.
+pub fn open(process: &Process, name: Name, options: Options) -> io::Result<Port> {
+    let scheduler_id = process.scheduler_id().unwrap();
+    let mut command = name.command(&options);
+    let mut child = command.spawn()?;
+
+    let port = Port::next();
+    let option_stdin = child.stdin.take();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let open_port = Arc::new(OpenPort {
+        port,
+        scheduler_id,
+        connected: Mutex::new(process.pid()),
+        linked: Default::default(),
+        binary: options.binary,
+        eof: options.eof,
+        exit_status: options.exit_status,
+        input: Mutex::new(Input {
+            queue: Default::default(),
+            // Opened with `in`, so it can't be written to
+            closed: option_stdin.is_none(),
+        }),
+        input_condvar: Condvar::new(),
+        child: Mutex::new(child),
+        events: Default::default(),
+    });
+
+    // The port's threads are detached, as they finish on their own once the program is killed
+    if let Some(stdin) = option_stdin {
+        let writer_open_port = Arc::clone(&open_port);
+
+        if let Err(err) = thread::Builder::new()
+            .name(format!("port-{}-writer", port.number()))
+            .spawn(move || write(writer_open_port, stdin))
+        {
+            let _ = open_port.child.lock().kill();
+
+            return Err(err);
+        }
+    }
+
+    let reader_open_port = Arc::clone(&open_port);
+
+    if let Err(err) = thread::Builder::new()
+        .name(format!("port-{}", port.number()))
+        .spawn(move || read(reader_open_port, stdout, stderr))
+    {
+        open_port.close_input();
+        let _ = open_port.child.lock().kill();
+
+        return Err(err);
+    }
+
+    link_open_port(process, &open_port);
+
+    OPEN_PORT_BY_PORT
+        .write()
+        .insert(port, Arc::clone(&open_port));
+    OPEN_PORTS_BY_SCHEDULER_ID
+        .write()
+        .entry(scheduler_id)
+        .or_insert_with(Vec::new)
+        .push(open_port);
+
+    Ok(port)
+}
+
+/// Queues `bytes` to be written to the standard input of the program of `port`.
+///
+/// Returns `false` if `port` is closed or can't be written to.
+pub fn command(port: Port, bytes: &[u8]) -> bool {
+    match open_port(port) {
+        Some(open_port) => {
+            let mut input = open_port.input.lock();
+
+            if input.closed {
+                false
+            } else {
+                input.queue.push_back(bytes.to_vec());
+                open_port.input_condvar.notify_one();
+
+                true
+            }
+        }
+        None => false,
+    }
+}
+
+/// Closes `port`, which kills its program.  No more messages are sent from `port` and its linked
+/// processes are sent an exit signal with `normal`.
+///
+/// Returns `false` if `port` is already closed.
+pub fn close(port: Port) -> bool {
+    exit(port, atom!("normal"), anyhow!("{} closed", port).into())
+}
+
+/// Sends the future messages from `port` to `pid` instead of its currently connected process and,
+/// like the BEAM, links `pid` to `port`.
+///
+/// Returns `false` if `port` is closed.
+pub fn connect(port: Port, pid: Pid) -> bool {
+    match open_port(port) {
+        Some(open_port) => {
+            *open_port.connected.lock() = pid;
+
+            if let Some(arc_process) = registry::pid_to_process(&pid) {
+                link_open_port(&arc_process, &open_port);
+            }
+
+            true
+        }
+        None => false,
+    }
+}
+
+/// The process connected to `port`, if `port` is open
+pub fn connected(port: Port) -> Option<Pid> {
+    open_port(port).map(|open_port| *open_port.connected.lock())
+}
+
+/// Closes `port` because of an exit signal with `reason`, sending an exit signal with `reason` to
+/// its linked processes.
+///
+/// Returns `false` if `port` is already closed.
+pub fn exit(port: Port, reason: Term, source: ArcError) -> bool {
+    match remove(port) {
+        Some(open_port) => {
+            open_port.exit(reason, source);
+
+            true
+        }
+        None => false,
+    }
+}
+
+/// Links `process` to `port`.
+///
+/// Returns `false` if `port` is closed.
+pub fn link(process: &Process, port: Port) -> bool {
+    match open_port(port) {
+        Some(open_port) => {
+            link_open_port(process, &open_port);
+
+            true
+        }
+        None => false,
+    }
+}
+
+/// Delivers the I/O events of the ports opened on the scheduler with `scheduler_id` to their
+/// connected processes, closing the ports whose programs exited.
+///
+/// Returns `true` if any event was delivered.
+pub fn poll(scheduler_id: &ID) -> bool {
+    // separate from the loop below so that the lock is not held while delivering
+    let port_events: Vec<(Arc<OpenPort>, VecDeque<Event>)> =
+        match OPEN_PORTS_BY_SCHEDULER_ID.read().get(scheduler_id) {
+            Some(open_ports) => open_ports
+                .iter()
+                .filter_map(|open_port| {
+                    let events = mem::take(&mut *open_port.events.lock());
+
+                    if events.is_empty() {
+                        None
+                    } else {
+                        Some((Arc::clone(open_port), events))
+                    }
+                })
+                .collect(),
+            None => return false,
+        };
+    let polled = !port_events.is_empty();
+
+    for (open_port, events) in port_events {
+        for event in events {
+            match event {
+                Event::Exit(_) => {
+                    // Closed while the events were delivered
+                    if remove(open_port.port).is_some() {
+                        open_port.deliver(event);
+                        open_port.exit(
+                            atom!("normal"),
+                            anyhow!("program of {} exited", open_port.port).into(),
+                        );
+                    }
+                }
+                _ => open_port.deliver(event),
+            }
+        }
+    }
+
+    polled
+}
+
+/// Sends the exit of `process` to the ports it is linked to.  The ports `process` is connected to
+/// always close, while the others only close if the exit is not `normal`.
+pub fn propagate_exit(process: &Process, exception: &RuntimeException) {
+    let pid = process.pid();
+    let reason = exception.reason().unwrap_or_else(|| atom!("system_error"));
+    let linked_ports: Vec<Port> = process.linked_port_set.lock().drain().collect();
+
+    for port in linked_ports {
+        if let Some(open_port) = open_port(port) {
+            open_port.linked.lock().remove(&pid);
+
+            if *open_port.connected.lock() == pid || reason != atom!("normal") {
+                exit(
+                    port,
+                    reason,
+                    anyhow!("propagating exit from {}", process).into(),
+                );
+            }
+        }
+    }
+}
+
+/// Unlinks `process` from `port`, if `port` is open
+pub fn unlink(process: &Process, port: Port) {
+    if let Some(open_port) = open_port(port) {
+        // link in the same order as `link_open_port` to prevent deadlocks
+        let mut port_linked = open_port.linked.lock();
+        let mut process_linked = process.linked_port_set.lock();
+
+        port_linked.remove(&process.pid());
+        process_linked.remove(&port);
+    }
+}
+
+// Private
+
+enum Event {
+    Data(Vec<u8>),
+    Eof,
+    Exit(i32),
+}
+
+/// Data queued by `command` for the port's writer thread
+struct Input {
+    queue: VecDeque<Vec<u8>>,
+    /// Once `true`, the writer thread stops and closes the program's standard input
+    closed: bool,
+}
+
+struct OpenPort {
+    port: Port,
+    scheduler_id: ID,
+    connected: Mutex<Pid>,
+    linked: Mutex<HashSet<Pid>>,
+    binary: bool,
+    eof: bool,
+    exit_status: bool,
+    input: Mutex<Input>,
+    input_condvar: Condvar,
+    child: Mutex<Child>,
+    events: Mutex<VecDeque<Event>>,
+}
+
+impl OpenPort {
+    fn close_input(&self) {
+        let mut input = self.input.lock();
+        input.queue.clear();
+        input.closed = true;
+
+        self.input_condvar.notify_one();
+    }
+
+    fn deliver(&self, event: Event) {
+        match event {
+            Event::Data(bytes) => {
+                // `{Port, {data, Data}}` with `Data` as either a list of 2-word cons cells or a
+                // binary, which is never bigger than the list.
+                let words = 6 + 2 * bytes.len() + 8;
+                let binary = self.binary;
+
+                self.send(words, |heap| {
+                    // Reads are never bigger than `READ_BUFFER_SIZE`, so the data fits in a heap
+                    // binary
+                    let data = if binary {
+                        heap.heapbin_from_bytes(&bytes)?.encode().unwrap()
+                    } else {
+                        let byte_terms = bytes.iter().map(|byte| (*byte).into());
+
+                        match heap.list_from_iter(byte_terms)? {
+                            Some(cons) => cons.encode().unwrap(),
+                            None => Term::NIL,
+                        }
+                    };
+                    let data_tuple = heap.tuple_from_slice(&[atom!("data"), data])?;
+
+                    Ok(data_tuple.encode().unwrap())
+                })
+            }
+            Event::Eof => {
+                if self.eof {
+                    self.send(3, |_| Ok(atom!("eof")))
+                }
+            }
+            Event::Exit(status) => {
+                if self.exit_status {
+                    self.send(6, |heap| {
+                        let status = heap.integer(status)?;
+                        let exit_status_tuple =
+                            heap.tuple_from_slice(&[atom!("exit_status"), status])?;
+
+                        Ok(exit_status_tuple.encode().unwrap())
+                    })
+                }
+            }
+        }
+    }
+
+    /// Kills the program of the port, which must already be removed, and sends an exit signal
+    /// with `reason` to the linked processes
+    fn exit(&self, reason: Term, source: ArcError) {
+        self.close_input();
+        // The program may have already exited, in which case there is nothing to kill
+        let _ = self.child.lock().kill();
+
+        let linked: Vec<Pid> = self.linked.lock().drain().collect();
+
+        for pid in linked {
+            if let Some(arc_process) = registry::pid_to_process(&pid) {
+                arc_process.linked_port_set.lock().remove(&self.port);
+
+                if arc_process.traps_exit() {
+                    // `{'EXIT', Port, Reason}`
+                    let words = 4 + reason.size_in_words();
+                    let port = self.port.encode().unwrap();
+
+                    send(&arc_process, words, |heap| {
+                        let reason = reason.clone_to_heap(heap)?;
+
+                        heap.tuple_from_slice(&[atom!("EXIT"), port, reason])
+                            .map(|tuple| tuple.encode().unwrap())
+                    });
+                } else if reason != atom!("normal") {
+                    process::exit_with_reason(&arc_process, reason, source.clone());
+                }
+            }
+        }
+    }
+
+    fn push(&self, event: Event) {
+        self.events.lock().push_back(event);
+
+        if let Some(arc_scheduler) = Scheduler::from_id(&self.scheduler_id) {
+            arc_scheduler.unpark();
+        }
+    }
+
+    /// Sends `{Port, Info}` to the connected process with `Info` built by `build` in a heap
+    /// fragment of `words`
+    fn send<F>(&self, words: usize, build: F)
+    where
+        F: FnOnce(&mut HeapFragment) -> AllocResult<Term>,
+    {
+        let connected = *self.connected.lock();
+
+        if let Some(destination_arc_process) = registry::pid_to_process(&connected) {
+            let port = self.port.encode().unwrap();
+
+            send(&destination_arc_process, words + 3, |heap| {
+                let info = build(heap)?;
+
+                heap.tuple_from_slice(&[port, info])
+                    .map(|tuple| tuple.encode().unwrap())
+            });
+        }
+    }
+}
+
+/// Links `process` and `open_port`, taking the locks in the same order as `unlink`
+fn link_open_port(process: &Process, open_port: &OpenPort) {
+    let mut port_linked = open_port.linked.lock();
+    let mut process_linked = process.linked_port_set.lock();
+
+    port_linked.insert(process.pid());
+    process_linked.insert(open_port.port);
+}
+
+fn open_port(port: Port) -> Option<Arc<OpenPort>> {
+    OPEN_PORT_BY_PORT.read().get(&port).cloned()
+}
+
+/// Runs on the port's thread, queuing the output of the program until it closes its output and
+/// then its exit status.
+fn read(
+    open_port: Arc<OpenPort>,
+    option_stdout: Option<ChildStdout>,
+    option_stderr: Option<ChildStderr>,
+) {
+    // With `stderr_to_stdout`, standard error is read on its own thread, so that a program
+    // blocked writing to one of them does not deadlock with this thread reading the other.
+    let option_stderr_thread = option_stderr.map(|stderr| {
+        let open_port = Arc::clone(&open_port);
+
+        thread::spawn(move || read_data(&open_port, stderr))
+    });
+
+    if let Some(stdout) = option_stdout {
+        read_data(&open_port, stdout);
+    }
+
+    if let Some(stderr_thread) = option_stderr_thread {
+        let _ = stderr_thread.join();
+    }
+
+    open_port.push(Event::Eof);
+
+    // Polled instead of waiting, so that `close` can lock `child` to kill the program
+    let status = loop {
+        match open_port.child.lock().try_wait() {
+            // Killed by a signal on Unix
+            Ok(Some(exit_status)) => break exit_status.code().unwrap_or(-1),
+            Ok(None) => (),
+            Err(_) => break -1,
+        }
+
+        thread::sleep(EXIT_POLL_INTERVAL);
+    };
+
+    open_port.push(Event::Exit(status));
+}
+
+fn read_data<R: Read>(open_port: &OpenPort, mut reader: R) {
+    let mut buffer = [0; READ_BUFFER_SIZE];
+
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(len) => open_port.push(Event::Data(buffer[..len].to_vec())),
+            Err(ref error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        }
+    }
+}
+
+/// Removes `port` from the open ports, so that it is no longer polled
+fn remove(port: Port) -> Option<Arc<OpenPort>> {
+    let open_port = OPEN_PORT_BY_PORT.write().remove(&port)?;
+
+    let mut open_ports_by_scheduler_id = OPEN_PORTS_BY_SCHEDULER_ID.write();
+
+    if let Some(open_ports) = open_ports_by_scheduler_id.get_mut(&open_port.scheduler_id) {
+        open_ports.retain(|other_open_port| other_open_port.port != port);
+
+        if open_ports.is_empty() {
+            open_ports_by_scheduler_id.remove(&open_port.scheduler_id);
+        }
+    }
+
+    Some(open_port)
+}
+
+/// Sends the message built by `build` in a heap fragment of `words` to `destination`, waking it if
+/// it is waiting
+fn send<F>(destination: &Process, words: usize, build: F)
+where
+    F: FnOnce(&mut HeapFragment) -> AllocResult<Term>,
+{
+    let mut non_null_heap_fragment = HeapFragment::new_from_word_size(words).unwrap();
+    let heap_fragment = unsafe { non_null_heap_fragment.as_mut() };
+
+    let message = build(heap_fragment).unwrap();
+
+    destination.send_heap_message(non_null_heap_fragment, message);
+
+    // status.write() scope
+    let stop_waiting = {
+        let mut writable_status = destination.status.write();
+
+        if *writable_status == Status::Waiting {
+            *writable_status = Status::Runnable;
+
+            true
+        } else {
+            false
+        }
+    };
+
+    if stop_waiting {
+        if let Some(arc_scheduler) = destination.scheduler() {
+            arc_scheduler.stop_waiting(destination);
+        }
+    }
+}
+
+/// Runs on the port's writer thread, writing the data queued by `command` to the program until the
+/// port's input is closed
+fn write(open_port: Arc<OpenPort>, mut stdin: ChildStdin) {
+    loop {
+        // input.lock() scope
+        let bytes = {
+            let mut input = open_port.input.lock();
+
+            loop {
+                if input.closed {
+                    // dropping `stdin` closes the program's standard input
+                    return;
+                }
+
+                match input.queue.pop_front() {
+                    Some(bytes) => break bytes,
+                    None => open_port.input_condvar.wait(&mut input),
+                }
+            }
+        };
+
+        // The program closed its standard input, so it can't be written to anymore, like a port
+        // opened with `in`
+        if stdin.write_all(&bytes).and_then(|_| stdin.flush()).is_err() {
+            open_port.close_input();
+
+            return;
+        }
+    }
+}
+
+lazy_static! {
+    static ref OPEN_PORT_BY_PORT: RwLock<HashMap<Port, Arc<OpenPort>>> = Default::default();
+    /// The open ports of each scheduler, so that `poll` only looks at the ports it delivers events
+    /// for
+    static ref OPEN_PORTS_BY_SCHEDULER_ID: RwLock<HashMap<ID, Vec<Arc<OpenPort>>>> =
+        Default::default();
+}
+
+impl Name {
+    fn command(&self, options: &Options) -> Command {
+        let mut command = match self {
+            Name::SpawnExecutable(path) => {
+                let mut command = Command::new(path);
+                command.args(&options.args);
+
+                command
+            }
+            Name::Spawn(program, args) => {
+                let mut command = Command::new(program);
+                command.args(args);
+
+                command
+            }
+        };
+
+        if let Some(ref cd) = options.cd {
+            command.current_dir(cd);
+        }
+
+        for (key, option_value) in &options.env {
+            match option_value {
+                Some(value) => command.env(key, value),
+                None => command.env_remove(key),
+            };
+        }
+
+        if options.use_stdio {
+            command.stdin(if options.output {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            });
+            command.stdout(if options.input {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            });
+
+            if options.stderr_to_stdout && options.input {
+                command.stderr(Stdio::piped());
+            }
+        } else {
+            command.stdin(Stdio::null()).stdout(Stdio::null());
+        }
+
+        command
+    }
+}
@@ -0,0 +1,270 @@
+use std::convert::{TryFrom, TryInto};
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception::{self, Exception};
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::binary_to_string::binary_to_string;
+use crate::proplist::TryPropListFromTermError;
+
+/// The program a port runs
+#[derive(Clone, Debug, PartialEq)]
+pub enum Name {
+    /// `{spawn_executable, FileName}` runs `FileName` with the `args` option as its arguments
+    SpawnExecutable(String),
+    /// `{spawn, Command}` runs the first word of `Command` with the rest as its arguments
+    Spawn(String, Vec<String>),
+}
+
+const SUPPORTED_NAME_CONTEXT: &str =
+    "supported port names are {spawn_executable, FileName} or {spawn, Command}";
+
+impl TryFrom<Term> for Name {
+    type Error = Exception;
+
+    fn try_from(term: Term) -> Result<Self, Self::Error> {
+        let tuple: Boxed<Tuple> = term.try_into().context(SUPPORTED_NAME_CONTEXT)?;
+
+        if tuple.len() != 2 {
+            return Err(TryPropListFromTermError::TupleNotPair)
+                .context(SUPPORTED_NAME_CONTEXT)
+                .map_err(From::from);
+        }
+
+        let atom: Atom = tuple[0].try_into().context(SUPPORTED_NAME_CONTEXT)?;
+
+        match atom.name() {
+            "spawn" => {
+                let command = term_try_into_string("command", tuple[1])?;
+                let mut words = command.split_whitespace().map(ToOwned::to_owned);
+
+                match words.next() {
+                    Some(program) => Ok(Name::Spawn(program, words.collect())),
+                    None => Err(anyhow!("command ({}) is empty", tuple[1]).into()),
+                }
+            }
+            "spawn_driver" => Err(anyhow!(
+                "driver ({}) is not loaded, as no drivers can be loaded",
+                tuple[1]
+            )
+            .into()),
+            "spawn_executable" => {
+                let file_name = term_try_into_string("file_name", tuple[1])?;
+
+                Ok(Name::SpawnExecutable(file_name))
+            }
+            name => Err(TryPropListFromTermError::KeywordKeyName(name))
+                .context(SUPPORTED_NAME_CONTEXT)
+                .map_err(From::from),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    /// Only used with `{spawn_executable, FileName}`
+    pub args: Vec<String>,
+    pub binary: bool,
+    pub cd: Option<String>,
+    /// `None` values unset the environment variable
+    pub env: Vec<(String, Option<String>)>,
+    pub eof: bool,
+    pub exit_status: bool,
+    /// Whether the port sends the output of the program to the connected process
+    pub input: bool,
+    /// Whether `port_command` writes to the input of the program
+    pub output: bool,
+    pub stderr_to_stdout: bool,
+    pub use_stdio: bool,
+}
+
+impl Options {
+    fn put_option_atom(&mut self, atom: Atom) -> exception::Result<&Self> {
+        match atom.name() {
+            "binary" => self.binary = true,
+            "eof" => self.eof = true,
+            "exit_status" => self.exit_status = true,
+            // There are no console windows to hide
+            "hide" => (),
+            "in" => {
+                self.input = true;
+                self.output = false;
+            }
+            "nouse_stdio" => self.use_stdio = false,
+            "out" => {
+                self.input = false;
+                self.output = true;
+            }
+            "stderr_to_stdout" => self.stderr_to_stdout = true,
+            // Data is always a stream as `{packet, N}` and `{line, L}` are not supported
+            "stream" => (),
+            "use_stdio" => self.use_stdio = true,
+            name => {
+                return Err(TryPropListFromTermError::AtomName(name))
+                    .context(SUPPORTED_OPTIONS_CONTEXT)
+                    .map_err(From::from)
+            }
+        }
+
+        Ok(self)
+    }
+
+    fn put_option_term(&mut self, term: Term) -> exception::Result<&Self> {
+        match term.decode().unwrap() {
+            TypedTerm::Atom(atom) => self.put_option_atom(atom),
+            TypedTerm::Tuple(tuple) => self.put_option_tuple(&tuple),
+            _ => Err(TryPropListFromTermError::PropertyType)
+                .context(SUPPORTED_OPTIONS_CONTEXT)
+                .map_err(From::from),
+        }
+    }
+
+    fn put_option_tuple(&mut self, tuple: &Tuple) -> exception::Result<&Self> {
+        if tuple.len() == 2 {
+            let atom: Atom = tuple[0]
+                .try_into()
+                .map_err(|_| TryPropListFromTermError::KeywordKeyType)
+                .context(SUPPORTED_OPTIONS_CONTEXT)?;
+
+            match atom.name() {
+                "args" => {
+                    self.args = term_try_into_strings("args", tuple[1])?;
+                }
+                "cd" => {
+                    self.cd = Some(term_try_into_string("cd", tuple[1])?);
+                }
+                "env" => {
+                    self.env = term_try_into_env(tuple[1])?;
+                }
+                name => {
+                    return Err(TryPropListFromTermError::KeywordKeyName(name))
+                        .context(SUPPORTED_OPTIONS_CONTEXT)
+                        .map_err(From::from)
+                }
+            }
+
+            Ok(self)
+        } else {
+            Err(TryPropListFromTermError::TupleNotPair)
+                .context(SUPPORTED_OPTIONS_CONTEXT)
+                .map_err(From::from)
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            args: Default::default(),
+            binary: false,
+            cd: None,
+            env: Default::default(),
+            eof: false,
+            exit_status: false,
+            input: true,
+            output: true,
+            stderr_to_stdout: false,
+            use_stdio: true,
+        }
+    }
+}
+
+const SUPPORTED_OPTIONS_CONTEXT: &str = "supported options are {args, [string]}, {cd, Dir}, \
+     {env, [{Name, Value | false}]}, binary, eof, exit_status, hide, in, nouse_stdio, out, \
+     stderr_to_stdout, stream, or use_stdio";
+
+impl TryFrom<Term> for Options {
+    type Error = Exception;
+
+    fn try_from(term: Term) -> Result<Self, Self::Error> {
+        let mut options: Options = Default::default();
+        let mut options_term = term;
+
+        loop {
+            match options_term.decode().unwrap() {
+                TypedTerm::Nil => return Ok(options),
+                TypedTerm::List(cons) => {
+                    options.put_option_term(cons.head)?;
+                    options_term = cons.tail;
+
+                    continue;
+                }
+                _ => {
+                    return Err(ImproperListError)
+                        .context(SUPPORTED_OPTIONS_CONTEXT)
+                        .map_err(From::from)
+                }
+            }
+        }
+    }
+}
+
+// Private
+
+/// Strings in port names and options can be either a string (list) or a binary
+fn term_try_into_string(name: &str, term: Term) -> exception::Result<String> {
+    match term.decode().unwrap() {
+        TypedTerm::Nil => Ok("".to_owned()),
+        TypedTerm::List(cons) => {
+            let string: String = cons
+                .try_into()
+                .with_context(|| format!("{} ({}) is not a string", name, term))?;
+
+            Ok(string)
+        }
+        _ => binary_to_string(term),
+    }
+}
+
+fn term_try_into_strings(name: &str, term: Term) -> exception::Result<Vec<String>> {
+    match term.decode().unwrap() {
+        TypedTerm::Nil => Ok(Vec::new()),
+        TypedTerm::List(cons) => cons
+            .into_iter()
+            .map(|result| match result {
+                Ok(element) => term_try_into_string(name, element),
+                Err(_) => Err(ImproperListError)
+                    .with_context(|| format!("{} ({}) is improper", name, term))
+                    .map_err(From::from),
+            })
+            .collect(),
+        _ => Err(TypeError)
+            .with_context(|| format!("{} ({}) is not a list of strings", name, term))
+            .map_err(From::from),
+    }
+}
+
+fn term_try_into_env(term: Term) -> exception::Result<Vec<(String, Option<String>)>> {
+    let context = || format!("env ({}) is not a list of {{Name, Value | false}}", term);
+
+    match term.decode().unwrap() {
+        TypedTerm::Nil => Ok(Vec::new()),
+        TypedTerm::List(cons) => cons
+            .into_iter()
+            .map(|result| {
+                let element = result
+                    .map_err(|_| ImproperListError)
+                    .with_context(context)?;
+                let tuple: Boxed<Tuple> = element.try_into().with_context(context)?;
+
+                if tuple.len() != 2 {
+                    return Err(TryPropListFromTermError::TupleNotPair)
+                        .with_context(context)
+                        .map_err(From::from);
+                }
+
+                let name = term_try_into_string("env name", tuple[0])?;
+                let false_term: Term = false.into();
+                let value = if tuple[1] == false_term {
+                    None
+                } else {
+                    Some(term_try_into_string("env value", tuple[1])?)
+                };
+
+                Ok((name, value))
+            })
+            .collect(),
+        _ => Err(TypeError).with_context(context).map_err(From::from),
+    }
+}
@@ -0,0 +1,130 @@
+use std::time::{Duration, Instant};
+
+use anyhow::*;
+
+use liblumen_alloc::erts::process::ProcessFlags;
+use liblumen_alloc::{atom, exit};
+
+use crate::process;
+use crate::scheduler::with_process_arc;
+use crate::test::{has_message, poll_ports_until_message};
+
+use super::*;
+
+#[test]
+fn command_does_not_wait_for_program_to_read() {
+    with_process_arc(|arc_process| {
+        let port = sleep(&arc_process);
+        // Far more than a pipe buffers, so writing it would block until the program reads it
+        let bytes = vec![0; 1024 * 1024];
+
+        let start = Instant::now();
+
+        assert!(command(port, &bytes));
+        assert!(command(port, &bytes));
+        assert!(start.elapsed() < Duration::from_secs(30));
+
+        assert!(close(port));
+    });
+}
+
+#[test]
+fn close_sends_exit_message_to_linked_process_trapping_exits() {
+    with_process_arc(|arc_process| {
+        arc_process.set_flags(ProcessFlags::TrapExit);
+        let port = sleep(&arc_process);
+
+        assert!(close(port));
+
+        assert!(has_message(
+            &arc_process,
+            arc_process
+                .tuple_from_slice(&[atom!("EXIT"), port.encode().unwrap(), atom!("normal")])
+                .unwrap()
+        ));
+        assert!(!arc_process.linked_port_set.lock().contains(&port));
+    });
+}
+
+#[test]
+fn program_exiting_sends_exit_message_to_linked_process_trapping_exits() {
+    with_process_arc(|arc_process| {
+        arc_process.set_flags(ProcessFlags::TrapExit);
+        let port = open(
+            &arc_process,
+            Name::SpawnExecutable("/bin/true".to_owned()),
+            Default::default(),
+        )
+        .unwrap();
+
+        assert!(poll_ports_until_message(
+            &arc_process,
+            arc_process
+                .tuple_from_slice(&[atom!("EXIT"), port.encode().unwrap(), atom!("normal")])
+                .unwrap()
+        ));
+        assert_eq!(connected(port), None);
+    });
+}
+
+#[test]
+fn connected_process_exiting_closes_port() {
+    with_process_arc(|arc_process| {
+        let port = sleep(&arc_process);
+
+        propagate_exit(
+            &arc_process,
+            &exit!(atom!("normal"), anyhow!("Test").into()),
+        );
+
+        assert_eq!(connected(port), None);
+    });
+}
+
+#[test]
+fn other_linked_process_exiting_normally_does_not_close_port() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = process::test(&arc_process);
+        let port = sleep(&arc_process);
+
+        assert!(link(&other_arc_process, port));
+
+        propagate_exit(
+            &other_arc_process,
+            &exit!(atom!("normal"), anyhow!("Test").into()),
+        );
+
+        assert_eq!(connected(port), Some(arc_process.pid()));
+        assert!(!other_arc_process.linked_port_set.lock().contains(&port));
+
+        assert!(close(port));
+    });
+}
+
+#[test]
+fn other_linked_process_exiting_abnormally_exits_port_and_its_linked_processes() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = process::test(&arc_process);
+        let port = sleep(&arc_process);
+        let reason = atom!("abnormal");
+
+        assert!(link(&other_arc_process, port));
+
+        propagate_exit(&other_arc_process, &exit!(reason, anyhow!("Test").into()));
+
+        assert_eq!(connected(port), None);
+        assert!(arc_process.is_exiting());
+    });
+}
+
+fn sleep(process: &Process) -> Port {
+    open(
+        process,
+        Name::SpawnExecutable("/bin/sleep".to_owned()),
+        Options {
+            args: vec!["60".to_owned()],
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
@@ -1,3 +1,4 @@
+pub mod info;
 pub mod monitor;
 pub mod spawn;
 
@@ -5,6 +6,7 @@ use alloc::sync::Arc;
 
 use std::convert::TryInto;
 
+use anyhow::anyhow;
 use hashbrown::HashMap;
 
 use liblumen_core::locks::RwLockWriteGuard;
@@ -12,18 +14,21 @@ use liblumen_core::locks::RwLockWriteGuard;
 use liblumen_alloc::erts::exception::{self, AllocResult, ArcError, RuntimeException};
 use liblumen_alloc::erts::process::alloc::{Heap, TermAlloc};
 use liblumen_alloc::erts::process::code::stack::frame::Frame;
-use liblumen_alloc::erts::process::{self, Process, ProcessHeap};
+use liblumen_alloc::erts::process::gc::GcError;
+use liblumen_alloc::erts::process::{self, Process, ProcessFlags, ProcessHeap, Status};
 use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::erts::ModuleFunctionArity;
 use liblumen_alloc::{atom, CloneToProcess, HeapFragment, Monitor};
 
 use crate::code;
+use crate::distribution;
+use crate::port;
 #[cfg(test)]
 use crate::process::spawn::options::Options;
 use crate::registry::*;
-use crate::scheduler::Scheduler;
 #[cfg(test)]
 use crate::scheduler::Spawned;
+use crate::scheduler::{Scheduled, Scheduler};
 use crate::system;
 #[cfg(test)]
 use crate::test;
@@ -97,16 +102,13 @@ pub fn monitor(process: &Process, monitored_process: &Process) -> AllocResult<Te
 pub fn propagate_exit(process: &Process, exception: &RuntimeException) {
     monitor::propagate_exit(process, exception);
     propagate_exit_to_links(process, exception);
+    distribution::propagate_exit(process, exception);
+    port::propagate_exit(process, exception);
 }
 
 pub fn propagate_exit_to_links(process: &Process, exception: &RuntimeException) {
     if !is_expected_exception(exception) {
-        let tag = atom!("EXIT");
-        let from = process.pid_term();
         let reason = exception.reason().unwrap_or_else(|| atom!("system_error"));
-        let reason_word_size = reason.size_in_words();
-        let exit_message_elements: &[Term] = &[tag, from, reason];
-        let exit_message_word_size = Tuple::need_in_words_from_elements(exit_message_elements);
         let source: ArcError = exception
             .source()
             .context(format!("propagating exit from {}", process));
@@ -114,55 +116,105 @@ pub fn propagate_exit_to_links(process: &Process, exception: &RuntimeException)
         for linked_pid in process.linked_pid_set.lock().iter() {
             if let Some(linked_pid_arc_process) = pid_to_process(linked_pid) {
                 if linked_pid_arc_process.traps_exit() {
-                    match linked_pid_arc_process.try_acquire_heap() {
-                        Some(ref mut linked_pid_heap) => {
-                            if exit_message_word_size <= linked_pid_heap.heap_available() {
-                                send_self_exit_message(
-                                    &linked_pid_arc_process,
-                                    linked_pid_heap,
-                                    exit_message_elements,
-                                );
-                            } else {
-                                send_heap_exit_message(
-                                    &linked_pid_arc_process,
-                                    exit_message_elements,
-                                );
-                            }
-                        }
-                        None => {
-                            send_heap_exit_message(&linked_pid_arc_process, exit_message_elements);
-                        }
-                    }
+                    send_exit_message(process, &linked_pid_arc_process, reason);
                 } else {
                     // only tell the linked process to exit.  When it is run by its scheduler, it
                     // will go through propagating its own exit.
-                    match linked_pid_arc_process.try_acquire_heap() {
-                        Some(ref mut linked_pid_heap) => {
-                            if reason_word_size <= linked_pid_heap.heap_available() {
-                                exit_in_heap(
-                                    &linked_pid_arc_process,
-                                    linked_pid_heap,
-                                    reason,
-                                    source.clone(),
-                                );
-                            } else {
-                                exit_in_heap_fragment(
-                                    &linked_pid_arc_process,
-                                    reason,
-                                    source.clone(),
-                                );
-                            }
-                        }
-                        None => {
-                            exit_in_heap_fragment(&linked_pid_arc_process, reason, source.clone());
-                        }
-                    }
+                    exit_with_reason(&linked_pid_arc_process, reason, source.clone());
                 }
             }
         }
     }
 }
 
+/// Sends an exit signal with `reason` from `process` to the other process `to`, like
+/// `exit(To, Reason)`:
+///
+/// * `kill` exits `to` with `killed`, even if it traps exits.
+/// * Otherwise, if `to` traps exits, it is sent `{'EXIT', From, Reason}`.
+/// * Otherwise, `normal` is ignored and any other `reason` exits `to` with `reason`.
+pub fn exit_signal(process: &Process, to: &Process, reason: Term, source: ArcError) {
+    if reason == atom!("kill") {
+        exit_with_reason(to, atom!("killed"), source);
+    } else if to.traps_exit() {
+        send_exit_message(process, to, reason);
+    } else if reason != atom!("normal") {
+        exit_with_reason(to, reason, source);
+    }
+}
+
+/// Collects the whole heap of `process`, which shrinks the heap to fit only the live terms, as for
+/// `garbage_collect/0` and `hibernate/3`.
+pub fn garbage_collect(process: &Process) -> exception::Result<()> {
+    process.set_flags(ProcessFlags::NeedFullSweep);
+
+    match process.garbage_collect(0, &mut []) {
+        Ok(_) => Ok(()),
+        Err(GcError::MaxHeapSizeExceeded) => {
+            Err(exit!(atom!("killed"), anyhow!("max_heap_size exceeded").into()).into())
+        }
+        Err(gc_err) => panic!("fatal garbage collection error: {:?}", gc_err),
+    }
+}
+
+/// Sends `{'EXIT', From, Reason}` to `to`, which traps exits
+fn send_exit_message(from: &Process, to: &Process, reason: Term) {
+    let exit_message_elements: &[Term] = &[atom!("EXIT"), from.pid_term(), reason];
+    let exit_message_word_size = Tuple::need_in_words_from_elements(exit_message_elements);
+
+    match to.try_acquire_heap() {
+        Some(ref mut heap) => {
+            if exit_message_word_size <= heap.heap_available() {
+                send_self_exit_message(to, heap, exit_message_elements);
+            } else {
+                send_heap_exit_message(to, exit_message_elements);
+            }
+        }
+        None => {
+            send_heap_exit_message(to, exit_message_elements);
+        }
+    }
+
+    // status.write() scope
+    let stop_waiting = {
+        let mut writable_status = to.status.write();
+
+        if *writable_status == Status::Waiting {
+            *writable_status = Status::Runnable;
+
+            true
+        } else {
+            false
+        }
+    };
+
+    if stop_waiting {
+        if let Some(arc_scheduler) = to.scheduler() {
+            arc_scheduler.stop_waiting(to);
+        }
+    }
+}
+
+/// Exits `process` with `reason`, running it if it was waiting so that it propagates its exit
+pub(crate) fn exit_with_reason(process: &Process, reason: Term, source: ArcError) {
+    match process.try_acquire_heap() {
+        Some(ref mut heap) => {
+            if reason.size_in_words() <= heap.heap_available() {
+                exit_in_heap(process, heap, reason, source);
+            } else {
+                exit_in_heap_fragment(process, reason, source);
+            }
+        }
+        None => {
+            exit_in_heap_fragment(process, reason, source);
+        }
+    }
+
+    if let Some(arc_scheduler) = process.scheduler() {
+        arc_scheduler.stop_waiting(process);
+    }
+}
+
 fn send_self_exit_message(
     process: &Process,
     heap: &mut ProcessHeap,
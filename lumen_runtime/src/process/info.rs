@@ -0,0 +1,400 @@
+//! The items of `process_info/1` and `process_info/2`.
+//!
+//! Values are allocated on the heap of the calling `process`, even when they describe another
+//! `target` process.
+use core::mem;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception::{AllocResult, InternalResult};
+use liblumen_alloc::erts::process::{Process, Status};
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::ModuleFunctionArity;
+use liblumen_alloc::{atom, CloneToProcess, HeapFragment};
+
+use crate::process::spawn::options::{MaxHeapSize, MessageQueueData};
+use crate::registry::pid_to_process;
+
+const SUPPORTED_ITEMS: &[&str] = &[
+    "backtrace",
+    "binary",
+    "catchlevel",
+    "current_function",
+    "current_location",
+    "current_stacktrace",
+    "dictionary",
+    "error_handler",
+    "garbage_collection",
+    "garbage_collection_info",
+    "group_leader",
+    "heap_size",
+    "initial_call",
+    "links",
+    "last_calls",
+    "memory",
+    "message_queue_len",
+    "messages",
+    "min_heap_size",
+    "min_bin_vheap_size",
+    "monitored_by",
+    "monitors",
+    "message_queue_data",
+    "priority",
+    "reductions",
+    "registered_name",
+    "sequential_trace_token",
+    "stack_size",
+    "status",
+    "suspending",
+    "total_heap_size",
+    "trace",
+    "trap_exit",
+];
+
+/// The items returned by `process_info/1`, which also returns `registered_name` first if the
+/// process is registered
+const DEFAULT_ITEMS: &[&str] = &[
+    "current_function",
+    "initial_call",
+    "status",
+    "message_queue_len",
+    "links",
+    "dictionary",
+    "trap_exit",
+    "error_handler",
+    "priority",
+    "group_leader",
+    "total_heap_size",
+    "heap_size",
+    "stack_size",
+    "reductions",
+    "garbage_collection",
+];
+
+pub fn try_into_item(item: Term) -> InternalResult<Atom> {
+    let atom: Atom = term_try_into_atom!(item)?;
+
+    if SUPPORTED_ITEMS.contains(&atom.name()) {
+        Ok(atom)
+    } else {
+        Err(TryAtomFromTermError(atom.name()))
+            .with_context(supported_items)
+            .map_err(From::from)
+    }
+}
+
+pub fn try_into_items(term: Term) -> InternalResult<Vec<Atom>> {
+    let mut items = Vec::new();
+    let mut items_term = term;
+
+    loop {
+        match items_term.decode()? {
+            TypedTerm::Nil => return Ok(items),
+            TypedTerm::List(cons) => {
+                items.push(try_into_item(cons.head)?);
+                items_term = cons.tail;
+            }
+            _ => {
+                return Err(ImproperListError)
+                    .with_context(supported_items)
+                    .map_err(From::from)
+            }
+        }
+    }
+}
+
+/// Calls `f` with the process with `pid`, or returns `undefined` if it is not alive
+pub fn with_target<F>(process: &Process, pid: Pid, f: F) -> InternalResult<Term>
+where
+    F: FnOnce(&Process) -> InternalResult<Term>,
+{
+    if process.pid() == pid {
+        f(process)
+    } else {
+        match pid_to_process(&pid) {
+            Some(target_arc_process) if !target_arc_process.is_exiting() => f(&target_arc_process),
+            _ => Ok(atom!("undefined")),
+        }
+    }
+}
+
+/// `{Item, Value}`, except for `registered_name` of an unregistered process, which is `[]`
+pub fn item(process: &Process, target: &Process, item: Atom) -> InternalResult<Term> {
+    let value = value(process, target, item)?;
+
+    if item.name() == "registered_name" && value.is_nil() {
+        Ok(value)
+    } else {
+        process
+            .tuple_from_slice(&[item.encode()?, value])
+            .map_err(From::from)
+    }
+}
+
+/// `[{Item, Value}]` in the order of `items`
+pub fn item_list(process: &Process, target: &Process, items: &[Atom]) -> InternalResult<Term> {
+    let mut tuples = Vec::with_capacity(items.len());
+
+    for item in items {
+        let value = value(process, target, *item)?;
+        tuples.push(process.tuple_from_slice(&[item.encode()?, value])?);
+    }
+
+    process.list_from_slice(&tuples).map_err(From::from)
+}
+
+/// The items of `process_info/1`
+pub fn default_item_list(process: &Process, target: &Process) -> InternalResult<Term> {
+    let mut items = Vec::with_capacity(DEFAULT_ITEMS.len() + 1);
+
+    if target.registered_name.read().is_some() {
+        items.push(Atom::try_from_str("registered_name").unwrap());
+    }
+
+    for name in DEFAULT_ITEMS {
+        items.push(Atom::try_from_str(name).unwrap());
+    }
+
+    item_list(process, target, &items)
+}
+
+// Private
+
+fn supported_items() -> String {
+    format!("supported items are {}", SUPPORTED_ITEMS.join(", "))
+}
+
+fn value(process: &Process, target: &Process, item: Atom) -> InternalResult<Term> {
+    let value = match item.name() {
+        "backtrace" => {
+            if target.is_sensitive() {
+                process.binary_from_str("")?
+            } else {
+                process.binary_from_str(&target.stacktrace().to_string())?
+            }
+        }
+        // Reference-counted binaries are not tracked per process, so none are listed
+        "binary" => Term::NIL,
+        // The enclosing catches are not counted
+        "catchlevel" => process.integer(0)?,
+        "current_function" => match target.current_module_function_arity() {
+            Some(module_function_arity) => mfa(process, &module_function_arity)?,
+            None => atom!("undefined"),
+        },
+        "current_location" => match target.current_module_function_arity() {
+            Some(module_function_arity) => location(process, &module_function_arity)?,
+            None => atom!("undefined"),
+        },
+        "current_stacktrace" if target.is_sensitive() => Term::NIL,
+        "current_stacktrace" => {
+            let mut locations = Vec::new();
+
+            for module_function_arity in target.stacktrace().iter() {
+                locations.push(location(process, module_function_arity)?);
+            }
+
+            process.list_from_slice(&locations)?
+        }
+        "dictionary" if target.is_sensitive() => Term::NIL,
+        "dictionary" => {
+            let entries = copy_from(process, target, || {
+                target
+                    .dictionary_entries()
+                    .into_iter()
+                    .flat_map(|(key, value)| vec![key, value])
+                    .collect()
+            })?;
+            let mut tuples = Vec::with_capacity(entries.len() / 2);
+
+            for entry in entries.chunks(2) {
+                tuples.push(process.tuple_from_slice(entry)?);
+            }
+
+            process.list_from_slice(&tuples)?
+        }
+        "error_handler" => target.error_handler().encode()?,
+        "garbage_collection" => {
+            let max_heap_size = MaxHeapSize::get(target).to_term(process)?;
+            let min_bin_vheap_size = process.integer(target.min_bin_vheap_size())?;
+            let min_heap_size = process.integer(target.min_heap_size())?;
+            let fullsweep_after = process.integer(target.fullsweep_after())?;
+
+            process.list_from_slice(&[
+                process.tuple_from_slice(&[atom!("max_heap_size"), max_heap_size])?,
+                process.tuple_from_slice(&[atom!("min_bin_vheap_size"), min_bin_vheap_size])?,
+                process.tuple_from_slice(&[atom!("min_heap_size"), min_heap_size])?,
+                process.tuple_from_slice(&[atom!("fullsweep_after"), fullsweep_after])?,
+            ])?
+        }
+        "garbage_collection_info" => {
+            let heap_size = process.integer(target.heap_size())?;
+            let total_heap_size = process.integer(target.total_heap_size())?;
+
+            process.list_from_slice(&[
+                process.tuple_from_slice(&[atom!("heap_size"), heap_size])?,
+                process.tuple_from_slice(&[atom!("total_heap_size"), total_heap_size])?,
+            ])?
+        }
+        "group_leader" => target.get_group_leader_pid_term(),
+        "heap_size" => process.integer(target.heap_size())?,
+        "initial_call" => mfa(process, &target.initial_module_function_arity)?,
+        "links" => {
+            let mut pids_and_ports: Vec<Term> = target
+                .linked_pid_set
+                .lock()
+                .iter()
+                .map(|pid| pid.encode().unwrap())
+                .collect();
+            pids_and_ports.extend(
+                target
+                    .linked_port_set
+                    .lock()
+                    .iter()
+                    .map(|port| port.encode().unwrap()),
+            );
+
+            process.list_from_slice(&pids_and_ports)?
+        }
+        "last_calls" => match target.last_calls() {
+            Some(last_calls) => {
+                let mut mfas = Vec::with_capacity(last_calls.len());
+
+                for module_function_arity in last_calls.iter() {
+                    mfas.push(mfa(process, module_function_arity)?);
+                }
+
+                process.list_from_slice(&mfas)?
+            }
+            None => false.into(),
+        },
+        "memory" => {
+            let bytes =
+                mem::size_of::<Process>() + target.total_heap_size() * mem::size_of::<Term>();
+
+            process.integer(bytes)?
+        }
+        "message_queue_len" => process.integer(target.mailbox.lock().borrow().len())?,
+        "messages" if target.is_sensitive() => Term::NIL,
+        "messages" => {
+            let messages = copy_from(process, target, || {
+                target
+                    .mailbox
+                    .lock()
+                    .borrow()
+                    .iter()
+                    .map(|message| *message.data())
+                    .collect()
+            })?;
+
+            process.list_from_slice(&messages)?
+        }
+        "min_heap_size" => process.integer(target.min_heap_size())?,
+        "min_bin_vheap_size" => process.integer(target.min_bin_vheap_size())?,
+        "monitored_by" => {
+            let pids: Vec<Term> = target
+                .monitor_by_reference
+                .lock()
+                .values()
+                .map(|monitor| monitor.monitoring_pid().encode().unwrap())
+                .collect();
+
+            process.list_from_slice(&pids)?
+        }
+        "monitors" => {
+            let monitored_pids: Vec<Pid> = target
+                .monitored_pid_by_reference
+                .lock()
+                .values()
+                .cloned()
+                .collect();
+            let mut tuples = Vec::with_capacity(monitored_pids.len());
+
+            for monitored_pid in monitored_pids {
+                tuples
+                    .push(process.tuple_from_slice(&[atom!("process"), monitored_pid.encode()?])?);
+            }
+
+            process.list_from_slice(&tuples)?
+        }
+        "message_queue_data" => MessageQueueData::get(target).into(),
+        "priority" => target.priority().into(),
+        "reductions" => process.integer(
+            target
+                .total_reductions
+                .load(core::sync::atomic::Ordering::SeqCst),
+        )?,
+        "registered_name" => match *target.registered_name.read() {
+            Some(registered_name) => registered_name.encode()?,
+            None => Term::NIL,
+        },
+        // Sequential tracing is not supported, so there is never a token
+        "sequential_trace_token" => Term::NIL,
+        "stack_size" => process.integer(target.stack_used())?,
+        "status" => match *target.status.read() {
+            Status::Runnable => atom!("runnable"),
+            Status::Running => atom!("running"),
+            Status::Waiting => atom!("waiting"),
+            Status::Exiting(_) => atom!("exiting"),
+        },
+        // Processes can't be suspended, so none are being suspended
+        "suspending" => Term::NIL,
+        "total_heap_size" => process.integer(target.total_heap_size())?,
+        // Tracing is not supported, so no trace flags are set
+        "trace" => process.integer(0)?,
+        "trap_exit" => target.traps_exit().into(),
+        name => unreachable!("{} is not a supported item", name),
+    };
+
+    Ok(value)
+}
+
+/// Copies the terms returned by `read` from the heap of `target` to a heap fragment of `process`.
+///
+/// The heap of `target` is locked while the terms are read and copied, so that it can't garbage
+/// collect them, while the heap of `process` is not, so that two processes reading each other
+/// can't deadlock.
+fn copy_from<F>(process: &Process, target: &Process, read: F) -> AllocResult<Vec<Term>>
+where
+    F: FnOnce() -> Vec<Term>,
+{
+    if process.pid() == target.pid() {
+        return Ok(read());
+    }
+
+    let target_heap = target.acquire_heap();
+    let terms = read();
+
+    if terms.is_empty() {
+        return Ok(terms);
+    }
+
+    let words = terms.iter().map(|term| term.size_in_words()).sum();
+    let mut non_null_heap_fragment = HeapFragment::new_from_word_size(words)?;
+    let heap_fragment = unsafe { non_null_heap_fragment.as_mut() };
+    let copies = terms
+        .iter()
+        .map(|term| term.clone_to_heap(heap_fragment))
+        .collect::<AllocResult<Vec<Term>>>()?;
+    drop(target_heap);
+
+    process.attach_fragment(heap_fragment);
+
+    Ok(copies)
+}
+
+fn location(process: &Process, module_function_arity: &ModuleFunctionArity) -> AllocResult<Term> {
+    process.tuple_from_slice(&[
+        module_function_arity.module.encode().unwrap(),
+        module_function_arity.function.encode().unwrap(),
+        process.integer(module_function_arity.arity)?,
+        Term::NIL,
+    ])
+}
+
+fn mfa(process: &Process, module_function_arity: &ModuleFunctionArity) -> AllocResult<Term> {
+    process.tuple_from_slice(&[
+        module_function_arity.module.encode().unwrap(),
+        module_function_arity.function.encode().unwrap(),
+        process.integer(module_function_arity.arity)?,
+    ])
+}
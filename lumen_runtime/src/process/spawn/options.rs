@@ -1,21 +1,23 @@
 mod message_queue_data;
+#[cfg(test)]
+mod test;
 
 use std::convert::{TryFrom, TryInto};
 use std::sync::Arc;
 
 use anyhow::*;
 
-use liblumen_alloc::erts::exception::Alloc;
+use liblumen_alloc::erts::exception::{Alloc, AllocResult};
 use liblumen_alloc::erts::process::alloc::{default_heap_size, heap, next_heap_size};
 use liblumen_alloc::erts::process::priority::Priority;
-use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::process::{Process, ProcessFlags};
 use liblumen_alloc::erts::term::prelude::*;
-use liblumen_alloc::ModuleFunctionArity;
+use liblumen_alloc::{atom, ModuleFunctionArity};
 
 use crate::process;
 use crate::proplist::TryPropListFromTermError;
 
-use message_queue_data::*;
+pub use message_queue_data::*;
 
 #[must_use]
 pub struct Connection {
@@ -24,13 +26,109 @@ pub struct Connection {
     pub monitor_reference: Option<Term>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct MaxHeapSize {
     size: Option<usize>,
     kill: Option<bool>,
     error_logger: Option<bool>,
 }
 
+impl MaxHeapSize {
+    /// The `max_heap_size` of `process`
+    pub fn get(process: &Process) -> Self {
+        Self {
+            size: Some(process.max_heap_size()),
+            kill: Some(process.are_flags_set(ProcessFlags::MaxHeapSizeKill)),
+            error_logger: Some(process.are_flags_set(ProcessFlags::MaxHeapSizeErrorLogger)),
+        }
+    }
+
+    /// Sets the `max_heap_size` of `process`, returning the old `max_heap_size`.
+    ///
+    /// Like the BEAM, a missing `size` is no maximum, while a missing `kill` or `error_logger` is
+    /// `true`.
+    pub fn put(&self, process: &Process) -> Self {
+        let old = Self::get(process);
+
+        process.set_max_heap_size(self.size.unwrap_or(0));
+        process.put_flag(ProcessFlags::MaxHeapSizeKill, self.kill.unwrap_or(true));
+        process.put_flag(
+            ProcessFlags::MaxHeapSizeErrorLogger,
+            self.error_logger.unwrap_or(true),
+        );
+
+        old
+    }
+
+    /// `#{error_logger => ErrorLogger, kill => Kill, size => Size}`
+    pub fn to_term(&self, process: &Process) -> AllocResult<Term> {
+        let size = process.integer(self.size.unwrap_or(0))?;
+
+        process.map_from_slice(&[
+            (
+                atom!("error_logger"),
+                self.error_logger.unwrap_or(true).into(),
+            ),
+            (atom!("kill"), self.kill.unwrap_or(true).into()),
+            (atom!("size"), size),
+        ])
+    }
+}
+
+const SUPPORTED_MAX_HEAP_SIZE_CONTEXT: &str = "max_heap_size is either size in words or a map \
+     with keys size (non-negative integer), kill (boolean), and error_logger (boolean)";
+
+impl TryFrom<Term> for MaxHeapSize {
+    type Error = anyhow::Error;
+
+    fn try_from(term: Term) -> Result<Self, Self::Error> {
+        match term.decode().unwrap() {
+            TypedTerm::Map(map) => {
+                let mut max_heap_size = MaxHeapSize {
+                    size: None,
+                    kill: None,
+                    error_logger: None,
+                };
+
+                for (key, value) in map.iter() {
+                    let key_atom: Atom =
+                        (*key).try_into().context(SUPPORTED_MAX_HEAP_SIZE_CONTEXT)?;
+
+                    match key_atom.name() {
+                        "error_logger" => {
+                            let error_logger = (*value).try_into().context("error_logger")?;
+                            max_heap_size.error_logger = Some(error_logger);
+                        }
+                        "kill" => {
+                            let kill = (*value).try_into().context("kill")?;
+                            max_heap_size.kill = Some(kill);
+                        }
+                        "size" => {
+                            let size = (*value).try_into().context("size")?;
+                            max_heap_size.size = Some(size);
+                        }
+                        name => {
+                            return Err(TryAtomFromTermError(name))
+                                .context(SUPPORTED_MAX_HEAP_SIZE_CONTEXT)
+                        }
+                    }
+                }
+
+                Ok(max_heap_size)
+            }
+            _ => {
+                let size = term.try_into().context(SUPPORTED_MAX_HEAP_SIZE_CONTEXT)?;
+
+                Ok(MaxHeapSize {
+                    size: Some(size),
+                    kill: None,
+                    error_logger: None,
+                })
+            }
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Options {
     pub link: bool,
@@ -73,7 +171,8 @@ impl Options {
         })
     }
 
-    /// Creates a new process with the memory and priority options.
+    /// Creates a new process with the memory, garbage collection, message queue and priority
+    /// options.
     ///
     /// To fully apply all options, call `options.connect(&parent_process, &child_process)` after
     /// placing any frames in the `child_process` returns from this function.
@@ -99,6 +198,7 @@ impl Options {
             heap,
             heap_size,
         );
+        self.put_process_flags(&process);
 
         Ok(process)
     }
@@ -109,7 +209,7 @@ impl Options {
         match self.priority {
             Some(priority) => priority,
             None => match parent_process {
-                Some(process) => process.priority,
+                Some(process) => process.priority(),
                 None => Default::default(),
             },
         }
@@ -123,6 +223,24 @@ impl Options {
         }
     }
 
+    /// Puts the options that don't need to be known to create `process`.  `min_heap_size` is
+    /// already the size `process` was created with.
+    fn put_process_flags(&self, process: &Process) {
+        if let Some(fullsweep_after) = self.fullsweep_after {
+            process.set_fullsweep_after(fullsweep_after);
+        }
+
+        if let Some(min_bin_vheap_size) = self.min_bin_vheap_size {
+            process.set_min_bin_vheap_size(min_bin_vheap_size);
+        }
+
+        if let Some(max_heap_size) = self.max_heap_size {
+            max_heap_size.put(process);
+        }
+
+        self.message_queue_data.put(process);
+    }
+
     fn put_option_atom(&mut self, atom: Atom) -> Result<&Self, anyhow::Error> {
         match atom.name() {
             "link" => {
@@ -160,7 +278,12 @@ impl Options {
 
                     Ok(self)
                 }
-                "max_heap_size" => unimplemented!(),
+                "max_heap_size" => {
+                    let max_heap_size = tuple[1].try_into().context("max_heap_size")?;
+                    self.max_heap_size = Some(max_heap_size);
+
+                    Ok(self)
+                }
                 "message_queue_data" => {
                     let message_queue_data = tuple[1].try_into().context("message_queue_data")?;
                     self.message_queue_data = message_queue_data;
@@ -2,14 +2,39 @@ use std::convert::{TryFrom, TryInto};
 
 use anyhow::Context;
 
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::process::{Process, ProcessFlags};
 use liblumen_alloc::erts::term::prelude::*;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MessageQueueData {
     OnHeap,
     OffHeap,
 }
 
+impl MessageQueueData {
+    /// The `message_queue_data` of `process`
+    pub fn get(process: &Process) -> Self {
+        if process.are_flags_set(ProcessFlags::OffHeapMessageQueue) {
+            MessageQueueData::OffHeap
+        } else {
+            MessageQueueData::OnHeap
+        }
+    }
+
+    /// Sets the `message_queue_data` of `process`, returning the old `message_queue_data`
+    pub fn put(&self, process: &Process) -> Self {
+        let old = Self::get(process);
+
+        process.put_flag(
+            ProcessFlags::OffHeapMessageQueue,
+            *self == MessageQueueData::OffHeap,
+        );
+
+        old
+    }
+}
+
 impl Default for MessageQueueData {
     fn default() -> Self {
         MessageQueueData::OnHeap
@@ -32,3 +57,12 @@ impl TryFrom<Term> for MessageQueueData {
         }
     }
 }
+
+impl From<MessageQueueData> for Term {
+    fn from(message_queue_data: MessageQueueData) -> Self {
+        match message_queue_data {
+            MessageQueueData::OffHeap => atom!("off_heap"),
+            MessageQueueData::OnHeap => atom!("on_heap"),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use liblumen_alloc::erts::process::gc::GcError;
+use liblumen_alloc::erts::process::{Process, ProcessFlags};
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::process;
+use crate::process::spawn::options::Options;
+use crate::scheduler::{Scheduler, Spawned};
+use crate::test::{has_heap_message, has_process_message, r#loop};
+
+#[test]
+fn fullsweep_after_sets_minor_collections_before_full_sweep() {
+    let child_arc_process =
+        spawn(|process| option(process, "fullsweep_after", process.integer(10).unwrap()));
+
+    assert_eq!(child_arc_process.fullsweep_after(), 10);
+}
+
+#[test]
+fn min_bin_vheap_size_sets_min_bin_vheap_size() {
+    let child_arc_process = spawn(|process| {
+        option(
+            process,
+            "min_bin_vheap_size",
+            process.integer(1_000).unwrap(),
+        )
+    });
+
+    assert_eq!(child_arc_process.min_bin_vheap_size(), 1_000);
+}
+
+#[test]
+fn max_heap_size_with_kill_stops_heap_growing_past_size() {
+    let child_arc_process = spawn(|process| {
+        let max_heap_size = process
+            .map_from_slice(&[
+                (Atom::str_to_term("size"), process.integer(20_000).unwrap()),
+                (Atom::str_to_term("error_logger"), false.into()),
+            ])
+            .unwrap();
+
+        option(process, "max_heap_size", max_heap_size)
+    });
+
+    assert_eq!(child_arc_process.max_heap_size(), 20_000);
+    assert!(child_arc_process.are_flags_set(ProcessFlags::MaxHeapSizeKill));
+    assert_eq!(
+        child_arc_process.garbage_collect(100_000, &mut []),
+        Err(GcError::MaxHeapSizeExceeded)
+    );
+}
+
+#[test]
+fn max_heap_size_without_kill_lets_heap_grow_past_size() {
+    let child_arc_process = spawn(|process| {
+        let max_heap_size = process
+            .map_from_slice(&[
+                (Atom::str_to_term("size"), process.integer(20_000).unwrap()),
+                (Atom::str_to_term("kill"), false.into()),
+                (Atom::str_to_term("error_logger"), false.into()),
+            ])
+            .unwrap();
+
+        option(process, "max_heap_size", max_heap_size)
+    });
+
+    assert!(!child_arc_process.are_flags_set(ProcessFlags::MaxHeapSizeKill));
+    assert!(child_arc_process.garbage_collect(100_000, &mut []).is_ok());
+}
+
+#[test]
+fn message_queue_data_off_heap_keeps_messages_in_heap_fragments() {
+    let child_arc_process =
+        spawn(|process| option(process, "message_queue_data", Atom::str_to_term("off_heap")));
+    let message = tuple_message();
+    let total_heap_size_before = child_arc_process.total_heap_size();
+
+    child_arc_process.send_from_other(message).unwrap();
+
+    assert!(has_heap_message(&child_arc_process, message));
+    assert!(total_heap_size_before < child_arc_process.total_heap_size());
+}
+
+#[test]
+fn message_queue_data_on_heap_copies_messages_to_heap() {
+    let child_arc_process =
+        spawn(|process| option(process, "message_queue_data", Atom::str_to_term("on_heap")));
+    let message = tuple_message();
+    let total_heap_size_before = child_arc_process.total_heap_size();
+
+    child_arc_process.send_from_other(message).unwrap();
+
+    assert!(has_process_message(&child_arc_process, message));
+    assert_eq!(child_arc_process.total_heap_size(), total_heap_size_before);
+}
+
+/// Spawns a looping child of a new `init` with the options returned by `options`
+fn spawn<F>(options: F) -> Arc<Process>
+where
+    F: FnOnce(&Process) -> Term,
+{
+    let parent_arc_process = process::test_init();
+    let options_term = options(&parent_arc_process);
+    let options: Options = options_term.try_into().unwrap();
+
+    let Spawned { arc_process, .. } = Scheduler::spawn_code(
+        &parent_arc_process,
+        options,
+        r#loop::module(),
+        r#loop::function(),
+        &[],
+        r#loop::code,
+    )
+    .unwrap();
+
+    arc_process
+}
+
+/// `[{name, value}]`
+fn option(process: &Process, name: &str, value: Term) -> Term {
+    process
+        .list_from_slice(&[process
+            .tuple_from_slice(&[Atom::str_to_term(name), value])
+            .unwrap()])
+        .unwrap()
+}
+
+/// A message on the heap of another process, so that it has to be copied to be sent
+fn tuple_message() -> Term {
+    let sender_arc_process = process::test_init();
+
+    sender_arc_process
+        .tuple_from_slice(&[Atom::str_to_term("message")])
+        .unwrap()
+}
@@ -51,6 +51,16 @@ pub fn pid_to_self_or_process(pid: Pid, process_arc: &Arc<Process>) -> Option<Ar
     }
 }
 
+/// The processes that are alive, in no particular order
+pub fn processes() -> Vec<Arc<Process>> {
+    RW_LOCK_WEAK_PROCESS_CONTROL_BLOCK_BY_PID
+        .read()
+        .values()
+        .filter_map(|weak_process| weak_process.upgrade())
+        .filter(|arc_process| !arc_process.is_exiting())
+        .collect()
+}
+
 pub fn put_atom_to_process(name: Atom, arc_process: Arc<Process>) -> bool {
     let writable_registry = RW_LOCK_REGISTERED_BY_NAME.write();
 
@@ -56,7 +56,7 @@ impl Queues {
     }
 
     pub fn enqueue(&mut self, arc_process: Arc<Process>) {
-        match arc_process.priority {
+        match arc_process.priority() {
             Priority::Low | Priority::Normal => self.normal_low.enqueue(arc_process),
             Priority::High => self.high.enqueue(arc_process),
             Priority::Max => self.max.enqueue(arc_process),
@@ -64,7 +64,37 @@ impl Queues {
     }
 
     pub fn len(&self) -> usize {
-        self.waiting.len() + self.normal_low.len() + self.high.len() + self.max.len()
+        self.waiting.len() + self.runnable_len()
+    }
+
+    /// Moves `process` from the run queue for `old_priority` to the run queue for its current
+    /// priority.  Does nothing if `process` is not in a run queue because it is running or waiting.
+    pub fn reprioritize(&mut self, process: &Process, old_priority: Priority) {
+        let option_arc_process = match old_priority {
+            Priority::Low | Priority::Normal => self.normal_low.remove(process),
+            Priority::High => self.high.remove(process),
+            Priority::Max => self.max.remove(process),
+        };
+
+        if let Some(arc_process) = option_arc_process {
+            self.enqueue(arc_process);
+        }
+    }
+
+    /// The number of processes that could run, which excludes those waiting
+    pub fn runnable_len(&self) -> usize {
+        self.normal_low.len() + self.high.len() + self.max.len()
+    }
+
+    /// Removes half, rounded up, of the runnable processes of each priority, for another scheduler
+    /// to run
+    pub fn steal(&mut self) -> Vec<Arc<Process>> {
+        let mut stolen = Vec::new();
+        stolen.extend(self.max.steal((self.max.len() + 1) / 2));
+        stolen.extend(self.high.steal((self.high.len() + 1) / 2));
+        stolen.extend(self.normal_low.steal((self.normal_low.len() + 1) / 2));
+
+        stolen
     }
 
     /// Returns the process is not pushed back because it is exiting
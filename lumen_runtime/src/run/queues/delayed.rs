@@ -44,6 +44,25 @@ impl Delayed {
         let delayed_process = DelayedProcess::new(arc_process);
         self.0.push_back(delayed_process);
     }
+
+    pub fn remove(&mut self, process: &Process) -> Option<Arc<Process>> {
+        let index = self
+            .0
+            .iter()
+            .position(|delayed_process| delayed_process.arc_process.pid() == process.pid())?;
+
+        self.0
+            .remove(index)
+            .map(|delayed_process| delayed_process.arc_process)
+    }
+
+    /// Removes up to `count` processes from the back of the queue, which would have run last
+    pub fn steal(&mut self, count: usize) -> impl Iterator<Item = Arc<Process>> + '_ {
+        let len = self.0.len();
+        self.0
+            .drain(len - count.min(len)..)
+            .map(|delayed_process| delayed_process.arc_process)
+    }
 }
 
 type Delay = u8;
@@ -57,7 +76,7 @@ struct DelayedProcess {
 impl DelayedProcess {
     fn new(arc_process: Arc<Process>) -> DelayedProcess {
         DelayedProcess {
-            delay: Self::priority_to_delay(arc_process.priority),
+            delay: Self::priority_to_delay(arc_process.priority()),
             arc_process,
         }
     }
@@ -29,4 +29,19 @@ impl Immediate {
     pub fn enqueue(&mut self, process: Arc<Process>) {
         self.0.push_back(process);
     }
+
+    pub fn remove(&mut self, process: &Process) -> Option<Arc<Process>> {
+        let index = self
+            .0
+            .iter()
+            .position(|arc_process| arc_process.pid() == process.pid())?;
+
+        self.0.remove(index)
+    }
+
+    /// Removes up to `count` processes from the back of the queue, which would have run last
+    pub fn steal(&mut self, count: usize) -> impl Iterator<Item = Arc<Process>> + '_ {
+        let len = self.0.len();
+        self.0.drain(len - count.min(len)..)
+    }
 }
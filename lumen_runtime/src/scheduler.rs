@@ -1,23 +1,28 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod smp;
 #[cfg(test)]
 pub mod test;
 
 use core::fmt::{self, Debug};
 use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
 
 use alloc::sync::{Arc, Weak};
 
+use anyhow::anyhow;
 use hashbrown::HashMap;
 
-use liblumen_core::locks::{Mutex, RwLock};
+use liblumen_core::locks::{Condvar, Mutex, RwLock};
 
+use liblumen_alloc::atom;
 use liblumen_alloc::erts::exception::{Result, SystemException};
-use liblumen_alloc::erts::process::code::Code;
-#[cfg(test)]
-use liblumen_alloc::erts::process::Priority;
-use liblumen_alloc::erts::process::{Process, Status};
+use liblumen_alloc::erts::process::code::{self, Code};
+use liblumen_alloc::erts::process::gc::GcError;
+use liblumen_alloc::erts::process::{Priority, Process, ProcessFlags, Status};
 pub use liblumen_alloc::erts::scheduler::{id, ID};
 use liblumen_alloc::erts::term::prelude::*;
 
+use crate::port;
 use crate::process;
 use crate::process::spawn;
 use crate::process::spawn::options::{Connection, Options};
@@ -45,15 +50,29 @@ impl Scheduled for Reference {
 pub struct Scheduler {
     pub id: ID,
     pub hierarchy: RwLock<Hierarchy>,
+    // The number of times a process was run, for `erlang:statistics(context_switches)`
+    context_switches: AtomicU64,
     // References are always 64-bits even on 32-bit platforms
     reference_count: AtomicU64,
     run_queues: RwLock<run::queues::Queues>,
     // Non-monotonic unique integers are scoped to the scheduler ID and then use this per-scheduler
     // `u64`.
     unique_integer: AtomicU64,
+    // Set by `unpark` so that a `park` racing with it does not miss the wakeup
+    unparked: Mutex<bool>,
+    unpark_condvar: Condvar,
 }
 
 impl Scheduler {
+    /// The schedulers of all threads that have one
+    pub fn all() -> Vec<Arc<Scheduler>> {
+        SCHEDULER_BY_ID
+            .lock()
+            .values()
+            .filter_map(|weak_scheduler| weak_scheduler.upgrade())
+            .collect()
+    }
+
     pub fn current() -> Arc<Scheduler> {
         SCHEDULER.with(|thread_local_scheduler| thread_local_scheduler.clone())
     }
@@ -77,6 +96,10 @@ impl Scheduler {
         })
     }
 
+    pub fn context_switches(&self) -> u64 {
+        self.context_switches.load(Ordering::Relaxed)
+    }
+
     pub fn next_reference_number(&self) -> ReferenceNumber {
         self.reference_count.fetch_add(1, Ordering::SeqCst)
     }
@@ -96,9 +119,37 @@ impl Scheduler {
     /// > -- [The Scheduler Loop](https://blog.stenmans.org/theBeamBook/#_the_scheduler_loop)
     pub fn run(&self) {
         loop {
-            // TODO sleep or steal if nothing run
-            let _ = self.run_once();
+            if !self.run_once() {
+                self.park();
+            }
+        }
+    }
+
+    /// Blocks the thread until a process may be able to run, which is when `unpark` is called by
+    /// another thread making a process runnable or when the next timer times out.
+    ///
+    /// Returns immediately if `unpark` was called since the last `park`.
+    pub fn park(&self) {
+        let option_milliseconds = self.hierarchy.read().milliseconds_until_next_timeout();
+        let mut unparked = self.unparked.lock();
+
+        if !*unparked {
+            match option_milliseconds {
+                Some(milliseconds) => {
+                    self.unpark_condvar
+                        .wait_for(&mut unparked, Duration::from_millis(milliseconds));
+                }
+                None => self.unpark_condvar.wait(&mut unparked),
+            }
         }
+
+        *unparked = false;
+    }
+
+    /// Wakes the thread if it is parked in `park`, or stops the next `park` from blocking
+    pub fn unpark(&self) {
+        *self.unparked.lock() = true;
+        self.unpark_condvar.notify_one();
     }
 
     /// > 1. Update reduction counters
@@ -116,6 +167,7 @@ impl Scheduler {
     #[must_use]
     pub fn run_once(&self) -> bool {
         self.hierarchy.write().timeout();
+        port::poll(&self.id);
 
         loop {
             // separate from `match` below so that WriteGuard temporary is not held while process
@@ -129,12 +181,18 @@ impl Scheduler {
                     // Without this check, a process.exit() from outside the process during WAITING
                     // will return to the Frame that called `process.wait()`
                     if !arc_process.is_exiting() {
-                        match Process::run(&arc_process) {
+                        self.context_switches.fetch_add(1, Ordering::Relaxed);
+
+                        match Self::collect_forced_and_run(&arc_process) {
                             Ok(()) => (),
                             Err(exception) => match exception {
                                 SystemException::Alloc(_) => {
                                     match arc_process.garbage_collect(0, &mut []) {
                                         Ok(_freed) => (),
+                                        Err(GcError::MaxHeapSizeExceeded) => arc_process.exit(
+                                            atom!("killed"),
+                                            anyhow!("max_heap_size exceeded").into(),
+                                        ),
                                         Err(gc_err) => {
                                             panic!("fatal garbage collection error: {:?}", gc_err)
                                         }
@@ -147,7 +205,12 @@ impl Scheduler {
                         arc_process.reduce()
                     }
 
-                    match self.run_queues.write().requeue(arc_process) {
+                    // separate from `match` below so that the WriteGuard temporary is not held
+                    // while propagating the exit, which can wake linked processes on this
+                    // scheduler.
+                    let option_exiting_arc_process = self.run_queues.write().requeue(arc_process);
+
+                    match option_exiting_arc_process {
                         Some(exiting_arc_process) => match *exiting_arc_process.status.read() {
                             Status::Exiting(ref exception) => {
                                 process::log_exit(&exiting_arc_process, exception);
@@ -161,7 +224,7 @@ impl Scheduler {
                     break true;
                 }
                 Run::Delayed => continue,
-                // TODO steal processes or sleep if nothing to steal
+                // The caller steals processes or sleeps if there is nothing to steal
                 Run::None => break false,
             }
         }
@@ -171,6 +234,44 @@ impl Scheduler {
         self.run_queues.read().len()
     }
 
+    /// The number of processes that could run now, which excludes those waiting
+    pub fn runnable_len(&self) -> usize {
+        self.run_queues.read().runnable_len()
+    }
+
+    /// Moves half of the runnable processes of the busiest of `schedulers` to this scheduler.
+    ///
+    /// Returns `true` if any process was stolen.  Returns `false` if none of the other
+    /// `schedulers` had a process to spare.
+    #[must_use]
+    pub fn steal(&self, schedulers: &[Arc<Scheduler>]) -> bool {
+        let victim = schedulers
+            .iter()
+            .filter(|scheduler| scheduler.id != self.id)
+            .map(|scheduler| (scheduler.runnable_len(), scheduler))
+            .filter(|(runnable_len, _)| 0 < *runnable_len)
+            .max_by_key(|(runnable_len, _)| *runnable_len);
+
+        match victim {
+            Some((_, victim)) => {
+                // Only one scheduler's run queues are locked at a time, so that schedulers stealing
+                // from each other can't deadlock
+                let stolen = victim.run_queues.write().steal();
+                let stole = !stolen.is_empty();
+
+                let mut writable_run_queues = self.run_queues.write();
+
+                for arc_process in stolen {
+                    arc_process.schedule_with(self.id);
+                    writable_run_queues.enqueue(arc_process);
+                }
+
+                stole
+            }
+            None => false,
+        }
+    }
+
     #[cfg(test)]
     pub fn run_queue_len(&self, priority: Priority) -> usize {
         self.run_queues.read().run_queue_len(priority)
@@ -209,6 +310,9 @@ impl Scheduler {
         let arc_process = Arc::new(process);
 
         writable_run_queues.enqueue(Arc::clone(&arc_process));
+        drop(writable_run_queues);
+
+        self.unpark();
 
         arc_process
     }
@@ -282,25 +386,49 @@ impl Scheduler {
         let mut writable_run_queues = self.run_queues.write();
 
         writable_run_queues.enqueue(Arc::clone(&arc_process));
+        drop(writable_run_queues);
 
         put_pid_to_process(&arc_process);
+        self.unpark();
 
         Ok(arc_process)
     }
 
     pub fn stop_waiting(&self, process: &Process) {
         self.run_queues.write().stop_waiting(process);
+        self.unpark();
+    }
+
+    /// Moves `process` to the run queue for its new priority after it changed from `old_priority`
+    pub fn reprioritize(&self, process: &Process, old_priority: Priority) {
+        self.run_queues.write().reprioritize(process, old_priority);
     }
 
     // Private
 
+    /// Collects the heap first if `garbage_collect/1` was called on the process by another process
+    fn collect_forced_and_run(arc_process: &Arc<Process>) -> code::Result {
+        if arc_process.are_flags_set(ProcessFlags::ForceGC) {
+            arc_process.clear_flags(ProcessFlags::ForceGC);
+
+            if let Err(exception) = process::garbage_collect(arc_process) {
+                return code::result_from_exception(arc_process, 0, exception);
+            }
+        }
+
+        Process::run(arc_process)
+    }
+
     fn new() -> Scheduler {
         Scheduler {
             id: id::next(),
             hierarchy: Default::default(),
+            context_switches: AtomicU64::new(0),
             reference_count: AtomicU64::new(0),
             run_queues: Default::default(),
             unique_integer: AtomicU64::new(0),
+            unparked: Mutex::new(false),
+            unpark_condvar: Condvar::new(),
         }
     }
 
@@ -0,0 +1,149 @@
+//! Runs a `Scheduler` on each of several threads, so that processes can run on all cores.
+//!
+//! Processes are spawned on the scheduler of their parent, like in the BEAM, so the load is
+//! balanced by schedulers that run out of processes stealing half of the runnable processes of
+//! the busiest scheduler.  Schedulers with nothing to steal park until they are woken by a busy
+//! scheduler with processes to spare.
+//!
+//! The runtime's own schedulers are started by `runtime`, and processes spawned on them by
+//! `spawn_init` run on these threads from then on.
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use liblumen_core::locks::RwLock;
+
+use liblumen_alloc::erts::exception::Result;
+use liblumen_alloc::erts::process::Process;
+
+use crate::scheduler::Scheduler;
+use crate::system::host::cpus;
+
+lazy_static! {
+    static ref RUNTIME: Schedulers =
+        Schedulers::start().expect("could not start the scheduler threads");
+}
+
+/// The scheduler threads of the runtime, one for each logical CPU, which are started the first
+/// time this is called and run until the program exits
+pub fn runtime() -> &'static Schedulers {
+    &RUNTIME
+}
+
+/// Scheduler threads, which are stopped when dropped
+pub struct Schedulers {
+    shared: Arc<Shared>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+struct Shared {
+    schedulers: RwLock<Vec<Arc<Scheduler>>>,
+    stopping: AtomicBool,
+}
+
+impl Schedulers {
+    /// Starts a scheduler thread for each logical CPU
+    pub fn start() -> io::Result<Schedulers> {
+        Self::start_count(cpus::num_logical())
+    }
+
+    /// Starts `count` scheduler threads, which are all running when this returns
+    pub fn start_count(count: usize) -> io::Result<Schedulers> {
+        assert!(0 < count, "at least one scheduler is needed");
+
+        let mut schedulers = Schedulers {
+            shared: Arc::new(Shared {
+                schedulers: RwLock::new(Vec::with_capacity(count)),
+                stopping: AtomicBool::new(false),
+            }),
+            threads: Vec::with_capacity(count),
+        };
+        let (sender, receiver) = channel();
+
+        for index in 0..count {
+            let shared = Arc::clone(&schedulers.shared);
+            let sender = sender.clone();
+
+            // Any threads already started are stopped when `schedulers` is dropped on error
+            let thread = thread::Builder::new()
+                .name(format!("scheduler-{}", index))
+                .spawn(move || {
+                    let arc_scheduler = Scheduler::current();
+                    shared.schedulers.write().push(Arc::clone(&arc_scheduler));
+                    sender.send(()).unwrap();
+
+                    run(&arc_scheduler, &shared);
+                })?;
+            schedulers.threads.push(thread);
+        }
+
+        for _ in 0..count {
+            receiver.recv().unwrap();
+        }
+
+        Ok(schedulers)
+    }
+
+    pub fn len(&self) -> usize {
+        self.threads.len()
+    }
+
+    /// The schedulers in the order their threads registered them
+    pub fn schedulers(&self) -> Vec<Arc<Scheduler>> {
+        self.shared.schedulers.read().clone()
+    }
+
+    /// Spawns an `init` process on the least loaded scheduler, so that it and the processes it
+    /// spawns run on the scheduler threads
+    pub fn spawn_init(&self, minimum_heap_size: usize) -> Result<Arc<Process>> {
+        self.least_loaded().spawn_init(minimum_heap_size)
+    }
+
+    /// The scheduler with the fewest runnable processes, to spawn processes that have no parent on
+    pub fn least_loaded(&self) -> Arc<Scheduler> {
+        self.shared
+            .schedulers
+            .read()
+            .iter()
+            .min_by_key(|scheduler| scheduler.runnable_len())
+            .cloned()
+            .unwrap()
+    }
+}
+
+impl Drop for Schedulers {
+    fn drop(&mut self) {
+        self.shared.stopping.store(true, Ordering::SeqCst);
+
+        for scheduler in self.shared.schedulers.read().iter() {
+            scheduler.unpark();
+        }
+
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run(scheduler: &Scheduler, shared: &Shared) {
+    while !shared.stopping.load(Ordering::SeqCst) {
+        if scheduler.run_once() {
+            // More processes are waiting than this scheduler is running, so wake an idle
+            // scheduler to steal some
+            if 1 < scheduler.runnable_len() {
+                if let Some(idle) = shared
+                    .schedulers
+                    .read()
+                    .iter()
+                    .find(|other| other.id != scheduler.id && other.runnable_len() == 0)
+                {
+                    idle.unpark();
+                }
+            }
+        } else if !scheduler.steal(&shared.schedulers.read()) {
+            scheduler.park();
+        }
+    }
+}
@@ -1,5 +1,9 @@
+#[cfg(not(target_arch = "wasm32"))]
+mod smp;
 mod spawn_apply_3;
 
+use std::sync::Arc;
+
 use anyhow::*;
 
 use liblumen_alloc::erts::process::code::stack::frame::Placement;
@@ -44,3 +48,60 @@ fn scheduler_does_run_exiting_process() {
         assert!(!scheduler.is_run_queued(&arc_process));
     })
 }
+
+#[test]
+fn scheduler_steals_runnable_processes_from_other_schedulers() {
+    with_process_arc(|arc_process| {
+        let scheduler = Scheduler::current();
+        let thief = Scheduler::registered();
+
+        assert!(scheduler.is_run_queued(&arc_process));
+
+        assert!(thief.steal(&[Arc::clone(&scheduler)]));
+
+        assert!(!scheduler.is_run_queued(&arc_process));
+        assert!(thief.is_run_queued(&arc_process));
+        assert_eq!(arc_process.scheduler_id(), Some(thief.id));
+    })
+}
+
+#[test]
+fn scheduler_does_not_steal_from_itself() {
+    with_process_arc(|arc_process| {
+        let scheduler = Scheduler::current();
+
+        assert!(!scheduler.steal(&[Arc::clone(&scheduler)]));
+        assert!(scheduler.is_run_queued(&arc_process));
+    })
+}
+
+#[test]
+fn park_returns_immediately_after_unpark() {
+    let scheduler = Scheduler::current();
+
+    scheduler.unpark();
+    scheduler.park();
+}
+
+#[test]
+fn park_is_woken_by_unpark_from_another_thread() {
+    let scheduler = Scheduler::current();
+    let unparking_scheduler = Arc::clone(&scheduler);
+
+    let unparker = std::thread::spawn(move || unparking_scheduler.unpark());
+
+    scheduler.park();
+
+    unparker.join().unwrap();
+}
+
+#[test]
+fn park_without_timers_or_processes_has_no_timeout() {
+    assert_eq!(
+        Scheduler::current()
+            .hierarchy
+            .read()
+            .milliseconds_until_next_timeout(),
+        None
+    );
+}
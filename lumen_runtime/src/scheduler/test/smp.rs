@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::scheduler::smp::{self, Schedulers};
+use crate::scheduler::{Scheduler, Spawned};
+use crate::test::r#loop;
+
+#[test]
+fn starts_count_schedulers() {
+    let schedulers = Schedulers::start_count(2).unwrap();
+
+    assert_eq!(schedulers.len(), 2);
+
+    let ids: HashSet<_> = schedulers
+        .schedulers()
+        .iter()
+        .map(|scheduler| scheduler.id)
+        .collect();
+
+    assert_eq!(ids.len(), 2);
+    assert!(!ids.contains(&Scheduler::current().id));
+}
+
+#[test]
+fn idle_schedulers_steal_processes_spawned_on_busy_scheduler() {
+    let schedulers = Schedulers::start_count(2).unwrap();
+    let parent_arc_process = schedulers.least_loaded().spawn_init(16_000).unwrap();
+
+    let children: Vec<_> = (0..8)
+        .map(|_| {
+            let Spawned { arc_process, .. } = Scheduler::spawn_code(
+                &parent_arc_process,
+                Default::default(),
+                r#loop::module(),
+                r#loop::function(),
+                &[],
+                r#loop::code,
+            )
+            .unwrap();
+
+            arc_process
+        })
+        .collect();
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+
+    loop {
+        let scheduler_ids: HashSet<_> = children
+            .iter()
+            .map(|arc_process| arc_process.scheduler_id())
+            .collect();
+
+        if scheduler_ids.len() == 2 {
+            break;
+        }
+
+        assert!(Instant::now() < deadline, "processes were not stolen");
+        thread::yield_now();
+    }
+}
+
+#[test]
+fn runtime_spawns_init_on_scheduler_thread() {
+    let schedulers = smp::runtime();
+    let arc_process = schedulers.spawn_init(0).unwrap();
+
+    let ids: HashSet<_> = schedulers
+        .schedulers()
+        .iter()
+        .map(|scheduler| scheduler.id)
+        .collect();
+
+    assert!(ids.contains(&arc_process.scheduler_id().unwrap()));
+    assert_ne!(arc_process.scheduler_id(), Some(Scheduler::current().id));
+}
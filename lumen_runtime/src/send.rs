@@ -8,7 +8,7 @@ use liblumen_alloc::erts::exception::InternalResult;
 use liblumen_alloc::term::prelude::*;
 use liblumen_alloc::Process;
 
-use crate::distribution::nodes::node;
+use crate::distribution::{self, nodes::node};
 use crate::registry::{self, pid_to_process};
 use crate::scheduler::Scheduler;
 
@@ -37,17 +37,12 @@ pub fn send(
                     )
                 })?;
 
-                match node_atom.name() {
-                    node::DEAD_ATOM_NAME => send_to_name(name_atom, message, options, process),
-                    _ => {
-                        if !options.connect {
-                            Ok(Sent::ConnectRequired)
-                        } else if !options.suspend {
-                            Ok(Sent::SuspendRequired)
-                        } else {
-                            unimplemented!("distribution")
-                        }
-                    }
+                if node_atom == node::atom() {
+                    send_to_name(name_atom, message, options, process)
+                } else {
+                    Ok(send_to_node(node_atom, options, || {
+                        distribution::send_to_name(process, name_atom, node_atom, message)
+                    }))
                 }
             } else {
                 Err(anyhow!("destination ({}) is a tuple, but not 2-arity", destination).into())
@@ -73,6 +68,11 @@ pub fn send(
                 }
             }
         }
+        TypedTerm::ExternalPid(destination_external_pid) => Ok(send_to_node(
+            destination_external_pid.arc_node().name(),
+            options,
+            || distribution::send(process, &destination_external_pid, message),
+        )),
         _ => Err(TypeError)
             .context(format!(
                 "destination ({}) is not registered_name (atom), {{registered_name, node}}, or pid",
@@ -116,3 +116,20 @@ fn send_to_name(
         }
     }
 }
+
+/// Sends with `send` to `node` if it is connected or `options` allow connecting to it
+fn send_to_node<S: FnOnce()>(node: Atom, options: Options, send: S) -> Sent {
+    if distribution::is_connected(&node) {
+        send();
+
+        Sent::Sent
+    } else if !options.connect {
+        Sent::ConnectRequired
+    } else if !options.suspend {
+        Sent::SuspendRequired
+    } else {
+        send();
+
+        Sent::Sent
+    }
+}
@@ -7,10 +7,10 @@ use liblumen_alloc::erts::term::prelude::*;
 use crate::proplist::TryPropListFromTermError;
 
 pub struct Options {
-    // Send only suspends for some sends to ports and for remote (`ExternalPid` or
-    // `{name, remote_node}`) sends, so it does not apply at this time.
+    // Send only suspends for remote (`ExternalPid` or `{name, remote_node}`) sends that have to
+    // connect to the remote node first.
     pub suspend: bool,
-    // Connect only applies when there is distribution, which isn't implemented yet.
+    // Connect only applies to remote sends to nodes that aren't connected yet.
     pub connect: bool,
 }
 
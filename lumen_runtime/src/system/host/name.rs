@@ -0,0 +1,38 @@
+//! The name of the host, as used for the host part of node names
+
+/// The name of the host up to its first `.`, as `erl -sname` uses for the host part of node names
+pub fn short() -> String {
+    let full = full();
+
+    match full.find('.') {
+        Some(index) => full[..index].to_string(),
+        None => full,
+    }
+}
+
+#[cfg(unix)]
+fn full() -> String {
+    let mut buffer = [0 as libc::c_char; 256];
+
+    let result = unsafe { libc::gethostname(buffer.as_mut_ptr(), buffer.len()) };
+
+    if result == 0 {
+        let bytes: Vec<u8> = buffer
+            .iter()
+            .take_while(|c| **c != 0)
+            .map(|c| *c as u8)
+            .collect();
+
+        String::from_utf8(bytes).unwrap_or_else(|_| LOCALHOST.to_string())
+    } else {
+        LOCALHOST.to_string()
+    }
+}
+
+/// Without a way to get the name of the host, only nodes on the same host can connect
+#[cfg(not(unix))]
+fn full() -> String {
+    LOCALHOST.to_string()
+}
+
+const LOCALHOST: &str = "localhost";
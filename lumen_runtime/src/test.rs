@@ -16,7 +16,7 @@ pub use self::proptest::*;
 use std::convert::TryInto;
 use std::sync::{Arc, Barrier};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use liblumen_alloc::atom;
 use liblumen_alloc::erts::exception;
@@ -24,8 +24,9 @@ use liblumen_alloc::erts::process::{Process, Status};
 use liblumen_alloc::erts::term::prelude::*;
 
 use crate::otp::erlang;
+use crate::port;
 use crate::process::{self, SchedulerDependentAlloc};
-use crate::scheduler::with_process;
+use crate::scheduler::{with_process, Scheduler};
 use crate::time::Milliseconds;
 use crate::timer;
 
@@ -89,6 +90,27 @@ pub fn badarity_reason(process: &Process, function: Term, args: Term) -> Term {
     process.tuple_from_slice(&[tag, fun_args]).unwrap()
 }
 
+/// Polls the ports of the current scheduler until `process` has `data` as a message, giving up
+/// after 10 seconds.
+#[cfg(all(not(target_arch = "wasm32"), test))]
+pub fn poll_ports_until_message(process: &Process, data: Term) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(10);
+
+    loop {
+        port::poll(&Scheduler::current().id);
+
+        if has_message(process, data) {
+            break true;
+        }
+
+        if deadline < Instant::now() {
+            break false;
+        }
+
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
 pub fn timeout_after(milliseconds: Milliseconds) {
     thread::sleep(Duration::from_millis(milliseconds + 1));
     timer::timeout();
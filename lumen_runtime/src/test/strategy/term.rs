@@ -13,7 +13,7 @@ use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::erts::Process;
 use liblumen_alloc::{atom, fixnum_from};
 
-use super::size_range;
+use super::{node, size_range};
 
 pub mod atom;
 pub mod binary;
@@ -25,6 +25,7 @@ pub mod is_bitstring;
 pub mod list;
 pub mod map;
 pub mod pid;
+pub mod port;
 pub mod tuple;
 
 pub const NON_EXISTENT_ATOM_PREFIX: &str = "non_existent";
@@ -72,6 +73,16 @@ pub fn container(
     .boxed()
 }
 
+pub fn external_reference(arc_process: Arc<Process>) -> BoxedStrategy<Term> {
+    (node::external(), any::<u32>(), any::<u64>())
+        .prop_map(move |(arc_node, scheduler_id, number)| {
+            arc_process
+                .external_reference(arc_node, scheduler_id.into(), number, &[])
+                .unwrap()
+        })
+        .boxed()
+}
+
 pub fn float(arc_process: Arc<Process>) -> BoxedStrategy<Term> {
     any::<f64>()
         .prop_filter("Negative and positive 0.0 are the same for Erlang", |f| {
@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use proptest::strategy::{BoxedStrategy, Strategy};
+
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::Process;
+
+use crate::test::strategy::node;
+
+pub fn external(arc_process: Arc<Process>) -> BoxedStrategy<Term> {
+    (node::external(), number())
+        .prop_map(move |(arc_node, number)| {
+            arc_process
+                .external_port(arc_node, unsafe { Port::from_raw(number) })
+                .unwrap()
+        })
+        .boxed()
+}
+
+pub fn local() -> BoxedStrategy<Term> {
+    number()
+        .prop_map(|number| unsafe { Port::from_raw(number) }.encode().unwrap())
+        .boxed()
+}
+
+pub fn number() -> BoxedStrategy<usize> {
+    (0..=Port::NUMBER_MAX).boxed()
+}
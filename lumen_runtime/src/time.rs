@@ -3,6 +3,7 @@ use num_traits::Zero;
 
 pub mod datetime;
 pub mod monotonic;
+pub mod runtime;
 pub mod system;
 mod unit;
 
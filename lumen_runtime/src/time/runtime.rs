@@ -0,0 +1,24 @@
+//! The CPU time used by the runtime, as reported by `erlang:statistics(runtime)`
+use super::Milliseconds;
+
+/// The CPU time the runtime has used in user mode, summed over all of its threads
+#[cfg(unix)]
+pub fn time_in_milliseconds() -> Milliseconds {
+    let mut rusage = core::mem::MaybeUninit::<libc::rusage>::uninit();
+
+    let user_time = unsafe {
+        if libc::getrusage(libc::RUSAGE_SELF, rusage.as_mut_ptr()) != 0 {
+            return 0;
+        }
+
+        rusage.assume_init().ru_utime
+    };
+
+    (user_time.tv_sec as Milliseconds) * 1_000 + (user_time.tv_usec as Milliseconds) / 1_000
+}
+
+/// Without a way to measure CPU time, the time since the runtime started is used instead
+#[cfg(not(unix))]
+pub fn time_in_milliseconds() -> Milliseconds {
+    super::monotonic::time_in_milliseconds()
+}
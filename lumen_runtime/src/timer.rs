@@ -2,6 +2,8 @@ pub mod cancel;
 mod message;
 pub mod read;
 pub mod start;
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
 
 use core::cmp::Ordering::{self, *};
 use core::ops::{Index, IndexMut, RangeBounds};
@@ -16,6 +18,7 @@ use liblumen_core::locks::Mutex;
 use liblumen_alloc::erts::exception::AllocResult;
 use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::CloneToProcess;
+use liblumen_alloc::erts::process::Status;
 use liblumen_alloc::Process;
 
 use crate::registry;
@@ -56,6 +59,26 @@ pub fn start(
     result
 }
 
+/// Times out the current receive of `arc_process` at `monotonic_time_milliseconds`, so that its
+/// `after` clause runs if no message matched before then.
+///
+/// The timer is cancelled by passing the reference returned from `Process::receive_done` to
+/// `cancel`.
+pub fn start_receive_timeout(
+    monotonic_time_milliseconds: Milliseconds,
+    arc_process: &Arc<Process>,
+) {
+    let scheduler = Scheduler::current();
+
+    let timer_reference = scheduler.hierarchy.write().start_receive_timeout(
+        monotonic_time_milliseconds,
+        arc_process,
+        &scheduler,
+    );
+
+    arc_process.receive_set_timer(timer_reference);
+}
+
 /// Times out the timers for the thread that have timed out since the last time `timeout` was
 /// called.
 #[cfg(all(not(target_arch = "wasm32"), test))]
@@ -152,16 +175,49 @@ impl Hierarchy {
                 process_tuple.clone_to_fragment()?
             }
         };
-        let position = self.position(monotonic_time_milliseconds);
-
-        let timer = Timer {
-            reference_number,
-            monotonic_time_milliseconds,
+        let event = Event::Message {
             destination,
             message_heap: Mutex::new(message::HeapFragment {
                 heap_fragment,
                 term: heap_fragment_message,
             }),
+        };
+
+        self.insert(reference_number, monotonic_time_milliseconds, event);
+
+        Ok(process_reference)
+    }
+
+    fn start_receive_timeout(
+        &mut self,
+        monotonic_time_milliseconds: Milliseconds,
+        arc_process: &Arc<Process>,
+        scheduler: &Scheduler,
+    ) -> Reference {
+        let reference_number = scheduler.next_reference_number();
+        let reference = Reference::new(scheduler.id, reference_number);
+        let event = Event::ReceiveTimeout {
+            process: Arc::downgrade(arc_process),
+            reference,
+        };
+
+        self.insert(reference_number, monotonic_time_milliseconds, event);
+
+        reference
+    }
+
+    fn insert(
+        &mut self,
+        reference_number: ReferenceNumber,
+        monotonic_time_milliseconds: Milliseconds,
+        event: Event,
+    ) {
+        let position = self.position(monotonic_time_milliseconds);
+
+        let timer = Timer {
+            reference_number,
+            monotonic_time_milliseconds,
+            event,
             position: Mutex::new(position),
         };
 
@@ -178,8 +234,19 @@ impl Hierarchy {
 
         self.timer_by_reference_number
             .insert(reference_number, cancellable);
+    }
 
-        Ok(process_reference)
+    /// How long until `timeout` would time out the next timer, or `None` if there are no timers
+    pub fn milliseconds_until_next_timeout(&self) -> Option<Milliseconds> {
+        self.timer_by_reference_number
+            .values()
+            .filter_map(|weak_timer| weak_timer.upgrade())
+            .map(|arc_timer| arc_timer.monotonic_time_milliseconds)
+            .min()
+            .map(|monotonic_time_milliseconds| {
+                // `timeout` only times out the slot of a millisecond once that millisecond is over
+                (monotonic_time_milliseconds + 1).saturating_sub(monotonic::time_in_milliseconds())
+            })
     }
 
     pub fn timeout(&mut self) {
@@ -351,14 +418,29 @@ pub enum Timeout {
     TimeoutTuple,
 }
 
+/// What happens when a timer times out
+#[cfg_attr(debug_assertions, derive(Debug))]
+enum Event {
+    /// Sends the message to the destination, for `erlang:send_after` and `erlang:start_timer`
+    Message {
+        destination: Destination,
+        message_heap: Mutex<message::HeapFragment>,
+    },
+    /// Times out the receive of the process that started it, for `receive ... after`, without
+    /// sending a message
+    ReceiveTimeout {
+        process: Weak<Process>,
+        reference: Reference,
+    },
+}
+
 #[cfg_attr(debug_assertions, derive(Debug))]
 struct Timer {
     // Can't be a `Boxed` `LocalReference` `Term` because those are boxed and the original Process
     // could GC the unboxed `LocalReference` `Term`.
     reference_number: ReferenceNumber,
     monotonic_time_milliseconds: Milliseconds,
-    destination: Destination,
-    message_heap: Mutex<message::HeapFragment>,
+    event: Event,
     position: Mutex<Position>,
 }
 
@@ -377,18 +459,57 @@ impl Timer {
     }
 
     fn timeout(self) {
-        let option_destination_arc_process = match &self.destination {
-            Destination::Name(ref name) => registry::atom_to_process(name),
-            Destination::Process(destination_process_weak) => destination_process_weak.upgrade(),
-        };
+        match self.event {
+            Event::Message {
+                destination,
+                message_heap,
+            } => {
+                let option_destination_arc_process = match destination {
+                    Destination::Name(ref name) => registry::atom_to_process(name),
+                    Destination::Process(destination_process_weak) => {
+                        destination_process_weak.upgrade()
+                    }
+                };
 
-        if let Some(destination_arc_process) = option_destination_arc_process {
-            let message::HeapFragment {
-                heap_fragment,
-                term,
-            } = self.message_heap.into_inner();
+                if let Some(destination_arc_process) = option_destination_arc_process {
+                    let message::HeapFragment {
+                        heap_fragment,
+                        term,
+                    } = message_heap.into_inner();
+
+                    destination_arc_process.send_heap_message(heap_fragment, term);
+
+                    // status.write() scope
+                    let stop_waiting = {
+                        let mut writable_status = destination_arc_process.status.write();
+
+                        if *writable_status == Status::Waiting {
+                            *writable_status = Status::Runnable;
+
+                            true
+                        } else {
+                            false
+                        }
+                    };
+
+                    if stop_waiting {
+                        Self::stop_waiting(&destination_arc_process);
+                    }
+                }
+            }
+            Event::ReceiveTimeout { process, reference } => {
+                if let Some(arc_process) = process.upgrade() {
+                    if arc_process.receive_expire_timer(&reference) {
+                        Self::stop_waiting(&arc_process);
+                    }
+                }
+            }
+        }
+    }
 
-            destination_arc_process.send_heap_message(heap_fragment, term);
+    fn stop_waiting(arc_process: &Arc<Process>) {
+        if let Some(arc_scheduler) = arc_process.scheduler() {
+            arc_scheduler.stop_waiting(arc_process);
         }
     }
 }
@@ -0,0 +1,79 @@
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::process::Receive;
+
+use crate::scheduler::with_process_arc;
+use crate::test::{has_no_message, timeout_after};
+
+use super::*;
+
+mod start_receive_timeout {
+    use super::*;
+
+    #[test]
+    fn times_out_receive_when_timer_expires() {
+        with_process_arc(|arc_process| {
+            let milliseconds = soon_milliseconds();
+
+            arc_process.receive_start(None);
+            start_receive_timeout(
+                monotonic::time_in_milliseconds() + milliseconds,
+                &arc_process,
+            );
+
+            assert_eq!(arc_process.receive_next(), Receive::Wait);
+
+            assert_eq!(*arc_process.status.read(), Status::Waiting);
+
+            timeout_after(milliseconds);
+
+            assert_eq!(*arc_process.status.read(), Status::Runnable);
+            assert_eq!(arc_process.receive_next(), Receive::Timeout);
+            assert!(has_no_message(&arc_process));
+        });
+    }
+
+    #[test]
+    fn with_matched_message_does_not_time_out_next_receive() {
+        with_process_arc(|arc_process| {
+            let milliseconds = soon_milliseconds();
+            let message = atom!("message");
+
+            arc_process.receive_start(None);
+            start_receive_timeout(
+                monotonic::time_in_milliseconds() + milliseconds,
+                &arc_process,
+            );
+            arc_process.send_from_self(message);
+
+            assert_eq!(arc_process.receive_next(), Receive::Message(message));
+
+            let timer_reference = arc_process.receive_done().unwrap();
+
+            assert!(cancel(&timer_reference).is_some());
+
+            arc_process.receive_start(None);
+            timeout_after(milliseconds);
+
+            assert_eq!(arc_process.receive_next(), Receive::Wait);
+        });
+    }
+
+    #[test]
+    fn with_stale_timer_does_not_time_out_next_receive() {
+        with_process_arc(|arc_process| {
+            let milliseconds = soon_milliseconds();
+
+            arc_process.receive_start(None);
+            start_receive_timeout(
+                monotonic::time_in_milliseconds() + milliseconds,
+                &arc_process,
+            );
+
+            // A new receive without the first being done, such as after an exception
+            arc_process.receive_start(None);
+            timeout_after(milliseconds);
+
+            assert_eq!(arc_process.receive_next(), Receive::Wait);
+        });
+    }
+}
@@ -37,12 +37,14 @@ pub fn native_implemented_function(
             let frame = frame();
             let function = function_arity.function();
             let module_function_arity = signatures.module_function_arity();
+            let apply = signatures.apply();
 
             let all_tokens = quote! {
                 #place_frame_with_arguments
 
                 // Private
 
+                #apply
                 #code
                 #arity
                 #export
@@ -223,8 +225,8 @@ impl Signatures {
         })
     }
 
-    pub fn code(&self) -> proc_macro2::TokenStream {
-        let native_argument_ident: Vec<Box<dyn ToTokens>> = match self.native.process {
+    fn native_argument_ident(&self) -> Vec<Box<dyn ToTokens>> {
+        match self.native.process {
             Process::Arc => {
                 let mut native_argument_vec: Vec<Box<dyn ToTokens>> =
                     Vec::with_capacity(self.code.argument_ident_vec.len() + 1);
@@ -259,7 +261,11 @@ impl Signatures {
 
                 native_argument_vec
             }
-        };
+        }
+    }
+
+    pub fn code(&self) -> proc_macro2::TokenStream {
+        let native_argument_ident = self.native_argument_ident();
 
         let stack_peeked_code_argument_ident = &self.code.argument_ident_vec;
         let arguments_len = self.code.argument_ident_vec.len();
@@ -300,6 +306,32 @@ impl Signatures {
         }
     }
 
+    /// Calls `native` with the arguments as a slice, for callers that don't use the process stack
+    pub fn apply(&self) -> proc_macro2::TokenStream {
+        let native_argument_ident = self.native_argument_ident();
+        let code_argument_ident = &self.code.argument_ident_vec;
+        let arguments_len = self.code.argument_ident_vec.len();
+        let argument_index = 0..arguments_len;
+
+        let native_call = match self.native.return_type {
+            ReturnType::Result => quote! { native(#(#native_argument_ident),*) },
+            ReturnType::Term => quote! { Ok(native(#(#native_argument_ident),*)) },
+        };
+
+        quote! {
+            pub fn apply(
+                arc_process: &std::sync::Arc<liblumen_alloc::erts::process::Process>,
+                arguments: &[liblumen_alloc::erts::term::prelude::Term]
+            ) -> liblumen_alloc::erts::exception::Result<liblumen_alloc::erts::term::prelude::Term> {
+                assert_eq!(arguments.len(), #arguments_len);
+
+                #(let #code_argument_ident = arguments[#argument_index];)*
+
+                #native_call
+            }
+        }
+    }
+
     pub fn export(&self) -> proc_macro2::TokenStream {
         match crate_name("lumen_runtime") {
             // in other crates
@@ -367,7 +399,7 @@ impl FunctionArity {
         let arity = &self.arity;
 
         quote! {
-           const ARITY: liblumen_alloc::Arity = #arity;
+           pub const ARITY: liblumen_alloc::Arity = #arity;
         }
     }
 